@@ -1,8 +1,9 @@
+use crate::syntax_tree::DocumentSymbol;
 use async_trait::async_trait;
 use egui;
 use uuid::Uuid;
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use anyhow::{Result, anyhow};
 use serde::{Serialize, Deserialize};
 
@@ -98,6 +99,17 @@ pub enum ApiResult {
     Error(String),
 }
 
+impl ApiResult {
+    /// The returned value, if any, with `Error` turned into an `Err`
+    pub fn into_result(self) -> Result<Option<serde_json::Value>> {
+        match self {
+            ApiResult::Success => Ok(None),
+            ApiResult::Value(value) => Ok(Some(value)),
+            ApiResult::Error(message) => Err(anyhow::anyhow!(message)),
+        }
+    }
+}
+
 impl<T: Serialize> From<Result<T>> for ApiResult {
     fn from(result: Result<T>) -> Self {
         match result {
@@ -146,6 +158,40 @@ pub trait Actor: Send + Sync + ActorAPI {
 
     /// Get mutable actor as Any for downcasting
     fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Command palette commands the actor asked to run since the last call
+    /// (by id or search text)
+    fn take_palette_commands(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Outline of the actor's document, for "go to symbol"
+    fn document_symbols(&self) -> Vec<DocumentSymbol> {
+        Vec::new()
+    }
+
+    /// Words the actor offers to completion in other editors
+    fn completion_words(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether the actor asked for the other actors' words since the last call
+    fn take_workspace_words_request(&mut self) -> bool {
+        false
+    }
+
+    /// Words from every other actor, in answer to a request
+    fn set_workspace_words(&mut self, _words: Vec<String>) {}
+
+    /// Actors this actor created (e.g. a diff view) to be opened in new views
+    fn take_spawned_actors(&mut self) -> Vec<Box<dyn Actor>> {
+        Vec::new()
+    }
+
+    /// Whether the actor has changes that closing it would lose
+    fn is_modified(&self) -> bool {
+        false
+    }
 }
 
 /// Messages that can be sent to actors
@@ -194,6 +240,15 @@ impl ActorManager {
         }
     }
 
+    /// Drop an actor, returning it
+    pub fn remove_actor(&mut self, id: Uuid) -> Option<Box<dyn Actor>> {
+        let index = self.actors.iter().position(|a| a.id() == id)?;
+        if self.focused_actor == Some(id) {
+            self.focused_actor = None;
+        }
+        Some(self.actors.remove(index))
+    }
+
     pub fn get_actor(&self, id: Uuid) -> Option<&dyn Actor> {
         self.actors.iter()
             .find(|a| a.id() == id)
@@ -250,6 +305,16 @@ impl ActorManager {
         }).collect()
     }
 
+    /// Completion words from every actor except `exclude`, without duplicates
+    pub fn workspace_words(&self, exclude: Uuid) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.actors.iter()
+            .filter(|actor| actor.id() != exclude)
+            .flat_map(|actor| actor.completion_words())
+            .filter(|word| seen.insert(word.clone()))
+            .collect()
+    }
+
     /// Find actors by type
     pub fn find_actors_by_type(&self, actor_type: &str) -> Vec<Uuid> {
         self.actors.iter()
@@ -0,0 +1,273 @@
+use anyhow::{Result, anyhow};
+use memmap2::Mmap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+/// Bytes scanned per step when searching
+const SEARCH_CHUNK: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// The memory-mapped file
+    Original,
+    /// Bytes typed or pasted since the file was opened
+    Added,
+}
+
+/// A run of bytes from one of the sources
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    source: Source,
+    start: usize,
+    len: usize,
+}
+
+/// Editable bytes of a file, as a piece table over a read-only memory map
+///
+/// Opening costs nothing regardless of size: the file is mapped, never read
+/// up front, and edits only add pieces pointing into an append-only buffer.
+/// Undo snapshots are the piece lists, so they're cheap too.
+pub struct ByteBuffer {
+    original: Option<Mmap>,
+    added: Vec<u8>,
+    pieces: Vec<Piece>,
+    len: usize,
+    /// Piece lists to restore, with where the change was
+    undo_stack: Vec<(Vec<Piece>, usize)>,
+    redo_stack: Vec<(Vec<Piece>, usize)>,
+    /// Whether the next edit may be merged into the last undo step
+    group_open: bool,
+    version: u64,
+    saved_version: u64,
+}
+
+impl ByteBuffer {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut buffer = Self {
+            original: None,
+            added: Vec::new(),
+            pieces: Vec::new(),
+            len: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            group_open: false,
+            version: 0,
+            saved_version: 0,
+        };
+        buffer.map(path)?;
+        Ok(buffer)
+    }
+
+    /// (Re)map `path` as the original bytes, dropping all edits
+    fn map(&mut self, path: &Path) -> Result<()> {
+        let file = File::open(path)
+            .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+        let len = file.metadata()?.len() as usize;
+        // Empty files can't be mapped on every platform
+        self.original = if len == 0 {
+            None
+        } else {
+            // SAFETY: the map is only read. Another process truncating the
+            // file meanwhile can still fault reads, as with any mapped viewer.
+            Some(unsafe { Mmap::map(&file) }.map_err(|e| anyhow!("Failed to map {}: {}", path.display(), e))?)
+        };
+        self.added.clear();
+        self.pieces = if len == 0 { Vec::new() } else { vec![Piece { source: Source::Original, start: 0, len }] };
+        self.len = len;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group_open = false;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the bytes changed since they were last saved
+    pub fn is_modified(&self) -> bool {
+        self.version != self.saved_version
+    }
+
+    fn source(&self, source: Source) -> &[u8] {
+        match source {
+            Source::Original => self.original.as_deref().unwrap_or_default(),
+            Source::Added => &self.added,
+        }
+    }
+
+    /// Pieces covering `range`, trimmed to it
+    fn slice_pieces(&self, range: Range<usize>) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        let mut pos = 0;
+        for piece in &self.pieces {
+            let (start, end) = (pos, pos + piece.len);
+            pos = end;
+            if end <= range.start {
+                continue;
+            }
+            if start >= range.end {
+                break;
+            }
+            let from = range.start.max(start);
+            let to = range.end.min(end);
+            pieces.push(Piece { source: piece.source, start: piece.start + from - start, len: to - from });
+        }
+        pieces
+    }
+
+    /// Bytes in `range`, clipped to the end of the buffer
+    pub fn read(&self, range: Range<usize>) -> Vec<u8> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        let mut bytes = Vec::with_capacity(range.len());
+        for piece in self.slice_pieces(range) {
+            bytes.extend_from_slice(&self.source(piece.source)[piece.start..piece.start + piece.len]);
+        }
+        bytes
+    }
+
+    pub fn get(&self, offset: usize) -> Option<u8> {
+        self.read(offset..offset + 1).first().copied()
+    }
+
+    /// Parts of `range` that were typed or pasted rather than read from the file
+    pub fn edited_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut pos = 0;
+        for piece in &self.pieces {
+            let (start, end) = (pos, pos + piece.len);
+            pos = end;
+            if piece.source == Source::Added && start < range.end && end > range.start {
+                ranges.push(start.max(range.start)..end.min(range.end));
+            }
+        }
+        ranges
+    }
+
+    /// Replace `range` with `bytes` (an insertion when the range is empty,
+    /// a deletion when `bytes` is), merged into the last undo step while
+    /// the group is open
+    pub fn replace(&mut self, range: Range<usize>, bytes: &[u8]) {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        if range.is_empty() && bytes.is_empty() {
+            return;
+        }
+        if !self.group_open {
+            self.undo_stack.push((self.pieces.clone(), range.start));
+        }
+        self.redo_stack.clear();
+        self.group_open = true;
+
+        let mut pieces = self.slice_pieces(0..range.start);
+        if !bytes.is_empty() {
+            pieces.push(Piece { source: Source::Added, start: self.added.len(), len: bytes.len() });
+            self.added.extend_from_slice(bytes);
+        }
+        pieces.extend(self.slice_pieces(range.end..self.len));
+
+        // Typing forward keeps extending one piece
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last) if last.source == piece.source && last.start + last.len == piece.start => last.len += piece.len,
+                _ => merged.push(piece),
+            }
+        }
+        self.pieces = merged;
+        self.len = self.len - range.len() + bytes.len();
+        self.version += 1;
+    }
+
+    /// Make the next edit start a new undo step instead of merging
+    pub fn break_undo_group(&mut self) {
+        self.group_open = false;
+    }
+
+    /// Undo the last step, returning where it changed the bytes
+    pub fn undo(&mut self) -> Option<usize> {
+        let (pieces, offset) = self.undo_stack.pop()?;
+        self.redo_stack.push((std::mem::replace(&mut self.pieces, pieces), offset));
+        self.restored();
+        Some(offset)
+    }
+
+    /// Redo the last undone step, returning where it changed the bytes
+    pub fn redo(&mut self) -> Option<usize> {
+        let (pieces, offset) = self.redo_stack.pop()?;
+        self.undo_stack.push((std::mem::replace(&mut self.pieces, pieces), offset));
+        self.restored();
+        Some(offset)
+    }
+
+    fn restored(&mut self) {
+        self.len = self.pieces.iter().map(|piece| piece.len).sum();
+        self.group_open = false;
+        self.version += 1;
+    }
+
+    /// Write the bytes to `path` and map the written file
+    ///
+    /// The bytes go to a temporary file first, replacing `path` only once
+    /// they're all written. Undo history is cleared, as it refers to the
+    /// previous mapping.
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        let file_name = path.file_name()
+            .ok_or_else(|| anyhow!("Not a file path: {}", path.display()))?
+            .to_string_lossy();
+        let temp = path.with_file_name(format!(".{}.hexsave", file_name));
+        let written = (|| -> std::io::Result<()> {
+            let mut file = File::create(&temp)?;
+            for piece in &self.pieces {
+                file.write_all(&self.source(piece.source)[piece.start..piece.start + piece.len])?;
+            }
+            file.sync_all()
+        })();
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp);
+            return Err(anyhow!("Failed to write {}: {}", path.display(), e));
+        }
+
+        fs::rename(&temp, path)
+            .map_err(|e| anyhow!("Failed to replace {}: {}", path.display(), e))?;
+        self.map(path)?;
+        self.version += 1;
+        self.saved_version = self.version;
+        Ok(())
+    }
+
+    /// Offset of the first occurrence of `pattern` at or after `from`, or
+    /// (searching backwards) the last one starting before `from`
+    pub fn find(&self, pattern: &[u8], from: usize, forward: bool) -> Option<usize> {
+        if pattern.is_empty() || pattern.len() > self.len {
+            return None;
+        }
+        let overlap = pattern.len() - 1;
+        if forward {
+            let mut start = from;
+            while start + pattern.len() <= self.len {
+                let chunk = self.read(start..start + SEARCH_CHUNK + overlap);
+                if let Some(found) = chunk.windows(pattern.len()).position(|window| window == pattern) {
+                    return Some(start + found);
+                }
+                start += SEARCH_CHUNK;
+            }
+        } else {
+            // Matches must start before `from` but may extend past it
+            let mut end = (from + overlap).min(self.len);
+            while end >= pattern.len() {
+                let start = end.saturating_sub(SEARCH_CHUNK + overlap);
+                let chunk = self.read(start..end);
+                if let Some(found) = chunk.windows(pattern.len()).rposition(|window| window == pattern) {
+                    return Some(start + found);
+                }
+                if start == 0 {
+                    break;
+                }
+                end = start + overlap;
+            }
+        }
+        None
+    }
+}
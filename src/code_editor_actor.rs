@@ -1,247 +1,2936 @@
 use crate::actor::{Actor, ActorMessage, ActorAPI, ApiMethod, ApiParameter, ApiParams, ApiResult};
+use crate::completion::{self, CompletionAction, CompletionContext, CompletionEngine, CompletionPopup};
+use crate::config::{EditorConfig, IdeConfig};
+use crate::diff;
+use crate::diff_actor::{DiffActor, DiffSide};
+use crate::editor_buffer::{self, BufferWindow, EditorBuffer, Selection, TextPosition, TextRange, TextSelection};
+use crate::find_bar::{FindBar, FindBarAction};
+use crate::formatter::FormatterRegistry;
+use crate::git::{GitFile, HunkKind};
+use crate::merge::{self, Conflicts, Resolution};
+use crate::minimap::{self, Minimap, Viewport};
+use crate::large_file::LargeFile;
+use crate::language_servers::LanguageServers;
+use crate::keymap::{self, KeyOutcome, KeyPress, Keymap, KeymapContext, KeymapKind, KeymapRequest};
+use crate::snippet::{self, SnippetSession, TabStop};
+use crate::swap_file::{SwapFile, SwapStore};
+use crate::syntax_tree::{DocumentSymbol, SyntaxTree, TokenClass};
+use crate::text_search::{self, Search, SearchOptions};
 use async_trait::async_trait;
 use egui;
+use egui::text::{CCursor, CCursorRange};
 use uuid::Uuid;
 use std::any::Any;
 use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 use anyhow::{Result, anyhow};
 use serde_json;
 
+/// How long typing must pause before the swap file is rewritten
+const SWAP_WRITE_DELAY: Duration = Duration::from_secs(1);
+
+/// Language name for a file, from its extension ("text" if unknown)
+fn language_for_path(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()).unwrap_or_default() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "c++" | "hpp" | "hh" | "hxx" => "cpp",
+        "json" => "json",
+        "css" => "css",
+        "html" | "htm" => "html",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        _ => "text",
+    }
+}
+
+/// Most declaration lines sticky scroll pins at the top
+const MAX_STICKY_LINES: usize = 3;
+
+/// Lines laid out above and below the visible ones, so the caret and a
+/// drag selection can move off screen before the window follows
+const WINDOW_MARGIN: usize = 20;
+/// How many lines beyond the view the window may grow to keep the selection
+const MAX_WINDOW_STRETCH: usize = 2000;
+
+/// Bracket colors by nesting depth
+const RAINBOW: [egui::Color32; 3] = [
+    egui::Color32::from_rgb(255, 215, 0),
+    egui::Color32::from_rgb(218, 112, 214),
+    egui::Color32::from_rgb(23, 159, 255),
+];
+
+/// How often to check whether the formatter has finished
+const FORMAT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Formatting still running on a worker thread
+struct PendingFormat {
+    /// Buffer version being formatted; the result is dropped if it changes
+    version: u64,
+    /// File to write again once the result is applied (format on save)
+    save_to: Option<PathBuf>,
+    /// Behind a mutex only so the editor is `Sync`
+    formatted: Mutex<Receiver<Result<String>>>,
+}
+
 /// Simple Code Editor Actor - native egui implementation
 pub struct CodeEditorActor {
     id: Uuid,
     name: String,
-    content: String,
+    buffer: EditorBuffer,
     language: String,
     is_focused: bool,
+    /// Cursor position as a byte offset into the buffer
     cursor_pos: usize,
+    /// Other end of the selection (equal to `cursor_pos` when nothing is selected)
+    selection_anchor: usize,
+    /// All cursors while multi-cursor editing is active, primary last
+    /// (empty otherwise)
+    selections: Vec<Selection>,
+    /// Where an Alt+drag column selection started, relative to the text layout
+    block_origin: Option<egui::Vec2>,
+    /// Vim/Emacs key binding layer (`None` for the plain widget bindings)
+    keymap: Option<Box<dyn Keymap>>,
+    /// `(anchor, cursor)` last pushed into the widget for the keymap, which
+    /// may differ from the logical selection (e.g. Vim's inclusive visual mode)
+    displayed_selection: Option<(usize, usize)>,
+    /// Palette commands requested by the keymap, collected by the IDE
+    palette_commands: Vec<String>,
+    /// Text the keymap wants on the system clipboard
+    pending_copy: Option<String>,
+    /// Parse tree for the current language (`None` if there is no grammar)
+    syntax: Option<SyntaxTree>,
+    /// Outline and bracket depths, refreshed whenever the tree is reparsed
+    outline: Vec<DocumentSymbol>,
+    brackets: Vec<(Range<usize>, usize)>,
+    /// Lexical classes for the minimap, refreshed with the outline
+    tokens: Vec<(Range<usize>, TokenClass)>,
+    minimap: Minimap,
+    /// Vertical scroll offset to apply on the next frame (minimap, sticky headers)
+    scroll_request: Option<f32>,
+    /// First line in view, and the content margin and row height it was
+    /// computed with, from the last frame
+    first_visible_line: f32,
+    scroll_metrics: (f32, f32),
+    /// Selections that expand-selection grew from, for shrinking back
+    selection_history: Vec<Range<usize>>,
+    completion: CompletionEngine,
+    completion_popup: Option<CompletionPopup>,
+    /// Typing asked for the popup to be opened or refreshed
+    completion_requested: bool,
+    /// Buffer version and cursor the popup's items were computed for
+    completion_seen: (u64, usize),
+    /// Just below the cursor on screen, where the popup goes
+    completion_anchor: Option<egui::Pos2>,
+    /// Words from the other open editors, refreshed whenever the popup opens
+    workspace_words: Vec<String>,
+    wants_workspace_words: bool,
+    /// Tab stops of the snippet being filled in
+    snippet: Option<SnippetSession>,
+    file_path: Option<PathBuf>,
+    /// HEAD/index versions and hunks when the file is in a git repository
+    git: Option<GitFile>,
+    /// Actors opened from this editor (diff views), collected by the IDE
+    spawned: Vec<Box<dyn Actor>>,
+    /// Read-only, virtualized view used instead of the buffer for files
+    /// above the large-file threshold
+    large_file: Option<LargeFile>,
+    /// Word count for `get_stats`, and the buffer version it was counted for
+    word_count: Option<(u64, usize)>,
+    /// Merge conflict blocks, and the buffer version they're up to date with
+    conflicts: Conflicts,
+    conflicts_version: Option<u64>,
+    /// Show ours/base/theirs side by side for the conflict at the cursor
+    merge_view: bool,
+    config: EditorConfig,
+    formatters: FormatterRegistry,
+    /// Servers shared with the other editors, once the IDE hands its own over
+    language_servers: LanguageServers,
+    pending_format: Option<PendingFormat>,
+    find_bar: FindBar,
+    /// Give keyboard focus back to the text area on the next frame
+    focus_requested: bool,
+    /// Where unsaved changes are journaled (`None` if there is no config dir)
+    swap_store: Option<SwapStore>,
+    /// Buffer version the swap file was last written for
+    swapped_version: Option<u64>,
+    /// Buffer version seen by the last `update`, and when it changed
+    seen_version: u64,
+    last_change: Instant,
+    last_auto_save: Instant,
 }
 
 impl CodeEditorActor {
     pub fn new(name: String) -> Self {
+        let config = IdeConfig::default().editor;
+        let id = Uuid::new_v4();
+        let language_servers = LanguageServers::new();
         Self {
-            id: Uuid::new_v4(),
+            id,
             name,
-            content: String::from("// Welcome to Zellij IDE\n\nfn main() {\n    println!(\"Hello, world!\");\n}\n"),
+            buffer: EditorBuffer::new(String::from("// Welcome to Zellij IDE\n\nfn main() {\n    println!(\"Hello, world!\");\n}\n")),
             language: "rust".to_string(),
             is_focused: false,
             cursor_pos: 0,
+            selection_anchor: 0,
+            selections: Vec::new(),
+            block_origin: None,
+            keymap: config.keymap.create(),
+            displayed_selection: None,
+            palette_commands: Vec::new(),
+            pending_copy: None,
+            syntax: SyntaxTree::for_language("rust"),
+            outline: Vec::new(),
+            brackets: Vec::new(),
+            tokens: Vec::new(),
+            minimap: Minimap::new(egui::Id::new(("minimap", id))),
+            scroll_request: None,
+            first_visible_line: 0.0,
+            scroll_metrics: (0.0, 1.0),
+            selection_history: Vec::new(),
+            completion: CompletionEngine::new(language_servers.clone()),
+            completion_popup: None,
+            completion_requested: false,
+            completion_seen: (0, 0),
+            completion_anchor: None,
+            workspace_words: Vec::new(),
+            wants_workspace_words: false,
+            snippet: None,
+            file_path: None,
+            git: None,
+            spawned: Vec::new(),
+            large_file: None,
+            word_count: None,
+            conflicts: Conflicts::default(),
+            conflicts_version: None,
+            merge_view: false,
+            formatters: FormatterRegistry::from_config(&config),
+            language_servers,
+            pending_format: None,
+            config,
+            find_bar: FindBar::new(),
+            focus_requested: false,
+            swap_store: SwapStore::new().map_err(|e| log::warn!("Swap files disabled: {}", e)).ok(),
+            swapped_version: None,
+            seen_version: 0,
+            last_change: Instant::now(),
+            last_auto_save: Instant::now(),
+        }
+    }
+
+    /// Reopen a buffer from a swap file left behind by a crash
+    ///
+    /// The recovered text is applied as an undoable edit on top of the file's
+    /// current contents, so the buffer starts out modified.
+    pub fn from_swap(swap: SwapFile) -> Self {
+        let on_disk = swap.file_path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        let mut editor = Self::new(swap.name);
+        editor.buffer = EditorBuffer::new(on_disk);
+        editor.buffer.set_text(&swap.content);
+        editor.file_path = swap.file_path;
+        editor.set_language(&swap.language);
+        editor.attach_git();
+        editor
+    }
+
+    /// Open a file from disk
+    ///
+    /// Files at or above the configured large-file threshold are memory-mapped
+    /// and shown read-only, with only the visible lines laid out and the
+    /// per-keystroke features (parsing, minimap, git, completion) left off.
+    pub fn open(path: PathBuf, config: EditorConfig) -> Result<Self> {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let size = fs::metadata(&path)
+            .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?
+            .len();
+
+        let mut editor = Self::new(name).with_config(config);
+        editor.file_path = Some(path.clone());
+        if size >= editor.config.large_file_threshold {
+            log::info!("Opening {} ({} bytes) read-only in large-file mode", path.display(), size);
+            editor.large_file = Some(LargeFile::open(&path)?);
+            editor.buffer = EditorBuffer::new(String::new());
+            editor.language = language_for_path(&path).to_string();
+            editor.syntax = None;
+            editor.completion = CompletionEngine::empty();
+            editor.config.show_minimap = false;
+            editor.config.sticky_scroll = false;
+        } else {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+            editor.buffer = EditorBuffer::new(text);
+            editor.set_language(language_for_path(&path));
+            editor.attach_git();
         }
+        Ok(editor)
     }
 
     pub fn with_content(name: String, content: String) -> Self {
         let mut editor = Self::new(name);
-        editor.content = content;
+        editor.buffer = EditorBuffer::new(content);
         editor
     }
 
+    pub fn with_config(mut self, config: EditorConfig) -> Self {
+        self.formatters = FormatterRegistry::from_config(&config);
+        self.keymap = config.keymap.create();
+        self.config = config;
+        self
+    }
+
+    /// Use `language_servers` (shared with other editors) for completion
+    pub fn with_language_servers(mut self, language_servers: LanguageServers) -> Self {
+        if self.large_file.is_none() {
+            self.completion = CompletionEngine::new(language_servers.clone());
+        }
+        self.language_servers = language_servers;
+        self
+    }
+
+    /// Switch the key binding layer, resetting any modal state
+    pub fn set_keymap(&mut self, kind: KeymapKind) {
+        self.config.keymap = kind;
+        self.keymap = kind.create();
+        self.displayed_selection = None;
+        let (anchor, cursor) = (self.selection_anchor, self.cursor_pos);
+        self.select_range(anchor..cursor);
+    }
+
     pub fn set_language(&mut self, lang: &str) {
         self.language = lang.to_string();
+        self.syntax = SyntaxTree::for_language(lang);
+        self.outline.clear();
+        self.brackets.clear();
+        self.sync_changes();
     }
 
-    pub fn get_content(&self) -> &str {
-        &self.content
+    /// Feed buffer changes to the parse tree and snippet tab stops, and
+    /// refresh what's derived from them
+    fn sync_changes(&mut self) {
+        let changes = self.buffer.take_changes();
+        if let Some(session) = &mut self.snippet {
+            changes.iter().for_each(|change| session.apply_change(change));
+        }
+        match self.conflicts_version {
+            Some(_) => self.conflicts.update(self.buffer.text(), &changes),
+            None => self.conflicts = Conflicts::new(self.buffer.text()),
+        }
+        self.conflicts_version = Some(self.buffer.version());
+        let Some(syntax) = &mut self.syntax else {
+            return;
+        };
+        if syntax.update(self.buffer.text(), &changes) {
+            self.outline = syntax.symbols(self.buffer.text());
+            self.brackets = syntax.bracket_depths();
+            self.tokens = syntax.tokens();
+        }
     }
-}
 
-#[async_trait]
-impl Actor for CodeEditorActor {
-    fn id(&self) -> Uuid {
-        self.id
-    }
+    /// Pin the declaration lines of the items enclosing the top visible line
+    /// over the text; clicking one jumps to it
+    fn show_sticky_headers(&mut self, ui: &mut egui::Ui, viewport: egui::Rect, first_line: usize, row_height: f32, text_x: f32) {
+        let mut headers: Vec<(usize, usize)> = Vec::new();
+        // The outline lists parents before their children
+        for symbol in &self.outline {
+            if headers.len() >= MAX_STICKY_LINES {
+                break;
+            }
+            let top = first_line + headers.len();
+            let start = self.buffer.line_index().line(symbol.range.start);
+            let end = self.buffer.line_index().line(symbol.range.end);
+            if start < top && top < end {
+                headers.push((start, symbol.name_range.start));
+            }
+        }
+        if headers.is_empty() {
+            return;
+        }
 
-    fn name(&self) -> String {
-        self.name.clone()
+        let font = egui::FontId::monospace(self.config.font_size);
+        let painter = ui.painter_at(viewport);
+        let mut target = None;
+        for (k, (line, offset)) in headers.iter().enumerate() {
+            let rect = egui::Rect::from_min_size(
+                egui::pos2(viewport.left(), viewport.top() + k as f32 * row_height),
+                egui::vec2(viewport.width(), row_height),
+            );
+            let response = ui.interact(rect, egui::Id::new(("sticky_scroll", self.id, k)), egui::Sense::click());
+            let fill = if response.hovered() { ui.visuals().faint_bg_color } else { ui.visuals().extreme_bg_color };
+            painter.rect_filled(rect, 0.0, fill);
+
+            let start = self.buffer.line_index().line_start(*line).unwrap_or(0);
+            let end = self.buffer.line_index().line_start(line + 1).unwrap_or(self.buffer.text().len());
+            let text = self.buffer.text()[start..end].trim_end();
+            painter.text(egui::pos2(text_x, rect.center().y), egui::Align2::LEFT_CENTER, text, font.clone(), ui.visuals().text_color());
+            if response.clicked() {
+                target = Some((*line, *offset));
+            }
+        }
+        let bottom = viewport.top() + headers.len() as f32 * row_height;
+        painter.hline(viewport.x_range(), bottom, ui.visuals().widgets.noninteractive.bg_stroke);
+
+        if let Some((_, offset)) = target {
+            self.move_cursor(offset);
+            self.focus_requested = true;
+        }
     }
 
-    async fn handle_message(&mut self, message: ActorMessage) -> anyhow::Result<()> {
-        match message {
-            ActorMessage::Focus => {
-                self.is_focused = true;
-                log::info!("Code editor {} focused", self.name);
-            },
-            ActorMessage::Unfocus => {
-                self.is_focused = false;
-                log::info!("Code editor {} unfocused", self.name);
-            },
-            ActorMessage::TextInput(text) => {
-                self.content.push_str(&text);
-            },
-            ActorMessage::Custom(cmd, data) => {
-                match cmd.as_str() {
-                    "set_content" => {
-                        if let Ok(content) = serde_json::from_value::<String>(data) {
-                            self.content = content;
-                        }
-                    },
-                    "set_language" => {
-                        if let Ok(lang) = serde_json::from_value::<String>(data) {
-                            self.set_language(&lang);
-                        }
-                    },
-                    _ => {},
-                }
-            },
-            _ => {},
+    /// Lines to hand the text widget while `visible` are on screen, and
+    /// whether the selection is among them
+    ///
+    /// The window grows to take in a selection near the view. One further
+    /// away is left out (and highlighted instead) unless `reveal` asks for
+    /// it because input is about to move or edit it.
+    fn window_lines(&self, visible: Range<usize>, reveal: bool) -> (Range<usize>, bool) {
+        let line_count = self.buffer.line_count();
+        let padded = |lines: Range<usize>| {
+            let end = (lines.end + WINDOW_MARGIN).min(line_count);
+            lines.start.saturating_sub(WINDOW_MARGIN).min(end)..end
+        };
+        let view = padded(visible);
+        let (anchor, cursor) = self.displayed_selection.unwrap_or((self.selection_anchor, self.cursor_pos));
+        let index = self.buffer.line_index();
+        let selected = padded(index.line(anchor.min(cursor))..index.line(anchor.max(cursor)) + 1);
+        let spanned = view.start.min(selected.start)..view.end.max(selected.end);
+        if spanned.len() <= view.len() + MAX_WINDOW_STRETCH {
+            (spanned, true)
+        } else if reveal {
+            (selected, true)
+        } else {
+            (view, false)
         }
+    }
+
+    /// Index of the conflict block containing `line`
+    fn conflict_at(&self, line: usize) -> Option<usize> {
+        self.conflicts.blocks().iter().position(|conflict| conflict.lines().contains(&line))
+    }
+
+    /// Replace a conflict block (the one at the cursor by default) with the chosen version
+    pub fn resolve_conflict(&mut self, index: Option<usize>, resolution: Resolution) -> Result<()> {
+        self.sync_changes();
+        let index = index.or_else(|| self.conflict_at(self.cursor_line()))
+            .ok_or_else(|| anyhow!("No merge conflict at the cursor"))?;
+        let conflict = self.conflicts.blocks().get(index)
+            .ok_or_else(|| anyhow!("No merge conflict {} ({} remaining)", index, self.conflicts.blocks().len()))?;
+        let text = conflict.resolved(self.buffer.text(), resolution)
+            .ok_or_else(|| anyhow!("The conflict has no base version"))?;
+        let start = conflict.range.start;
+        self.buffer.apply_edits(vec![(conflict.range.clone(), text)]);
+        self.move_cursor(start);
+        self.sync_changes();
         Ok(())
     }
 
-    fn update(&mut self, _ctx: &egui::Context) {
-        // Could handle animations, async operations, etc.
+    /// Move the cursor to the next (or previous) conflict block, wrapping around
+    pub fn jump_conflict(&mut self, forward: bool) -> bool {
+        self.sync_changes();
+        let line = self.cursor_line();
+        let target = if forward {
+            self.conflicts.blocks().iter().find(|c| c.start_line > line).or(self.conflicts.blocks().first())
+        } else {
+            self.conflicts.blocks().iter().rev().find(|c| c.end_line < line).or(self.conflicts.blocks().last())
+        };
+        let Some(start) = target.map(|c| c.range.start) else {
+            return false;
+        };
+        self.move_cursor(start);
+        self.focus_requested = true;
+        true
     }
 
-    fn render(&mut self, ui: &mut egui::Ui) {
-        // Header with file info
-        ui.horizontal(|ui| {
-            ui.label(&self.name);
-            ui.separator();
-            ui.label(format!("Language: {}", self.language));
-            if self.is_focused {
-                ui.separator();
-                ui.colored_label(egui::Color32::GREEN, "● Active");
+    /// Document outline, nested items following their parent
+    pub fn document_symbols(&self) -> &[DocumentSymbol] {
+        &self.outline
+    }
+
+    /// Replace the outline, for languages without a grammar whose outline
+    /// comes from elsewhere (e.g. markdown headings)
+    pub fn set_outline(&mut self, outline: Vec<DocumentSymbol>) {
+        if self.syntax.is_none() {
+            self.outline = outline;
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn file_path(&self) -> Option<&Path> {
+        self.file_path.as_deref()
+    }
+
+    pub fn font_size(&self) -> f32 {
+        self.config.font_size
+    }
+
+    pub fn version(&self) -> u64 {
+        self.buffer.version()
+    }
+
+    pub fn is_large_file(&self) -> bool {
+        self.large_file.is_some()
+    }
+
+    /// Line at the top of the view, fractional while between lines
+    pub fn first_visible_line(&self) -> f32 {
+        self.first_visible_line
+    }
+
+    /// Scroll so `line` (possibly fractional) is at the top of the view
+    pub fn scroll_to_line(&mut self, line: f32) {
+        let (content_margin, row_height) = self.scroll_metrics;
+        self.scroll_request = Some(content_margin + line * row_height);
+        self.first_visible_line = line;
+    }
+
+    /// Surround each selection with `before` and `after`, or remove them if
+    /// they're already there (e.g. `**` to toggle bold)
+    pub fn wrap_selection(&mut self, before: &str, after: &str) {
+        self.buffer.break_undo_group();
+        self.edit_and_select(|text, _, selection| {
+            let range = selection.range();
+            let selected = text[range.clone()].to_string();
+            let wrapped = range.start >= before.len()
+                && text[range.end..].starts_with(after)
+                && text[..range.start].ends_with(before);
+            if wrapped {
+                let len = selected.len();
+                (range.start - before.len()..range.end + after.len(), selected, 0..len)
+            } else {
+                let inner = before.len()..before.len() + selected.len();
+                (range, format!("{}{}{}", before, selected, after), inner)
             }
         });
+        self.focus_requested = true;
+    }
 
-        ui.separator();
+    /// Outline items enclosing the cursor, outermost first
+    fn breadcrumbs(&self) -> Vec<&DocumentSymbol> {
+        self.outline.iter()
+            .filter(|symbol| symbol.range.start <= self.cursor_pos && self.cursor_pos < symbol.range.end)
+            .collect()
+    }
 
-        // Simple text editor using egui's native TextEdit
-        egui::ScrollArea::both()
-            .id_salt(format!("editor_{}", self.id))
-            .show(ui, |ui| {
-                // Use monospace font for code
-                let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
-                    let layout_job = egui::text::LayoutJob::simple(
-                        string.to_owned(),
-                        egui::FontId::monospace(14.0),
-                        egui::Color32::from_gray(200),
-                        f32::INFINITY,
-                    );
-                    ui.fonts(|f| f.layout_job(layout_job))
-                };
+    /// Grow the selection to the enclosing syntax node
+    pub fn expand_selection(&mut self) -> bool {
+        self.sync_changes();
+        let current = self.selected_range();
+        let Some(expanded) = self.syntax.as_ref().and_then(|s| s.expand_selection(current.clone())) else {
+            return false;
+        };
+        // History only holds while the selection keeps growing from it
+        if self.selection_history.last().is_some_and(|last| !(current.start <= last.start && last.end <= current.end)) {
+            self.selection_history.clear();
+        }
+        self.selection_history.push(current);
+        self.select_range(expanded);
+        true
+    }
 
-                egui::TextEdit::multiline(&mut self.content)
-                    .font(egui::TextStyle::Monospace)
-                    .code_editor()
-                    .desired_rows(30)
-                    .lock_focus(true)
-                    .desired_width(f32::INFINITY)
-                    .layouter(&mut layouter)
-                    .show(ui);
-            });
+    /// Undo the last expand-selection
+    pub fn shrink_selection(&mut self) -> bool {
+        let current = self.selected_range();
+        match self.selection_history.pop() {
+            Some(previous) if current.start <= previous.start && previous.end <= current.end => {
+                self.select_range(previous);
+                true
+            }
+            _ => {
+                self.selection_history.clear();
+                false
+            }
+        }
+    }
 
-        // Status line
-        ui.separator();
-        ui.horizontal(|ui| {
-            let lines = self.content.lines().count();
-            let chars = self.content.len();
-            ui.label(format!("Lines: {} | Chars: {}", lines, chars));
+    /// Move the cursor to the bracket matching the one next to it
+    pub fn jump_to_matching_bracket(&mut self) -> bool {
+        self.sync_changes();
+        let Some((_, other)) = self.syntax.as_ref().and_then(|s| s.matching_brackets(self.cursor_pos)) else {
+            return false;
+        };
+        self.move_cursor(other.start);
+        true
+    }
+
+    /// Insert a line break indented for the syntax around the cursor
+    fn insert_newline(&mut self) {
+        let selected = self.selected_range();
+        if !selected.is_empty() {
+            self.buffer.replace_range(selected.clone(), "");
+        }
+        self.sync_changes();
+
+        let offset = selected.start;
+        let text = self.buffer.text();
+        let unit = indent_unit(text, offset, self.config.tab_width);
+        let (indent, closing) = match &self.syntax {
+            Some(syntax) => syntax.newline_indent(text, offset, &unit),
+            None => (String::new(), None),
+        };
+
+        let (end, inserted) = match closing {
+            // Put the closing bracket on its own line below the cursor
+            Some(closing) => {
+                let spaces = text[offset..].len() - text[offset..].trim_start_matches([' ', '\t']).len();
+                (offset + spaces, format!("\n{}\n{}", indent, closing))
+            }
+            None => (offset, format!("\n{}", indent)),
+        };
+        self.buffer.replace_range(offset..end, &inserted);
+        self.move_cursor(offset + 1 + indent.len());
+    }
+
+    /// Move the cursor to a zero-based line and column (in chars)
+    pub fn go_to_position(&mut self, line: usize, column: usize) {
+        let offset = offset_of(self.buffer.text(), TextPosition { line, column });
+        self.move_cursor(offset);
+        self.focus_requested = true;
+    }
+
+    /// Recompute completion items at the cursor, opening the popup if there
+    /// are any and closing it if not
+    fn refresh_completion(&mut self, manual: bool) {
+        if self.cursor_pos != self.selection_anchor {
+            self.completion_popup = None;
+            return;
+        }
+        let ctx = CompletionContext::new(self.buffer.text(), self.cursor_pos, &self.language)
+            .with_file_path(self.file_path.as_deref())
+            .with_workspace_words(&self.workspace_words);
+        self.completion_seen = (self.buffer.version(), self.cursor_pos);
+
+        // Moving to another word starts over
+        let popup = self.completion_popup.take().filter(|p| p.word_start == ctx.word_start);
+        let manual = manual || popup.as_ref().is_some_and(|p| p.manual);
+        if popup.is_none() {
+            self.wants_workspace_words = true;
+        }
+        let items = self.completion.complete(&ctx, manual);
+        if items.is_empty() {
+            return;
+        }
+        let word_start = ctx.word_start;
+        self.completion_popup = Some(match popup {
+            Some(mut popup) => {
+                popup.set_items(items);
+                popup
+            }
+            None => CompletionPopup::new(items, word_start, manual),
         });
     }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    /// Keep the popup in step with typing, cursor moves and late results
+    fn update_completion(&mut self) {
+        let requested = std::mem::take(&mut self.completion_requested);
+        let arrived = self.completion.poll();
+        let moved = self.completion_seen != (self.buffer.version(), self.cursor_pos);
+        match &self.completion_popup {
+            Some(popup) if popup.fixed => {
+                self.completion_popup = self.completion_popup.take().filter(|_| !moved);
+            }
+            Some(_) if requested || arrived || moved => self.refresh_completion(false),
+            None if requested || (arrived && !moved) => self.refresh_completion(false),
+            _ => {}
+        }
+
+        // Leaving the snippet ends tab-stop navigation
+        if self.snippet.as_ref().is_some_and(|s| !(s.span().start..=s.span().end).contains(&self.cursor_pos)) {
+            self.snippet = None;
+        }
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+    /// Insert the popup item at `index`
+    pub fn accept_completion(&mut self, index: usize) -> bool {
+        let Some(popup) = self.completion_popup.take() else {
+            return false;
+        };
+        let word_start = popup.word_start.min(self.cursor_pos);
+        let Some(item) = popup.items.into_iter().nth(index) else {
+            return false;
+        };
+        self.completion.record_accepted(&item);
+
+        let range = item.replace.clone().unwrap_or(word_start..self.cursor_pos);
+        if item.is_snippet {
+            self.insert_snippet(range, &item.insert_text);
+        } else {
+            let end = range.start + item.insert_text.len();
+            self.selections.clear();
+            self.buffer.apply_edits(vec![(range, item.insert_text)]);
+            self.sync_changes();
+            self.move_cursor(end);
+        }
+        self.completion_seen = (self.buffer.version(), self.cursor_pos);
+        true
     }
-}
 
-impl ActorAPI for CodeEditorActor {
-    fn actor_type(&self) -> String {
-        "CodeEditorActor".to_string()
+    /// Replace `range` with a snippet and select its first tab stop
+    pub fn insert_snippet(&mut self, range: Range<usize>, body: &str) {
+        let text = self.buffer.text();
+        let line_start = text[..range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let indent: String = text[line_start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+        let unit = indent_unit(text, range.start, self.config.tab_width);
+        let expanded = snippet::expand(body, &indent, &unit, &self.snippet_variables());
+
+        self.snippet = None;
+        self.buffer.apply_edits(vec![(range.clone(), expanded.text.clone())]);
+        self.sync_changes();
+        let session = SnippetSession::new(&expanded, range.start);
+        let first = session.current().clone();
+        self.snippet = (!session.is_finished()).then_some(session);
+        self.select_tab_stop(&first);
     }
 
-    fn get_api_methods(&self) -> Vec<ApiMethod> {
-        vec![
-            ApiMethod {
-                name: "get_content".to_string(),
-                description: "Get the current content of the editor".to_string(),
-                parameters: vec![],
-                return_type: "string".to_string(),
-                category: "content".to_string(),
+    /// Values for `$TM_*` snippet variables
+    fn snippet_variables(&self) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        let text = self.buffer.text();
+        let selected = &text[self.selected_range()];
+        if !selected.is_empty() {
+            variables.insert("TM_SELECTED_TEXT".to_string(), selected.to_string());
+        }
+        let line = text[..self.cursor_pos].matches('\n').count();
+        variables.insert("TM_LINE_INDEX".to_string(), line.to_string());
+        variables.insert("TM_LINE_NUMBER".to_string(), (line + 1).to_string());
+        if let Some(path) = &self.file_path {
+            variables.insert("TM_FILEPATH".to_string(), path.display().to_string());
+            if let Some(name) = path.file_name() {
+                variables.insert("TM_FILENAME".to_string(), name.to_string_lossy().into_owned());
+            }
+            if let Some(stem) = path.file_stem() {
+                variables.insert("TM_FILENAME_BASE".to_string(), stem.to_string_lossy().into_owned());
+            }
+            if let Some(dir) = path.parent() {
+                variables.insert("TM_DIRECTORY".to_string(), dir.display().to_string());
+            }
+        }
+        variables
+    }
+
+    /// Select a tab stop, with one cursor per mirror
+    fn select_tab_stop(&mut self, stop: &TabStop) {
+        self.selections.clear();
+        self.set_selections(stop.ranges.iter().cloned().map(Selection::from).collect());
+        if stop.choices.len() > 1 {
+            if let Some(range) = stop.ranges.last() {
+                self.completion_popup = Some(CompletionPopup::choices(&stop.choices, range.clone()));
+                self.completion_seen = (self.buffer.version(), self.cursor_pos);
+            }
+        }
+        self.focus_requested = true;
+    }
+
+    /// Move to the next (or previous) snippet tab stop
+    pub fn jump_tab_stop(&mut self, forward: bool) -> bool {
+        self.sync_changes();
+        let Some(session) = &mut self.snippet else {
+            return false;
+        };
+        let stop = if forward { session.next() } else { session.previous() }.clone();
+        if session.is_finished() {
+            self.snippet = None;
+        }
+        self.completion_popup = None;
+        self.select_tab_stop(&stop);
+        true
+    }
+
+    /// Popup navigation, snippet tab stops and completion triggers
+    ///
+    /// Runs before the keymap and the text widget so Tab/Enter/arrows reach
+    /// the popup first.
+    fn handle_completion_input(&mut self, ui: &mut egui::Ui) {
+        if let Some(popup) = &mut self.completion_popup {
+            match popup.handle_keys(ui) {
+                Some(CompletionAction::Accept(index)) => {
+                    self.accept_completion(index);
+                }
+                Some(CompletionAction::Close) => self.completion_popup = None,
+                None => {}
+            }
+        } else if self.snippet.is_some() {
+            let (previous, next) = ui.input_mut(|i| (
+                i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+            ));
+            if next || previous {
+                self.jump_tab_stop(next);
+            }
+            if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.snippet = None;
+            }
+        }
+
+        let accepts_text = self.keymap.as_ref().is_none_or(|k| k.accepts_text());
+        if !accepts_text || !self.selections.is_empty() {
+            return;
+        }
+        if ui.input_mut(|i| i.consume_key(egui::Modifiers::CTRL, egui::Key::Space)) {
+            self.completion_popup = None;
+            self.refresh_completion(true);
+        }
+        let typed = ui.input(|i| i.events.iter().any(|event| match event {
+            egui::Event::Text(text) => text.chars().any(|c| completion::is_word_char(c) || c == '/' || c == '.'),
+            _ => false,
+        }));
+        self.completion_requested |= typed;
+    }
+
+    pub fn get_content(&self) -> &str {
+        self.buffer.text()
+    }
+
+    /// Run the language formatter (or the language server, without one) on
+    /// a worker thread
+    ///
+    /// The result is applied as a minimal edit in `update` once it arrives,
+    /// unless the buffer changed in the meantime.
+    pub fn format(&mut self) {
+        self.start_format(None);
+    }
+
+    /// Format, writing the file to `save_to` again if the result arrives
+    /// after it was saved
+    fn start_format(&mut self, save_to: Option<PathBuf>) {
+        let formatters = self.formatters.clone();
+        let servers = self.language_servers.clone();
+        let language = self.language.clone();
+        let text = self.buffer.text().to_string();
+        let file_path = self.file_path.clone();
+        let tab_width = self.config.tab_width;
+        let (reply, formatted) = mpsc::channel();
+        thread::spawn(move || {
+            // The editor may have closed, or started over, since asking
+            let _ = reply.send(formatters.format(&servers, &language, &text, file_path.as_deref(), tab_width));
+        });
+        self.pending_format = Some(PendingFormat { version: self.buffer.version(), save_to, formatted: Mutex::new(formatted) });
+    }
+
+    /// Apply the formatter's result, if it has arrived
+    fn poll_format(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &mut self.pending_format else {
+            return;
+        };
+        let reply = match pending.formatted.get_mut() {
+            Ok(formatted) => formatted.try_recv(),
+            Err(_) => Err(TryRecvError::Disconnected),
+        };
+        let formatted = match reply {
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(FORMAT_POLL_INTERVAL);
+                return;
+            }
+            Err(TryRecvError::Disconnected) => Err(anyhow!("the formatter stopped")),
+            Ok(formatted) => formatted,
+        };
+        let Some(PendingFormat { version, save_to, .. }) = self.pending_format.take() else {
+            return;
+        };
+
+        match formatted {
+            Err(e) => log::warn!("Formatting failed for {}: {}", self.name, e),
+            Ok(_) if version != self.buffer.version() => {
+                log::info!("Dropped formatting of {}, which changed while it was formatted", self.name);
+            }
+            Ok(formatted) => {
+                self.apply_formatted(&formatted);
+                log::info!("Formatted code in {}", self.name);
+                let Some(path) = save_to.filter(|_| self.buffer.version() != version) else {
+                    return;
+                };
+                match self.write_file(&path) {
+                    Ok(()) => log::info!("Saved {} to {}", self.name, path.display()),
+                    Err(e) => log::warn!("Failed to save formatted {}: {}", self.name, e),
+                }
+            }
+        }
+    }
+
+    /// Replace the buffer with `formatted`, touching only the lines that changed
+    /// so the selections and undo history stay meaningful
+    fn apply_formatted(&mut self, formatted: &str) {
+        let edits = diff::text_edits(self.buffer.text(), formatted);
+        if edits.is_empty() {
+            return;
+        }
+
+        let selections = self.cursors().iter()
+            .map(|selection| Selection::new(
+                EditorBuffer::map_offset(selection.anchor, &edits),
+                EditorBuffer::map_offset(selection.cursor, &edits),
+            ))
+            .collect();
+        self.buffer.apply_edits(edits);
+        self.set_selections(selections);
+    }
+
+    /// Write the buffer to `path` (or the current file), and with format on
+    /// save, again once it's formatted
+    pub fn save(&mut self, path: Option<PathBuf>) -> Result<()> {
+        let new_path = path.is_some();
+        if let Some(path) = path {
+            if let Some(name) = path.file_name() {
+                self.name = name.to_string_lossy().into_owned();
+            }
+            // A new extension picks the new language's formatter and server
+            let language = language_for_path(&path);
+            if language != self.language && self.large_file.is_none() {
+                self.set_language(language);
+            }
+            self.file_path = Some(path);
+        }
+        let path = self.file_path.clone()
+            .ok_or_else(|| anyhow!("{} has no file path; pass 'path' to save it", self.name))?;
+
+        if self.config.format_on_save {
+            self.start_format(Some(path.clone()));
+        }
+
+        self.write_file(&path)?;
+        log::info!("Saved {} to {}", self.name, path.display());
+        if new_path {
+            self.attach_git();
+        }
+        Ok(())
+    }
+
+    /// Start tracking the file's git status, if it's in a repository
+    fn attach_git(&mut self) {
+        self.git = self.file_path.as_deref().and_then(GitFile::open);
+        if let Some(git) = &mut self.git {
+            git.refresh(self.buffer.text(), self.buffer.version());
+        }
+    }
+
+    /// Zero-based line of the cursor
+    fn cursor_line(&self) -> usize {
+        self.buffer.text()[..self.cursor_pos].matches('\n').count()
+    }
+
+    /// Stage the change at `line` (the cursor's by default)
+    pub fn stage_hunk(&mut self, line: Option<usize>) -> Result<bool> {
+        let line = line.unwrap_or_else(|| self.cursor_line());
+        let git = self.git.as_mut().ok_or_else(|| anyhow!("{} is not in a git repository", self.name))?;
+        git.refresh(self.buffer.text(), self.buffer.version());
+        git.stage_hunk(self.buffer.text(), line)
+    }
+
+    /// Unstage the staged change at `line` (the cursor's by default)
+    pub fn unstage_hunk(&mut self, line: Option<usize>) -> Result<bool> {
+        let line = line.unwrap_or_else(|| self.cursor_line());
+        let git = self.git.as_mut().ok_or_else(|| anyhow!("{} is not in a git repository", self.name))?;
+        git.refresh(self.buffer.text(), self.buffer.version());
+        git.unstage_hunk(line)
+    }
+
+    /// Undo the unstaged change at `line` (the cursor's by default) in the buffer
+    pub fn revert_hunk(&mut self, line: Option<usize>) -> Result<bool> {
+        let line = line.unwrap_or_else(|| self.cursor_line());
+        let git = self.git.as_mut().ok_or_else(|| anyhow!("{} is not in a git repository", self.name))?;
+        git.refresh(self.buffer.text(), self.buffer.version());
+        let Some((range, original)) = git.revert_hunk(self.buffer.text(), line) else {
+            return Ok(false);
+        };
+        let cursor = range.start;
+        self.buffer.apply_edits(vec![(range, original)]);
+        self.move_cursor(cursor);
+        Ok(true)
+    }
+
+    /// Open a diff of the file at a git revision (HEAD by default) against the buffer
+    pub fn compare_with_revision(&mut self, revision: Option<&str>) -> Result<()> {
+        let path = self.file_path.clone()
+            .ok_or_else(|| anyhow!("{} has no file path to compare", self.name))?;
+        let diff = DiffActor::against_revision(&path, revision.unwrap_or("HEAD"), self.buffer.text())?;
+        self.spawned.push(Box::new(diff));
+        Ok(())
+    }
+
+    /// Open a diff of the file on disk against the buffer, e.g. after it changed externally
+    pub fn compare_with_saved(&mut self) -> Result<()> {
+        let path = self.file_path.clone()
+            .ok_or_else(|| anyhow!("{} has no file path to compare", self.name))?;
+        let on_disk = DiffSide::file(&path)?.with_label(format!("{} (on disk)", self.name));
+        let buffer = DiffSide::text(format!("{} (buffer)", self.name), self.buffer.text());
+        self.spawned.push(Box::new(DiffActor::new(on_disk, buffer)));
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &std::path::Path) -> Result<()> {
+        fs::write(path, self.buffer.text())?;
+        self.buffer.mark_saved();
+        self.last_auto_save = Instant::now();
+        self.remove_swap_file();
+        Ok(())
+    }
+
+    /// Write the file every `auto_save_interval` seconds while it has unsaved changes
+    ///
+    /// Auto-save never formats, so code doesn't shift around mid-edit.
+    fn auto_save(&mut self, ctx: &egui::Context) {
+        if self.config.auto_save_interval == 0 {
+            return;
+        }
+        let Some(path) = self.file_path.clone() else {
+            return;
+        };
+        if !self.buffer.is_modified() {
+            // The interval starts with the first unsaved change
+            self.last_auto_save = Instant::now();
+            return;
+        }
+
+        let interval = Duration::from_secs(self.config.auto_save_interval);
+        let elapsed = self.last_auto_save.elapsed();
+        if elapsed < interval {
+            ctx.request_repaint_after(interval - elapsed);
+            return;
+        }
+
+        self.last_auto_save = Instant::now();
+        match self.write_file(&path) {
+            Ok(()) => log::info!("Auto-saved {}", self.name),
+            Err(e) => log::warn!("Auto-save failed for {}: {}", self.name, e),
+        }
+    }
+
+    /// Keep the swap file in sync with unsaved changes, once typing pauses
+    fn update_swap_file(&mut self, ctx: &egui::Context) {
+        if !self.buffer.is_modified() {
+            self.remove_swap_file();
+            return;
+        }
+        if self.swapped_version == Some(self.seen_version) || self.swap_store.is_none() {
+            return;
+        }
+
+        let idle = self.last_change.elapsed();
+        if idle < SWAP_WRITE_DELAY {
+            ctx.request_repaint_after(SWAP_WRITE_DELAY - idle);
+            return;
+        }
+        self.write_swap_file();
+    }
+
+    /// Journal the buffer to its swap file, unless it already is up to date
+    fn write_swap_file(&mut self) {
+        let version = self.buffer.version();
+        let Some(store) = self.swap_store.as_ref().filter(|_| self.swapped_version != Some(version)) else {
+            return;
+        };
+        let swap = SwapFile::new(&self.name, self.file_path.clone(), &self.language, self.buffer.text());
+        if let Err(e) = store.write(self.id, &swap) {
+            log::warn!("Failed to write swap file for {}: {}", self.name, e);
+        }
+        // Also on failure: retrying every frame would only spam the log
+        self.swapped_version = Some(version);
+    }
+
+    fn remove_swap_file(&mut self) {
+        if self.swapped_version.take().is_some() {
+            if let Some(store) = &self.swap_store {
+                store.remove(self.id);
+            }
+        }
+    }
+
+    /// Find all matches of `query`, as line/column ranges (only those inside
+    /// a selection with `in_selection`)
+    pub fn find(&self, query: &str, options: SearchOptions, in_selection: bool) -> Result<Vec<TextRange>> {
+        let text = self.buffer.text();
+        let index = self.buffer.line_index();
+        Ok(self.find_offsets(query, options, in_selection)?.iter().map(|range| index.range(text, range)).collect())
+    }
+
+    fn find_offsets(&self, query: &str, options: SearchOptions, in_selection: bool) -> Result<Vec<Range<usize>>> {
+        let search = Search::new(query, options)?;
+        let mut matches = search.find_all(self.buffer.text());
+        if in_selection {
+            let selected: Vec<Range<usize>> = self.cursors().iter()
+                .filter(|selection| !selection.is_empty())
+                .map(Selection::range)
+                .collect();
+            matches.retain(|found| selected.iter().any(|range| range.start <= found.start && found.end <= range.end));
+        }
+        Ok(matches)
+    }
+
+    /// Replace every match of `query` in one undo step, returning where the
+    /// replacements ended up
+    pub fn replace_all(&mut self, query: &str, replacement: &str, options: SearchOptions, preserve_case: bool) -> Result<Vec<TextRange>> {
+        let search = Search::new(query, options)?;
+        let edits = search.replace_all(self.buffer.text(), replacement, preserve_case);
+        let new_ranges = text_search::ranges_after_edits(&edits);
+
+        let cursors = self.cursors().iter()
+            .map(|selection| Selection::caret(EditorBuffer::map_offset(selection.cursor, &edits)))
+            .collect();
+        self.buffer.apply_edits(edits);
+        self.set_selections(cursors);
+
+        let text = self.buffer.text();
+        let index = self.buffer.line_index();
+        Ok(new_ranges.iter().map(|range| index.range(text, range)).collect())
+    }
+
+    /// Put a cursor on every match of `query` (only those inside a
+    /// selection with `in_selection`)
+    pub fn select_all_occurrences(&mut self, query: &str, options: SearchOptions, in_selection: bool) -> Result<Vec<TextRange>> {
+        let matches = self.find_offsets(query, options, in_selection)?;
+        self.set_selections(matches.iter().cloned().map(Selection::from).collect());
+
+        let text = self.buffer.text();
+        let index = self.buffer.line_index();
+        Ok(matches.iter().map(|range| index.range(text, range)).collect())
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.buffer.undo_selections() {
+            Some(selections) => {
+                self.selections.clear();
+                self.set_selections(selections);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.buffer.redo_selections() {
+            Some(selections) => {
+                self.selections.clear();
+                self.set_selections(selections);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the whole text as one undo step, keeping every cursor at its
+    /// line and column
+    pub fn set_content(&mut self, content: &str) {
+        let text = self.buffer.text();
+        let index = self.buffer.line_index();
+        let before = self.cursors();
+        let after: Vec<Selection> = before.iter()
+            .map(|selection| {
+                let position = index.selection(text, selection);
+                Selection::new(offset_of(content, position.anchor), offset_of(content, position.cursor))
+            })
+            .collect();
+
+        let len = text.len();
+        self.buffer.break_undo_group();
+        self.buffer.apply_selection_edits(vec![(0..len, content.to_string())], before, after.clone());
+        self.buffer.break_undo_group();
+        self.selections.clear();
+        self.set_selections(after);
+    }
+
+    fn move_cursor(&mut self, offset: usize) {
+        self.select_range(offset..offset);
+    }
+
+    /// Select `range`, with the cursor at its end
+    fn select_range(&mut self, range: Range<usize>) {
+        self.select(range.into());
+    }
+
+    fn select(&mut self, selection: Selection) {
+        self.selection_anchor = selection.anchor;
+        self.cursor_pos = selection.cursor;
+        self.displayed_selection = None;
+    }
+
+    fn selected_range(&self) -> Range<usize> {
+        self.selection_anchor.min(self.cursor_pos)..self.selection_anchor.max(self.cursor_pos)
+    }
+
+    /// Every cursor, primary last (just the one outside multi-cursor mode)
+    pub fn cursors(&self) -> Vec<Selection> {
+        if self.selections.is_empty() {
+            vec![Selection::new(self.selection_anchor, self.cursor_pos)]
+        } else {
+            self.selections.clone()
+        }
+    }
+
+    /// Set the cursors (the last one is primary), entering multi-cursor mode
+    /// when there are several; overlapping ones merge
+    fn set_selections(&mut self, selections: Vec<Selection>) {
+        let selections = editor_buffer::normalize_selections(selections);
+        match selections.last() {
+            Some(primary) => self.select(*primary),
+            None => return,
+        }
+        self.selections = if selections.len() > 1 { selections } else { Vec::new() };
+    }
+
+    /// Add a caret at `offset`, or remove the cursor there if there are others
+    fn toggle_cursor(&mut self, offset: usize) {
+        let mut cursors = self.cursors();
+        match cursors.iter().position(|selection| selection.cursor == offset || selection.range().contains(&offset)) {
+            Some(_) if cursors.len() == 1 => return,
+            Some(hit) => {
+                cursors.remove(hit);
+            }
+            None => cursors.push(Selection::caret(offset)),
+        }
+        self.set_selections(cursors);
+    }
+
+    /// Add a cursor on the line above the topmost cursor (or below the
+    /// bottommost one), at the primary cursor's column
+    pub fn add_cursor_vertically(&mut self, above: bool) -> bool {
+        let mut cursors = self.cursors();
+        let text = self.buffer.text();
+        let column = cursors.last().map_or(0, |primary| keymap::column(text, primary.cursor));
+        let offsets = cursors.iter().map(|selection| selection.cursor);
+        let edge = if above { offsets.min() } else { offsets.max() }.unwrap_or(0);
+        let target = keymap::line_offset(text, edge, if above { -1 } else { 1 });
+        if target == keymap::line_start(text, edge) {
+            return false;
+        }
+        cursors.push(Selection::caret(keymap::offset_at_column(text, target, column)));
+        self.set_selections(cursors);
+        true
+    }
+
+    /// Column selection: a selection on each line from `anchor`'s to
+    /// `cursor`'s, between their columns
+    ///
+    /// Lines that end before the block starts are skipped, except the
+    /// cursor's own, which holds the primary selection.
+    pub fn select_block(&mut self, anchor: TextPosition, cursor: TextPosition) {
+        let text = self.buffer.text();
+        let last_line = self.buffer.line_count() - 1;
+        let (anchor_line, cursor_line) = (anchor.line.min(last_line), cursor.line.min(last_line));
+        let left = anchor.column.min(cursor.column);
+        let mut selections = Vec::new();
+        for line in anchor_line.min(cursor_line)..=anchor_line.max(cursor_line) {
+            let start = keymap::line_at(text, line);
+            let length = keymap::column(text, keymap::line_end(text, start));
+            if anchor.column != cursor.column && length < left && line != cursor_line {
+                continue;
+            }
+            selections.push(Selection::new(
+                keymap::offset_at_column(text, start, anchor.column),
+                keymap::offset_at_column(text, start, cursor.column),
+            ));
+        }
+        if cursor_line < anchor_line {
+            selections.reverse();
+        }
+        self.selections.clear();
+        self.set_selections(selections);
+    }
+
+    /// Move every cursor for an arrow, Home or End key, extending the
+    /// selections with `extend`
+    fn move_selections(&mut self, key: egui::Key, extend: bool) {
+        let text = self.buffer.text();
+        let moved = self.cursors().into_iter()
+            .map(|selection| {
+                let (range, from) = (selection.range(), selection.cursor);
+                let cursor = match key {
+                    egui::Key::ArrowLeft if !extend && !selection.is_empty() => range.start,
+                    egui::Key::ArrowRight if !extend && !selection.is_empty() => range.end,
+                    egui::Key::ArrowLeft => text[..from].char_indices().next_back().map_or(0, |(i, _)| i),
+                    egui::Key::ArrowRight => text[from..].chars().next().map_or(from, |c| from + c.len_utf8()),
+                    egui::Key::ArrowUp | egui::Key::ArrowDown => {
+                        let up = key == egui::Key::ArrowUp;
+                        let target = keymap::line_offset(text, from, if up { -1 } else { 1 });
+                        match target == keymap::line_start(text, from) {
+                            // No line to move to: go to the start or end of the text
+                            true if up => 0,
+                            true => text.len(),
+                            false => keymap::offset_at_column(text, target, keymap::column(text, from)),
+                        }
+                    }
+                    egui::Key::Home => match keymap::first_non_blank(text, from) {
+                        first if first == from => keymap::line_start(text, from),
+                        first => first,
+                    },
+                    _ => keymap::line_end(text, from),
+                };
+                if extend { Selection::new(selection.anchor, cursor) } else { Selection::caret(cursor) }
+            })
+            .collect();
+        self.set_selections(moved);
+    }
+
+    /// Selected text of every cursor, in document order
+    fn selected_texts(&self) -> Vec<&str> {
+        let mut ranges: Vec<Range<usize>> = self.cursors().iter().map(Selection::range).collect();
+        ranges.sort_by_key(|range| range.start);
+        ranges.into_iter().map(|range| &self.buffer.text()[range]).collect()
+    }
+
+    /// Apply one edit per cursor as a single undo step
+    ///
+    /// `make` maps the text, a cursor's index in document order and its
+    /// selection to the range to replace and its replacement; cursors end
+    /// up after their inserted text.
+    fn edit_selections(&mut self, make: impl Fn(&str, usize, &Selection) -> (Range<usize>, String)) {
+        self.edit_and_select(|text, rank, selection| {
+            let (range, replacement) = make(text, rank, selection);
+            let end = replacement.len();
+            (range, replacement, end..end)
+        });
+    }
+
+    /// Like `edit_selections`, with `make` also giving the part of each
+    /// replacement to select afterwards
+    fn edit_and_select(&mut self, make: impl Fn(&str, usize, &Selection) -> (Range<usize>, String, Range<usize>)) {
+        let before = self.cursors();
+        let text = self.buffer.text();
+        let mut order: Vec<usize> = (0..before.len()).collect();
+        order.sort_by_key(|&i| before[i].range().start);
+        let mut edits: Vec<(usize, Range<usize>, String, Range<usize>)> = order.iter()
+            .enumerate()
+            .map(|(rank, &i)| {
+                let (range, replacement, selected) = make(text, rank, &before[i]);
+                (i, range, replacement, selected)
+            })
+            .collect();
+        edits.sort_by_key(|(_, range, _, _)| range.start);
+        // Cursors whose edits collide collapse into one
+        let mut last_end = None;
+        edits.retain(|(_, range, _, _)| {
+            let keep = last_end.is_none_or(|end| range.start >= end);
+            if keep {
+                last_end = Some(range.end);
+            }
+            keep
+        });
+
+        let owners: Vec<(usize, Range<usize>)> = edits.iter().map(|(i, _, _, selected)| (*i, selected.clone())).collect();
+        let edits: Vec<(Range<usize>, String)> = edits.into_iter().map(|(_, range, replacement, _)| (range, replacement)).collect();
+        let mut after: Vec<(usize, Selection)> = owners.into_iter()
+            .zip(text_search::ranges_after_edits(&edits))
+            .map(|((i, selected), range)| (i, Selection::from(range.start + selected.start..range.start + selected.end)))
+            .collect();
+        // The primary cursor stays primary unless its edit collided
+        if let Some(at) = after.iter().position(|(i, _)| *i == before.len() - 1) {
+            let primary = after.remove(at);
+            after.push(primary);
+        }
+        let after: Vec<Selection> = after.into_iter().map(|(_, selection)| selection).collect();
+
+        let edits: Vec<_> = edits.into_iter().filter(|(range, replacement)| !range.is_empty() || !replacement.is_empty()).collect();
+        self.buffer.apply_selection_edits(edits, before, after.clone());
+        self.set_selections(after);
+    }
+
+    /// Route typing, deletion, clipboard and cursor movement to every cursor
+    /// while multi-cursor mode is active
+    fn handle_multi_cursor_input(&mut self, ui: &mut egui::Ui) {
+        let copies = self.selections.iter().any(|selection| !selection.is_empty());
+        let events = ui.input_mut(|i| {
+            let mut taken = Vec::new();
+            i.events.retain(|event| {
+                let take = match event {
+                    egui::Event::Text(_) | egui::Event::Paste(_) => true,
+                    egui::Event::Copy | egui::Event::Cut => copies,
+                    egui::Event::Key { key, pressed: true, modifiers, .. } => !modifiers.command && !modifiers.alt && matches!(
+                        key,
+                        egui::Key::Backspace | egui::Key::Delete | egui::Key::Enter | egui::Key::Tab | egui::Key::Escape
+                            | egui::Key::ArrowLeft | egui::Key::ArrowRight | egui::Key::ArrowUp | egui::Key::ArrowDown
+                            | egui::Key::Home | egui::Key::End
+                    ),
+                    _ => false,
+                };
+                if take {
+                    taken.push(event.clone());
+                }
+                !take
+            });
+            taken
+        });
+
+        for event in events {
+            match event {
+                egui::Event::Text(text) => {
+                    self.edit_selections(|_, _, selection| (selection.range(), text.clone()));
+                }
+                egui::Event::Paste(text) => {
+                    // As many lines as cursors: one line each
+                    let lines: Vec<&str> = text.lines().collect();
+                    let distribute = lines.len() == self.cursors().len();
+                    self.buffer.break_undo_group();
+                    self.edit_selections(|_, rank, selection| {
+                        let pasted = if distribute { lines[rank].to_string() } else { text.clone() };
+                        (selection.range(), pasted)
+                    });
+                }
+                egui::Event::Copy | egui::Event::Cut => {
+                    self.pending_copy = Some(self.selected_texts().join("\n"));
+                    if matches!(event, egui::Event::Cut) {
+                        self.buffer.break_undo_group();
+                        self.edit_selections(|_, _, selection| (selection.range(), String::new()));
+                    }
+                }
+                egui::Event::Key { key: egui::Key::Enter, .. } => {
+                    self.edit_selections(|_, _, selection| (selection.range(), "\n".to_string()));
+                }
+                egui::Event::Key { key: egui::Key::Tab, .. } => {
+                    let tab_width = self.config.tab_width;
+                    self.edit_selections(|text, _, selection| (selection.range(), indent_unit(text, selection.range().start, tab_width)));
+                }
+                egui::Event::Key { key: egui::Key::Backspace, .. } => {
+                    self.edit_selections(|text, _, selection| {
+                        let range = selection.range();
+                        if range.is_empty() {
+                            let start = text[..range.start].char_indices().next_back().map(|(i, _)| i).unwrap_or(0);
+                            (start..range.start, String::new())
+                        } else {
+                            (range, String::new())
+                        }
+                    });
+                }
+                egui::Event::Key { key: egui::Key::Delete, .. } => {
+                    self.edit_selections(|text, _, selection| {
+                        let range = selection.range();
+                        if range.is_empty() {
+                            let len = text[range.end..].chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+                            (range.end..range.end + len, String::new())
+                        } else {
+                            (range, String::new())
+                        }
+                    });
+                }
+                egui::Event::Key { key: egui::Key::Escape, .. } => {
+                    self.selections.clear();
+                }
+                egui::Event::Key { key, modifiers, .. } => {
+                    self.buffer.break_undo_group();
+                    self.move_selections(key, modifiers.shift);
+                }
+                _ => {}
+            }
+        }
+
+        // Other navigation (page keys, word jumps) drops back to the primary cursor
+        let navigated = ui.input(|i| i.events.iter().any(|event| matches!(
+            event,
+            egui::Event::Key { key, pressed: true, .. } if matches!(
+                key,
+                egui::Key::ArrowLeft | egui::Key::ArrowRight | egui::Key::ArrowUp | egui::Key::ArrowDown
+                    | egui::Key::Home | egui::Key::End | egui::Key::PageUp | egui::Key::PageDown
+            )
+        )));
+        if navigated {
+            self.selections.clear();
+        }
+    }
+
+    /// Run keys through the keymap layer and apply what it asked for
+    fn feed_keys(&mut self, keys: impl IntoIterator<Item = KeyPress>) -> Vec<KeyOutcome> {
+        let Some(keymap) = self.keymap.as_mut() else {
+            return Vec::new();
+        };
+        let mut ctx = KeymapContext::new(&mut self.buffer, self.selection_anchor, self.cursor_pos, self.config.tab_width);
+        let outcomes = keys.into_iter().map(|key| keymap.handle_key(key, &mut ctx)).collect();
+        let (anchor, cursor, requests) = (ctx.anchor, ctx.cursor, ctx.requests);
+
+        self.selections.clear();
+        self.set_keymap_selection(anchor, cursor);
+        for request in requests {
+            match request {
+                KeymapRequest::Save => {
+                    if let Err(e) = self.save(None) {
+                        log::error!("Failed to save {}: {}", self.name, e);
+                    }
+                }
+                KeymapRequest::PaletteCommand(command) => self.palette_commands.push(command),
+                KeymapRequest::CopyToClipboard(text) => self.pending_copy = Some(text),
+            }
+        }
+        outcomes
+    }
+
+    /// Set the logical selection and push the keymap's rendering of it into the widget
+    fn set_keymap_selection(&mut self, anchor: usize, cursor: usize) {
+        self.selection_anchor = anchor;
+        self.cursor_pos = cursor;
+        let displayed = match &self.keymap {
+            Some(keymap) => keymap.display_selection(self.buffer.text(), anchor, cursor),
+            None => (anchor, cursor),
+        };
+        self.displayed_selection = Some(displayed);
+    }
+
+    /// Route keyboard input through the keymap before the text widget sees it
+    ///
+    /// Keys the keymap ignores stay queued for the widget and the editor shortcuts.
+    fn handle_keymap_input(&mut self, ui: &mut egui::Ui) {
+        let Some(accepts_text) = self.keymap.as_ref().map(|k| k.accepts_text()) else {
+            return;
+        };
+        let events = ui.input_mut(|i| std::mem::take(&mut i.events));
+        let mut remaining = Vec::new();
+        // Alt chords can also produce a text event (e.g. Option+f on macOS)
+        let mut skip_text = false;
+
+        for event in events {
+            match &event {
+                egui::Event::Key { key, pressed: true, modifiers, .. } if !modifiers.mac_cmd => {
+                    let Some(press) = keymap::key_press_from_egui(*key, *modifiers) else {
+                        remaining.push(event);
+                        continue;
+                    };
+                    if self.feed_keys([press]) == [KeyOutcome::Handled] {
+                        skip_text = press.alt;
+                    } else {
+                        remaining.push(event);
+                    }
+                }
+                egui::Event::Text(text) => {
+                    if !std::mem::take(&mut skip_text) {
+                        self.feed_keys(text.chars().map(|c| KeyPress::new(keymap::Key::Char(c))));
+                    }
+                }
+                egui::Event::Paste(_) | egui::Event::Cut if !accepts_text => {}
+                _ => remaining.push(event),
+            }
+        }
+
+        ui.input_mut(|i| i.events = remaining);
+    }
+
+    fn handle_find_bar_actions(&mut self, actions: Vec<FindBarAction>) {
+        self.find_bar.update_matches(self.buffer.text(), self.buffer.version());
+
+        for action in actions {
+            match action {
+                FindBarAction::Next => {
+                    let from = (self.selected_range().start + 1).min(self.buffer.text().len());
+                    if let Some(range) = self.find_bar.select_next(from) {
+                        self.select_range(range);
+                    }
+                }
+                FindBarAction::Previous => {
+                    if let Some(range) = self.find_bar.select_previous(self.selected_range().end) {
+                        self.select_range(range);
+                    }
+                }
+                FindBarAction::Replace => self.replace_current_match(),
+                FindBarAction::ReplaceAll => {
+                    let query = self.find_bar.query.clone();
+                    let replacement = self.find_bar.replacement.clone();
+                    let options = self.find_bar.options.clone();
+                    if let Err(e) = self.replace_all(&query, &replacement, options, self.find_bar.preserve_case) {
+                        log::warn!("Replace all failed: {}", e);
+                    }
+                }
+                FindBarAction::SelectAll => {
+                    let matches = self.find_bar.matches().iter().cloned().map(Selection::from).collect();
+                    self.set_selections(matches);
+                    self.focus_requested = true;
+                }
+                FindBarAction::Close => {
+                    self.find_bar.close();
+                    self.focus_requested = true;
+                }
+            }
+            self.find_bar.update_matches(self.buffer.text(), self.buffer.version());
+        }
+    }
+
+    /// Replace the selected match (if the selection is one) and move to the next
+    fn replace_current_match(&mut self) {
+        let selected = self.selected_range();
+        if self.find_bar.matches().contains(&selected) {
+            if let Ok(search) = self.find_bar.search() {
+                let new_text = search.replacement_for(
+                    self.buffer.text(),
+                    &selected,
+                    &self.find_bar.replacement,
+                    self.find_bar.preserve_case,
+                );
+                let end = selected.start + new_text.len();
+                self.buffer.apply_edits(vec![(selected, new_text)]);
+                self.move_cursor(end);
+                self.find_bar.update_matches(self.buffer.text(), self.buffer.version());
+            }
+        }
+
+        if let Some(range) = self.find_bar.select_next(self.cursor_pos) {
+            self.select_range(range);
+        }
+    }
+
+    /// Background highlights for matches and extra cursors, sorted by position
+    fn highlights(&self) -> Vec<(Range<usize>, egui::Color32)> {
+        let mut highlights = Vec::new();
+        if self.find_bar.visible {
+            let current = self.find_bar.current_match();
+            highlights.extend(self.find_bar.matches().iter().map(|range| {
+                let color = if Some(range) == current.as_ref() {
+                    egui::Color32::from_rgb(130, 95, 20)
+                } else {
+                    egui::Color32::from_rgb(75, 65, 25)
+                };
+                (range.clone(), color)
+            }));
+        }
+        highlights.extend(self.selections.iter()
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| (selection.range(), egui::Color32::from_rgb(40, 70, 120))));
+        if let Some((a, b)) = self.syntax.as_ref().and_then(|s| s.matching_brackets(self.cursor_pos)) {
+            let color = egui::Color32::from_gray(70);
+            highlights.extend([(a, color), (b, color)]);
+        }
+        highlights.sort_by_key(|(range, _)| range.start);
+        highlights
+    }
+
+    fn text_edit_id(&self) -> egui::Id {
+        egui::Id::new(("code_editor", self.id))
+    }
+
+    /// Editor shortcuts that must be handled before `TextEdit` sees the keys
+    fn handle_shortcuts(&mut self, ui: &mut egui::Ui) {
+        let smart_newline = self.syntax.is_some() && self.keymap.is_none() && self.selections.is_empty();
+        let (cursor_above, cursor_below) = ui.input_mut(|i| (
+            i.consume_key(egui::Modifiers::ALT | egui::Modifiers::SHIFT, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::ALT | egui::Modifiers::SHIFT, egui::Key::ArrowDown),
+        ));
+        if cursor_above {
+            self.add_cursor_vertically(true);
+        }
+        if cursor_below {
+            self.add_cursor_vertically(false);
+        }
+
+        let single_cursor = self.keymap.is_none() && self.selections.is_empty();
+        let (expand, shrink, bracket, newline, indent) = ui.input_mut(|i| {
+            let expand = i.consume_key(egui::Modifiers::ALT | egui::Modifiers::SHIFT, egui::Key::ArrowRight);
+            let shrink = i.consume_key(egui::Modifiers::ALT | egui::Modifiers::SHIFT, egui::Key::ArrowLeft);
+            let bracket = i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Backslash);
+            let newline = smart_newline && i.consume_key(egui::Modifiers::NONE, egui::Key::Enter);
+            // `TextEdit` would always insert a tab character
+            let indent = single_cursor && i.consume_key(egui::Modifiers::NONE, egui::Key::Tab);
+            (expand, shrink, bracket, newline, indent)
+        });
+        if expand {
+            self.expand_selection();
+        }
+        if shrink {
+            self.shrink_selection();
+        }
+        if bracket {
+            self.jump_to_matching_bracket();
+        }
+        if newline {
+            self.insert_newline();
+        }
+        if indent {
+            let selected = self.selected_range();
+            let unit = indent_unit(self.buffer.text(), selected.start, self.config.tab_width);
+            self.buffer.replace_range(selected.clone(), &unit);
+            self.move_cursor(selected.start + unit.len());
+        }
+
+        let (redo, undo, save, find, replace) = ui.input_mut(|i| {
+            // Check redo first: Cmd+Z would also match Cmd+Shift+Z
+            let redo = i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y);
+            let undo = i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+            let save = i.consume_key(egui::Modifiers::COMMAND, egui::Key::S);
+            let find = i.consume_key(egui::Modifiers::COMMAND, egui::Key::F);
+            let replace = i.consume_key(egui::Modifiers::COMMAND, egui::Key::H);
+            (redo, undo, save, find, replace)
+        });
+
+        if find || replace {
+            let selected = self.buffer.text().get(self.selected_range()).map(str::to_string);
+            self.find_bar.open(replace, selected.as_deref());
+        }
+
+        if redo {
+            self.redo();
+        }
+        if undo {
+            self.undo();
+        }
+        if save {
+            if let Err(e) = self.save(None) {
+                log::error!("Failed to save {}: {}", self.name, e);
+            }
+        }
+    }
+}
+
+impl Drop for CodeEditorActor {
+    fn drop(&mut self) {
+        // Unsaved changes stay journaled for the recovery prompt at the next
+        // launch; a clean buffer leaves nothing to recover
+        if self.buffer.is_modified() {
+            self.write_swap_file();
+        } else {
+            self.remove_swap_file();
+        }
+    }
+}
+
+/// One level of indentation for the line `offset` is on: a tab if the line
+/// is indented with tabs, else `tab_width` spaces
+fn indent_unit(text: &str, offset: usize, tab_width: usize) -> String {
+    let line_start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    if text[line_start..].starts_with('\t') {
+        "\t".to_string()
+    } else {
+        " ".repeat(tab_width)
+    }
+}
+
+/// Monospace layout with background and foreground colors for the given byte
+/// ranges (each list sorted by start)
+fn highlighted_layout_job(
+    text: &str,
+    font_size: f32,
+    backgrounds: &[(Range<usize>, egui::Color32)],
+    foregrounds: &[(Range<usize>, egui::Color32)],
+) -> egui::text::LayoutJob {
+    let plain = egui::TextFormat::simple(egui::FontId::monospace(font_size), egui::Color32::from_gray(200));
+    let backgrounds = fitting_ranges(text, backgrounds);
+    let foregrounds = fitting_ranges(text, foregrounds);
+
+    let mut boundaries: Vec<usize> = backgrounds.iter().chain(&foregrounds)
+        .flat_map(|(range, _)| [range.start, range.end])
+        .chain([0, text.len()])
+        .collect();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut job = egui::text::LayoutJob::default();
+    let (mut bg, mut fg) = (backgrounds.iter().peekable(), foregrounds.iter().peekable());
+    for segment in boundaries.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        while bg.next_if(|(range, _)| range.end <= start).is_some() {}
+        while fg.next_if(|(range, _)| range.end <= start).is_some() {}
+        let background = bg.peek().filter(|(range, _)| range.start <= start).map(|(_, color)| *color);
+        let color = fg.peek().filter(|(range, _)| range.start <= start).map(|(_, color)| *color);
+        job.append(&text[start..end], 0.0, egui::TextFormat {
+            color: color.unwrap_or(plain.color),
+            background: background.unwrap_or(plain.background),
+            ..plain.clone()
+        });
+    }
+    if job.sections.is_empty() {
+        job.append("", 0.0, plain);
+    }
+    job
+}
+
+/// Char position in the widget showing `window` of `text`, for an offset in the window
+fn window_ccursor(text: &str, window: &Range<usize>, offset: usize) -> Option<CCursor> {
+    (window.start..=window.end).contains(&offset)
+        .then(|| CCursor::new(editor_buffer::byte_to_char(&text[window.clone()], offset - window.start)))
+}
+
+/// Offset in `text` of a char position in the widget showing `window` of it
+fn window_offset(text: &str, window: &Range<usize>, ccursor: CCursor) -> usize {
+    window.start + editor_buffer::char_to_byte(&text[window.clone()], ccursor.index)
+}
+
+/// The parts of `ranges` inside `window`, relative to its start
+fn window_ranges(ranges: &[(Range<usize>, egui::Color32)], window: &Range<usize>) -> Vec<(Range<usize>, egui::Color32)> {
+    ranges.iter()
+        .filter(|(range, _)| range.start < window.end && range.end > window.start)
+        .map(|(range, color)| (range.start.max(window.start) - window.start..range.end.min(window.end) - window.start, *color))
+        .collect()
+}
+
+/// Ranges that are in bounds and don't overlap an earlier one
+///
+/// Ranges can be a frame stale after an edit, so anything that no longer fits is dropped.
+fn fitting_ranges(text: &str, ranges: &[(Range<usize>, egui::Color32)]) -> Vec<(Range<usize>, egui::Color32)> {
+    let mut pos = 0;
+    ranges.iter()
+        .filter(|(range, _)| {
+            let fits = range.start >= pos
+                && range.end <= text.len()
+                && text.is_char_boundary(range.start)
+                && text.is_char_boundary(range.end);
+            if fits {
+                pos = range.end;
+            }
+            fits
+        })
+        .cloned()
+        .collect()
+}
+
+#[async_trait]
+impl Actor for CodeEditorActor {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn handle_message(&mut self, message: ActorMessage) -> anyhow::Result<()> {
+        match message {
+            ActorMessage::Focus => {
+                self.is_focused = true;
+                log::info!("Code editor {} focused", self.name);
+            },
+            ActorMessage::Unfocus => {
+                self.is_focused = false;
+                log::info!("Code editor {} unfocused", self.name);
+            },
+            ActorMessage::TextInput(text) => {
+                let end = self.buffer.text().len();
+                self.buffer.replace_range(end..end, &text);
+            },
+            ActorMessage::Custom(cmd, data) => {
+                match cmd.as_str() {
+                    "set_content" => {
+                        if let Ok(content) = serde_json::from_value::<String>(data) {
+                            self.set_content(&content);
+                        }
+                    },
+                    "set_language" => {
+                        if let Ok(lang) = serde_json::from_value::<String>(data) {
+                            self.set_language(&lang);
+                        }
+                    },
+                    _ => {},
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, ctx: &egui::Context) {
+        if self.large_file.is_some() {
+            return;
+        }
+        let version = self.buffer.version();
+        if version != self.seen_version {
+            self.seen_version = version;
+            self.last_change = Instant::now();
+        }
+
+        self.sync_changes();
+        if let Some(git) = &mut self.git {
+            git.refresh(self.buffer.text(), version);
+        }
+        self.poll_format(ctx);
+        self.auto_save(ctx);
+        self.update_swap_file(ctx);
+    }
+
+    fn render(&mut self, ui: &mut egui::Ui) {
+        if let Some(large_file) = &mut self.large_file {
+            ui.horizontal(|ui| {
+                ui.label(&self.name);
+                ui.separator();
+                ui.weak(format!("Read-only large file · {} lines · {:.1} MB",
+                    large_file.line_count(), large_file.len() as f64 / (1024.0 * 1024.0)));
+            });
+            ui.separator();
+            let id = egui::Id::new(("large_file", self.id));
+            large_file.show(ui, id, self.config.font_size, self.config.show_line_numbers);
+            return;
+        }
+        self.sync_changes();
+
+        // Header with file info
+        ui.horizontal(|ui| {
+            ui.label(&self.name);
+            if self.buffer.is_modified() {
+                ui.label("●");
+            }
+            ui.separator();
+            ui.label(format!("Language: {}", self.language));
+            if let Some(branch) = self.git.as_ref().and_then(|g| g.branch()) {
+                ui.separator();
+                ui.label(format!("⎇ {}", branch));
+            }
+            if !self.conflicts.blocks().is_empty() {
+                ui.separator();
+                let count = self.conflicts.blocks().len();
+                ui.colored_label(egui::Color32::from_rgb(230, 160, 60),
+                    format!("⚠ {} conflict{}", count, if count == 1 { "" } else { "s" }));
+                if ui.small_button("◀").on_hover_text("Previous conflict").clicked() {
+                    self.jump_conflict(false);
+                }
+                if ui.small_button("▶").on_hover_text("Next conflict").clicked() {
+                    self.jump_conflict(true);
+                }
+                ui.toggle_value(&mut self.merge_view, "Merge view");
+            }
+            if self.is_focused {
+                ui.separator();
+                ui.colored_label(egui::Color32::GREEN, "● Active");
+            }
+            if let Some(status) = self.keymap.as_ref().map(|k| k.status()).filter(|s| !s.is_empty()) {
+                ui.separator();
+                ui.monospace(status);
+            }
+        });
+
+        let crumbs: Vec<(String, usize)> = self.breadcrumbs().iter()
+            .map(|symbol| (format!("{} {}", symbol.kind, symbol.name), symbol.name_range.start))
+            .collect();
+        if !crumbs.is_empty() {
+            let mut target = None;
+            ui.horizontal(|ui| {
+                for (i, (label, offset)) in crumbs.iter().enumerate() {
+                    if i > 0 {
+                        ui.weak("›");
+                    }
+                    if ui.small_button(label).clicked() {
+                        target = Some(*offset);
+                    }
+                }
+            });
+            if let Some(offset) = target {
+                self.move_cursor(offset);
+                self.focus_requested = true;
+            }
+        }
+
+        ui.separator();
+
+        let find_actions = self.find_bar.show(ui);
+        if self.find_bar.visible {
+            self.handle_find_bar_actions(find_actions);
+        }
+
+        let merge_index = self.conflict_at(self.cursor_line()).or((!self.conflicts.blocks().is_empty()).then_some(0));
+        if let Some(index) = merge_index.filter(|_| self.merge_view) {
+            let conflict = &self.conflicts.blocks()[index];
+            let mut picked = merge::show_merge_panes(ui, self.buffer.text(), conflict, self.config.font_size);
+            ui.horizontal(|ui| {
+                ui.weak(format!("Conflict {} of {}", index + 1, self.conflicts.blocks().len()));
+                if ui.small_button(Resolution::Both.label()).clicked() {
+                    picked = Some(Resolution::Both);
+                }
+            });
+            if let Some(resolution) = picked {
+                if let Err(e) = self.resolve_conflict(Some(index), resolution) {
+                    log::warn!("Failed to resolve conflict: {}", e);
+                }
+            }
+            ui.separator();
+        }
+
+        let text_edit_id = self.text_edit_id();
+        let has_focus = ui.memory(|m| m.has_focus(text_edit_id));
+        if has_focus {
+            self.handle_completion_input(ui);
+            if !self.selections.is_empty() {
+                self.handle_shortcuts(ui);
+                self.handle_multi_cursor_input(ui);
+            } else {
+                // The keymap gets first pick so e.g. Emacs C-f isn't taken as Find
+                self.handle_keymap_input(ui);
+                self.handle_shortcuts(ui);
+            }
+        }
+        if let Some(text) = self.pending_copy.take() {
+            ui.ctx().copy_text(text);
+        }
+        if std::mem::take(&mut self.focus_requested) {
+            ui.memory_mut(|m| m.request_focus(text_edit_id));
+        }
+
+        let font_size = self.config.font_size;
+        let row_height = ui.fonts(|f| f.row_height(&egui::FontId::monospace(font_size)));
+        let highlights = self.highlights();
+        let block_cursor = self.keymap.as_ref().is_some_and(|k| k.block_cursor());
+        let alt_held = ui.input(|i| i.modifiers.alt);
+        let char_width = ui.fonts(|f| f.glyph_width(&egui::FontId::monospace(font_size), ' '));
+        let wants_anchor = has_focus && (self.completion_popup.is_some() || self.completion_requested);
+        let gutter = self.git.as_ref().map(|git| git.line_statuses()).unwrap_or_default();
+        let conflicts = self.conflicts.blocks().to_vec();
+        let mut conflict_action = None;
+        let left_margin = if self.git.is_some() { 10.0 } else { 4.0 };
+        let cursor_line = self.cursor_line();
+        let blame = match &mut self.git {
+            Some(git) if has_focus => git.blame(self.buffer.text(), cursor_line, self.buffer.version())
+                .map(|info| info.describe()),
+            _ => None,
+        };
+        if has_focus && self.git.is_some() && blame.is_none() {
+            // The blame lookup finishes in the background
+            ui.ctx().request_repaint_after(Duration::from_millis(200));
+        }
+        // Queued keys and text are about to move or edit the selection
+        let reveal = has_focus && ui.input(|i| i.events.iter().any(|event| matches!(event,
+            egui::Event::Text(_) | egui::Event::Paste(_) | egui::Event::Copy | egui::Event::Cut
+                | egui::Event::Ime(_) | egui::Event::Key { pressed: true, .. })));
+
+        // The minimap takes a column on the right of the text
+        let full_rect = ui.available_rect_before_wrap();
+        let minimap_width = if self.config.show_minimap { minimap::WIDTH } else { 0.0 };
+        let editor_rect = egui::Rect::from_min_max(full_rect.min, egui::pos2(full_rect.max.x - minimap_width, full_rect.max.y));
+        let mut editor_ui = ui.new_child(egui::UiBuilder::new().max_rect(editor_rect).layout(*ui.layout()));
+
+        // Only the lines around the view go to the text widget, below empty
+        // space as high as the lines above them
+        let mut scroll_area = egui::ScrollArea::both()
+            .id_salt(format!("editor_{}", self.id));
+        if let Some(offset) = self.scroll_request.take() {
+            scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
+        }
+        let scroll = scroll_area
+            .show_viewport(&mut editor_ui, |ui, viewport| {
+                // Every line, and the widget's margins
+                ui.set_min_height(self.buffer.line_count() as f32 * row_height + 4.0);
+                let visible = (viewport.top() / row_height).max(0.0) as usize..(viewport.bottom() / row_height).ceil().max(0.0) as usize;
+                let (lines, holds_selection) = self.window_lines(visible, reveal);
+                let first_line = lines.start;
+                let text = self.buffer.text();
+                let index = self.buffer.line_index();
+                let window = index.line_start(lines.start).unwrap_or(text.len())..index.line_start(lines.end).unwrap_or(text.len());
+
+                // The widget only has the selection while it's in the window
+                let (anchor, cursor) = self.displayed_selection.unwrap_or((self.selection_anchor, self.cursor_pos));
+                let mut state = egui::text_edit::TextEditState::load(ui.ctx(), text_edit_id).unwrap_or_default();
+                let range = holds_selection.then(|| CCursorRange::two(
+                    window_ccursor(text, &window, anchor).unwrap_or_default(),
+                    window_ccursor(text, &window, cursor).unwrap_or_default(),
+                ));
+                if state.cursor.char_range() != range {
+                    state.cursor.set_char_range(range);
+                    state.store(ui.ctx(), text_edit_id);
+                }
+
+                let mut backgrounds = window_ranges(&highlights, &window);
+                if !holds_selection && anchor != cursor {
+                    let selection = window_ranges(&[(anchor.min(cursor)..anchor.max(cursor), ui.visuals().selection.bg_fill)], &window);
+                    backgrounds.extend(selection);
+                    backgrounds.sort_by_key(|(range, _)| range.start);
+                }
+                let bracket_colors: Vec<_> = self.brackets.iter()
+                    .filter(|(range, _)| window.start <= range.start && range.end <= window.end)
+                    .map(|(range, depth)| (range.start - window.start..range.end - window.start, RAINBOW[depth % RAINBOW.len()]))
+                    .collect();
+                // Use monospace font for code
+                let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
+                    let layout_job = highlighted_layout_job(string, font_size, &backgrounds, &bracket_colors);
+                    ui.fonts(|f| f.layout_job(layout_job))
+                };
+
+                ui.add_space(first_line as f32 * row_height);
+                let mut text_window = BufferWindow::new(&mut self.buffer, window);
+                let output = ui.scope(|ui| {
+                    if !holds_selection {
+                        // Its caret would sit at the end of the window
+                        ui.visuals_mut().text_cursor.stroke = egui::Stroke::NONE;
+                    }
+                    egui::TextEdit::multiline(&mut text_window)
+                        .id(text_edit_id)
+                        .font(egui::TextStyle::Monospace)
+                        .code_editor()
+                        .desired_rows(30)
+                        .lock_focus(true)
+                        .desired_width(f32::INFINITY)
+                        .margin(egui::Margin { left: left_margin, right: 4.0, top: 2.0, bottom: 2.0 })
+                        .layouter(&mut layouter)
+                        .show(ui)
+                }).inner;
+                let window = text_window.range();
+                let row_at = |line: usize| line.checked_sub(first_line).and_then(|row| output.galley.rows.get(row));
+
+                // Git change markers in the left margin; staged changes are dimmed
+                for (line, status) in &gutter {
+                    let Some(row) = row_at(*line) else {
+                        continue;
+                    };
+                    let rect = row.rect.translate(output.galley_pos.to_vec2());
+                    let color = match status.kind {
+                        HunkKind::Added => egui::Color32::from_rgb(80, 170, 90),
+                        HunkKind::Modified => egui::Color32::from_rgb(70, 130, 200),
+                        HunkKind::Deleted => egui::Color32::from_rgb(200, 80, 80),
+                    };
+                    let color = if status.staged { color.gamma_multiply(0.4) } else { color };
+                    let x = output.galley_pos.x - 7.0;
+                    if status.kind == HunkKind::Deleted {
+                        ui.painter().hline(x..=x + 5.0, rect.top(), egui::Stroke::new(2.0, color));
+                    } else {
+                        let bar = egui::Rect::from_x_y_ranges(x..=x + 3.0, rect.y_range());
+                        ui.painter().rect_filled(bar, 0.0, color);
+                    }
+                }
+
+                // Conflict blocks, with their resolutions after the opening marker
+                for (index, conflict) in conflicts.iter().enumerate() {
+                    for (line, color) in merge::line_tints(conflict) {
+                        if let Some(row) = row_at(line) {
+                            let rect = row.rect.translate(output.galley_pos.to_vec2());
+                            let rect = egui::Rect::from_x_y_ranges(output.response.rect.x_range(), rect.y_range());
+                            ui.painter().rect_filled(rect, 0.0, color);
+                        }
+                    }
+                    let Some(row) = row_at(conflict.start_line) else {
+                        continue;
+                    };
+                    let pos = row.rect.right_top() + output.galley_pos.to_vec2() + egui::vec2(font_size * 2.0, 0.0);
+                    let rect = egui::Rect::from_min_size(pos, egui::vec2(f32::INFINITY, row.rect.height()));
+                    let mut actions = ui.new_child(egui::UiBuilder::new()
+                        .max_rect(rect)
+                        .layout(egui::Layout::left_to_right(egui::Align::Center)));
+                    for resolution in Resolution::ALL {
+                        if resolution == Resolution::Base && conflict.base.is_none() {
+                            continue;
+                        }
+                        if actions.small_button(resolution.label()).clicked() {
+                            conflict_action = Some((index, resolution));
+                        }
+                    }
+                }
+
+                // The cursor line's blame would sit under a conflict's actions
+                let blame = blame.filter(|_| !conflicts.iter().any(|c| c.start_line == cursor_line));
+                if let Some(row) = blame.as_ref().and_then(|_| row_at(cursor_line)) {
+                    let pos = row.rect.right_top() + output.galley_pos.to_vec2() + egui::vec2(font_size * 2.0, 0.0);
+                    ui.painter().text(
+                        pos,
+                        egui::Align2::LEFT_TOP,
+                        blame.as_deref().unwrap_or_default(),
+                        egui::FontId::monospace(font_size * 0.9),
+                        ui.visuals().weak_text_color(),
+                    );
+                }
+
+                // Without the selection the widget reports a caret of its own,
+                // which only counts once it's placed with the pointer
+                let placed = holds_selection || output.response.is_pointer_button_down_on();
+                if let Some(range) = output.cursor_range.filter(|_| placed) {
+                    let cursor = window_offset(self.buffer.text(), &window, range.primary.ccursor);
+                    let anchor = window_offset(self.buffer.text(), &window, range.secondary.ccursor);
+                    // Keep the keymap's logical selection unless the user moved it (mouse, ...)
+                    if self.displayed_selection != Some((anchor, cursor)) {
+                        self.cursor_pos = cursor;
+                        self.selection_anchor = anchor;
+                        self.displayed_selection = None;
+                        if let Some(primary) = self.selections.last_mut() {
+                            *primary = Selection::new(anchor, cursor);
+                        }
+                    }
+                }
+
+                let caret = window_ccursor(self.buffer.text(), &window, self.cursor_pos);
+                if let Some(ccursor) = caret.filter(|_| wants_anchor) {
+                    let caret = output.galley.pos_from_ccursor(ccursor).translate(output.galley_pos.to_vec2());
+                    self.completion_anchor = Some(caret.left_bottom());
+                }
+
+                if let Some(ccursor) = caret.filter(|_| block_cursor) {
+                    let caret = output.galley.pos_from_ccursor(ccursor);
+                    let next = output.galley.pos_from_ccursor(ccursor + 1);
+                    let width = if next.min.y == caret.min.y && next.min.x > caret.min.x {
+                        next.min.x - caret.min.x
+                    } else {
+                        font_size * 0.6
+                    };
+                    let rect = egui::Rect::from_min_size(caret.min, egui::vec2(width, caret.height()))
+                        .translate(output.galley_pos.to_vec2());
+                    ui.painter().rect_filled(rect, 0.0, egui::Color32::from_rgba_unmultiplied(200, 200, 255, 90));
+                }
+                if output.response.clicked() || output.response.drag_started() {
+                    self.selections.clear();
+                }
+
+                // Alt+click adds or removes a cursor and Alt+drag selects a
+                // block, on a layer above the text so the widget doesn't also
+                // take the click
+                if alt_held || self.block_origin.is_some() {
+                    let block = ui.interact(output.response.rect, text_edit_id.with("block"), egui::Sense::click_and_drag())
+                        .on_hover_cursor(egui::CursorIcon::Crosshair);
+                    let galley = &output.galley;
+                    let pointer = block.interact_pointer_pos().map(|pos| pos - output.galley_pos);
+                    let position_at = |at: egui::Vec2| TextPosition {
+                        line: first_line + galley.cursor_from_pos(at).rcursor.row,
+                        column: (at.x / char_width).round().max(0.0) as usize,
+                    };
+                    if block.drag_started() {
+                        self.block_origin = pointer;
+                    }
+                    if let (Some(origin), Some(pointer)) = (self.block_origin, pointer.filter(|_| block.dragged())) {
+                        self.select_block(position_at(origin), position_at(pointer));
+                    }
+                    if block.drag_stopped() {
+                        self.block_origin = None;
+                    }
+                    if let Some(pointer) = pointer.filter(|_| block.clicked()) {
+                        let offset = window_offset(self.buffer.text(), &window, galley.cursor_from_pos(pointer).ccursor);
+                        self.toggle_cursor(offset);
+                    }
+                    if block.clicked() || block.dragged() {
+                        self.focus_requested = true;
+                        ui.ctx().request_repaint();
+                    }
+                }
+
+                // The widget only draws the primary cursor
+                for selection in &self.selections {
+                    let Some(ccursor) = window_ccursor(self.buffer.text(), &window, selection.cursor) else {
+                        continue;
+                    };
+                    let caret = output.galley.pos_from_ccursor(ccursor)
+                        .translate(output.galley_pos.to_vec2());
+                    ui.painter().vline(
+                        caret.min.x,
+                        caret.y_range(),
+                        egui::Stroke::new(1.5, egui::Color32::from_rgb(200, 200, 255)),
+                    );
+                }
+
+                // Where the first line would be
+                output.galley_pos - egui::vec2(0.0, first_line as f32 * row_height)
+            });
+        ui.advance_cursor_after_rect(egui::Rect::from_min_max(
+            full_rect.min,
+            egui::pos2(full_rect.max.x, editor_ui.min_rect().max.y),
+        ));
+
+        let text_origin = scroll.inner;
+        let viewport = scroll.inner_rect;
+        // Distance from the top of the scrolled content to the first row
+        let content_margin = text_origin.y - (viewport.top() - scroll.state.offset.y);
+        let first_line = ((scroll.state.offset.y - content_margin) / row_height).max(0.0);
+        self.first_visible_line = first_line;
+        self.scroll_metrics = (content_margin, row_height);
+        if self.config.sticky_scroll {
+            self.show_sticky_headers(ui, viewport, first_line as usize, row_height, text_origin.x);
+        }
+        if self.config.show_minimap {
+            let rect = egui::Rect::from_x_y_ranges(viewport.right()..=full_rect.right(), viewport.y_range());
+            let shown = Viewport { first_line, visible_lines: viewport.height() / row_height };
+            let text = self.buffer.text();
+            if let Some(line) = self.minimap.show(ui, rect, text, &self.tokens, self.buffer.version(), shown) {
+                self.scroll_request = Some(content_margin + line * row_height - viewport.height() / 2.0);
+            }
+        }
+
+        if let Some((index, resolution)) = conflict_action {
+            if let Err(e) = self.resolve_conflict(Some(index), resolution) {
+                log::warn!("Failed to resolve conflict: {}", e);
+            }
+            self.focus_requested = true;
+        }
+
+        if has_focus {
+            self.update_completion();
+        } else {
+            self.completion_popup = None;
+        }
+        if let (Some(popup), Some(anchor)) = (&mut self.completion_popup, self.completion_anchor) {
+            match popup.show(ui.ctx(), egui::Id::new(("completion", self.id)), anchor) {
+                Some(CompletionAction::Accept(index)) => {
+                    self.accept_completion(index);
+                    self.focus_requested = true;
+                }
+                Some(CompletionAction::Close) => self.completion_popup = None,
+                None => {}
+            }
+            // Keep polling for results from the language server
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+
+        // Status line
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("Lines: {} | Chars: {}", self.buffer.line_count(), self.buffer.text().len()));
+            if !self.selections.is_empty() {
+                ui.separator();
+                ui.label(format!("{} cursors", self.selections.len()));
+            }
+        });
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn take_palette_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.palette_commands)
+    }
+
+    fn document_symbols(&self) -> Vec<DocumentSymbol> {
+        self.outline.clone()
+    }
+
+    fn completion_words(&self) -> Vec<String> {
+        completion::buffer_words(self.buffer.text())
+    }
+
+    fn take_workspace_words_request(&mut self) -> bool {
+        std::mem::take(&mut self.wants_workspace_words)
+    }
+
+    fn set_workspace_words(&mut self, words: Vec<String>) {
+        self.workspace_words = words;
+        self.completion_requested |= self.completion_popup.is_some();
+    }
+
+    fn take_spawned_actors(&mut self) -> Vec<Box<dyn Actor>> {
+        std::mem::take(&mut self.spawned)
+    }
+
+    fn is_modified(&self) -> bool {
+        self.buffer.is_modified()
+    }
+}
+
+/// Search flags shared by the find/replace API methods
+/// Byte offset of a line/column position, clamped to the text
+fn offset_of(text: &str, position: TextPosition) -> usize {
+    keymap::offset_at_column(text, keymap::line_at(text, position.line), position.column)
+}
+
+fn search_options(params: &ApiParams) -> SearchOptions {
+    SearchOptions {
+        regex: params.get_optional("regex").unwrap_or(false),
+        case_sensitive: params.get_optional("case_sensitive").unwrap_or(false),
+        whole_word: params.get_optional("whole_word").unwrap_or(false),
+    }
+}
+
+impl ActorAPI for CodeEditorActor {
+    fn actor_type(&self) -> String {
+        "CodeEditorActor".to_string()
+    }
+
+    fn get_api_methods(&self) -> Vec<ApiMethod> {
+        vec![
+            ApiMethod {
+                name: "get_content".to_string(),
+                description: "Get the current content of the editor".to_string(),
+                parameters: vec![],
+                return_type: "string".to_string(),
+                category: "content".to_string(),
+            },
+            ApiMethod {
+                name: "set_content".to_string(),
+                description: "Set the content of the editor, keeping each cursor at its line and column".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "content".to_string(),
+                        param_type: "string".to_string(),
+                        description: "The new content for the editor".to_string(),
+                        required: true,
+                        default_value: None,
+                    }
+                ],
+                return_type: "void".to_string(),
+                category: "content".to_string(),
+            },
+            ApiMethod {
+                name: "get_language".to_string(),
+                description: "Get the current programming language".to_string(),
+                parameters: vec![],
+                return_type: "string".to_string(),
+                category: "language".to_string(),
+            },
+            ApiMethod {
+                name: "set_language".to_string(),
+                description: "Set the programming language for syntax highlighting".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "language".to_string(),
+                        param_type: "string".to_string(),
+                        description: "The programming language (e.g., 'rust', 'javascript', 'python')".to_string(),
+                        required: true,
+                        default_value: None,
+                    }
+                ],
+                return_type: "void".to_string(),
+                category: "language".to_string(),
+            },
+            ApiMethod {
+                name: "format".to_string(),
+                description: "Format the code in the editor".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "undo".to_string(),
+                description: "Undo the last edit".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "redo".to_string(),
+                description: "Redo the last undone edit".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "save".to_string(),
+                description: "Save the current content to file".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Optional file path (uses the current file if not provided)".to_string(),
+                        required: false,
+                        default_value: None,
+                    }
+                ],
+                return_type: "void".to_string(),
+                category: "file".to_string(),
+            },
+            ApiMethod {
+                name: "find".to_string(),
+                description: "Find matches in the editor, returned as line/column ranges".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Text or pattern to search for".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "case_sensitive".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Whether search should be case sensitive".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "regex".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Treat the query as a regular expression".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "whole_word".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Only match whole words".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "in_selection".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Only match inside the selections".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                ],
+                return_type: "array".to_string(),
+                category: "search".to_string(),
+            },
+            ApiMethod {
+                name: "replace".to_string(),
+                description: "Replace all matches, returning the ranges of the replacements".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Text or pattern to search for".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "replacement".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Replacement text ($1 or ${name} refer to capture groups in regex mode)".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "case_sensitive".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Whether search should be case sensitive".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "regex".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Treat the query as a regular expression".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "whole_word".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Only match whole words".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "preserve_case".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Follow the casing of each replaced match".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                ],
+                return_type: "array".to_string(),
+                category: "search".to_string(),
+            },
+            ApiMethod {
+                name: "select_all_occurrences".to_string(),
+                description: "Place a cursor on every match".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "query".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Text or pattern to search for".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "case_sensitive".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Whether search should be case sensitive".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "regex".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Treat the query as a regular expression".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "whole_word".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Only match whole words".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                    ApiParameter {
+                        name: "in_selection".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Only match inside the selections".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                ],
+                return_type: "array".to_string(),
+                category: "search".to_string(),
+            },
+            ApiMethod {
+                name: "get_selections".to_string(),
+                description: "Get every cursor's selection as line/column positions, primary last".to_string(),
+                parameters: vec![],
+                return_type: "array".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "set_selections".to_string(),
+                description: "Set the cursors, each as an anchor and a cursor position (the last one is primary)".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "selections".to_string(),
+                        param_type: "array".to_string(),
+                        description: "Objects like {\"anchor\": {\"line\": 0, \"column\": 0}, \"cursor\": {\"line\": 0, \"column\": 4}}".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                ],
+                return_type: "void".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "add_cursor".to_string(),
+                description: "Add a cursor at a position, or remove the one there".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "line".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based line".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "column".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based column in characters".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::json!(0)),
+                    },
+                ],
+                return_type: "number".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "add_cursor_above".to_string(),
+                description: "Add a cursor on the line above the cursors (Alt+Shift+Up)".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "add_cursor_below".to_string(),
+                description: "Add a cursor on the line below the cursors (Alt+Shift+Down)".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "select_block".to_string(),
+                description: "Column selection between two positions, one cursor per line (Alt+drag)".to_string(),
+                parameters: ["start_line", "start_column", "end_line", "end_column"].iter()
+                    .map(|name| ApiParameter {
+                        name: name.to_string(),
+                        param_type: "number".to_string(),
+                        description: format!("Zero-based {}", name.replace('_', " ")),
+                        required: true,
+                        default_value: None,
+                    })
+                    .collect(),
+                return_type: "number".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "clear_extra_cursors".to_string(),
+                description: "Leave multi-cursor mode, keeping the primary cursor".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "set_keymap".to_string(),
+                description: "Switch the key binding layer".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "keymap".to_string(),
+                        param_type: "string".to_string(),
+                        description: "'default', 'vim' or 'emacs'".to_string(),
+                        required: true,
+                        default_value: None,
+                    }
+                ],
+                return_type: "void".to_string(),
+                category: "editing".to_string(),
             },
             ApiMethod {
-                name: "set_content".to_string(),
-                description: "Set the content of the editor".to_string(),
+                name: "send_keys".to_string(),
+                description: "Feed a key sequence to the active keymap, e.g. 'd2w' or 'ihello<Esc>'".to_string(),
                 parameters: vec![
                     ApiParameter {
-                        name: "content".to_string(),
+                        name: "keys".to_string(),
                         param_type: "string".to_string(),
-                        description: "The new content for the editor".to_string(),
+                        description: "Keys in Vim notation (<Esc>, <CR>, <C-x>, <M-f>, ...)".to_string(),
                         required: true,
                         default_value: None,
                     }
                 ],
-                return_type: "void".to_string(),
-                category: "content".to_string(),
+                return_type: "object".to_string(),
+                category: "editing".to_string(),
             },
             ApiMethod {
-                name: "get_language".to_string(),
-                description: "Get the current programming language".to_string(),
+                name: "expand_selection".to_string(),
+                description: "Grow the selection to the enclosing syntax node".to_string(),
                 parameters: vec![],
-                return_type: "string".to_string(),
-                category: "language".to_string(),
+                return_type: "object".to_string(),
+                category: "selection".to_string(),
             },
             ApiMethod {
-                name: "set_language".to_string(),
-                description: "Set the programming language for syntax highlighting".to_string(),
+                name: "shrink_selection".to_string(),
+                description: "Shrink the selection back to what it was before the last expand".to_string(),
+                parameters: vec![],
+                return_type: "object".to_string(),
+                category: "selection".to_string(),
+            },
+            ApiMethod {
+                name: "jump_to_matching_bracket".to_string(),
+                description: "Move the cursor to the bracket matching the one next to it".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "navigation".to_string(),
+            },
+            ApiMethod {
+                name: "document_symbols".to_string(),
+                description: "Get the document outline (functions, types, ...)".to_string(),
+                parameters: vec![],
+                return_type: "array".to_string(),
+                category: "navigation".to_string(),
+            },
+            ApiMethod {
+                name: "go_to_position".to_string(),
+                description: "Move the cursor to a line and column".to_string(),
                 parameters: vec![
                     ApiParameter {
-                        name: "language".to_string(),
-                        param_type: "string".to_string(),
-                        description: "The programming language (e.g., 'rust', 'javascript', 'python')".to_string(),
+                        name: "line".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based line".to_string(),
                         required: true,
                         default_value: None,
-                    }
+                    },
+                    ApiParameter {
+                        name: "column".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based column in characters".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::json!(0)),
+                    },
                 ],
                 return_type: "void".to_string(),
-                category: "language".to_string(),
+                category: "navigation".to_string(),
             },
             ApiMethod {
-                name: "format".to_string(),
-                description: "Format the code in the editor".to_string(),
+                name: "complete".to_string(),
+                description: "Open the completion popup at the cursor and list its items, best first".to_string(),
                 parameters: vec![],
-                return_type: "void".to_string(),
+                return_type: "array".to_string(),
                 category: "editing".to_string(),
             },
             ApiMethod {
-                name: "save".to_string(),
-                description: "Save the current content to file".to_string(),
+                name: "accept_completion".to_string(),
+                description: "Insert an item from the open completion popup".to_string(),
                 parameters: vec![
                     ApiParameter {
-                        name: "path".to_string(),
+                        name: "index".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Position in the list returned by complete".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::json!(0)),
+                    },
+                ],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "insert_snippet".to_string(),
+                description: "Replace the selection with a snippet and select its first tab stop".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "body".to_string(),
                         param_type: "string".to_string(),
-                        description: "Optional file path (uses current name if not provided)".to_string(),
+                        description: "Snippet in TextMate/VSCode syntax ($1, ${2:default}, $0)".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                ],
+                return_type: "object".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "next_tab_stop".to_string(),
+                description: "Move to the next tab stop of the snippet being filled in".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "previous_tab_stop".to_string(),
+                description: "Move to the previous tab stop of the snippet being filled in".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "git_hunks".to_string(),
+                description: "List the file's unstaged and staged git hunks".to_string(),
+                parameters: vec![],
+                return_type: "object".to_string(),
+                category: "git".to_string(),
+            },
+            ApiMethod {
+                name: "stage_hunk".to_string(),
+                description: "Stage the git hunk at the cursor".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "line".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based line (defaults to the cursor's)".to_string(),
                         required: false,
                         default_value: None,
-                    }
+                    },
                 ],
-                return_type: "void".to_string(),
-                category: "file".to_string(),
+                return_type: "boolean".to_string(),
+                category: "git".to_string(),
             },
             ApiMethod {
-                name: "find".to_string(),
-                description: "Find text in the editor".to_string(),
+                name: "unstage_hunk".to_string(),
+                description: "Unstage the git hunk at the cursor".to_string(),
                 parameters: vec![
                     ApiParameter {
-                        name: "query".to_string(),
+                        name: "line".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based line (defaults to the cursor's)".to_string(),
+                        required: false,
+                        default_value: None,
+                    },
+                ],
+                return_type: "boolean".to_string(),
+                category: "git".to_string(),
+            },
+            ApiMethod {
+                name: "revert_hunk".to_string(),
+                description: "Revert the unstaged git hunk at the cursor".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "line".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based line (defaults to the cursor's)".to_string(),
+                        required: false,
+                        default_value: None,
+                    },
+                ],
+                return_type: "boolean".to_string(),
+                category: "git".to_string(),
+            },
+            ApiMethod {
+                name: "blame_line".to_string(),
+                description: "Show who last changed the line".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "line".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Zero-based line (defaults to the cursor's)".to_string(),
+                        required: false,
+                        default_value: None,
+                    },
+                ],
+                return_type: "object".to_string(),
+                category: "git".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_minimap".to_string(),
+                description: "Show or hide the minimap".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "display".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_sticky_scroll".to_string(),
+                description: "Pin or unpin the enclosing declarations while scrolling".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "display".to_string(),
+            },
+            ApiMethod {
+                name: "list_conflicts".to_string(),
+                description: "List the merge conflict blocks in the buffer".to_string(),
+                parameters: vec![],
+                return_type: "array".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "resolve_conflict".to_string(),
+                description: "Replace a merge conflict block with one of its versions".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "resolution".to_string(),
                         param_type: "string".to_string(),
-                        description: "Text to search for".to_string(),
+                        description: "ours, theirs, both or base".to_string(),
                         required: true,
                         default_value: None,
                     },
                     ApiParameter {
-                        name: "case_sensitive".to_string(),
-                        param_type: "boolean".to_string(),
-                        description: "Whether search should be case sensitive".to_string(),
+                        name: "index".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Conflict to resolve (defaults to the one at the cursor)".to_string(),
                         required: false,
-                        default_value: Some(serde_json::Value::Bool(false)),
-                    }
+                        default_value: None,
+                    },
                 ],
-                return_type: "array".to_string(),
-                category: "search".to_string(),
+                return_type: "void".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "accept_ours".to_string(),
+                description: "Keep our version of the conflict at the cursor".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "accept_theirs".to_string(),
+                description: "Keep their version of the conflict at the cursor".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "accept_both".to_string(),
+                description: "Keep both versions of the conflict at the cursor".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "accept_base".to_string(),
+                description: "Keep the common ancestor of the conflict at the cursor".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "next_conflict".to_string(),
+                description: "Move to the next merge conflict".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "previous_conflict".to_string(),
+                description: "Move to the previous merge conflict".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_merge_view".to_string(),
+                description: "Show or hide the three-pane merge view".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "merge".to_string(),
+            },
+            ApiMethod {
+                name: "compare_with_head".to_string(),
+                description: "Open a diff of the file at a git revision against the buffer".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "revision".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Revision to compare with".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::String("HEAD".to_string())),
+                    },
+                ],
+                return_type: "void".to_string(),
+                category: "git".to_string(),
+            },
+            ApiMethod {
+                name: "compare_with_saved".to_string(),
+                description: "Open a diff of the file on disk against the buffer".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "file".to_string(),
             },
             ApiMethod {
                 name: "get_stats".to_string(),
@@ -254,13 +2943,36 @@ impl ActorAPI for CodeEditorActor {
     }
 
     fn execute_api_method(&mut self, method: &str, params: ApiParams) -> Result<ApiResult> {
+        if let Some(large_file) = &mut self.large_file {
+            return match method {
+                "get_content" => Ok(ApiResult::Value(serde_json::Value::String(large_file.text().into_owned()))),
+                "get_language" => Ok(ApiResult::Value(serde_json::Value::String(self.language.clone()))),
+                "go_to_position" => {
+                    large_file.go_to_line(params.get("line")?);
+                    Ok(ApiResult::Success)
+                },
+                "get_stats" => Ok(ApiResult::Value(serde_json::json!({
+                    "lines": large_file.line_count(),
+                    "characters": large_file.len(),
+                    "words": null,
+                    "language": self.language,
+                    "name": self.name,
+                    "is_focused": self.is_focused,
+                    "large_file": true,
+                }))),
+                _ if self.can_handle_method(method) => {
+                    Err(anyhow!("{} is open read-only in large-file mode; '{}' is unavailable", self.name, method))
+                }
+                _ => Err(anyhow!("Unknown method: {}", method)),
+            };
+        }
         match method {
             "get_content" => {
-                Ok(ApiResult::Value(serde_json::Value::String(self.content.clone())))
+                Ok(ApiResult::Value(serde_json::Value::String(self.buffer.text().to_string())))
             },
             "set_content" => {
                 let content: String = params.get("content")?;
-                self.content = content;
+                self.set_content(&content);
                 Ok(ApiResult::Success)
             },
             "get_language" => {
@@ -272,49 +2984,222 @@ impl ActorAPI for CodeEditorActor {
                 Ok(ApiResult::Success)
             },
             "format" => {
-                // Simple formatting - just normalize whitespace for now
-                self.content = self.content
-                    .lines()
-                    .map(|line| line.trim())
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                log::info!("Formatted code in {}", self.name);
+                self.format();
                 Ok(ApiResult::Success)
             },
+            "undo" => {
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.undo())))
+            },
+            "redo" => {
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.redo())))
+            },
             "save" => {
-                let _path: Option<String> = params.get_optional("path");
-                // In a real implementation, this would save to disk
-                log::info!("Saved {}", self.name);
+                let path: Option<String> = params.get_optional("path");
+                self.save(path.map(PathBuf::from))?;
                 Ok(ApiResult::Success)
             },
             "find" => {
                 let query: String = params.get("query")?;
-                let case_sensitive: bool = params.get_optional("case_sensitive").unwrap_or(false);
-
-                let content_to_search = if case_sensitive {
-                    self.content.clone()
-                } else {
-                    self.content.to_lowercase()
-                };
+                let in_selection = params.get_optional("in_selection").unwrap_or(false);
+                let ranges = self.find(&query, search_options(&params), in_selection)?;
+                Ok(ApiResult::Value(serde_json::to_value(ranges)?))
+            },
+            "replace" => {
+                let query: String = params.get("query")?;
+                let replacement: String = params.get("replacement")?;
+                let preserve_case: bool = params.get_optional("preserve_case").unwrap_or(false);
+                let ranges = self.replace_all(&query, &replacement, search_options(&params), preserve_case)?;
+                Ok(ApiResult::Value(serde_json::to_value(ranges)?))
+            },
+            "select_all_occurrences" => {
+                let query: String = params.get("query")?;
+                let in_selection = params.get_optional("in_selection").unwrap_or(false);
+                let ranges = self.select_all_occurrences(&query, search_options(&params), in_selection)?;
+                Ok(ApiResult::Value(serde_json::to_value(ranges)?))
+            },
+            "get_selections" => {
+                let text = self.buffer.text();
+                let index = self.buffer.line_index();
+                let selections: Vec<TextSelection> = self.cursors().iter().map(|selection| index.selection(text, selection)).collect();
+                Ok(ApiResult::Value(serde_json::to_value(selections)?))
+            },
+            "set_selections" => {
+                let selections: Vec<TextSelection> = params.get("selections")?;
+                let text = self.buffer.text();
+                let selections = selections.iter()
+                    .map(|selection| Selection::new(offset_of(text, selection.anchor), offset_of(text, selection.cursor)))
+                    .collect();
+                self.selections.clear();
+                self.set_selections(selections);
+                Ok(ApiResult::Success)
+            },
+            "add_cursor" => {
+                let position = TextPosition { line: params.get("line")?, column: params.get_optional("column").unwrap_or(0) };
+                self.toggle_cursor(offset_of(self.buffer.text(), position));
+                Ok(ApiResult::Value(serde_json::json!(self.cursors().len())))
+            },
+            "add_cursor_above" | "add_cursor_below" => {
+                let added = self.add_cursor_vertically(method == "add_cursor_above");
+                Ok(ApiResult::Value(serde_json::Value::Bool(added)))
+            },
+            "select_block" => {
+                let anchor = TextPosition { line: params.get("start_line")?, column: params.get("start_column")? };
+                let cursor = TextPosition { line: params.get("end_line")?, column: params.get("end_column")? };
+                self.select_block(anchor, cursor);
+                Ok(ApiResult::Value(serde_json::json!(self.cursors().len())))
+            },
+            "clear_extra_cursors" => {
+                self.selections.clear();
+                Ok(ApiResult::Success)
+            },
+            "set_keymap" => {
+                let name: String = params.get("keymap")?;
+                self.set_keymap(KeymapKind::parse(&name)?);
+                Ok(ApiResult::Success)
+            },
+            "send_keys" => {
+                let keys: String = params.get("keys")?;
+                if self.keymap.is_none() {
+                    return Err(anyhow!("No keymap is active; call set_keymap first"));
+                }
+                self.feed_keys(keymap::parse_keys(&keys)?);
 
-                let query_to_search = if case_sensitive {
-                    query
+                let text = self.buffer.text();
+                let index = self.buffer.line_index();
+                Ok(ApiResult::Value(serde_json::json!({
+                    "cursor": index.position(text, self.cursor_pos),
+                    "selection": index.range(text, &self.selected_range()),
+                    "status": self.keymap.as_ref().map(|k| k.status()),
+                })))
+            },
+            "expand_selection" | "shrink_selection" => {
+                if method == "expand_selection" {
+                    self.expand_selection();
                 } else {
-                    query.to_lowercase()
+                    self.shrink_selection();
+                }
+                let text = self.buffer.text();
+                let range = self.buffer.line_index().range(text, &self.selected_range());
+                Ok(ApiResult::Value(serde_json::to_value(range)?))
+            },
+            "jump_to_matching_bracket" => {
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.jump_to_matching_bracket())))
+            },
+            "document_symbols" => {
+                self.sync_changes();
+                Ok(ApiResult::Value(serde_json::to_value(self.document_symbols())?))
+            },
+            "go_to_position" => {
+                let line: usize = params.get("line")?;
+                let column: usize = params.get_optional("column").unwrap_or(0);
+                self.go_to_position(line, column);
+                Ok(ApiResult::Success)
+            },
+            "complete" => {
+                self.completion_popup = None;
+                self.refresh_completion(true);
+                let items = self.completion_popup.as_ref().map(|p| p.items.as_slice()).unwrap_or_default();
+                Ok(ApiResult::Value(serde_json::to_value(items)?))
+            },
+            "accept_completion" => {
+                let index: usize = params.get_optional("index").unwrap_or(0);
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.accept_completion(index))))
+            },
+            "insert_snippet" => {
+                let body: String = params.get("body")?;
+                self.insert_snippet(self.selected_range(), &body);
+                let text = self.buffer.text();
+                let range = self.buffer.line_index().range(text, &self.selected_range());
+                Ok(ApiResult::Value(serde_json::to_value(range)?))
+            },
+            "next_tab_stop" | "previous_tab_stop" => {
+                let moved = self.jump_tab_stop(method == "next_tab_stop");
+                Ok(ApiResult::Value(serde_json::Value::Bool(moved)))
+            },
+            "git_hunks" => {
+                let git = self.git.as_mut().ok_or_else(|| anyhow!("{} is not in a git repository", self.name))?;
+                git.refresh(self.buffer.text(), self.buffer.version());
+                Ok(ApiResult::Value(serde_json::json!({
+                    "unstaged": git.unstaged_hunks(),
+                    "staged": git.staged_hunks(),
+                })))
+            },
+            "stage_hunk" => {
+                let staged = self.stage_hunk(params.get_optional("line"))?;
+                Ok(ApiResult::Value(serde_json::Value::Bool(staged)))
+            },
+            "unstage_hunk" => {
+                let unstaged = self.unstage_hunk(params.get_optional("line"))?;
+                Ok(ApiResult::Value(serde_json::Value::Bool(unstaged)))
+            },
+            "revert_hunk" => {
+                let reverted = self.revert_hunk(params.get_optional("line"))?;
+                Ok(ApiResult::Value(serde_json::Value::Bool(reverted)))
+            },
+            "blame_line" => {
+                let line = params.get_optional("line").unwrap_or_else(|| self.cursor_line());
+                let git = self.git.as_ref().ok_or_else(|| anyhow!("{} is not in a git repository", self.name))?;
+                Ok(ApiResult::Value(serde_json::to_value(git.blame_now(self.buffer.text(), line)?)?))
+            },
+            "toggle_minimap" => {
+                self.config.show_minimap = !self.config.show_minimap;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.config.show_minimap)))
+            },
+            "toggle_sticky_scroll" => {
+                self.config.sticky_scroll = !self.config.sticky_scroll;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.config.sticky_scroll)))
+            },
+            "list_conflicts" => {
+                self.sync_changes();
+                Ok(ApiResult::Value(serde_json::to_value(self.conflicts.blocks())?))
+            },
+            "resolve_conflict" => {
+                let resolution: Resolution = params.get("resolution")?;
+                self.resolve_conflict(params.get_optional("index"), resolution)?;
+                Ok(ApiResult::Success)
+            },
+            "accept_ours" | "accept_theirs" | "accept_both" | "accept_base" => {
+                let resolution = match method {
+                    "accept_ours" => Resolution::Ours,
+                    "accept_theirs" => Resolution::Theirs,
+                    "accept_both" => Resolution::Both,
+                    _ => Resolution::Base,
                 };
-
-                let matches: Vec<_> = content_to_search
-                    .match_indices(&query_to_search)
-                    .map(|(pos, _)| pos)
-                    .collect();
-
-                Ok(ApiResult::Value(serde_json::to_value(matches)?))
+                self.resolve_conflict(None, resolution)?;
+                Ok(ApiResult::Success)
+            },
+            "next_conflict" | "previous_conflict" => {
+                let moved = self.jump_conflict(method == "next_conflict");
+                Ok(ApiResult::Value(serde_json::Value::Bool(moved)))
+            },
+            "toggle_merge_view" => {
+                self.merge_view = !self.merge_view;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.merge_view)))
+            },
+            "compare_with_head" => {
+                let revision: Option<String> = params.get_optional("revision");
+                self.compare_with_revision(revision.as_deref())?;
+                Ok(ApiResult::Success)
+            },
+            "compare_with_saved" => {
+                self.compare_with_saved()?;
+                Ok(ApiResult::Success)
             },
             "get_stats" => {
+                // Lines and length are kept by the buffer; words are counted once per edit
+                let version = self.buffer.version();
+                let words = match self.word_count {
+                    Some((counted, words)) if counted == version => words,
+                    _ => {
+                        let words = self.buffer.text().split_whitespace().count();
+                        self.word_count = Some((version, words));
+                        words
+                    }
+                };
                 let stats = serde_json::json!({
-                    "lines": self.content.lines().count(),
-                    "characters": self.content.len(),
-                    "words": self.content.split_whitespace().count(),
+                    "lines": self.buffer.line_count(),
+                    "characters": self.buffer.text().len(),
+                    "words": words,
                     "language": self.language,
                     "name": self.name,
                     "is_focused": self.is_focused
@@ -337,10 +3222,164 @@ impl ActorAPI for CodeEditorActor {
 
     fn get_state(&self) -> HashMap<String, serde_json::Value> {
         let mut state = HashMap::new();
-        state.insert("content_length".to_string(), serde_json::Value::Number(serde_json::Number::from(self.content.len())));
+        state.insert("content_length".to_string(), serde_json::Value::Number(serde_json::Number::from(self.buffer.text().len())));
         state.insert("language".to_string(), serde_json::Value::String(self.language.clone()));
         state.insert("is_focused".to_string(), serde_json::Value::Bool(self.is_focused));
-        state.insert("line_count".to_string(), serde_json::Value::Number(serde_json::Number::from(self.content.lines().count())));
+        state.insert("line_count".to_string(), serde_json::Value::Number(serde_json::Number::from(self.buffer.line_count())));
+        state.insert("is_modified".to_string(), serde_json::Value::Bool(self.buffer.is_modified()));
+        state.insert("file_path".to_string(), serde_json::json!(self.file_path));
+        state.insert("conflict_count".to_string(), serde_json::json!(self.conflicts.blocks().len()));
+        state.insert("cursor_count".to_string(), serde_json::json!(self.cursors().len()));
+        let index = self.buffer.line_index();
+        let selections: Vec<TextSelection> = self.cursors().iter()
+            .map(|selection| index.selection(self.buffer.text(), selection))
+            .collect();
+        state.insert("selections".to_string(), serde_json::json!(selections));
         state
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_follow_the_extension() {
+        let cases = [
+            ("main.rs", "rust"), ("tool.py", "python"), ("app.jsx", "javascript"),
+            ("app.tsx", "typescript"), ("main.go", "go"), ("lib.h", "c"),
+            ("lib.cpp", "cpp"), ("package.json", "json"), ("site.css", "css"),
+            ("index.htm", "html"), ("ci.yml", "yaml"), ("README.md", "markdown"),
+            ("notes.txt", "text"), ("Makefile", "text"),
+        ];
+        for (file, language) in cases {
+            assert_eq!(language_for_path(Path::new(file)), language, "{}", file);
+        }
+    }
+
+    #[test]
+    fn only_dirty_buffers_keep_their_swap_file_when_dropped() {
+        let dir = std::env::temp_dir().join(format!("zellij-ide-swap-{}", Uuid::new_v4()));
+        let editor = |name: &str| {
+            let mut editor = CodeEditorActor::with_content(name.to_string(), "fn main() {}\n".to_string());
+            editor.swap_store = Some(SwapStore::at(dir.clone()));
+            editor.buffer.set_text("fn main() { todo!() }\n");
+            editor
+        };
+
+        let dirty = editor("dirty.rs");
+        let mut clean = editor("clean.rs");
+        clean.write_swap_file();
+        clean.buffer.mark_saved();
+        assert_eq!(SwapStore::at(dir.clone()).load_all().len(), 1);
+        drop(dirty);
+        drop(clean);
+
+        let left: Vec<String> = SwapStore::at(dir.clone()).load_all().into_iter().map(|(_, swap)| swap.name).collect();
+        assert_eq!(left, ["dirty.rs"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_text_widget_gets_the_lines_around_the_view() {
+        let text: String = (0..10_000).map(|i| format!("line {}\n", i)).collect();
+        let mut editor = CodeEditorActor::with_content("long.txt".to_string(), text);
+        let line_start = |editor: &CodeEditorActor, line| editor.buffer.line_index().line_start(line).unwrap();
+
+        // A caret far above the view stays out unless input is about to use it
+        assert_eq!(editor.window_lines(5000..5040, false), (5000 - WINDOW_MARGIN..5040 + WINDOW_MARGIN, false));
+        assert_eq!(editor.window_lines(5000..5040, true), (0..1 + WINDOW_MARGIN, true));
+
+        // One near the view stretches the window
+        editor.move_cursor(line_start(&editor, 4000));
+        assert_eq!(editor.window_lines(5000..5040, false), (4000 - WINDOW_MARGIN..5040 + WINDOW_MARGIN, true));
+
+        // The window is clamped to the text
+        editor.move_cursor(editor.buffer.text().len());
+        assert_eq!(editor.window_lines(9990..10_040, false), (9970..10_001, true));
+    }
+
+    #[test]
+    fn edits_through_the_window_reach_the_buffer() {
+        use egui::TextBuffer;
+
+        let mut buffer = EditorBuffer::new("one\ntwo\nthree\n".to_string());
+        let mut window = BufferWindow::new(&mut buffer, 4..8);
+        assert_eq!(window.as_str(), "two\n");
+        window.insert_text("é", 1);
+        window.delete_char_range(2..4);
+        assert_eq!(window.as_str(), "té\n");
+        assert_eq!(window.range(), 4..8);
+        assert_eq!(buffer.text(), "one\nté\nthree\n");
+
+        buffer.undo();
+        assert_eq!(buffer.text(), "one\ntwo\nthree\n");
+    }
+
+    fn run_frame(ctx: &egui::Context, editor: &mut CodeEditorActor, events: Vec<egui::Event>) {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0))),
+            events,
+            max_texture_side: Some(16_384),
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| editor.render(ui));
+        });
+    }
+
+    #[test]
+    fn typing_goes_to_the_caret_wherever_the_view_is() {
+        let text: String = (0..10_000).map(|i| format!("line {}\n", i)).collect();
+        let mut editor = CodeEditorActor::with_content("long.txt".to_string(), text);
+        editor.set_language("text");
+        let ctx = egui::Context::default();
+        editor.focus_requested = true;
+        run_frame(&ctx, &mut editor, Vec::new());
+
+        // Far below the view
+        let line_start = |editor: &CodeEditorActor, line| editor.buffer.line_index().line_start(line).unwrap();
+        editor.move_cursor(line_start(&editor, 5000));
+        run_frame(&ctx, &mut editor, vec![egui::Event::Text("a".to_string())]);
+        run_frame(&ctx, &mut editor, vec![egui::Event::Text("b".to_string())]);
+        assert!(editor.buffer.text()[line_start(&editor, 5000)..].starts_with("abline 5000"));
+
+        // Back at the top after scrolling there
+        editor.scroll_to_line(0.0);
+        run_frame(&ctx, &mut editor, Vec::new());
+        editor.move_cursor(line_start(&editor, 3) + 4);
+        run_frame(&ctx, &mut editor, vec![egui::Event::Text("!".to_string())]);
+        assert!(editor.buffer.text()[line_start(&editor, 3)..].starts_with("line! 3\n"));
+        assert_eq!(editor.cursor_pos, line_start(&editor, 3) + 5);
+    }
+
+    #[test]
+    fn tab_indents_the_way_the_line_is_indented() {
+        let mut editor = CodeEditorActor::with_content("notes.txt".to_string(), "a\n\tb\nc\n".to_string());
+        editor.set_language("text");
+        let spaces = " ".repeat(editor.config.tab_width);
+        let ctx = egui::Context::default();
+        editor.focus_requested = true;
+        run_frame(&ctx, &mut editor, Vec::new());
+        // The text widget locks Tab in from its first frame with focus
+        run_frame(&ctx, &mut editor, Vec::new());
+        let tab = || egui::Event::Key {
+            key: egui::Key::Tab,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        };
+
+        editor.move_cursor(0);
+        run_frame(&ctx, &mut editor, vec![tab()]);
+        assert_eq!(editor.buffer.text(), format!("{}a\n\tb\nc\n", spaces));
+        assert_eq!(editor.cursor_pos, spaces.len());
+
+        let line_start = |editor: &CodeEditorActor, line| editor.buffer.line_index().line_start(line).unwrap();
+        let carets = vec![Selection::caret(line_start(&editor, 1)), Selection::caret(line_start(&editor, 2))];
+        editor.set_selections(carets);
+        run_frame(&ctx, &mut editor, vec![tab()]);
+        assert_eq!(editor.buffer.text(), format!("{0}a\n\t\tb\n{0}c\n", spaces));
+    }
+}
@@ -141,7 +141,8 @@ headlessly for programmatic access, keybindings, and API integration.
 
 */
 
-use crate::actor::ActorManager;
+use crate::actor::{ActorManager, ApiParams};
+use crate::tiling_system::PaneDirection;
 use crate::view_system::ViewContainer;
 use crate::widgets::{Widget, WidgetContext, WidgetPosition};
 use egui::{self, Vec2};
@@ -189,6 +190,8 @@ pub enum CommandAction {
     Global(GlobalAction),
     /// Actor-specific action
     Actor(Uuid, String),
+    /// Actor API call with arguments
+    ActorCall(Uuid, String, ApiParams),
     /// Custom action with closure
     Custom(Arc<dyn Fn(&CommandContext) -> Result<()> + Send + Sync>),
 }
@@ -199,6 +202,7 @@ impl std::fmt::Debug for CommandAction {
             CommandAction::NoOp => write!(f, "NoOp"),
             CommandAction::Global(action) => write!(f, "Global({:?})", action),
             CommandAction::Actor(id, action) => write!(f, "Actor({}, {})", id, action),
+            CommandAction::ActorCall(id, method, params) => write!(f, "ActorCall({}, {}, {:?})", id, method, params.params),
             CommandAction::Custom(_) => write!(f, "Custom(<closure>)"),
         }
     }
@@ -221,6 +225,48 @@ pub enum GlobalAction {
     ShowCommandPalette,
     ExitApplication,
     ResetTransform,
+    /// Show every scene view as a card on one canvas, or the active one alone
+    ToggleCanvasMode,
+    /// Animate the canvas camera to fit the active view's card
+    FocusCard,
+    /// Animate the canvas camera to fit every card
+    FitCanvas,
+    ToggleSnapToGrid,
+    ToggleCanvasMinimap,
+    /// Bookmark the scene camera under a new name
+    SaveBookmark,
+    /// Move the scene camera's bookmark in a quick slot (from 0) to the
+    /// current zoom and pan, or add one if the slot is empty
+    SaveBookmarkSlot(usize),
+    /// Animate the scene camera to a bookmark, by slot
+    JumpToBookmark(usize),
+    /// Focus the tile next to the focused one
+    FocusPane(PaneDirection),
+    /// Exchange the focused tile with its neighbor
+    SwapPane(PaneDirection),
+    /// Move the focused tile into a new split beside its neighbor
+    MovePane(PaneDirection),
+    /// Move the divider nearest the focused tile
+    ResizePane(PaneDirection),
+    /// Show the focused tile alone, or restore the layout
+    TogglePaneZoom,
+    /// Show the next (`true`) or previous tab of the focused tile
+    CyclePaneTab(bool),
+    /// Show or hide the floating panes (opening a terminal in one if none)
+    ToggleFloatingPanes,
+    NewFloatingTerminal,
+    /// Move the active view's actor into a floating pane
+    FloatActiveView,
+    /// Move the focused floating pane's actor into the active view
+    EmbedFloatingPane,
+    /// Keep the focused floating pane above the others
+    TogglePinFloatingPane,
+    /// Write the current arrangement to the layout file
+    SaveLayout,
+    /// Write the open session out now
+    SaveSession,
+    /// Save the open session and restore (or start) the named one
+    SwitchSession(String),
 }
 
 /// Context provided to commands during execution
@@ -346,6 +392,15 @@ impl GlobalCommandProvider {
                 group: Some("view_operations".to_string()),
                 ..Default::default()
             },
+            Command {
+                id: "view.nested_system".to_string(),
+                title: "Switch to Nested Views".to_string(),
+                description: Some("Use nested splits and tabs with resizable borders".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::SwitchViewSystem("nested".to_string())),
+                group: Some("view_operations".to_string()),
+                ..Default::default()
+            },
             Command {
                 id: "palette.show".to_string(),
                 title: "Show Command Palette".to_string(),
@@ -365,7 +420,230 @@ impl GlobalCommandProvider {
                 group: Some("transform_operations".to_string()),
                 ..Default::default()
             },
+            Command {
+                id: "canvas.toggle".to_string(),
+                title: "Toggle Canvas Mode".to_string(),
+                description: Some("Show every scene view as a card on one zoomable canvas".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::ToggleCanvasMode),
+                group: Some("transform_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "canvas.focus_card".to_string(),
+                title: "Focus Card".to_string(),
+                description: Some("Zoom and pan the canvas to fit the active view's card".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::FocusCard),
+                group: Some("transform_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "canvas.fit".to_string(),
+                title: "Fit Canvas".to_string(),
+                description: Some("Zoom and pan the canvas to fit every card".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::FitCanvas),
+                group: Some("transform_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "canvas.snap_to_grid".to_string(),
+                title: "Toggle Snap to Grid".to_string(),
+                description: Some("Round moved and resized cards to the canvas grid".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::ToggleSnapToGrid),
+                group: Some("transform_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "canvas.minimap".to_string(),
+                title: "Toggle Canvas Minimap".to_string(),
+                description: Some("Show or hide the canvas overview".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::ToggleCanvasMinimap),
+                group: Some("transform_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "bookmark.save".to_string(),
+                title: "Save Camera Bookmark".to_string(),
+                description: Some("Remember the current zoom and pan of the view".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::SaveBookmark),
+                group: Some("transform_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "layout.save".to_string(),
+                title: "Save Layout".to_string(),
+                description: Some("Write the panes and their actors to the layout file".to_string()),
+                category: "View".to_string(),
+                action: CommandAction::Global(GlobalAction::SaveLayout),
+                group: Some("view_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "session.save".to_string(),
+                title: "Save Session".to_string(),
+                description: Some("Write the open views, files and terminals to the session".to_string()),
+                category: "File".to_string(),
+                action: CommandAction::Global(GlobalAction::SaveSession),
+                group: Some("file_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "pane.toggle_zoom".to_string(),
+                title: "Toggle Pane Zoom".to_string(),
+                description: Some("Show the focused pane alone, or restore the layout".to_string()),
+                category: "Pane".to_string(),
+                shortcut: Some("Ctrl+Alt+F".to_string()),
+                action: CommandAction::Global(GlobalAction::TogglePaneZoom),
+                group: Some("tiling_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "pane.next_tab".to_string(),
+                title: "Next Tab in Pane".to_string(),
+                description: Some("Show the focused pane's next tab".to_string()),
+                category: "Pane".to_string(),
+                shortcut: Some("Ctrl+PageDown".to_string()),
+                action: CommandAction::Global(GlobalAction::CyclePaneTab(true)),
+                group: Some("tab_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "pane.previous_tab".to_string(),
+                title: "Previous Tab in Pane".to_string(),
+                description: Some("Show the focused pane's previous tab".to_string()),
+                category: "Pane".to_string(),
+                shortcut: Some("Ctrl+PageUp".to_string()),
+                action: CommandAction::Global(GlobalAction::CyclePaneTab(false)),
+                group: Some("tab_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "float.toggle".to_string(),
+                title: "Toggle Floating Panes".to_string(),
+                description: Some("Show or hide the floating panes, opening a scratch terminal if there are none".to_string()),
+                category: "Pane".to_string(),
+                shortcut: Some("Ctrl+Alt+W".to_string()),
+                action: CommandAction::Global(GlobalAction::ToggleFloatingPanes),
+                group: Some("pane_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "float.new_terminal".to_string(),
+                title: "New Floating Terminal".to_string(),
+                description: Some("Open a shell in a new floating pane".to_string()),
+                category: "Pane".to_string(),
+                action: CommandAction::Global(GlobalAction::NewFloatingTerminal),
+                group: Some("pane_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "float.float_view".to_string(),
+                title: "Float Active View".to_string(),
+                description: Some("Move the active view's file or terminal into a floating pane".to_string()),
+                category: "Pane".to_string(),
+                action: CommandAction::Global(GlobalAction::FloatActiveView),
+                group: Some("pane_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "float.embed".to_string(),
+                title: "Embed Floating Pane".to_string(),
+                description: Some("Move the focused floating pane into the active view".to_string()),
+                category: "Pane".to_string(),
+                action: CommandAction::Global(GlobalAction::EmbedFloatingPane),
+                group: Some("pane_operations".to_string()),
+                ..Default::default()
+            },
+            Command {
+                id: "float.toggle_pin".to_string(),
+                title: "Pin Floating Pane".to_string(),
+                description: Some("Keep the focused floating pane above the others, or unpin it".to_string()),
+                category: "Pane".to_string(),
+                action: CommandAction::Global(GlobalAction::TogglePinFloatingPane),
+                group: Some("pane_operations".to_string()),
+                ..Default::default()
+            },
         ]);
+
+        for direction in PaneDirection::ALL {
+            let name = direction.name();
+            let key = match direction {
+                PaneDirection::Left => "Left",
+                PaneDirection::Right => "Right",
+                PaneDirection::Up => "Up",
+                PaneDirection::Down => "Down",
+            };
+            self.commands.extend([
+                Command {
+                    id: format!("pane.focus_{}", name),
+                    title: format!("Focus Pane {}", key),
+                    description: Some(format!("Focus the pane to the {} of the focused one", name)),
+                    category: "Pane".to_string(),
+                    shortcut: Some(format!("Ctrl+Alt+{}", key)),
+                    action: CommandAction::Global(GlobalAction::FocusPane(direction)),
+                    group: Some("tiling_operations".to_string()),
+                    ..Default::default()
+                },
+                Command {
+                    id: format!("pane.swap_{}", name),
+                    title: format!("Swap Pane {}", key),
+                    description: Some(format!("Exchange the focused pane with the one to the {}", name)),
+                    category: "Pane".to_string(),
+                    shortcut: Some(format!("Ctrl+Alt+Shift+{}", key)),
+                    action: CommandAction::Global(GlobalAction::SwapPane(direction)),
+                    group: Some("tiling_operations".to_string()),
+                    ..Default::default()
+                },
+                Command {
+                    id: format!("pane.move_{}", name),
+                    title: format!("Move Pane {}", key),
+                    description: Some(format!("Move the focused pane into a new split to the {}", name)),
+                    category: "Pane".to_string(),
+                    action: CommandAction::Global(GlobalAction::MovePane(direction)),
+                    group: Some("tiling_operations".to_string()),
+                    ..Default::default()
+                },
+                Command {
+                    id: format!("pane.resize_{}", name),
+                    title: format!("Resize Pane {}", key),
+                    description: Some(format!("Move the focused pane's divider {}", name)),
+                    category: "Pane".to_string(),
+                    action: CommandAction::Global(GlobalAction::ResizePane(direction)),
+                    group: Some("tiling_operations".to_string()),
+                    ..Default::default()
+                },
+            ]);
+        }
+
+        for slot in 1..=9 {
+            self.commands.extend([
+                Command {
+                    id: format!("bookmark.jump_{}", slot),
+                    title: format!("Jump to Bookmark {}", slot),
+                    description: Some(format!("Animate the camera to bookmark {}", slot)),
+                    category: "View".to_string(),
+                    shortcut: Some(format!("Ctrl+{}", slot)),
+                    action: CommandAction::Global(GlobalAction::JumpToBookmark(slot - 1)),
+                    group: Some("transform_operations".to_string()),
+                    ..Default::default()
+                },
+                Command {
+                    id: format!("bookmark.save_{}", slot),
+                    title: format!("Save Bookmark {}", slot),
+                    description: Some(format!("Set bookmark {} to the current zoom and pan", slot)),
+                    category: "View".to_string(),
+                    shortcut: Some(format!("Ctrl+Shift+{}", slot)),
+                    action: CommandAction::Global(GlobalAction::SaveBookmarkSlot(slot - 1)),
+                    group: Some("transform_operations".to_string()),
+                    ..Default::default()
+                },
+            ]);
+        }
     }
 
     pub fn add_command(&mut self, command: Command) {
@@ -433,6 +711,8 @@ impl ActorCommandProvider {
             },
         ];
 
+        // The markdown editor wraps a code editor and takes the same commands
+        self.actor_commands.insert("MarkdownEditorActor".to_string(), commands.clone());
         self.actor_commands.insert("CodeEditorActor".to_string(), commands);
     }
 
@@ -475,6 +755,74 @@ impl CommandProvider for ActorCommandProvider {
     }
 }
 
+/// "Go to symbol" commands built from the focused actor's document outline
+pub struct SymbolCommandProvider;
+
+impl CommandProvider for SymbolCommandProvider {
+    fn get_commands(&self, ctx: &CommandContext) -> Vec<Command> {
+        let Some(actor_id) = ctx.focused_actor else {
+            return Vec::new();
+        };
+        let Some(actor) = ctx.actor_manager.get_actor(actor_id) else {
+            return Vec::new();
+        };
+
+        actor.document_symbols().into_iter().map(|symbol| {
+            let line = symbol.position.line;
+            Command {
+                id: format!("symbol.{}.{}", line + 1, symbol.name),
+                title: symbol.name.clone(),
+                description: Some(format!("{} {} on line {}", symbol.kind, symbol.name, line + 1)),
+                category: "Go to Symbol".to_string(),
+                action: CommandAction::ActorCall(
+                    actor_id,
+                    "go_to_position".to_string(),
+                    ApiParams::new()
+                        .with_param("line", line)
+                        .with_param("column", symbol.position.column),
+                ),
+                ..Default::default()
+            }
+        }).collect()
+    }
+
+    fn provider_id(&self) -> String {
+        "symbols".to_string()
+    }
+
+    fn is_active(&self, ctx: &CommandContext) -> bool {
+        ctx.focused_actor.is_some()
+    }
+}
+
+/// "Go to bookmark" commands for the camera bookmarks of the view system
+pub struct BookmarkCommandProvider;
+
+impl CommandProvider for BookmarkCommandProvider {
+    fn get_commands(&self, ctx: &CommandContext) -> Vec<Command> {
+        let Some(bookmarks) = ctx.view_container.system().as_bookmarks() else {
+            return Vec::new();
+        };
+
+        bookmarks.bookmarks().iter().enumerate().map(|(index, bookmark)| Command {
+            id: format!("bookmark.go.{}.{}", index + 1, bookmark.name),
+            title: bookmark.name.clone(),
+            description: Some(format!("Bookmark {} at {:.0}% zoom", index + 1, bookmark.zoom * 100.0)),
+            category: "Go to Bookmark".to_string(),
+            action: CommandAction::Global(GlobalAction::JumpToBookmark(index)),
+            ..Default::default()
+        }).collect()
+    }
+
+    fn provider_id(&self) -> String {
+        "bookmarks".to_string()
+    }
+
+    fn is_active(&self, ctx: &CommandContext) -> bool {
+        ctx.view_container.system().as_bookmarks().is_some()
+    }
+}
+
 /// Main command palette system
 pub struct CommandPalette {
     providers: HashMap<String, Box<dyn CommandProvider>>,
@@ -497,9 +845,13 @@ impl CommandPalette {
         // Register default providers
         palette.register_provider(Box::new(GlobalCommandProvider::new()));
         palette.register_provider(Box::new(ActorCommandProvider::new()));
+        palette.register_provider(Box::new(SymbolCommandProvider));
+        palette.register_provider(Box::new(BookmarkCommandProvider));
 
         // Register default groups
         palette.add_default_groups();
+        // The scene's cards have no tabs
+        palette.add_custom_activator("tabbed_views".to_string(), Box::new(|ctx| ctx.current_view_system != "scene"));
 
         palette
     }
@@ -541,6 +893,22 @@ impl CommandPalette {
                 enabled: true,
                 activation_context: GroupActivation::ViewSystem("scene".to_string()),
             },
+            CommandGroup {
+                id: "tiling_operations".to_string(),
+                title: "Tiling Operations".to_string(),
+                description: Some("Pane focus, layout and zoom".to_string()),
+                priority: 60,
+                enabled: true,
+                activation_context: GroupActivation::ViewSystem("tiling".to_string()),
+            },
+            CommandGroup {
+                id: "tab_operations".to_string(),
+                title: "Tab Operations".to_string(),
+                description: Some("Switching between a view's tabs".to_string()),
+                priority: 50,
+                enabled: true,
+                activation_context: GroupActivation::Custom("tabbed_views".to_string()),
+            },
         ];
 
         for group in groups {
@@ -602,6 +970,17 @@ impl CommandPalette {
         }).collect()
     }
 
+    /// Best command for free-form input: exact ID, then exact title, then the
+    /// first search result
+    pub fn resolve_command(&self, query: &str, ctx: &CommandContext) -> Option<Command> {
+        let available = self.get_available_commands(ctx);
+        let query_lower = query.to_lowercase();
+        available.iter().find(|c| c.id == query)
+            .or_else(|| available.iter().find(|c| c.title.to_lowercase() == query_lower))
+            .cloned()
+            .or_else(|| self.search_commands(query, ctx).into_iter().next())
+    }
+
     /// Execute a command by ID
     pub fn execute_command(&self, command_id: &str, ctx: &CommandContext) -> Result<CommandExecutionResult> {
         let available = self.get_available_commands(ctx);
@@ -621,6 +1000,9 @@ impl CommandPalette {
                     // Actor actions would be forwarded to the specific actor
                     Ok(CommandExecutionResult::ActorAction(*actor_id, action.clone()))
                 }
+                CommandAction::ActorCall(actor_id, method, params) => {
+                    Ok(CommandExecutionResult::ActorCall(*actor_id, method.clone(), params.clone()))
+                }
                 CommandAction::Custom(func) => {
                     func(ctx)?;
                     Ok(CommandExecutionResult::Success)
@@ -695,6 +1077,7 @@ pub enum CommandExecutionResult {
     Disabled,
     GlobalAction(GlobalAction),
     ActorAction(Uuid, String),
+    ActorCall(Uuid, String, ApiParams),
     Error(String),
 }
 
@@ -783,4 +1166,42 @@ impl Default for CommandPaletteWidget {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_system::SceneSystem;
+    use crate::tiling_system::TilingSystem;
+    use crate::view::ViewManager;
+    use crate::view_system::ViewSystem;
+
+    /// IDs of the palette's commands while `system` is the view system
+    fn command_ids(system: Box<dyn ViewSystem>, name: &str) -> Vec<String> {
+        let palette = CommandPalette::new();
+        let view_container = ViewContainer::new(system);
+        let actor_manager = ActorManager::new();
+        let ctx = CommandContext {
+            focused_actor: None,
+            view_container: &view_container,
+            actor_manager: &actor_manager,
+            current_view_system: name.to_string(),
+        };
+        palette.get_available_commands(&ctx).into_iter().map(|command| command.id).collect()
+    }
+
+    #[test]
+    fn pane_commands_follow_the_view_system() {
+        let tiling = command_ids(Box::new(TilingSystem::new()), "tiling");
+        let nested = command_ids(Box::new(ViewManager::new()), "nested");
+        let scene = command_ids(Box::new(SceneSystem::new()), "scene");
+        let has = |ids: &[String], id: &str| ids.iter().any(|command| command == id);
+
+        for id in ["pane.focus_left", "pane.swap_up", "pane.move_right", "pane.resize_down", "pane.toggle_zoom"] {
+            assert!(has(&tiling, id), "{}", id);
+            assert!(!has(&nested, id) && !has(&scene, id), "{}", id);
+        }
+        assert!(has(&tiling, "pane.next_tab") && has(&nested, "pane.next_tab"));
+        assert!(!has(&scene, "pane.next_tab"));
+        assert!(has(&scene, "float.toggle") && has(&nested, "float.toggle"));
+    }
+}
@@ -0,0 +1,828 @@
+use crate::language_servers::{CompletionReply, LanguageServers};
+use crate::snippet::{self, SnippetLibrary};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+/// Most items the popup will show
+const MAX_ITEMS: usize = 200;
+/// Score added to an item accepted just now, fading as other items are accepted
+const RECENCY_BOOST: i32 = 40;
+
+/// What a completion item inserts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionKind {
+    Word,
+    File,
+    Folder,
+    Snippet,
+    Function,
+    Method,
+    Variable,
+    Field,
+    Type,
+    Module,
+    Keyword,
+    Constant,
+    Other,
+}
+
+impl CompletionKind {
+    fn from_lsp(kind: Option<lsp_types::CompletionItemKind>) -> Self {
+        use lsp_types::CompletionItemKind as K;
+        match kind {
+            Some(K::FUNCTION) | Some(K::CONSTRUCTOR) => CompletionKind::Function,
+            Some(K::METHOD) => CompletionKind::Method,
+            Some(K::VARIABLE) | Some(K::VALUE) => CompletionKind::Variable,
+            Some(K::FIELD) | Some(K::PROPERTY) | Some(K::ENUM_MEMBER) => CompletionKind::Field,
+            Some(K::CLASS) | Some(K::STRUCT) | Some(K::INTERFACE) | Some(K::ENUM) | Some(K::TYPE_PARAMETER) => CompletionKind::Type,
+            Some(K::MODULE) => CompletionKind::Module,
+            Some(K::KEYWORD) => CompletionKind::Keyword,
+            Some(K::CONSTANT) => CompletionKind::Constant,
+            Some(K::SNIPPET) => CompletionKind::Snippet,
+            Some(K::FILE) => CompletionKind::File,
+            Some(K::FOLDER) => CompletionKind::Folder,
+            Some(K::TEXT) => CompletionKind::Word,
+            _ => CompletionKind::Other,
+        }
+    }
+
+    /// Short tag shown next to the label
+    pub fn tag(&self) -> &'static str {
+        match self {
+            CompletionKind::Word => "abc",
+            CompletionKind::File => "file",
+            CompletionKind::Folder => "dir",
+            CompletionKind::Snippet => "snip",
+            CompletionKind::Function => "fn",
+            CompletionKind::Method => "meth",
+            CompletionKind::Variable => "var",
+            CompletionKind::Field => "field",
+            CompletionKind::Type => "type",
+            CompletionKind::Module => "mod",
+            CompletionKind::Keyword => "kw",
+            CompletionKind::Constant => "const",
+            CompletionKind::Other => "·",
+        }
+    }
+}
+
+/// A candidate offered by a completion source
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// One-line extra info, e.g. a signature
+    pub detail: Option<String>,
+    /// Shown in the preview next to the list
+    pub documentation: Option<String>,
+    pub insert_text: String,
+    /// Whether `insert_text` uses snippet syntax
+    pub is_snippet: bool,
+    /// Text replaced on accept; defaults to the word before the cursor
+    pub replace: Option<Range<usize>>,
+    /// Source-specific ranking adjustment
+    pub boost: i32,
+    pub source: &'static str,
+}
+
+impl CompletionItem {
+    pub fn new(label: impl Into<String>, kind: CompletionKind, source: &'static str) -> Self {
+        let label = label.into();
+        Self {
+            insert_text: label.clone(),
+            label,
+            kind,
+            detail: None,
+            documentation: None,
+            is_snippet: false,
+            replace: None,
+            boost: 0,
+            source,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn with_documentation(mut self, documentation: impl Into<String>) -> Self {
+        self.documentation = Some(documentation.into());
+        self
+    }
+
+    pub fn with_snippet(mut self, body: impl Into<String>) -> Self {
+        self.insert_text = body.into();
+        self.is_snippet = true;
+        self
+    }
+}
+
+/// Where completion was asked for
+pub struct CompletionContext<'a> {
+    pub text: &'a str,
+    /// Cursor byte offset
+    pub offset: usize,
+    /// Start of the identifier the cursor is in
+    pub word_start: usize,
+    pub language: &'a str,
+    pub file_path: Option<&'a Path>,
+    /// Words from the other open editors
+    pub workspace_words: &'a [String],
+}
+
+impl<'a> CompletionContext<'a> {
+    pub fn new(text: &'a str, offset: usize, language: &'a str) -> Self {
+        Self {
+            text,
+            offset,
+            word_start: word_start(text, offset),
+            language,
+            file_path: None,
+            workspace_words: &[],
+        }
+    }
+
+    pub fn with_file_path(mut self, path: Option<&'a Path>) -> Self {
+        self.file_path = path;
+        self
+    }
+
+    pub fn with_workspace_words(mut self, words: &'a [String]) -> Self {
+        self.workspace_words = words;
+        self
+    }
+}
+
+/// A pluggable provider of completion items
+pub trait CompletionSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Items for the cursor position; the engine does the filtering and ranking
+    fn complete(&mut self, ctx: &CompletionContext) -> Vec<CompletionItem>;
+
+    /// Collect results that arrived in the background; returns whether
+    /// `complete` now has more to offer
+    fn poll(&mut self) -> bool {
+        false
+    }
+}
+
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Start of the identifier ending at `offset`
+pub fn word_start(text: &str, offset: usize) -> usize {
+    text[..offset].char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word_char(*c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(offset)
+}
+
+/// Identifiers in `text` worth offering (at least three characters, not numbers)
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = None;
+    text.char_indices().chain(std::iter::once((text.len(), ' '))).filter_map(move |(i, c)| {
+        if is_word_char(c) {
+            start.get_or_insert(i);
+            None
+        } else {
+            let s = start.take()?;
+            let word = &text[s..i];
+            let useful = word.chars().count() >= 3 && !word.starts_with(|c: char| c.is_ascii_digit());
+            useful.then_some((s, word))
+        }
+    })
+}
+
+/// Identifiers in an editor's buffer, for completion in other editors
+pub fn buffer_words(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    words(text).filter(|(_, w)| seen.insert(*w)).map(|(_, w)| w.to_string()).collect()
+}
+
+/// Words from the current buffer, favouring those near the cursor
+pub struct BufferWordsSource;
+
+impl CompletionSource for BufferWordsSource {
+    fn name(&self) -> &'static str {
+        "buffer"
+    }
+
+    fn complete(&mut self, ctx: &CompletionContext) -> Vec<CompletionItem> {
+        let mut nearest: HashMap<&str, usize> = HashMap::new();
+        for (start, word) in words(ctx.text) {
+            // Skip the word being typed
+            if start == ctx.word_start {
+                continue;
+            }
+            let distance = start.abs_diff(ctx.offset);
+            let entry = nearest.entry(word).or_insert(distance);
+            *entry = (*entry).min(distance);
+        }
+        nearest.into_iter()
+            .map(|(word, distance)| {
+                let mut item = CompletionItem::new(word, CompletionKind::Word, self.name());
+                item.boost = 4 - ((distance + 1).ilog2() as i32) / 4;
+                item
+            })
+            .collect()
+    }
+}
+
+/// Words from the other open editors
+pub struct WorkspaceWordsSource;
+
+impl CompletionSource for WorkspaceWordsSource {
+    fn name(&self) -> &'static str {
+        "open editors"
+    }
+
+    fn complete(&mut self, ctx: &CompletionContext) -> Vec<CompletionItem> {
+        ctx.workspace_words.iter()
+            .map(|word| {
+                let mut item = CompletionItem::new(word.clone(), CompletionKind::Word, self.name());
+                item.boost = -2;
+                item
+            })
+            .collect()
+    }
+}
+
+/// File and directory names for a path being typed (`./src/ma`, `~/`, `/etc/`)
+pub struct FilePathSource;
+
+impl FilePathSource {
+    /// Byte range of the path-looking token before the cursor
+    fn path_token(ctx: &CompletionContext) -> Option<Range<usize>> {
+        let before = &ctx.text[..ctx.offset];
+        let start = before.char_indices()
+            .rev()
+            .take_while(|(_, c)| !c.is_whitespace() && !"\"'`()[]{}<>,;=".contains(*c))
+            .last()
+            .map(|(i, _)| i)?;
+        before[start..].contains('/').then_some(start..ctx.offset)
+    }
+}
+
+impl CompletionSource for FilePathSource {
+    fn name(&self) -> &'static str {
+        "paths"
+    }
+
+    fn complete(&mut self, ctx: &CompletionContext) -> Vec<CompletionItem> {
+        let Some(token) = Self::path_token(ctx) else {
+            return Vec::new();
+        };
+        let path = &ctx.text[token.clone()];
+        let slash = path.rfind('/').unwrap_or(0);
+        let (dir, partial) = (&path[..=slash], &path[slash + 1..]);
+
+        let dir_path = if let Some(rest) = dir.strip_prefix("~/") {
+            dirs::home_dir().map(|home| home.join(rest))
+        } else if dir.starts_with('/') {
+            Some(PathBuf::from(dir))
+        } else {
+            let base = ctx.file_path.and_then(|p| p.parent()).map(Path::to_path_buf)
+                .or_else(|| std::env::current_dir().ok());
+            base.map(|base| base.join(dir))
+        };
+        let Some(entries) = dir_path.and_then(|d| fs::read_dir(d).ok()) else {
+            return Vec::new();
+        };
+
+        let replace = token.start + slash + 1..ctx.offset;
+        entries.flatten()
+            .take(500)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.starts_with('.') && !partial.starts_with('.') {
+                    return None;
+                }
+                let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                let (kind, label) = if is_dir {
+                    (CompletionKind::Folder, format!("{}/", name))
+                } else {
+                    (CompletionKind::File, name)
+                };
+                let mut item = CompletionItem::new(label, kind, self.name())
+                    .with_detail(entry.path().display().to_string());
+                item.replace = Some(replace.clone());
+                Some(item)
+            })
+            .collect()
+    }
+}
+
+/// Snippets for the editor's language
+pub struct SnippetSource {
+    library: SnippetLibrary,
+}
+
+impl SnippetSource {
+    pub fn new(library: SnippetLibrary) -> Self {
+        Self { library }
+    }
+}
+
+impl CompletionSource for SnippetSource {
+    fn name(&self) -> &'static str {
+        "snippets"
+    }
+
+    fn complete(&mut self, ctx: &CompletionContext) -> Vec<CompletionItem> {
+        self.library.for_language(ctx.language)
+            .map(|s| {
+                let preview = snippet::expand(&s.body, "", "    ", &HashMap::new()).text;
+                CompletionItem::new(s.prefix.clone(), CompletionKind::Snippet, self.name())
+                    .with_detail(s.description.clone())
+                    .with_documentation(preview)
+                    .with_snippet(s.body.clone())
+            })
+            .collect()
+    }
+}
+
+/// Items from the language server, when one is registered and installed
+///
+/// Servers are shared through `LanguageServers` and run on their own
+/// threads, so a slow server never blocks typing; results show up through
+/// `poll` once they arrive. A server is asked again only when the cursor
+/// moves to a different word.
+pub struct LspSource {
+    servers: LanguageServers,
+    replies: Sender<CompletionReply>,
+    /// Behind a mutex only so the source is `Sync`
+    responses: Mutex<Receiver<CompletionReply>>,
+    /// Request still waiting for its response
+    pending: Option<(PathBuf, usize)>,
+    latest: Option<LspResponse>,
+}
+
+struct LspResponse {
+    path: PathBuf,
+    word_start: usize,
+    items: Vec<CompletionItem>,
+}
+
+impl LspSource {
+    pub fn new(servers: LanguageServers) -> Self {
+        let (replies, responses) = mpsc::channel();
+        Self {
+            servers,
+            replies,
+            responses: Mutex::new(responses),
+            pending: None,
+            latest: None,
+        }
+    }
+}
+
+fn lsp_items(response: lsp_types::CompletionResponse) -> Vec<CompletionItem> {
+    let items = match response {
+        lsp_types::CompletionResponse::Array(items) => items,
+        lsp_types::CompletionResponse::List(list) => list.items,
+    };
+    items.into_iter()
+        .map(|item| {
+            let insert = match &item.text_edit {
+                Some(lsp_types::CompletionTextEdit::Edit(edit)) => Some(edit.new_text.clone()),
+                Some(lsp_types::CompletionTextEdit::InsertAndReplace(edit)) => Some(edit.new_text.clone()),
+                None => item.insert_text.clone(),
+            };
+            let mut result = CompletionItem::new(item.label.clone(), CompletionKind::from_lsp(item.kind), "language server");
+            result.detail = item.detail;
+            result.documentation = item.documentation.map(|doc| match doc {
+                lsp_types::Documentation::String(s) => s,
+                lsp_types::Documentation::MarkupContent(markup) => markup.value,
+            });
+            if let Some(insert) = insert {
+                result.insert_text = insert;
+            }
+            result.is_snippet = item.insert_text_format == Some(lsp_types::InsertTextFormat::SNIPPET);
+            result.boost = 6;
+            result
+        })
+        .collect()
+}
+
+impl CompletionSource for LspSource {
+    fn name(&self) -> &'static str {
+        "language server"
+    }
+
+    fn complete(&mut self, ctx: &CompletionContext) -> Vec<CompletionItem> {
+        // Servers need a file on disk to resolve the project
+        let Some(path) = ctx.file_path.map(Path::to_path_buf) else {
+            return Vec::new();
+        };
+        if let Some(latest) = &self.latest {
+            if latest.path == path && latest.word_start == ctx.word_start {
+                return latest.items.clone();
+            }
+        }
+
+        let key = (path.clone(), ctx.word_start);
+        if self.pending.as_ref() == Some(&key) {
+            return Vec::new();
+        }
+        let Some(server) = self.servers.server_for(ctx.language, &path) else {
+            return Vec::new();
+        };
+        let line_start = ctx.text[..ctx.offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let position = lsp_types::Position {
+            line: ctx.text[..line_start].matches('\n').count() as u32,
+            character: ctx.text[line_start..ctx.offset].encode_utf16().count() as u32,
+        };
+        // A server that failed to start or died just leaves this unanswered
+        if server.complete(path, ctx.text.to_string(), position, ctx.word_start, self.replies.clone()) {
+            self.pending = Some(key);
+        }
+        Vec::new()
+    }
+
+    fn poll(&mut self) -> bool {
+        let replies: Vec<CompletionReply> = match self.responses.get_mut() {
+            Ok(receiver) => receiver.try_iter().collect(),
+            Err(_) => return false,
+        };
+        let arrived = !replies.is_empty();
+        for reply in replies {
+            if self.pending.as_ref().is_some_and(|(path, start)| *path == reply.path && *start == reply.word_start) {
+                self.pending = None;
+            }
+            let items = reply.response.map(lsp_items).unwrap_or_default();
+            self.latest = Some(LspResponse { path: reply.path, word_start: reply.word_start, items });
+        }
+        arrived
+    }
+}
+
+/// Fuzzy match `query` against `candidate`, higher is better
+///
+/// Every query character must appear in order (case-insensitively). Matches at
+/// the start, at word boundaries and in runs score higher; skipped characters
+/// cost a little.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for q in query.chars() {
+        let found = (next..chars.len()).find(|&i| chars[i].to_lowercase().eq(q.to_lowercase()))?;
+        score += 1;
+        if found == 0 {
+            score += 8;
+        } else {
+            let before = chars[found - 1];
+            let boundary = !before.is_alphanumeric() || (before.is_lowercase() && chars[found].is_uppercase());
+            if boundary {
+                score += 6;
+            }
+        }
+        match previous_match {
+            Some(prev) if prev + 1 == found => score += 4,
+            Some(prev) => score -= (found - prev - 1).min(3) as i32,
+            None => score -= found.min(3) as i32,
+        }
+        if chars[found] == q {
+            score += 1;
+        }
+        previous_match = Some(found);
+        next = found + 1;
+    }
+
+    if candidate.to_lowercase().starts_with(&query.to_lowercase()) {
+        score += 10;
+    }
+    score -= (chars.len().saturating_sub(query.chars().count()) / 4) as i32;
+    Some(score)
+}
+
+/// Gathers items from its sources and ranks them for the popup
+pub struct CompletionEngine {
+    sources: Vec<Box<dyn CompletionSource>>,
+    /// Label -> value of `accepted` when it was last chosen
+    recent: HashMap<String, u64>,
+    accepted: u64,
+}
+
+impl CompletionEngine {
+    /// Engine with no sources
+    pub fn empty() -> Self {
+        Self {
+            sources: Vec::new(),
+            recent: HashMap::new(),
+            accepted: 0,
+        }
+    }
+
+    /// Engine with the standard sources, asking `language_servers`
+    pub fn new(language_servers: LanguageServers) -> Self {
+        Self::empty()
+            .with_source(LspSource::new(language_servers))
+            .with_source(SnippetSource::new(SnippetLibrary::load()))
+            .with_source(FilePathSource)
+            .with_source(BufferWordsSource)
+            .with_source(WorkspaceWordsSource)
+    }
+
+    pub fn with_source(mut self, source: impl CompletionSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Matching items, best first
+    ///
+    /// With `manual` set (explicitly invoked) an empty prefix lists everything;
+    /// otherwise only items the user has started typing are offered.
+    pub fn complete(&mut self, ctx: &CompletionContext, manual: bool) -> Vec<CompletionItem> {
+        let mut ranked: Vec<(i32, CompletionItem)> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for source in &mut self.sources {
+            for item in source.complete(ctx) {
+                let replace = item.replace.clone().unwrap_or(ctx.word_start..ctx.offset);
+                let query = &ctx.text[replace];
+                if query.is_empty() && !manual && item.replace.is_none() {
+                    continue;
+                }
+                // Offering what's already typed is noise
+                if item.kind == CompletionKind::Word && item.label == query {
+                    continue;
+                }
+                let Some(score) = fuzzy_score(query, &item.label) else {
+                    continue;
+                };
+                // Earlier sources win ties between identical labels
+                if !seen.insert((item.label.clone(), item.insert_text.clone())) {
+                    continue;
+                }
+                let recency = self.recent.get(&item.label)
+                    .map(|&at| RECENCY_BOOST * 8 / (8 + (self.accepted - at) as i32))
+                    .unwrap_or(0);
+                ranked.push((score + item.boost + recency, item));
+            }
+        }
+
+        ranked.sort_by(|(a, x), (b, y)| b.cmp(a).then(x.label.len().cmp(&y.label.len())).then(x.label.cmp(&y.label)));
+        ranked.into_iter().take(MAX_ITEMS).map(|(_, item)| item).collect()
+    }
+
+    /// Remember an accepted item so it ranks higher next time
+    pub fn record_accepted(&mut self, item: &CompletionItem) {
+        self.accepted += 1;
+        self.recent.insert(item.label.clone(), self.accepted);
+    }
+
+    /// Whether any source has new results since the last `complete`
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        for source in &mut self.sources {
+            changed |= source.poll();
+        }
+        changed
+    }
+}
+
+impl Default for CompletionEngine {
+    fn default() -> Self {
+        Self::new(LanguageServers::new())
+    }
+}
+
+/// Something the user did in the completion popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionAction {
+    Accept(usize),
+    Close,
+}
+
+/// The completion list shown under the cursor
+pub struct CompletionPopup {
+    pub items: Vec<CompletionItem>,
+    pub selected: usize,
+    /// Start of the word the popup was opened for
+    pub word_start: usize,
+    /// Explicitly invoked (Ctrl+Space) rather than opened by typing
+    pub manual: bool,
+    /// Fixed list (snippet choices) that isn't refiltered as the user types
+    pub fixed: bool,
+    scroll_to_selected: bool,
+}
+
+impl CompletionPopup {
+    pub fn new(items: Vec<CompletionItem>, word_start: usize, manual: bool) -> Self {
+        Self {
+            items,
+            selected: 0,
+            word_start,
+            manual,
+            fixed: false,
+            scroll_to_selected: true,
+        }
+    }
+
+    /// Popup offering the options of a snippet choice placeholder
+    pub fn choices(choices: &[String], range: Range<usize>) -> Self {
+        let items = choices.iter()
+            .map(|choice| {
+                let mut item = CompletionItem::new(choice.clone(), CompletionKind::Other, "snippets");
+                item.replace = Some(range.clone());
+                item
+            })
+            .collect();
+        Self {
+            fixed: true,
+            ..Self::new(items, range.start, true)
+        }
+    }
+
+    /// Replace the items, keeping the selected one if it's still there
+    pub fn set_items(&mut self, items: Vec<CompletionItem>) {
+        let selected = self.items.get(self.selected).map(|item| item.label.clone());
+        self.selected = selected
+            .and_then(|label| items.iter().position(|item| item.label == label))
+            .unwrap_or(0);
+        self.items = items;
+        self.scroll_to_selected = true;
+    }
+
+    pub fn select_next(&mut self, step: usize) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected + step).min(self.items.len() - 1);
+            self.scroll_to_selected = true;
+        }
+    }
+
+    pub fn select_previous(&mut self, step: usize) {
+        self.selected = self.selected.saturating_sub(step);
+        self.scroll_to_selected = true;
+    }
+
+    /// Take the popup's keys out of the input queue
+    pub fn handle_keys(&mut self, ui: &mut egui::Ui) -> Option<CompletionAction> {
+        let page = 8;
+        ui.input_mut(|i| {
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+                return Some(CompletionAction::Close);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Enter) || i.consume_key(egui::Modifiers::NONE, egui::Key::Tab) {
+                return Some(CompletionAction::Accept(self.selected));
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) || i.consume_key(egui::Modifiers::CTRL, egui::Key::N) {
+                self.select_next(1);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) || i.consume_key(egui::Modifiers::CTRL, egui::Key::P) {
+                self.select_previous(1);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::PageDown) {
+                self.select_next(page);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::PageUp) {
+                self.select_previous(page);
+            }
+            None
+        })
+    }
+
+    /// Draw the list below `anchor` with a preview of the selected item
+    pub fn show(&mut self, ctx: &egui::Context, id: egui::Id, anchor: egui::Pos2) -> Option<CompletionAction> {
+        let mut action = None;
+        let scroll_to_selected = std::mem::take(&mut self.scroll_to_selected);
+
+        egui::Area::new(id)
+            .order(egui::Order::Foreground)
+            .fixed_pos(anchor)
+            .show(ctx, |ui| {
+                ui.horizontal_top(|ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_width(280.0);
+                        egui::ScrollArea::vertical().max_height(220.0).show(ui, |ui| {
+                            for (i, item) in self.items.iter().enumerate() {
+                                let response = ui.horizontal(|ui| {
+                                    ui.add_sized([40.0, 14.0], egui::Label::new(egui::RichText::new(item.kind.tag()).weak().small()));
+                                    ui.selectable_label(i == self.selected, egui::RichText::new(&item.label).monospace())
+                                }).inner;
+                                if response.clicked() {
+                                    action = Some(CompletionAction::Accept(i));
+                                }
+                                if i == self.selected && scroll_to_selected {
+                                    response.scroll_to_me(None);
+                                }
+                            }
+                        });
+                    });
+
+                    if let Some(item) = self.items.get(self.selected) {
+                        if item.detail.is_some() || item.documentation.is_some() {
+                            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                                ui.set_max_width(360.0);
+                                if let Some(detail) = &item.detail {
+                                    ui.strong(detail);
+                                }
+                                if let Some(documentation) = &item.documentation {
+                                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                                        ui.monospace(documentation);
+                                    });
+                                }
+                                ui.weak(item.source);
+                            });
+                        }
+                    }
+                });
+            });
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A source offering the same items wherever it's asked
+    struct Fixed(Vec<CompletionItem>);
+
+    impl CompletionSource for Fixed {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn complete(&mut self, _ctx: &CompletionContext) -> Vec<CompletionItem> {
+            self.0.clone()
+        }
+    }
+
+    fn items(labels: &[&str]) -> Vec<CompletionItem> {
+        labels.iter().map(|label| CompletionItem::new(*label, CompletionKind::Function, "fixed")).collect()
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn fuzzy_scores_favor_prefixes_boundaries_and_runs() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("ba", "abc"), None);
+        assert_eq!(fuzzy_score("abcd", "abc"), None);
+        assert!(fuzzy_score("GET", "get_name").is_some());
+
+        let score = |query, candidate| fuzzy_score(query, candidate).unwrap();
+        assert!(score("get", "get_name") > score("get", "target"));
+        assert!(score("gn", "get_name") > score("gn", "signing"));
+        assert!(score("gn", "getName") > score("gn", "agent"));
+        assert!(score("name", "name") > score("name", "name_of_the_thing"));
+        assert!(score("Name", "Name") > score("Name", "name"));
+    }
+
+    #[test]
+    fn completions_are_filtered_and_ranked() {
+        let mut engine = CompletionEngine::empty()
+            .with_source(Fixed(items(&["format", "from_str", "unrelated", "f_or_mat", "Formatter"])));
+        let ctx = CompletionContext::new("let x = for", 11, "rust");
+        assert_eq!(labels(&engine.complete(&ctx, false)), ["format", "Formatter", "f_or_mat", "from_str"]);
+
+        // Only an explicit request lists everything for an empty prefix
+        let ctx = CompletionContext::new("let x = ", 8, "rust");
+        assert!(engine.complete(&ctx, false).is_empty());
+        assert_eq!(engine.complete(&ctx, true).len(), 5);
+    }
+
+    #[test]
+    fn completions_skip_duplicates_and_the_typed_word() {
+        let mut typed = CompletionItem::new("value", CompletionKind::Word, "fixed");
+        typed.boost = 100;
+        let mut engine = CompletionEngine::empty()
+            .with_source(Fixed(vec![typed, CompletionItem::new("values", CompletionKind::Word, "first")]))
+            .with_source(Fixed(vec![CompletionItem::new("values", CompletionKind::Word, "second")]));
+        let ctx = CompletionContext::new("value", 5, "text");
+        let completed = engine.complete(&ctx, false);
+        assert_eq!(labels(&completed), ["values"]);
+        assert_eq!(completed[0].source, "first");
+    }
+
+    #[test]
+    fn accepted_items_rank_higher() {
+        let mut engine = CompletionEngine::empty().with_source(Fixed(items(&["print", "println"])));
+        let ctx = CompletionContext::new("pri", 3, "rust");
+        assert_eq!(labels(&engine.complete(&ctx, false)), ["print", "println"]);
+
+        let println = engine.complete(&ctx, false).remove(1);
+        engine.record_accepted(&println);
+        assert_eq!(labels(&engine.complete(&ctx, false)), ["println", "print"]);
+    }
+}
@@ -1,4 +1,7 @@
+use crate::formatter::FormatterSpec;
+use crate::keymap::KeymapKind;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use anyhow::Result;
@@ -30,6 +33,40 @@ pub struct EditorConfig {
     pub tab_width: usize,
     /// Auto-save interval in seconds (0 = disabled)
     pub auto_save_interval: u64,
+    /// Run the language formatter before writing a file
+    #[serde(default)]
+    pub format_on_save: bool,
+    /// Maximum time an external formatter may run, in milliseconds
+    #[serde(default = "default_format_timeout_ms")]
+    pub format_timeout_ms: u64,
+    /// Per-language formatter overrides (e.g. `rust = { command = "rustfmt" }`)
+    #[serde(default)]
+    pub formatters: HashMap<String, FormatterSpec>,
+    /// Key binding layer: "default", "vim" or "emacs"
+    #[serde(default)]
+    pub keymap: KeymapKind,
+    /// Show a zoomed-out overview of the file beside the text
+    #[serde(default = "default_true")]
+    pub show_minimap: bool,
+    /// Pin the declarations enclosing the top line while scrolling
+    #[serde(default = "default_true")]
+    pub sticky_scroll: bool,
+    /// Files at least this many bytes open read-only, memory-mapped, with
+    /// only the visible lines laid out and highlighting turned off
+    #[serde(default = "default_large_file_threshold")]
+    pub large_file_threshold: u64,
+}
+
+fn default_format_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_large_file_threshold() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +101,13 @@ impl Default for IdeConfig {
                 show_line_numbers: true,
                 tab_width: 4,
                 auto_save_interval: 0, // Disabled
+                format_on_save: false,
+                format_timeout_ms: default_format_timeout_ms(),
+                formatters: HashMap::new(),
+                keymap: KeymapKind::Default,
+                show_minimap: true,
+                sticky_scroll: true,
+                large_file_threshold: default_large_file_threshold(),
             },
             window: WindowConfig {
                 default_width: 1400.0,
@@ -142,4 +186,11 @@ impl IdeConfig {
         self.save()?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Update the editor key binding layer and save
+    pub fn set_keymap(&mut self, keymap: KeymapKind) -> Result<()> {
+        self.editor.keymap = keymap;
+        self.save()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use std::ops::Range;
+
+/// Kind of change described by a [`DiffOp`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+    Replace,
+}
+
+/// A run of elements that is equal, removed, added or replaced between two sequences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOp {
+    pub tag: DiffTag,
+    /// Range in the old sequence (empty for inserts)
+    pub old: Range<usize>,
+    /// Range in the new sequence (empty for deletes)
+    pub new: Range<usize>,
+}
+
+/// Diff two slices using Myers' O(ND) algorithm
+///
+/// Adjacent delete/insert runs are merged into `Replace` ops.
+pub fn diff_slices<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    // Common prefix and suffix never take part in the edit script
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut steps = Vec::with_capacity(prefix + suffix + old_mid.len() + new_mid.len());
+    steps.extend((0..prefix).map(|i| (DiffTag::Equal, i, i)));
+    for (tag, x, y) in myers_steps(old_mid, new_mid) {
+        steps.push((tag, x + prefix, y + prefix));
+    }
+    let old_tail = old.len() - suffix;
+    let new_tail = new.len() - suffix;
+    steps.extend((0..suffix).map(|i| (DiffTag::Equal, old_tail + i, new_tail + i)));
+
+    compress_steps(&steps)
+}
+
+/// Compute the byte-range replacements that turn `old` into `new`
+///
+/// Each entry is `(range in old, replacement text)`, sorted by position. Only
+/// changed lines are touched so offsets in unchanged regions stay valid.
+pub fn text_edits(old: &str, new: &str) -> Vec<(Range<usize>, String)> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let old_offsets = line_offsets(&old_lines);
+    let new_offsets = line_offsets(&new_lines);
+
+    diff_slices(&old_lines, &new_lines)
+        .into_iter()
+        .filter(|op| op.tag != DiffTag::Equal)
+        .map(|op| {
+            let old_range = old_offsets[op.old.start]..old_offsets[op.old.end];
+            let new_range = new_offsets[op.new.start]..new_offsets[op.new.end];
+            (old_range, new[new_range].to_string())
+        })
+        .collect()
+}
+
+/// Byte ranges that differ between two versions of a line, word by word
+///
+/// Returns the changed ranges in `old` and in `new`.
+pub fn word_diff(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_tokens = tokens(old);
+    let new_tokens = tokens(new);
+    let old_words: Vec<&str> = old_tokens.iter().map(|r| &old[r.clone()]).collect();
+    let new_words: Vec<&str> = new_tokens.iter().map(|r| &new[r.clone()]).collect();
+
+    // Insertions and deletions leave nothing to mark on the other side
+    let span = |tokens: &[Range<usize>], range: Range<usize>| {
+        (!range.is_empty()).then(|| tokens[range.start].start..tokens[range.end - 1].end)
+    };
+    let mut old_changes = Vec::new();
+    let mut new_changes = Vec::new();
+    for op in diff_slices(&old_words, &new_words).into_iter().filter(|op| op.tag != DiffTag::Equal) {
+        old_changes.extend(span(&old_tokens, op.old));
+        new_changes.extend(span(&new_tokens, op.new));
+    }
+    (old_changes, new_changes)
+}
+
+/// Split a line into words, runs of whitespace and single punctuation characters
+fn tokens(line: &str) -> Vec<Range<usize>> {
+    let class = |c: char| if c.is_alphanumeric() || c == '_' { 0 } else if c.is_whitespace() { 1 } else { 2 };
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut previous = None;
+    for (i, c) in line.char_indices() {
+        let current = class(c);
+        match tokens.last_mut() {
+            Some(last) if previous == Some(current) && current != 2 => last.end = i + c.len_utf8(),
+            _ => tokens.push(i..i + c.len_utf8()),
+        }
+        previous = Some(current);
+    }
+    tokens
+}
+
+/// Byte offset of the start of each line, plus the total length
+fn line_offsets(lines: &[&str]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    let mut pos = 0;
+    offsets.push(0);
+    for line in lines {
+        pos += line.len();
+        offsets.push(pos);
+    }
+    offsets
+}
+
+/// Unit edit script from the greedy Myers algorithm
+fn myers_steps<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(DiffTag, usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (n + m) as usize;
+    if max == 0 {
+        return Vec::new();
+    }
+
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // Snapshot of the frontier before each round (only the k-range that round can read)
+    let mut trace: Vec<(isize, Vec<isize>)> = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        let lo = (offset - d - 1) as usize;
+        let hi = (offset + d + 1) as usize;
+        trace.push((lo as isize, v[lo..=hi].to_vec()));
+
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    // Walk the trace backwards to recover the path
+    let mut steps = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, (base, frontier)) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| frontier[(k + offset - base) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            steps.push((DiffTag::Equal, (x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                steps.push((DiffTag::Insert, x as usize, (y - 1) as usize));
+            } else {
+                steps.push((DiffTag::Delete, (x - 1) as usize, y as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    steps.reverse();
+    steps
+}
+
+/// Merge unit steps into ranged ops
+fn compress_steps(steps: &[(DiffTag, usize, usize)]) -> Vec<DiffOp> {
+    let mut ops: Vec<DiffOp> = Vec::new();
+
+    for &(tag, x, y) in steps {
+        let (old, new) = match tag {
+            DiffTag::Equal => (x..x + 1, y..y + 1),
+            DiffTag::Delete => (x..x + 1, y..y),
+            DiffTag::Insert => (x..x, y..y + 1),
+            DiffTag::Replace => unreachable!("unit steps are never replacements"),
+        };
+
+        if let Some(last) = ops.last_mut() {
+            let merged_tag = match (last.tag, tag) {
+                (DiffTag::Equal, DiffTag::Equal) => Some(DiffTag::Equal),
+                (DiffTag::Equal, _) | (_, DiffTag::Equal) => None,
+                (DiffTag::Delete, DiffTag::Delete) => Some(DiffTag::Delete),
+                (DiffTag::Insert, DiffTag::Insert) => Some(DiffTag::Insert),
+                _ => Some(DiffTag::Replace),
+            };
+            if let Some(merged_tag) = merged_tag {
+                if last.old.end == old.start && last.new.end == new.start {
+                    last.tag = merged_tag;
+                    last.old.end = old.end;
+                    last.new.end = new.end;
+                    continue;
+                }
+            }
+        }
+
+        ops.push(DiffOp { tag, old, new });
+    }
+
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `old` with `edits` applied, back to front
+    fn apply(old: &str, edits: &[(Range<usize>, String)]) -> String {
+        let mut text = old.to_string();
+        for (range, replacement) in edits.iter().rev() {
+            text.replace_range(range.clone(), replacement);
+        }
+        text
+    }
+
+    fn op(tag: DiffTag, old: Range<usize>, new: Range<usize>) -> DiffOp {
+        DiffOp { tag, old, new }
+    }
+
+    #[test]
+    fn text_edits_touch_only_changed_lines() {
+        let cases = [
+            ("same\ntext\n", "same\ntext\n", vec![]),
+            ("a\nc\n", "a\nb\nc\n", vec![(2..2, "b\n")]),
+            ("a\nb\nc\n", "a\nc\n", vec![(2..4, "")]),
+            ("a\nb\n", "z\na\nb\n", vec![(0..0, "z\n")]),
+            ("a\nb", "a\nb\nc", vec![(2..3, "b\nc")]),
+            ("é\nß\n", "é\nñ\n", vec![(3..6, "ñ\n")]),
+        ];
+        for (old, new, expected) in cases {
+            let edits = text_edits(old, new);
+            let expected: Vec<(Range<usize>, String)> = expected.into_iter().map(|(range, text)| (range, text.to_string())).collect();
+            assert_eq!(edits, expected, "{:?} -> {:?}", old, new);
+            assert_eq!(apply(old, &edits), new);
+        }
+    }
+
+    #[test]
+    fn diff_slices_handles_empty_inputs() {
+        let empty: &[char] = &[];
+        assert_eq!(diff_slices(empty, empty), []);
+        assert_eq!(diff_slices(empty, &['a', 'b']), [op(DiffTag::Insert, 0..0, 0..2)]);
+        assert_eq!(diff_slices(&['a', 'b'], empty), [op(DiffTag::Delete, 0..2, 0..0)]);
+        assert_eq!(diff_slices(&['a', 'b'], &['a', 'b']), [op(DiffTag::Equal, 0..2, 0..2)]);
+    }
+
+    #[test]
+    fn diff_slices_replaces_and_inserts() {
+        let old: Vec<char> = "abc".chars().collect();
+        assert_eq!(diff_slices(&old, &['x', 'y']), [op(DiffTag::Replace, 0..3, 0..2)]);
+        let new: Vec<char> = "abXYc".chars().collect();
+        assert_eq!(diff_slices(&old, &new), [
+            op(DiffTag::Equal, 0..2, 0..2),
+            op(DiffTag::Insert, 2..2, 2..4),
+            op(DiffTag::Equal, 2..3, 4..5),
+        ]);
+        let new: Vec<char> = "aXc".chars().collect();
+        assert_eq!(diff_slices(&old, &new), [
+            op(DiffTag::Equal, 0..1, 0..1),
+            op(DiffTag::Replace, 1..2, 1..2),
+            op(DiffTag::Equal, 2..3, 2..3),
+        ]);
+    }
+
+    #[test]
+    fn word_diff_marks_changed_words() {
+        let none = Vec::<Range<usize>>::new;
+        assert_eq!(word_diff("", ""), (none(), none()));
+        assert_eq!(word_diff("same line", "same line"), (none(), none()));
+        assert_eq!(word_diff("", "new"), (none(), vec![Range { start: 0, end: 3 }]));
+        assert_eq!(word_diff("old words", ""), (vec![Range { start: 0, end: 9 }], none()));
+        assert_eq!(word_diff("old words", "new text"), (vec![0..3, 4..9], vec![0..3, 4..8]));
+
+        let (old, new) = word_diff("let x = 1;", "let mut x = 1;");
+        assert_eq!(old, none());
+        assert_eq!(new.len(), 1);
+        assert_eq!(&"let mut x = 1;"[new[0].clone()].trim(), &"mut");
+        assert_eq!(word_diff("call(a, b)", "call(a, c)"), (vec![Range { start: 8, end: 9 }], vec![Range { start: 8, end: 9 }]));
+        // Ranges are in bytes
+        assert_eq!(word_diff("café au lait", "café noir lait"), (vec![Range { start: 6, end: 8 }], vec![Range { start: 6, end: 10 }]));
+    }
+}
@@ -0,0 +1,606 @@
+use crate::actor::{Actor, ActorMessage, ActorAPI, ApiMethod, ApiParameter, ApiParams, ApiResult};
+use crate::diff::{self, DiffTag};
+use crate::git::{self, GitRepo};
+use async_trait::async_trait;
+use egui::{self, Color32, FontId};
+use egui::text::CCursor;
+use uuid::Uuid;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// Lines of context kept above a change when jumping to it
+const CONTEXT_LINES: usize = 3;
+
+/// How changed lines of one side are painted
+struct ChangeStyle {
+    line_bg: Color32,
+    word_bg: Color32,
+    marker: &'static str,
+}
+
+const DELETED: ChangeStyle = ChangeStyle {
+    line_bg: Color32::from_rgba_premultiplied(60, 16, 16, 60),
+    word_bg: Color32::from_rgba_premultiplied(130, 30, 30, 130),
+    marker: "-",
+};
+
+const ADDED: ChangeStyle = ChangeStyle {
+    line_bg: Color32::from_rgba_premultiplied(16, 50, 16, 60),
+    word_bg: Color32::from_rgba_premultiplied(30, 110, 30, 130),
+    marker: "+",
+};
+
+/// One of the two texts being compared
+#[derive(Debug, Clone)]
+pub struct DiffSide {
+    pub label: String,
+    pub text: String,
+    /// File the text came from, if any
+    pub path: Option<PathBuf>,
+}
+
+impl DiffSide {
+    pub fn text(label: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            text: text.into(),
+            path: None,
+        }
+    }
+
+    /// The file's contents on disk
+    pub fn file(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Ok(Self {
+            label: file_name(path),
+            text,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    /// The file's contents at a git revision (empty if it didn't exist yet)
+    pub fn revision(path: &Path, revision: &str) -> Result<Self> {
+        let repo = GitRepo::discover(path)
+            .ok_or_else(|| anyhow!("{} is not in a git repository", path.display()))?;
+        let rel_path = repo.relative_path(path)
+            .ok_or_else(|| anyhow!("{} is outside the repository", path.display()))?;
+        Ok(Self {
+            label: format!("{} @ {}", file_name(path), revision),
+            text: repo.revision_text(revision, &rel_path).unwrap_or_default(),
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    /// Side described by API parameters: `text`, or `path` with an optional `revision`
+    fn from_params(params: &ApiParams) -> Result<Self> {
+        let side = if let Some(text) = params.get_optional::<String>("text") {
+            Self::text("text", text)
+        } else if let Some(path) = params.get_optional::<String>("path") {
+            match params.get_optional::<String>("revision") {
+                Some(revision) => Self::revision(Path::new(&path), &revision)?,
+                None => Self::file(Path::new(&path))?,
+            }
+        } else {
+            return Err(anyhow!("Pass either 'text' or 'path'"));
+        };
+        Ok(match params.get_optional::<String>("label") {
+            Some(label) => side.with_label(label),
+            None => side,
+        })
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// How the two texts are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLayout {
+    SideBySide,
+    Unified,
+}
+
+/// A line of one side shown on a row, with its changed byte ranges
+#[derive(Debug, Clone)]
+struct RowLine {
+    line: usize,
+    changes: Vec<Range<usize>>,
+}
+
+/// One rendered row: a line pair side by side, or a single line when unified
+#[derive(Debug, Clone)]
+struct DiffRow {
+    left: Option<RowLine>,
+    right: Option<RowLine>,
+    changed: bool,
+}
+
+/// Compares two texts side by side or as a unified diff
+pub struct DiffActor {
+    id: Uuid,
+    left: DiffSide,
+    right: DiffSide,
+    layout: DiffLayout,
+    /// Byte ranges of each line (without its newline), per side
+    left_lines: Vec<Range<usize>>,
+    right_lines: Vec<Range<usize>>,
+    rows: Vec<DiffRow>,
+    /// Row each hunk starts on
+    hunk_rows: Vec<usize>,
+    current_hunk: Option<usize>,
+    /// Row to scroll to on the next frame
+    scroll_to: Option<usize>,
+    font_size: f32,
+}
+
+impl DiffActor {
+    pub fn new(left: DiffSide, right: DiffSide) -> Self {
+        let mut actor = Self {
+            id: Uuid::new_v4(),
+            left,
+            right,
+            layout: DiffLayout::SideBySide,
+            left_lines: Vec::new(),
+            right_lines: Vec::new(),
+            rows: Vec::new(),
+            hunk_rows: Vec::new(),
+            current_hunk: None,
+            scroll_to: None,
+            font_size: 13.0,
+        };
+        actor.rebuild();
+        actor
+    }
+
+    /// Compare a file at a git revision with `text` (e.g. an editor's unsaved buffer)
+    pub fn against_revision(path: &Path, revision: &str, text: &str) -> Result<Self> {
+        let left = DiffSide::revision(path, revision)?;
+        let right = DiffSide {
+            label: file_name(path),
+            text: text.to_string(),
+            path: Some(path.to_path_buf()),
+        };
+        Ok(Self::new(left, right))
+    }
+
+    pub fn set_left(&mut self, side: DiffSide) {
+        self.left = side;
+        self.current_hunk = None;
+        self.rebuild();
+    }
+
+    pub fn set_right(&mut self, side: DiffSide) {
+        self.right = side;
+        self.current_hunk = None;
+        self.rebuild();
+    }
+
+    pub fn set_layout(&mut self, layout: DiffLayout) {
+        if self.layout != layout {
+            self.layout = layout;
+            self.rebuild();
+            self.scroll_to = self.current_hunk.map(|hunk| self.hunk_rows[hunk]);
+        }
+    }
+
+    /// Line hunks turning the left text into the right one
+    pub fn hunks(&self) -> Vec<git::Hunk> {
+        git::hunks(&self.left.text, &self.right.text)
+    }
+
+    /// Move to the next change (wrapping around); false if there are none
+    pub fn next_hunk(&mut self) -> bool {
+        self.go_to_hunk(|current, count| current.map_or(0, |i| (i + 1) % count))
+    }
+
+    /// Move to the previous change (wrapping around); false if there are none
+    pub fn previous_hunk(&mut self) -> bool {
+        self.go_to_hunk(|current, count| current.map_or(count - 1, |i| (i + count - 1) % count))
+    }
+
+    fn go_to_hunk(&mut self, step: impl Fn(Option<usize>, usize) -> usize) -> bool {
+        if self.hunk_rows.is_empty() {
+            return false;
+        }
+        let hunk = step(self.current_hunk, self.hunk_rows.len());
+        self.current_hunk = Some(hunk);
+        self.scroll_to = Some(self.hunk_rows[hunk]);
+        true
+    }
+
+    /// Recompute the rows for the current texts and layout
+    fn rebuild(&mut self) {
+        self.left_lines = line_ranges(&self.left.text);
+        self.right_lines = line_ranges(&self.right.text);
+        let old_lines: Vec<&str> = self.left_lines.iter().map(|r| &self.left.text[r.clone()]).collect();
+        let new_lines: Vec<&str> = self.right_lines.iter().map(|r| &self.right.text[r.clone()]).collect();
+
+        let mut rows = Vec::new();
+        let mut hunk_rows = Vec::new();
+        for op in diff::diff_slices(&old_lines, &new_lines) {
+            if op.tag == DiffTag::Equal {
+                rows.extend(op.old.zip(op.new).map(|(left, right)| DiffRow {
+                    left: Some(RowLine { line: left, changes: Vec::new() }),
+                    right: Some(RowLine { line: right, changes: Vec::new() }),
+                    changed: false,
+                }));
+                continue;
+            }
+            hunk_rows.push(rows.len());
+
+            // Replaced lines are paired up in order for word highlighting
+            let paired = op.old.len().min(op.new.len());
+            let mut left: Vec<RowLine> = op.old.clone().map(|line| RowLine { line, changes: Vec::new() }).collect();
+            let mut right: Vec<RowLine> = op.new.clone().map(|line| RowLine { line, changes: Vec::new() }).collect();
+            for k in 0..paired {
+                let (old_changes, new_changes) = diff::word_diff(old_lines[left[k].line], new_lines[right[k].line]);
+                left[k].changes = old_changes;
+                right[k].changes = new_changes;
+            }
+
+            match self.layout {
+                DiffLayout::SideBySide => {
+                    let mut left = left.into_iter();
+                    let mut right = right.into_iter();
+                    for _ in 0..op.old.len().max(op.new.len()) {
+                        rows.push(DiffRow { left: left.next(), right: right.next(), changed: true });
+                    }
+                }
+                DiffLayout::Unified => {
+                    rows.extend(left.into_iter().map(|line| DiffRow { left: Some(line), right: None, changed: true }));
+                    rows.extend(right.into_iter().map(|line| DiffRow { left: None, right: Some(line), changed: true }));
+                }
+            }
+        }
+
+        self.rows = rows;
+        self.hunk_rows = hunk_rows;
+        if self.current_hunk.is_some_and(|hunk| hunk >= self.hunk_rows.len()) {
+            self.current_hunk = None;
+        }
+    }
+
+    fn left_line(&self, line: usize) -> &str {
+        &self.left.text[self.left_lines[line].clone()]
+    }
+
+    fn right_line(&self, line: usize) -> &str {
+        &self.right.text[self.right_lines[line].clone()]
+    }
+
+    fn handle_keys(&mut self, ui: &egui::Ui) {
+        if ui.ctx().wants_keyboard_input() || !ui.ui_contains_pointer() {
+            return;
+        }
+        let (next, previous) = ui.input(|i| (
+            i.key_pressed(egui::Key::F7) && !i.modifiers.shift,
+            i.key_pressed(egui::Key::F7) && i.modifiers.shift,
+        ));
+        if next {
+            self.next_hunk();
+        } else if previous {
+            self.previous_hunk();
+        }
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        let mut layout = self.layout;
+        ui.horizontal(|ui| {
+            let paths: Vec<String> = [&self.left, &self.right].iter()
+                .filter_map(|side| side.path.as_ref().map(|path| path.display().to_string()))
+                .collect();
+            let title = ui.label(format!("{} ↔ {}", self.left.label, self.right.label));
+            if !paths.is_empty() {
+                title.on_hover_text(paths.join("\n"));
+            }
+            ui.separator();
+            if ui.small_button("▲").on_hover_text("Previous change (Shift+F7)").clicked() {
+                self.previous_hunk();
+            }
+            if ui.small_button("▼").on_hover_text("Next change (F7)").clicked() {
+                self.next_hunk();
+            }
+            match self.current_hunk {
+                Some(hunk) => ui.label(format!("Change {} of {}", hunk + 1, self.hunk_rows.len())),
+                None if self.hunk_rows.is_empty() => ui.weak("No changes"),
+                None => ui.label(format!("{} changes", self.hunk_rows.len())),
+            };
+            ui.separator();
+            ui.selectable_value(&mut layout, DiffLayout::SideBySide, "Side by side");
+            ui.selectable_value(&mut layout, DiffLayout::Unified, "Inline");
+        });
+        self.set_layout(layout);
+    }
+
+    fn paint_row(&self, ui: &egui::Ui, rect: egui::Rect, row: &DiffRow, font: &FontId, current: bool) {
+        match self.layout {
+            DiffLayout::SideBySide => {
+                let middle = rect.center().x;
+                let left_rect = egui::Rect::from_min_max(rect.min, egui::pos2(middle - 1.0, rect.max.y));
+                let right_rect = egui::Rect::from_min_max(egui::pos2(middle + 1.0, rect.min.y), rect.max);
+                self.paint_cell(ui, left_rect, row.left.as_ref().map(|l| (l, self.left_line(l.line))),
+                    row.changed, &DELETED, font);
+                self.paint_cell(ui, right_rect, row.right.as_ref().map(|l| (l, self.right_line(l.line))),
+                    row.changed, &ADDED, font);
+                ui.painter().vline(middle, rect.y_range(), ui.visuals().widgets.noninteractive.bg_stroke);
+            }
+            DiffLayout::Unified => match (&row.left, &row.right) {
+                (Some(left), None) => self.paint_cell(ui, rect, Some((left, self.left_line(left.line))),
+                    true, &DELETED, font),
+                (_, Some(right)) => self.paint_cell(ui, rect, Some((right, self.right_line(right.line))),
+                    row.changed, &ADDED, font),
+                (None, None) => {}
+            },
+        }
+        if current {
+            ui.painter().vline(rect.left() + 1.0, rect.y_range(), egui::Stroke::new(2.0, ui.visuals().selection.stroke.color));
+        }
+    }
+
+    /// Paint a line number, change marker and the text, with changed words highlighted
+    fn paint_cell(
+        &self,
+        ui: &egui::Ui,
+        rect: egui::Rect,
+        line: Option<(&RowLine, &str)>,
+        changed: bool,
+        style: &ChangeStyle,
+        font: &FontId,
+    ) {
+        let painter = ui.painter_at(rect);
+        let Some((row_line, text)) = line else {
+            // Filler opposite lines that only exist on the other side
+            painter.rect_filled(rect, 0.0, ui.visuals().faint_bg_color);
+            return;
+        };
+        if changed {
+            painter.rect_filled(rect, 0.0, style.line_bg);
+        }
+
+        let char_width = ui.fonts(|f| f.glyph_width(font, '0'));
+        let gutter = char_width * 6.0;
+        let weak = ui.visuals().weak_text_color();
+        painter.text(egui::pos2(rect.left() + gutter - char_width * 1.5, rect.center().y), egui::Align2::RIGHT_CENTER,
+            (row_line.line + 1).to_string(), font.clone(), weak);
+        if changed {
+            painter.text(egui::pos2(rect.left() + gutter - char_width * 0.5, rect.center().y), egui::Align2::CENTER_CENTER,
+                style.marker, font.clone(), weak);
+        }
+
+        let text_color = ui.visuals().text_color();
+        let galley = ui.fonts(|f| f.layout_no_wrap(text.to_string(), font.clone(), text_color));
+        let origin = egui::pos2(rect.left() + gutter + char_width, rect.center().y - galley.size().y / 2.0);
+        for change in &row_line.changes {
+            let x = |offset: usize| origin.x + galley.pos_from_ccursor(CCursor::new(text[..offset].chars().count())).left();
+            let word_rect = egui::Rect::from_x_y_ranges(x(change.start)..=x(change.end), rect.y_range());
+            painter.rect_filled(word_rect, 2.0, style.word_bg);
+        }
+        painter.galley(origin, galley, text_color);
+    }
+}
+
+/// Byte range of each line of `text`, excluding line endings
+fn line_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            let range = start..start + line.trim_end_matches(['\n', '\r']).len();
+            start += line.len();
+            range
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Actor for DiffActor {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> String {
+        format!("Diff: {} ↔ {}", self.left.label, self.right.label)
+    }
+
+    async fn handle_message(&mut self, message: ActorMessage) -> anyhow::Result<()> {
+        if let ActorMessage::KeyEvent { key: egui::Key::F7, modifiers } = message {
+            if modifiers.shift {
+                self.previous_hunk();
+            } else {
+                self.next_hunk();
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &egui::Context) {}
+
+    fn render(&mut self, ui: &mut egui::Ui) {
+        self.handle_keys(ui);
+        self.show_toolbar(ui);
+        ui.separator();
+
+        let font = FontId::monospace(self.font_size);
+        let row_height = ui.fonts(|f| f.row_height(&font)) + 2.0;
+        let row_step = row_height + ui.spacing().item_spacing.y;
+
+        // One scroll area for both columns keeps them in step
+        let mut scroll = egui::ScrollArea::vertical()
+            .id_salt(self.id)
+            .auto_shrink([false, false]);
+        if let Some(row) = self.scroll_to.take() {
+            scroll = scroll.vertical_scroll_offset(row.saturating_sub(CONTEXT_LINES) as f32 * row_step);
+        }
+
+        let current_rows = self.current_hunk.map(|hunk| {
+            let start = self.hunk_rows[hunk];
+            let end = self.rows[start..].iter().position(|row| !row.changed).map_or(self.rows.len(), |n| start + n);
+            start..end
+        });
+        scroll.show_rows(ui, row_height, self.rows.len(), |ui, visible| {
+            for index in visible {
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), row_height), egui::Sense::hover());
+                let current = current_rows.as_ref().is_some_and(|rows| rows.contains(&index));
+                self.paint_row(ui, rect, &self.rows[index], &font, current);
+            }
+        });
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl ActorAPI for DiffActor {
+    fn actor_type(&self) -> String {
+        "DiffActor".to_string()
+    }
+
+    fn get_api_methods(&self) -> Vec<ApiMethod> {
+        let side_parameters = || vec![
+            ApiParameter {
+                name: "text".to_string(),
+                param_type: "string".to_string(),
+                description: "Text to compare".to_string(),
+                required: false,
+                default_value: None,
+            },
+            ApiParameter {
+                name: "path".to_string(),
+                param_type: "string".to_string(),
+                description: "File to compare (when no text is given)".to_string(),
+                required: false,
+                default_value: None,
+            },
+            ApiParameter {
+                name: "revision".to_string(),
+                param_type: "string".to_string(),
+                description: "Git revision of the file, e.g. HEAD (defaults to the file on disk)".to_string(),
+                required: false,
+                default_value: None,
+            },
+            ApiParameter {
+                name: "label".to_string(),
+                param_type: "string".to_string(),
+                description: "Title shown for this side".to_string(),
+                required: false,
+                default_value: None,
+            },
+        ];
+        vec![
+            ApiMethod {
+                name: "set_left".to_string(),
+                description: "Set the original (left) side of the diff".to_string(),
+                parameters: side_parameters(),
+                return_type: "void".to_string(),
+                category: "diff".to_string(),
+            },
+            ApiMethod {
+                name: "set_right".to_string(),
+                description: "Set the modified (right) side of the diff".to_string(),
+                parameters: side_parameters(),
+                return_type: "void".to_string(),
+                category: "diff".to_string(),
+            },
+            ApiMethod {
+                name: "set_layout".to_string(),
+                description: "Show the diff side by side or inline".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "layout".to_string(),
+                        param_type: "string".to_string(),
+                        description: "side_by_side or unified (toggles when omitted)".to_string(),
+                        required: false,
+                        default_value: None,
+                    },
+                ],
+                return_type: "void".to_string(),
+                category: "display".to_string(),
+            },
+            ApiMethod {
+                name: "next_hunk".to_string(),
+                description: "Jump to the next change".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "navigation".to_string(),
+            },
+            ApiMethod {
+                name: "previous_hunk".to_string(),
+                description: "Jump to the previous change".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "navigation".to_string(),
+            },
+            ApiMethod {
+                name: "get_hunks".to_string(),
+                description: "List the changed line ranges".to_string(),
+                parameters: vec![],
+                return_type: "array".to_string(),
+                category: "info".to_string(),
+            },
+        ]
+    }
+
+    fn execute_api_method(&mut self, method: &str, params: ApiParams) -> Result<ApiResult> {
+        match method {
+            "set_left" => {
+                self.set_left(DiffSide::from_params(&params)?);
+                Ok(ApiResult::Success)
+            },
+            "set_right" => {
+                self.set_right(DiffSide::from_params(&params)?);
+                Ok(ApiResult::Success)
+            },
+            "set_layout" => {
+                let layout = match params.get_optional::<DiffLayout>("layout") {
+                    Some(layout) => layout,
+                    None if self.layout == DiffLayout::SideBySide => DiffLayout::Unified,
+                    None => DiffLayout::SideBySide,
+                };
+                self.set_layout(layout);
+                Ok(ApiResult::Success)
+            },
+            "next_hunk" => Ok(ApiResult::Value(serde_json::Value::Bool(self.next_hunk()))),
+            "previous_hunk" => Ok(ApiResult::Value(serde_json::Value::Bool(self.previous_hunk()))),
+            "get_hunks" => Ok(ApiResult::Value(serde_json::to_value(self.hunks())?)),
+            _ => Err(anyhow!("Unknown method: {}", method))
+        }
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        vec![
+            "diff".to_string(),
+            "word_diff".to_string(),
+        ]
+    }
+
+    fn get_state(&self) -> HashMap<String, serde_json::Value> {
+        let mut state = HashMap::new();
+        state.insert("left".to_string(), serde_json::Value::String(self.left.label.clone()));
+        state.insert("right".to_string(), serde_json::Value::String(self.right.label.clone()));
+        state.insert("left_path".to_string(), serde_json::json!(self.left.path));
+        state.insert("right_path".to_string(), serde_json::json!(self.right.path));
+        state.insert("layout".to_string(), serde_json::json!(self.layout));
+        state.insert("hunk_count".to_string(), serde_json::json!(self.hunk_rows.len()));
+        state.insert("current_hunk".to_string(), serde_json::json!(self.current_hunk));
+        state
+    }
+}
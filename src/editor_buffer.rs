@@ -0,0 +1,615 @@
+use serde::{Serialize, Deserialize};
+use std::ops::Range;
+
+/// A single recorded change to the buffer (byte offsets)
+#[derive(Debug, Clone)]
+pub struct BufferEdit {
+    pub offset: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl BufferEdit {
+    fn inserted_end(&self) -> usize {
+        self.offset + self.inserted.len()
+    }
+}
+
+/// A change to the text, with row/column points for incremental parsers
+///
+/// Points are `(row, byte column)`; `old_end` refers to the text before the
+/// change and `new_end` to the text after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextChange {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+    pub start_point: (usize, usize),
+    pub old_end_point: (usize, usize),
+    pub new_end_point: (usize, usize),
+}
+
+/// A selection as byte offsets: `anchor` is where it was started and
+/// `cursor` where the caret is (the same offset for a plain caret)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    pub anchor: usize,
+    pub cursor: usize,
+}
+
+impl Selection {
+    pub fn new(anchor: usize, cursor: usize) -> Self {
+        Self { anchor, cursor }
+    }
+
+    pub fn caret(offset: usize) -> Self {
+        Self::new(offset, offset)
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.anchor.min(self.cursor)..self.anchor.max(self.cursor)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.cursor
+    }
+}
+
+/// Selects the range with the caret at its end
+impl From<Range<usize>> for Selection {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+/// Merge selections that overlap (or carets at the same offset)
+///
+/// Selection lists keep the primary selection last; the others come back
+/// sorted by position, and a merged selection is primary if any part was.
+pub fn normalize_selections(selections: Vec<Selection>) -> Vec<Selection> {
+    let primary = selections.len().saturating_sub(1);
+    let mut sorted: Vec<(Selection, bool)> = selections.into_iter()
+        .enumerate()
+        .map(|(i, selection)| (selection, i == primary))
+        .collect();
+    sorted.sort_by_key(|(selection, _)| (selection.range().start, selection.range().end));
+
+    let mut merged: Vec<(Selection, bool)> = Vec::with_capacity(sorted.len());
+    for (selection, is_primary) in sorted {
+        match merged.last_mut() {
+            Some((last, last_primary)) if selection.range().start < last.range().end
+                || selection.range().start == last.range().start =>
+            {
+                let range = last.range().start..selection.range().end.max(last.range().end);
+                // Keep the direction of whichever selection was primary
+                let backwards = if is_primary { selection.cursor < selection.anchor } else { last.cursor < last.anchor };
+                *last = if backwards { Selection::new(range.end, range.start) } else { range.into() };
+                *last_primary |= is_primary;
+            }
+            _ => merged.push((selection, is_primary)),
+        }
+    }
+    let primary = merged.iter().position(|(_, is_primary)| *is_primary);
+    let mut selections: Vec<Selection> = merged.iter().map(|(selection, _)| *selection).collect();
+    if let Some(primary) = primary {
+        let selection = selections.remove(primary);
+        selections.push(selection);
+    }
+    selections
+}
+
+/// Edits that are undone/redone together
+#[derive(Debug, Clone, Default)]
+struct EditGroup {
+    edits: Vec<BufferEdit>,
+    /// Selections before and after the edits, for multi-cursor changes
+    selections: Option<(Vec<Selection>, Vec<Selection>)>,
+}
+
+/// Text buffer backing the code editor
+///
+/// All mutations are recorded as edits so undo/redo history survives
+/// programmatic changes such as formatting. Offsets are byte offsets.
+#[derive(Debug, Clone, Default)]
+pub struct EditorBuffer {
+    text: String,
+    undo_stack: Vec<EditGroup>,
+    redo_stack: Vec<EditGroup>,
+    /// Whether the next edit may be merged into the last undo group
+    group_open: bool,
+    version: u64,
+    saved_version: u64,
+    /// Changes since the last `take_changes`, oldest first
+    changes: Vec<TextChange>,
+    /// Line starts, kept up to date by every edit
+    lines: LineIndex,
+}
+
+impl EditorBuffer {
+    pub fn new(text: String) -> Self {
+        Self {
+            lines: LineIndex::new(&text),
+            text,
+            ..Default::default()
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Number of lines, as the editor shows them (a trailing newline starts an empty line)
+    pub fn line_count(&self) -> usize {
+        self.lines.line_count()
+    }
+
+    /// Line starts of the current text
+    pub fn line_index(&self) -> &LineIndex {
+        &self.lines
+    }
+
+    /// Monotonic counter bumped on every change
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Whether the buffer changed since the last `mark_saved`
+    pub fn is_modified(&self) -> bool {
+        self.version != self.saved_version
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_version = self.version;
+    }
+
+    /// Drain the changes made since the last call
+    pub fn take_changes(&mut self) -> Vec<TextChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Replace the whole text as a single undoable edit
+    pub fn set_text(&mut self, text: &str) {
+        let len = self.text.len();
+        self.apply_edits(vec![(0..len, text.to_string())]);
+    }
+
+    /// Replace a byte range, merging with the previous edit when it continues it
+    pub fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        let edit = self.apply_raw(range, text);
+        self.redo_stack.clear();
+
+        let continues_last = self.group_open
+            && !text.contains('\n')
+            && self.undo_stack.last()
+                .and_then(|g| g.edits.last())
+                .map(|last| {
+                    // Typing forward, or deleting backwards from the last edit
+                    edit.offset == last.inserted_end()
+                        || (edit.inserted.is_empty() && edit.offset + edit.removed.len() == last.offset)
+                })
+                .unwrap_or(false);
+
+        if continues_last {
+            if let Some(group) = self.undo_stack.last_mut() {
+                group.edits.push(edit);
+            }
+        } else {
+            self.undo_stack.push(EditGroup { edits: vec![edit], selections: None });
+        }
+        self.group_open = true;
+    }
+
+    /// Make the next edit start a new undo step instead of merging
+    pub fn break_undo_group(&mut self) {
+        self.group_open = false;
+    }
+
+    /// Apply several non-overlapping replacements as one undo step
+    ///
+    /// Ranges refer to the text before any of the edits are applied.
+    pub fn apply_edits(&mut self, edits: Vec<(Range<usize>, String)>) {
+        if edits.is_empty() {
+            return;
+        }
+        let edits = self.apply_all(edits);
+        self.undo_stack.push(EditGroup { edits, selections: None });
+        self.redo_stack.clear();
+        self.group_open = false;
+    }
+
+    /// Apply one replacement per cursor as one undo step that restores
+    /// every cursor
+    ///
+    /// `before` and `after` are the selections around the change, primary
+    /// last. Single-line edits made from where the last ones left the
+    /// cursors (typing) merge into the same step.
+    pub fn apply_selection_edits(&mut self, edits: Vec<(Range<usize>, String)>, before: Vec<Selection>, after: Vec<Selection>) {
+        if edits.is_empty() {
+            return;
+        }
+        let single_line = edits.iter().all(|(_, text)| !text.contains('\n'));
+        let continues_last = self.group_open
+            && single_line
+            && self.undo_stack.last()
+                .and_then(|group| group.selections.as_ref())
+                .is_some_and(|(_, last_after)| *last_after == before);
+
+        let edits = self.apply_all(edits);
+        match self.undo_stack.last_mut() {
+            Some(group) if continues_last => {
+                group.edits.extend(edits);
+                group.selections = group.selections.take().map(|(before, _)| (before, after));
+            }
+            _ => self.undo_stack.push(EditGroup { edits, selections: Some((before, after)) }),
+        }
+        self.redo_stack.clear();
+        self.group_open = single_line;
+    }
+
+    fn apply_all(&mut self, mut edits: Vec<(Range<usize>, String)>) -> Vec<BufferEdit> {
+        // Apply back to front so earlier ranges stay valid
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        edits.into_iter()
+            .map(|(range, text)| self.apply_raw(range, &text))
+            .collect()
+    }
+
+    /// Undo the last group, returning where the cursor should go
+    pub fn undo(&mut self) -> Option<usize> {
+        self.undo_selections().and_then(|selections| selections.last().map(|s| s.cursor))
+    }
+
+    /// Redo the last undone group, returning where the cursor should go
+    pub fn redo(&mut self) -> Option<usize> {
+        self.redo_selections().and_then(|selections| selections.last().map(|s| s.cursor))
+    }
+
+    /// Undo the last group, returning the selections to restore: the ones
+    /// from before a multi-cursor change, otherwise a caret where it was
+    pub fn undo_selections(&mut self) -> Option<Vec<Selection>> {
+        let group = self.undo_stack.pop()?;
+        let mut cursor = 0;
+        for edit in group.edits.iter().rev() {
+            self.splice(edit.offset..edit.inserted_end(), &edit.removed);
+            cursor = edit.offset + edit.removed.len();
+        }
+        let selections = match &group.selections {
+            Some((before, _)) => before.clone(),
+            None => vec![Selection::caret(cursor)],
+        };
+        self.redo_stack.push(group);
+        self.group_open = false;
+        Some(selections)
+    }
+
+    /// Redo the last undone group, returning the selections to restore
+    pub fn redo_selections(&mut self) -> Option<Vec<Selection>> {
+        let group = self.redo_stack.pop()?;
+        let mut cursor = 0;
+        for edit in &group.edits {
+            self.splice(edit.offset..edit.offset + edit.removed.len(), &edit.inserted);
+            cursor = edit.inserted_end();
+        }
+        let selections = match &group.selections {
+            Some((_, after)) => after.clone(),
+            None => vec![Selection::caret(cursor)],
+        };
+        self.undo_stack.push(group);
+        self.group_open = false;
+        Some(selections)
+    }
+
+    /// Map an offset from before a set of edits to after them
+    ///
+    /// `edits` uses the same convention as [`EditorBuffer::apply_edits`].
+    pub fn map_offset(offset: usize, edits: &[(Range<usize>, String)]) -> usize {
+        let mut delta: isize = 0;
+        for (range, text) in edits {
+            if range.end <= offset {
+                delta += text.len() as isize - range.len() as isize;
+            } else if range.start < offset {
+                // Offset falls inside a replaced region: keep its relative position
+                let within = (offset - range.start).min(text.len());
+                return (range.start as isize + delta) as usize + within;
+            }
+        }
+        (offset as isize + delta).max(0) as usize
+    }
+
+    /// Clamp to the text length and back off to a char boundary
+    fn clamp_offset(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    fn apply_raw(&mut self, range: Range<usize>, text: &str) -> BufferEdit {
+        let start = self.clamp_offset(range.start);
+        let end = self.clamp_offset(range.end.max(start));
+        let removed = self.text[start..end].to_string();
+        self.splice(start..end, text);
+        BufferEdit {
+            offset: start,
+            removed,
+            inserted: text.to_string(),
+        }
+    }
+
+    fn splice(&mut self, range: Range<usize>, text: &str) {
+        let line = self.lines.line(range.start);
+        let start_point = (line, range.start - self.lines.line_starts[line]);
+        self.changes.push(TextChange {
+            start: range.start,
+            old_end: range.end,
+            new_end: range.start + text.len(),
+            start_point,
+            old_end_point: point_after(start_point, &self.text[range.clone()]),
+            new_end_point: point_after(start_point, text),
+        });
+
+        self.lines.edit(range.clone(), text);
+        self.text.replace_range(range, text);
+        self.version += 1;
+    }
+}
+
+/// Point reached by inserting `text` at `start`
+fn point_after(start: (usize, usize), text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(last) => (start.0 + text.bytes().filter(|&b| b == b'\n').count(), text.len() - last - 1),
+        None => (start.0, start.1 + text.len()),
+    }
+}
+
+/// Zero-based line and column (in chars) within a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A line/column range, end exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextRange {
+    pub start: TextPosition,
+    pub end: TextPosition,
+}
+
+/// A selection as line/column positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSelection {
+    pub anchor: TextPosition,
+    pub cursor: TextPosition,
+}
+
+/// Line start offsets for fast offset <-> line/column conversion
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { line_starts }
+    }
+
+    pub fn position(&self, text: &str, offset: usize) -> TextPosition {
+        let offset = offset.min(text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = text.get(line_start..offset).map(|s| s.chars().count()).unwrap_or(0);
+        TextPosition { line, column }
+    }
+
+    /// Zero-based line containing `offset`
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// Byte offset where `line` starts, if the text has that many lines
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Follow `text` replacing the byte range `range`: the lines inside it
+    /// are recounted and the ones after it shifted
+    pub fn edit(&mut self, range: Range<usize>, text: &str) {
+        let first = self.line_starts.partition_point(|&start| start <= range.start);
+        let last = self.line_starts.partition_point(|&start| start <= range.end);
+        let inserted = text.match_indices('\n').map(|(i, _)| range.start + i + 1);
+        let count = inserted.clone().count();
+        self.line_starts.splice(first..last, inserted);
+        let delta = text.len() as isize - range.len() as isize;
+        for start in &mut self.line_starts[first + count..] {
+            *start = (*start as isize + delta) as usize;
+        }
+    }
+
+    pub fn range(&self, text: &str, range: &Range<usize>) -> TextRange {
+        TextRange {
+            start: self.position(text, range.start),
+            end: self.position(text, range.end),
+        }
+    }
+
+    pub fn selection(&self, text: &str, selection: &Selection) -> TextSelection {
+        TextSelection {
+            anchor: self.position(text, selection.anchor),
+            cursor: self.position(text, selection.cursor),
+        }
+    }
+}
+
+/// Byte offset of the `char_index`th char of `text` (its length past the end)
+pub fn char_to_byte(text: &str, char_index: usize) -> usize {
+    text.char_indices().nth(char_index).map(|(i, _)| i).unwrap_or(text.len())
+}
+
+/// Chars of `text` before `offset`, rounded down to a char boundary
+pub fn byte_to_char(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    text[..offset].chars().count()
+}
+
+/// Whole lines of a buffer handed to the text widget, so it only lays out
+/// what's near the view; edits go through the buffer and its undo history
+pub struct BufferWindow<'a> {
+    buffer: &'a mut EditorBuffer,
+    range: Range<usize>,
+}
+
+impl<'a> BufferWindow<'a> {
+    pub fn new(buffer: &'a mut EditorBuffer, range: Range<usize>) -> Self {
+        Self { buffer, range }
+    }
+
+    /// Where the window is in the buffer, after any edits made through it
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    fn offset(&self, char_index: usize) -> usize {
+        self.range.start + char_to_byte(&self.buffer.text[self.range.clone()], char_index)
+    }
+}
+
+impl egui::TextBuffer for BufferWindow<'_> {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        &self.buffer.text[self.range.clone()]
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        let offset = self.offset(char_index);
+        self.buffer.replace_range(offset..offset, text);
+        self.range.end += text.len();
+        text.chars().count()
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        let start = self.offset(char_range.start);
+        let end = self.offset(char_range.end);
+        if start < end {
+            self.buffer.replace_range(start..end, "");
+            self.range.end -= end - start;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_index_follows_edits() {
+        let mut buffer = EditorBuffer::new("one\ntwo\nthree\n".to_string());
+        let edits: [(Range<usize>, &str); 5] = [
+            (4..4, "inserted\n"),
+            (0..3, "a\nb\nc"),
+            (6..20, ""),
+            (0..0, "\n"),
+            (3..5, "é\n\n"),
+        ];
+        for (range, text) in edits {
+            buffer.replace_range(range, text);
+            assert_eq!(buffer.line_index().line_starts, LineIndex::new(buffer.text()).line_starts, "{:?}", buffer.text());
+        }
+        assert_eq!(buffer.line_count(), buffer.text().split('\n').count());
+
+        buffer.undo();
+        assert_eq!(buffer.line_index().line_starts, LineIndex::new(buffer.text()).line_starts);
+    }
+
+    #[test]
+    fn changes_carry_row_and_column_points() {
+        let mut buffer = EditorBuffer::new("fn a() {}\nfn b() {}\n".to_string());
+        buffer.take_changes();
+        buffer.replace_range(13..15, "x\ny");
+        assert_eq!(buffer.take_changes(), vec![TextChange {
+            start: 13,
+            old_end: 15,
+            new_end: 16,
+            start_point: (1, 3),
+            old_end_point: (1, 5),
+            new_end_point: (2, 1),
+        }]);
+    }
+
+    #[test]
+    fn overlapping_selections_merge_with_the_primary_last() {
+        let selections = vec![
+            Selection::new(8, 12),
+            Selection::caret(2),
+            Selection::caret(2),
+            Selection::new(10, 6),
+            Selection::caret(20),
+            Selection::new(4, 0),
+        ];
+        assert_eq!(normalize_selections(selections), [
+            // Going the way of the one that starts first
+            Selection::new(12, 6),
+            Selection::caret(20),
+            // The primary swallowed the duplicate carets and kept its direction
+            Selection::new(4, 0),
+        ]);
+
+        // Touching selections stay apart
+        let touching = vec![Selection::new(0, 3), Selection::new(3, 5)];
+        assert_eq!(normalize_selections(touching.clone()), touching);
+        assert_eq!(normalize_selections(Vec::new()), Vec::new());
+    }
+
+    #[test]
+    fn selection_edits_undo_as_one_step_and_typing_merges() {
+        let mut buffer = EditorBuffer::new("a b c".to_string());
+        let carets = |offsets: &[usize]| offsets.iter().map(|&offset| Selection::caret(offset)).collect::<Vec<_>>();
+
+        let edits = vec![(1..1, "x".to_string()), (3..3, "x".to_string()), (5..5, "x".to_string())];
+        buffer.apply_selection_edits(edits, carets(&[1, 3, 5]), carets(&[2, 5, 8]));
+        let edits = vec![(2..2, "y".to_string()), (5..5, "y".to_string()), (8..8, "y".to_string())];
+        buffer.apply_selection_edits(edits, carets(&[2, 5, 8]), carets(&[3, 7, 11]));
+        assert_eq!(buffer.text(), "axy bxy cxy");
+
+        // A line break starts its own step
+        buffer.apply_selection_edits(vec![(3..3, "\n".to_string())], carets(&[3]), carets(&[4]));
+        assert_eq!(buffer.undo_selections(), Some(carets(&[3])));
+        assert_eq!(buffer.undo_selections(), Some(carets(&[1, 3, 5])));
+        assert_eq!(buffer.text(), "a b c");
+        assert_eq!(buffer.redo_selections(), Some(carets(&[3, 7, 11])));
+        assert_eq!(buffer.text(), "axy bxy cxy");
+    }
+
+    #[test]
+    fn offsets_map_through_edits() {
+        let edits = vec![(2..4, "long".to_string()), (6..9, String::new()), (9..9, "!".to_string())];
+        assert_eq!(EditorBuffer::map_offset(0, &edits), 0);
+        assert_eq!(EditorBuffer::map_offset(2, &edits), 2);
+        assert_eq!(EditorBuffer::map_offset(4, &edits), 6);
+        assert_eq!(EditorBuffer::map_offset(5, &edits), 7);
+        // Inside a replacement it keeps its place, up to the new text's end
+        assert_eq!(EditorBuffer::map_offset(3, &edits), 3);
+        assert_eq!(EditorBuffer::map_offset(8, &edits), 8);
+        assert_eq!(EditorBuffer::map_offset(9, &edits), 9);
+        assert_eq!(EditorBuffer::map_offset(12, &edits), 12);
+    }
+}
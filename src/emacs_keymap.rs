@@ -0,0 +1,568 @@
+use crate::keymap::{
+    self, line_end, line_start, next_char, prev_char, Key, KeyOutcome, KeyPress, Keymap,
+    KeymapContext, KeymapRequest,
+};
+use std::ops::Range;
+
+/// Lines moved by C-v / M-v
+const PAGE: isize = 30;
+/// Entries kept in the kill ring
+const KILL_RING_SIZE: usize = 60;
+
+/// Incremental search in progress
+struct Isearch {
+    forward: bool,
+    query: String,
+    /// Where the search started, restored by C-g
+    origin: usize,
+    /// Current match
+    found: Option<Range<usize>>,
+}
+
+/// Emacs emulation: kill ring, mark and region, `C-x` prefix commands,
+/// incremental search and an `M-x` minibuffer
+///
+/// `M-x` input is handed to the command palette.
+pub struct EmacsKeymap {
+    /// Most recent kill last
+    kill_ring: Vec<String>,
+    /// Whether the previous command killed text, so the next kill appends
+    last_was_kill: bool,
+    /// Text inserted by the previous yank and the ring entry it came from, for M-y
+    last_yank: Option<(Range<usize>, usize)>,
+    /// Set mark; the region is active while this is `Some`
+    mark: Option<usize>,
+    /// `C-x` was pressed
+    prefix: bool,
+    isearch: Option<Isearch>,
+    last_isearch: Option<String>,
+    /// `M-x` input being typed
+    minibuffer: Option<String>,
+    /// Column C-n / C-p try to keep
+    goal_column: Option<usize>,
+    message: Option<String>,
+}
+
+impl EmacsKeymap {
+    pub fn new() -> Self {
+        Self {
+            kill_ring: Vec::new(),
+            last_was_kill: false,
+            last_yank: None,
+            mark: None,
+            prefix: false,
+            isearch: None,
+            last_isearch: None,
+            minibuffer: None,
+            goal_column: None,
+            message: None,
+        }
+    }
+
+    /// Add killed text to the ring, appending to the previous kill if the last
+    /// command was also a kill
+    fn kill(&mut self, ctx: &mut KeymapContext, range: Range<usize>, backward: bool) {
+        if range.is_empty() {
+            return;
+        }
+        let killed = ctx.text()[range.clone()].to_string();
+        match self.kill_ring.last_mut() {
+            Some(last) if self.last_was_kill => {
+                if backward {
+                    last.insert_str(0, &killed);
+                } else {
+                    last.push_str(&killed);
+                }
+            }
+            _ => {
+                self.kill_ring.push(killed);
+                if self.kill_ring.len() > KILL_RING_SIZE {
+                    self.kill_ring.remove(0);
+                }
+            }
+        }
+        ctx.replace(range.clone(), "");
+        ctx.set_cursor(range.start);
+        self.last_was_kill = true;
+    }
+
+    /// The active region, if any
+    fn region(&self, ctx: &KeymapContext) -> Option<Range<usize>> {
+        self.mark.map(|mark| mark.min(ctx.cursor)..mark.max(ctx.cursor))
+    }
+
+    fn yank(&mut self, ctx: &mut KeymapContext, index: usize) {
+        let Some(text) = self.kill_ring.get(index).cloned() else {
+            self.message = Some("Kill ring is empty".to_string());
+            return;
+        };
+        let start = ctx.cursor;
+        ctx.replace(start..start, &text);
+        self.last_yank = Some((start..start + text.len(), index));
+    }
+
+    fn undo(&mut self, ctx: &mut KeymapContext, redo: bool) {
+        self.mark = None;
+        let cursor = if redo { ctx.buffer.redo() } else { ctx.buffer.undo() };
+        match cursor {
+            Some(cursor) => ctx.set_cursor(cursor),
+            None if redo => self.message = Some("No further redo information".to_string()),
+            None => self.message = Some("No further undo information".to_string()),
+        }
+    }
+
+    fn handle_isearch_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> Option<KeyOutcome> {
+        let search = self.isearch.as_mut()?;
+
+        let repeat = if key.is_ctrl('s') {
+            Some(true)
+        } else if key.is_ctrl('r') {
+            Some(false)
+        } else {
+            None
+        };
+
+        if let Some(forward) = repeat {
+            if search.query.is_empty() {
+                search.query = self.last_isearch.clone().unwrap_or_default();
+            }
+            let from = match (&search.found, forward) {
+                (Some(found), true) => next_char(ctx.text(), found.start),
+                (Some(found), false) => found.start.saturating_sub(1),
+                (None, _) => ctx.cursor,
+            };
+            search.forward = forward;
+            search.found = isearch_from(ctx.text(), &search.query, from, forward)
+                .or(search.found.clone());
+        } else if key.is_ctrl('g') {
+            let origin = search.origin;
+            self.isearch = None;
+            ctx.set_cursor(origin);
+            self.message = Some("Quit".to_string());
+            return Some(KeyOutcome::Handled);
+        } else if key.key == Key::Backspace {
+            search.query.pop();
+            search.found = isearch_from(ctx.text(), &search.query, search.origin, search.forward);
+        } else if let Some(c) = key.plain_char() {
+            search.query.push(c);
+            let from = search.found.as_ref().map(|f| f.start).unwrap_or(search.origin);
+            search.found = isearch_from(ctx.text(), &search.query, from, search.forward);
+        } else {
+            // Any other key ends the search at the match and runs normally
+            let search = self.isearch.take()?;
+            if !search.query.is_empty() {
+                self.last_isearch = Some(search.query);
+            }
+            ctx.set_cursor(ctx.cursor);
+            self.mark = None;
+            return if key.key == Key::Enter {
+                Some(KeyOutcome::Handled)
+            } else {
+                None
+            };
+        }
+
+        let search = self.isearch.as_ref()?;
+        if let Some(found) = &search.found {
+            // Highlight the match with point at its far end
+            if search.forward {
+                ctx.anchor = found.start;
+                ctx.cursor = found.end;
+            } else {
+                ctx.anchor = found.end;
+                ctx.cursor = found.start;
+            }
+        }
+        Some(KeyOutcome::Handled)
+    }
+
+    fn handle_minibuffer_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> Option<KeyOutcome> {
+        let input = self.minibuffer.as_mut()?;
+        if key.key == Key::Enter {
+            let command = self.minibuffer.take().unwrap_or_default();
+            if !command.trim().is_empty() {
+                ctx.requests.push(KeymapRequest::PaletteCommand(command.trim().to_string()));
+            }
+        } else if key.is_ctrl('g') || key.key == Key::Escape {
+            self.minibuffer = None;
+            self.message = Some("Quit".to_string());
+        } else if key.key == Key::Backspace {
+            input.pop();
+        } else if let Some(c) = key.plain_char() {
+            input.push(c);
+        }
+        Some(KeyOutcome::Handled)
+    }
+
+    fn handle_prefix_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        self.prefix = false;
+        if key.is_ctrl('s') {
+            ctx.requests.push(KeymapRequest::Save);
+        } else if key.is_ctrl('x') {
+            // Exchange point and mark
+            if let Some(mark) = self.mark {
+                self.mark = Some(ctx.cursor);
+                ctx.cursor = mark;
+            }
+        } else if key.plain_char() == Some('h') {
+            self.mark = Some(0);
+            ctx.cursor = ctx.text().len();
+        } else if key.plain_char() == Some('u') {
+            self.undo(ctx, false);
+        } else if key.plain_char() == Some('k') {
+            ctx.requests.push(KeymapRequest::PaletteCommand("tab.close".to_string()));
+        } else if !key.is_ctrl('g') {
+            self.message = Some("C-x: key not bound".to_string());
+        }
+        KeyOutcome::Handled
+    }
+
+    /// Run a command outside of any prefix, search or minibuffer
+    fn run(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        let text = ctx.text();
+        let cursor = ctx.cursor;
+
+        let is_vertical = matches!(key.key, Key::Up | Key::Down | Key::PageUp | Key::PageDown)
+            || key.is_ctrl('n')
+            || key.is_ctrl('p')
+            || key.is_ctrl('v')
+            || key.is_alt('v');
+        if !is_vertical {
+            self.goal_column = None;
+        }
+
+        // Plain motions
+        let motion = match key.key {
+            Key::Left if !key.alt => Some(prev_char(text, cursor)),
+            Key::Right if !key.alt => Some(next_char(text, cursor)),
+            Key::Left => Some(backward_word(text, cursor)),
+            Key::Right => Some(forward_word(text, cursor)),
+            Key::Home => Some(line_start(text, cursor)),
+            Key::End => Some(line_end(text, cursor)),
+            Key::Up => Some(self.vertical(text, cursor, -1)),
+            Key::Down => Some(self.vertical(text, cursor, 1)),
+            Key::PageUp => Some(self.vertical(text, cursor, -PAGE)),
+            Key::PageDown => Some(self.vertical(text, cursor, PAGE)),
+            Key::Char(c) if key.ctrl && !key.alt => match c {
+                'f' => Some(next_char(text, cursor)),
+                'b' => Some(prev_char(text, cursor)),
+                'n' => Some(self.vertical(text, cursor, 1)),
+                'p' => Some(self.vertical(text, cursor, -1)),
+                'a' => Some(line_start(text, cursor)),
+                'e' => Some(line_end(text, cursor)),
+                'v' => Some(self.vertical(text, cursor, PAGE)),
+                _ => None,
+            },
+            Key::Char(c) if key.alt && !key.ctrl => match c {
+                'f' => Some(forward_word(text, cursor)),
+                'b' => Some(backward_word(text, cursor)),
+                '<' => Some(0),
+                '>' => Some(text.len()),
+                'v' => Some(self.vertical(text, cursor, -PAGE)),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(target) = motion {
+            ctx.cursor = target;
+            return KeyOutcome::Handled;
+        }
+
+        // Editing commands
+        if let Some(c) = key.plain_char() {
+            self.mark = None;
+            let mut buf = [0; 4];
+            ctx.type_text(c.encode_utf8(&mut buf));
+        } else if (key.key == Key::Enter && !key.ctrl && !key.alt) || key.is_ctrl('m') {
+            self.mark = None;
+            ctx.type_text("\n");
+        } else if key.key == Key::Tab && !key.ctrl && !key.alt {
+            self.mark = None;
+            ctx.type_text("\t");
+        } else if key.key == Key::Backspace && !key.alt {
+            self.mark = None;
+            if cursor > 0 {
+                let start = prev_char(text, cursor);
+                ctx.buffer.break_undo_group();
+                ctx.replace(start..cursor, "");
+            }
+        } else if key.key == Key::Delete || key.is_ctrl('d') {
+            self.mark = None;
+            if cursor < text.len() {
+                ctx.replace(cursor..next_char(text, cursor), "");
+            }
+        } else if key.is_ctrl('k') {
+            let end = line_end(text, cursor);
+            let end = if text[cursor..end].trim().is_empty() && end < text.len() {
+                end + 1
+            } else {
+                end
+            };
+            self.kill(ctx, cursor..end, false);
+            return KeyOutcome::Handled;
+        } else if key.is_alt('d') {
+            let end = forward_word(text, cursor);
+            self.kill(ctx, cursor..end, false);
+            return KeyOutcome::Handled;
+        } else if key.alt && key.key == Key::Backspace {
+            let start = backward_word(text, cursor);
+            self.kill(ctx, start..cursor, true);
+            return KeyOutcome::Handled;
+        } else if key.is_ctrl('w') {
+            match self.region(ctx) {
+                Some(region) => {
+                    self.mark = None;
+                    self.kill(ctx, region, cursor < ctx.anchor);
+                    return KeyOutcome::Handled;
+                }
+                None => self.message = Some("The mark is not set now".to_string()),
+            }
+        } else if key.is_alt('w') {
+            if let Some(region) = self.region(ctx) {
+                let copied = text[region].to_string();
+                self.kill_ring.push(copied.clone());
+                if self.kill_ring.len() > KILL_RING_SIZE {
+                    self.kill_ring.remove(0);
+                }
+                ctx.requests.push(KeymapRequest::CopyToClipboard(copied));
+                self.mark = None;
+            }
+        } else if key.is_ctrl('y') {
+            self.mark = None;
+            if let Some(last) = self.kill_ring.len().checked_sub(1) {
+                self.yank(ctx, last);
+                return KeyOutcome::Handled;
+            }
+            self.message = Some("Kill ring is empty".to_string());
+        } else if key.is_alt('y') {
+            match self.last_yank.clone() {
+                Some((range, index)) => {
+                    let index = index.checked_sub(1).unwrap_or(self.kill_ring.len() - 1);
+                    ctx.replace(range.clone(), "");
+                    ctx.set_cursor(range.start);
+                    self.yank(ctx, index);
+                    return KeyOutcome::Handled;
+                }
+                None => self.message = Some("Previous command was not a yank".to_string()),
+            }
+        } else if key.is_ctrl(' ') || key.is_ctrl('@') {
+            if self.mark == Some(cursor) {
+                self.mark = None;
+                self.message = Some("Mark deactivated".to_string());
+            } else {
+                self.mark = Some(cursor);
+                self.message = Some("Mark set".to_string());
+            }
+        } else if key.is_ctrl('g') {
+            self.mark = None;
+            self.message = Some("Quit".to_string());
+        } else if key.is_ctrl('x') {
+            self.prefix = true;
+        } else if key.is_ctrl('/') || key.is_ctrl('_') {
+            self.undo(ctx, false);
+        } else if key.is_ctrl('?') || key.is_alt('_') {
+            self.undo(ctx, true);
+        } else if key.is_ctrl('s') || key.is_ctrl('r') {
+            self.mark = None;
+            self.isearch = Some(Isearch {
+                forward: key.is_ctrl('s'),
+                query: String::new(),
+                origin: cursor,
+                found: None,
+            });
+        } else if key.is_alt('x') {
+            self.minibuffer = Some(String::new());
+        } else if key.is_ctrl('o') {
+            ctx.replace(cursor..cursor, "\n");
+            ctx.set_cursor(cursor);
+        } else if key.is_ctrl('j') {
+            let start = line_start(text, cursor);
+            let indent: String = text[start..cursor]
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect();
+            ctx.replace(cursor..cursor, &format!("\n{}", indent));
+        } else if key.is_ctrl('t') {
+            // Transpose the characters around point (the two before it at line end)
+            let (mid, end) = if cursor == line_end(text, cursor) {
+                (prev_char(text, cursor), cursor)
+            } else {
+                (cursor, next_char(text, cursor))
+            };
+            let start = prev_char(text, mid);
+            if start < mid && mid < end && !text[start..end].contains('\n') {
+                let swapped = format!("{}{}", &text[mid..end], &text[start..mid]);
+                ctx.replace(start..end, &swapped);
+            }
+        } else if key.is_alt('u') || key.is_alt('l') || key.is_alt('c') {
+            let end = forward_word(text, cursor);
+            let word = &text[cursor..end];
+            let converted = match key.key {
+                Key::Char('u') => word.to_uppercase(),
+                Key::Char('l') => word.to_lowercase(),
+                _ => capitalize(word),
+            };
+            ctx.replace(cursor..end, &converted);
+        } else if key.is_ctrl('l') {
+            // Recentering is left to the view
+        } else {
+            return KeyOutcome::Ignored;
+        }
+        KeyOutcome::Handled
+    }
+
+    /// Offset `lines` lines away, keeping the goal column
+    fn vertical(&mut self, text: &str, cursor: usize, lines: isize) -> usize {
+        let column = *self.goal_column.get_or_insert_with(|| keymap::column(text, cursor));
+        let line = keymap::line_offset(text, cursor, lines);
+        keymap::offset_at_column(text, line, column)
+    }
+}
+
+impl Default for EmacsKeymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keymap for EmacsKeymap {
+    fn handle_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        self.message = None;
+
+        if let Some(outcome) = self.handle_minibuffer_key(key, ctx) {
+            return outcome;
+        }
+        if let Some(outcome) = self.handle_isearch_key(key, ctx) {
+            return outcome;
+        }
+
+        // A selection made with the mouse becomes the region
+        if ctx.anchor != ctx.cursor && self.mark != Some(ctx.anchor) {
+            self.mark = Some(ctx.anchor);
+        }
+
+        let was_kill = std::mem::take(&mut self.last_was_kill);
+        let last_yank = self.last_yank.take();
+        let outcome = if self.prefix {
+            self.handle_prefix_key(key, ctx)
+        } else {
+            // Only a kill directly after a kill appends, and only M-y after a yank cycles
+            self.last_was_kill = was_kill && is_kill(&key);
+            self.last_yank = last_yank.filter(|_| key.is_alt('y'));
+            self.run(key, ctx)
+        };
+
+        if self.isearch.is_none() {
+            ctx.anchor = self.mark.unwrap_or(ctx.cursor);
+        }
+        outcome
+    }
+
+    fn status(&self) -> String {
+        if let Some(input) = &self.minibuffer {
+            return format!("M-x {}", input);
+        }
+        if let Some(search) = &self.isearch {
+            let failing = if search.found.is_none() && !search.query.is_empty() { "Failing " } else { "" };
+            let direction = if search.forward { "" } else { " backward" };
+            return format!("{}I-search{}: {}", failing, direction, search.query);
+        }
+        if self.prefix {
+            return "C-x-".to_string();
+        }
+        self.message.clone().unwrap_or_default()
+    }
+}
+
+/// Whether a key runs one of the kill commands
+fn is_kill(key: &KeyPress) -> bool {
+    key.is_ctrl('k') || key.is_ctrl('w') || key.is_alt('d') || (key.alt && key.key == Key::Backspace)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// End of the next word (Emacs `M-f`)
+fn forward_word(text: &str, pos: usize) -> usize {
+    let rest = &text[pos..];
+    let start = rest.find(is_word_char).unwrap_or(rest.len());
+    let end = rest[start..].find(|c| !is_word_char(c)).map(|i| start + i).unwrap_or(rest.len());
+    pos + end
+}
+
+/// Start of the previous word (Emacs `M-b`)
+fn backward_word(text: &str, pos: usize) -> usize {
+    let before = &text[..pos];
+    let end = before.rfind(is_word_char).map(|i| next_char(before, i)).unwrap_or(0);
+    before[..end].rfind(|c| !is_word_char(c)).map(|i| next_char(before, i)).unwrap_or(0)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut result = String::with_capacity(word.len());
+    let mut seen_letter = false;
+    for c in word.chars() {
+        if !seen_letter && c.is_alphanumeric() {
+            result.extend(c.to_uppercase());
+            seen_letter = true;
+        } else {
+            result.extend(c.to_lowercase());
+        }
+    }
+    result
+}
+
+/// Literal match of `query` starting at or after `from` (forward) or at or
+/// before it (backward), case-insensitive unless the query has capitals
+fn isearch_from(text: &str, query: &str, from: usize, forward: bool) -> Option<Range<usize>> {
+    if query.is_empty() {
+        return None;
+    }
+    let options = crate::text_search::SearchOptions {
+        case_sensitive: query.chars().any(|c| c.is_uppercase()),
+        ..Default::default()
+    };
+    let matches = crate::text_search::Search::new(query, options).ok()?.find_all(text);
+    if forward {
+        matches.into_iter().find(|m| m.start >= from)
+    } else {
+        matches.into_iter().rev().find(|m| m.start <= from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_buffer::EditorBuffer;
+    use crate::keymap::parse_keys;
+
+    /// Feed `keys` to a fresh Emacs keymap over `text` with the cursor at
+    /// `cursor`, returning the text and cursor after
+    fn run(text: &str, cursor: usize, keys: &str) -> (String, usize) {
+        let mut keymap = EmacsKeymap::new();
+        let mut buffer = EditorBuffer::new(text.to_string());
+        let mut ctx = KeymapContext::new(&mut buffer, cursor, cursor, 4);
+        for key in parse_keys(keys).unwrap() {
+            keymap.handle_key(key, &mut ctx);
+        }
+        let cursor = ctx.cursor;
+        (buffer.text().to_string(), cursor)
+    }
+
+    #[test]
+    fn kill_line_and_yank_it_back() {
+        assert_eq!(run("hello world\nnext", 5, "<C-k>"), ("hello\nnext".to_string(), 5));
+        assert_eq!(run("hello world\nnext", 5, "<C-k><C-e><C-f><C-e><C-y>"), ("hello\nnext world".to_string(), 16));
+    }
+
+    #[test]
+    fn consecutive_kills_yank_as_one() {
+        assert_eq!(run("one\ntwo\n", 0, "<C-k><C-k><C-k><C-y><C-y>"), ("one\ntwoone\ntwo\n".to_string(), 14));
+    }
+
+    #[test]
+    fn kill_word_forward() {
+        assert_eq!(run("foo bar baz", 3, "<M-d>"), ("foo baz".to_string(), 3));
+        assert_eq!(run("foo bar baz", 3, "<M-d><C-e><C-y>"), ("foo baz bar".to_string(), 11));
+    }
+}
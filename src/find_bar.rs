@@ -0,0 +1,202 @@
+use crate::text_search::{Search, SearchOptions};
+use std::ops::Range;
+
+/// Something the user asked the find bar to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindBarAction {
+    Next,
+    Previous,
+    Replace,
+    ReplaceAll,
+    /// Turn every match into a cursor
+    SelectAll,
+    Close,
+}
+
+/// In-editor find/replace bar
+///
+/// Keeps the match list up to date for the editor; the editor applies the
+/// actions it returns.
+#[derive(Default)]
+pub struct FindBar {
+    pub visible: bool,
+    pub show_replace: bool,
+    pub query: String,
+    pub replacement: String,
+    pub options: SearchOptions,
+    pub preserve_case: bool,
+    matches: Vec<Range<usize>>,
+    current: Option<usize>,
+    error: Option<String>,
+    /// What `matches` was computed from: query, options and buffer version
+    searched: Option<(String, SearchOptions, u64)>,
+    focus_query: bool,
+}
+
+impl FindBar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the bar, seeding the query from the selection if there is one
+    pub fn open(&mut self, with_replace: bool, seed: Option<&str>) {
+        self.visible = true;
+        self.show_replace = with_replace;
+        self.focus_query = true;
+        if let Some(seed) = seed.filter(|s| !s.is_empty() && !s.contains('\n')) {
+            self.query = seed.to_string();
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.matches.clear();
+        self.current = None;
+        self.searched = None;
+    }
+
+    pub fn search(&self) -> anyhow::Result<Search> {
+        Search::new(&self.query, self.options.clone())
+    }
+
+    /// Recompute matches if the query, options or text changed
+    pub fn update_matches(&mut self, text: &str, version: u64) {
+        let key = (self.query.clone(), self.options.clone(), version);
+        if self.searched.as_ref() == Some(&key) {
+            return;
+        }
+
+        self.error = None;
+        self.matches = if self.query.is_empty() {
+            Vec::new()
+        } else {
+            match self.search() {
+                Ok(search) => search.find_all(text),
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    Vec::new()
+                }
+            }
+        };
+        self.current = self.current.filter(|&i| i < self.matches.len());
+        self.searched = Some(key);
+    }
+
+    pub fn matches(&self) -> &[Range<usize>] {
+        &self.matches
+    }
+
+    pub fn current_match(&self) -> Option<Range<usize>> {
+        self.current.and_then(|i| self.matches.get(i).cloned())
+    }
+
+    /// First match starting at or after `offset`, wrapping around
+    pub fn select_next(&mut self, offset: usize) -> Option<Range<usize>> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let index = self.matches.iter().position(|m| m.start >= offset).unwrap_or(0);
+        self.current = Some(index);
+        self.current_match()
+    }
+
+    /// Last match ending before `offset`, wrapping around
+    pub fn select_previous(&mut self, offset: usize) -> Option<Range<usize>> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let index = self.matches.iter()
+            .rposition(|m| m.end < offset)
+            .unwrap_or(self.matches.len() - 1);
+        self.current = Some(index);
+        self.current_match()
+    }
+
+    /// Render the bar and collect the actions triggered this frame
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Vec<FindBarAction> {
+        let mut actions = Vec::new();
+        if !self.visible {
+            return actions;
+        }
+
+        ui.horizontal(|ui| {
+            let query = ui.add(egui::TextEdit::singleline(&mut self.query)
+                .hint_text("Find")
+                .desired_width(220.0));
+            if std::mem::take(&mut self.focus_query) {
+                query.request_focus();
+            }
+            if query.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let modifiers = ui.input(|i| i.modifiers);
+                actions.push(if modifiers.alt {
+                    FindBarAction::SelectAll
+                } else if modifiers.shift {
+                    FindBarAction::Previous
+                } else {
+                    FindBarAction::Next
+                });
+                query.request_focus();
+            }
+
+            ui.toggle_value(&mut self.options.case_sensitive, "Aa").on_hover_text("Match case");
+            ui.toggle_value(&mut self.options.whole_word, "ab").on_hover_text("Match whole word");
+            ui.toggle_value(&mut self.options.regex, ".*").on_hover_text("Use regular expression");
+
+            match (&self.error, self.current) {
+                (Some(error), _) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                (None, _) if self.matches.is_empty() => {
+                    ui.label("No results");
+                }
+                (None, Some(current)) => {
+                    ui.label(format!("{} of {}", current + 1, self.matches.len()));
+                }
+                (None, None) => {
+                    ui.label(format!("{} results", self.matches.len()));
+                }
+            }
+
+            if ui.small_button("↑").on_hover_text("Previous match (Shift+Enter)").clicked() {
+                actions.push(FindBarAction::Previous);
+            }
+            if ui.small_button("↓").on_hover_text("Next match (Enter)").clicked() {
+                actions.push(FindBarAction::Next);
+            }
+            if ui.small_button("Select All").on_hover_text("Select all occurrences (Alt+Enter)").clicked() {
+                actions.push(FindBarAction::SelectAll);
+            }
+            ui.toggle_value(&mut self.show_replace, "Replace");
+            if ui.small_button("✕").clicked() {
+                actions.push(FindBarAction::Close);
+            }
+        });
+
+        if self.show_replace {
+            ui.horizontal(|ui| {
+                let replacement = ui.add(egui::TextEdit::singleline(&mut self.replacement)
+                    .hint_text("Replace")
+                    .desired_width(220.0));
+                if replacement.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    actions.push(FindBarAction::Replace);
+                    replacement.request_focus();
+                }
+
+                ui.toggle_value(&mut self.preserve_case, "AB").on_hover_text("Preserve case");
+                if ui.button("Replace").clicked() {
+                    actions.push(FindBarAction::Replace);
+                }
+                if ui.button("Replace All").clicked() {
+                    actions.push(FindBarAction::ReplaceAll);
+                }
+            });
+        }
+
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            actions.push(FindBarAction::Close);
+        }
+
+        ui.separator();
+        actions
+    }
+}
@@ -0,0 +1,317 @@
+use egui::{self, Pos2, Rect, Vec2};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Height of a floating pane's title bar
+const TITLE_BAR_HEIGHT: f32 = 22.0;
+
+/// Width of the strip along a floating pane's edges that resizes it
+const RESIZE_BORDER: f32 = 5.0;
+
+const MIN_FLOAT_SIZE: Vec2 = Vec2::new(200.0, 120.0);
+
+/// A pane hosting an actor above the view system's layout
+#[derive(Debug, Clone)]
+pub struct FloatingPane {
+    pub id: Uuid,
+    pub actor_id: Uuid,
+    pub rect: Rect,
+    /// Pinned panes stay above every unpinned one
+    pub pinned: bool,
+}
+
+/// Floating panes, like Zellij's: each one draggable by its title bar,
+/// resizable from its edges and raised by clicking it
+pub struct FloatingPanes {
+    /// Back to front
+    panes: Vec<FloatingPane>,
+    visible: bool,
+    /// The pane last clicked into, until something outside the panes is
+    focused: Option<Uuid>,
+    /// Panes whose close button was clicked, by actor
+    close_requests: Vec<Uuid>,
+    /// The area the panes were kept within on the last frame
+    bounds: Rect,
+}
+
+impl FloatingPanes {
+    pub fn new() -> Self {
+        Self {
+            panes: Vec::new(),
+            visible: true,
+            focused: None,
+            close_requests: Vec::new(),
+            bounds: Rect::from_min_size(Pos2::ZERO, Vec2::new(1200.0, 800.0)),
+        }
+    }
+
+    /// Float `actor_id` in a new pane on top of the others, showing and
+    /// focusing them; each new pane is offset from the last
+    pub fn open(&mut self, actor_id: Uuid) -> Uuid {
+        let size = (self.bounds.size() * Vec2::new(0.6, 0.5)).max(MIN_FLOAT_SIZE);
+        let cascade = Vec2::splat(24.0 * (self.panes.len() % 8) as f32);
+        let rect = Rect::from_center_size(self.bounds.center() + cascade, size);
+        let pane = FloatingPane { id: Uuid::new_v4(), actor_id, rect, pinned: false };
+        let id = pane.id;
+        self.panes.push(pane);
+        self.visible = true;
+        self.focused = Some(id);
+        id
+    }
+
+    /// Take the pane holding `actor_id` away, returning it
+    pub fn remove_actor(&mut self, actor_id: Uuid) -> Option<FloatingPane> {
+        let index = self.panes.iter().position(|pane| pane.actor_id == actor_id)?;
+        let pane = self.panes.remove(index);
+        if self.focused == Some(pane.id) {
+            self.focused = None;
+        }
+        Some(pane)
+    }
+
+    pub fn panes(&self) -> &[FloatingPane] {
+        &self.panes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.panes.is_empty()
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Hide or show every pane; hiding hands focus back to the layout
+    pub fn toggle_visible(&mut self) {
+        self.visible = !self.visible;
+        if !self.visible {
+            self.focused = None;
+        }
+    }
+
+    /// The actor of the focused pane, while the panes are shown
+    pub fn focused_actor(&self) -> Option<Uuid> {
+        let focused = self.focused.filter(|_| self.visible)?;
+        self.panes.iter().find(|pane| pane.id == focused).map(|pane| pane.actor_id)
+    }
+
+    /// Pin or unpin the focused pane (or the top one, with none focused)
+    pub fn toggle_pinned(&mut self) -> bool {
+        let target = self.focused.or(self.panes.last().map(|pane| pane.id));
+        match self.panes.iter_mut().find(|pane| Some(pane.id) == target) {
+            Some(pane) => {
+                pane.pinned = !pane.pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Panes closed from their title bars since the last call, by actor;
+    /// they stay open until removed
+    pub fn take_close_requests(&mut self) -> Vec<Uuid> {
+        std::mem::take(&mut self.close_requests)
+    }
+
+    fn raise(&mut self, id: Uuid) {
+        if let Some(index) = self.panes.iter().position(|pane| pane.id == id) {
+            let pane = self.panes.remove(index);
+            self.panes.push(pane);
+        }
+        self.focused = Some(id);
+    }
+
+    fn layer_id(pane: &FloatingPane) -> egui::LayerId {
+        let order = if pane.pinned { egui::Order::Foreground } else { egui::Order::Middle };
+        egui::LayerId::new(order, egui::Id::new(("floating_pane", pane.id)))
+    }
+
+    /// Draw the panes above everything in `ui`, keeping them within its
+    /// rect, with `titles` by actor
+    pub fn render(&mut self, ui: &mut egui::Ui, titles: &HashMap<Uuid, String>, render_actor: &dyn Fn(&mut egui::Ui, Uuid)) {
+        self.bounds = ui.max_rect();
+        if !self.visible || self.panes.is_empty() {
+            return;
+        }
+
+        // Pressing in a pane raises and focuses it; pressing anywhere else
+        // gives focus back to the layout
+        let ctx = ui.ctx().clone();
+        let pressed_at = ctx.input(|i| if i.pointer.any_pressed() { i.pointer.interact_pos() } else { None });
+        if let Some(pos) = pressed_at {
+            let layer = ctx.layer_id_at(pos);
+            match self.panes.iter().find(|pane| Some(Self::layer_id(pane)) == layer) {
+                Some(pane) => self.raise(pane.id),
+                None => self.focused = None,
+            }
+        }
+
+        for index in 0..self.panes.len() {
+            let pane = self.panes[index].clone();
+            let title = titles.get(&pane.actor_id).map_or("Floating Pane", String::as_str);
+            let focused = self.focused == Some(pane.id);
+            let layer = Self::layer_id(&pane);
+            let area = egui::Area::new(layer.id)
+                .order(layer.order)
+                .fixed_pos(pane.rect.min)
+                .constrain(false)
+                .show(&ctx, |ui| Self::render_pane(ui, &pane, title, focused, render_actor));
+
+            let action = area.inner;
+            if action.close {
+                self.close_requests.push(pane.actor_id);
+            }
+            if action.pin {
+                self.panes[index].pinned = !pane.pinned;
+            }
+            if let Some(rect) = action.rect {
+                self.panes[index].rect = self.keep_reachable(rect);
+            }
+        }
+    }
+
+    /// `rect` moved as needed to keep enough of its title bar in bounds to
+    /// drag it back
+    fn keep_reachable(&self, rect: Rect) -> Rect {
+        let bounds = self.bounds;
+        let margin = 40.0;
+        let x = rect.min.x.clamp(bounds.min.x - rect.width() + margin, (bounds.max.x - margin).max(bounds.min.x));
+        let y = rect.min.y.clamp(bounds.min.y, (bounds.max.y - TITLE_BAR_HEIGHT).max(bounds.min.y));
+        Rect::from_min_size(Pos2::new(x, y), rect.size())
+    }
+
+    fn render_pane(
+        ui: &mut egui::Ui,
+        pane: &FloatingPane,
+        title: &str,
+        focused: bool,
+        render_actor: &dyn Fn(&mut egui::Ui, Uuid),
+    ) -> PaneAction {
+        let mut action = PaneAction::default();
+        let (rect, _) = ui.allocate_exact_size(pane.rect.size(), egui::Sense::hover());
+        let painter = ui.painter().clone();
+        let accent = egui::Color32::from_rgb(100, 150, 255);
+
+        painter.add(egui::epaint::Shadow {
+            offset: Vec2::new(0.0, 4.0),
+            blur: 12.0,
+            spread: 0.0,
+            color: egui::Color32::from_black_alpha(120),
+        }.as_shape(rect, 4.0));
+        painter.rect_filled(rect, 4.0, egui::Color32::from_gray(40));
+
+        // Title bar: drag to move, with pin and close buttons
+        let bar = Rect::from_min_size(rect.min, Vec2::new(rect.width(), TITLE_BAR_HEIGHT));
+        painter.rect_filled(bar, egui::Rounding { nw: 4.0, ne: 4.0, sw: 0.0, se: 0.0 }, egui::Color32::from_gray(if focused { 65 } else { 52 }));
+        let id = ui.id().with(pane.id);
+        let drag = ui.interact(bar, id.with("title"), egui::Sense::drag());
+        if drag.dragged() {
+            action.rect = Some(pane.rect.translate(drag.drag_delta()));
+        }
+        if drag.hovered() || drag.dragged() {
+            ui.ctx().set_cursor_icon(if drag.dragged() { egui::CursorIcon::Grabbing } else { egui::CursorIcon::Grab });
+        }
+
+        let font = egui::FontId::proportional(13.0);
+        let close_rect = Rect::from_center_size(Pos2::new(bar.max.x - 12.0, bar.center().y), Vec2::splat(16.0));
+        let pin_rect = close_rect.translate(Vec2::new(-20.0, 0.0));
+        for (button_rect, glyph, is_close) in [(pin_rect, "📌", false), (close_rect, "×", true)] {
+            let button = ui.interact(button_rect, id.with(glyph), egui::Sense::click());
+            if button.hovered() {
+                painter.rect_filled(button_rect, 2.0, egui::Color32::from_gray(90));
+            }
+            let color = if !is_close && pane.pinned { accent } else { egui::Color32::from_gray(200) };
+            painter.text(button_rect.center(), egui::Align2::CENTER_CENTER, glyph, font.clone(), color);
+            if button.clicked() {
+                if is_close {
+                    action.close = true;
+                } else {
+                    action.pin = true;
+                }
+            }
+        }
+        painter.with_clip_rect(bar.with_max_x(pin_rect.min.x - 4.0)).text(
+            Pos2::new(bar.min.x + 8.0, bar.center().y),
+            egui::Align2::LEFT_CENTER,
+            title,
+            font,
+            egui::Color32::from_gray(if focused { 240 } else { 180 }),
+        );
+
+        let content = Rect::from_min_max(Pos2::new(rect.min.x, bar.max.y), rect.max).shrink(4.0);
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(content), |ui| {
+            ui.set_clip_rect(content.intersect(ui.clip_rect()));
+            render_actor(ui, pane.actor_id);
+        });
+
+        painter.rect_stroke(rect, 4.0, egui::Stroke::new(1.0, if focused { accent } else { egui::Color32::from_gray(90) }));
+
+        // Edges and corners resize; they go last to sit above the content
+        for (x_side, y_side) in RESIZE_HANDLES {
+            let handle = resize_handle(rect, x_side, y_side);
+            let response = ui.interact(handle, id.with(("resize", x_side, y_side)), egui::Sense::drag());
+            if response.hovered() || response.dragged() {
+                ui.ctx().set_cursor_icon(resize_cursor(x_side, y_side));
+            }
+            if response.dragged() {
+                action.rect = Some(resize(pane.rect, x_side, y_side, response.drag_delta()));
+            }
+        }
+        action
+    }
+}
+
+/// What a click or drag on a floating pane's frame asked for
+#[derive(Default)]
+struct PaneAction {
+    close: bool,
+    pin: bool,
+    /// The pane's new rect, moved or resized
+    rect: Option<Rect>,
+}
+
+/// Which sides each resize handle moves: -1 for the left or top, 1 for the
+/// right or bottom, 0 for neither
+const RESIZE_HANDLES: [(i8, i8); 8] = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+fn resize_handle(rect: Rect, x_side: i8, y_side: i8) -> Rect {
+    let corner = RESIZE_BORDER * 2.0;
+    let span = |side: i8, min: f32, max: f32| match side {
+        -1 => egui::Rangef::new(min - RESIZE_BORDER / 2.0, min + RESIZE_BORDER / 2.0),
+        1 => egui::Rangef::new(max - RESIZE_BORDER / 2.0, max + RESIZE_BORDER / 2.0),
+        _ => egui::Rangef::new(min + corner, max - corner),
+    };
+    let (mut x, mut y) = (span(x_side, rect.min.x, rect.max.x), span(y_side, rect.min.y, rect.max.y));
+    // Corners take a bigger square so they're easy to grab
+    if x_side != 0 && y_side != 0 {
+        x = x.expand(corner / 2.0);
+        y = y.expand(corner / 2.0);
+    }
+    Rect::from_x_y_ranges(x, y)
+}
+
+fn resize_cursor(x_side: i8, y_side: i8) -> egui::CursorIcon {
+    match (x_side, y_side) {
+        (0, _) => egui::CursorIcon::ResizeVertical,
+        (_, 0) => egui::CursorIcon::ResizeHorizontal,
+        (-1, -1) | (1, 1) => egui::CursorIcon::ResizeNwSe,
+        _ => egui::CursorIcon::ResizeNeSw,
+    }
+}
+
+/// `rect` with the sides a handle moves dragged by `delta`, no smaller than
+/// the minimum size
+fn resize(rect: Rect, x_side: i8, y_side: i8, delta: Vec2) -> Rect {
+    let mut rect = rect;
+    match x_side {
+        -1 => rect.min.x = (rect.min.x + delta.x).min(rect.max.x - MIN_FLOAT_SIZE.x),
+        1 => rect.max.x = (rect.max.x + delta.x).max(rect.min.x + MIN_FLOAT_SIZE.x),
+        _ => {}
+    }
+    match y_side {
+        -1 => rect.min.y = (rect.min.y + delta.y).min(rect.max.y - MIN_FLOAT_SIZE.y),
+        1 => rect.max.y = (rect.max.y + delta.y).max(rect.min.y + MIN_FLOAT_SIZE.y),
+        _ => {}
+    }
+    rect
+}
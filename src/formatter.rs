@@ -0,0 +1,218 @@
+use crate::config::EditorConfig;
+use crate::language_servers::LanguageServers;
+use crate::lsp_client;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// External formatter command that reads source on stdin and writes it to stdout
+///
+/// `{file}` in any argument is replaced by the buffer's file path (or
+/// `untitled` with the language's extension) so tools can pick up the
+/// language and per-project configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatterSpec {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl FormatterSpec {
+    pub fn new(command: &str, args: &[&str]) -> Self {
+        Self {
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+}
+
+/// Formatters keyed by editor language name, with LSP formatting as fallback
+#[derive(Debug, Clone)]
+pub struct FormatterRegistry {
+    formatters: HashMap<String, FormatterSpec>,
+    timeout: Duration,
+}
+
+impl FormatterRegistry {
+    pub fn new() -> Self {
+        let mut formatters = HashMap::new();
+        formatters.insert("rust".to_string(), FormatterSpec::new("rustfmt", &["--edition", "2021"]));
+        formatters.insert("python".to_string(), FormatterSpec::new("black", &["--quiet", "-"]));
+        formatters.insert("go".to_string(), FormatterSpec::new("gofmt", &[]));
+        for lang in ["c", "cpp"] {
+            formatters.insert(lang.to_string(), FormatterSpec::new("clang-format", &["--assume-filename={file}"]));
+        }
+        for lang in ["javascript", "typescript", "json", "css", "html", "markdown", "yaml"] {
+            formatters.insert(lang.to_string(), FormatterSpec::new("prettier", &["--stdin-filepath", "{file}"]));
+        }
+
+        Self {
+            formatters,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Default registry with the user's overrides and timeout applied
+    pub fn from_config(config: &EditorConfig) -> Self {
+        let mut registry = Self::new().with_timeout(Duration::from_millis(config.format_timeout_ms));
+        for (language, spec) in &config.formatters {
+            registry.register(language, spec.clone());
+        }
+        registry
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn register(&mut self, language: &str, spec: FormatterSpec) {
+        self.formatters.insert(language.to_string(), spec);
+    }
+
+    /// Format `text`, trying the external formatter first and the language
+    /// server from `servers` second
+    ///
+    /// Each may take up to the timeout, so editors call this on a worker
+    /// thread.
+    pub fn format(&self, servers: &LanguageServers, language: &str, text: &str, file_path: Option<&Path>, tab_width: usize) -> Result<String> {
+        let external_error = match self.formatters.get(language) {
+            Some(spec) => match self.run_external(spec, language, text, file_path) {
+                Ok(formatted) => return Ok(formatted),
+                Err(e) => e,
+            },
+            None => anyhow!("No formatter registered for '{}'", language),
+        };
+
+        log::warn!("{}; trying language server formatting", external_error);
+        self.format_with_lsp(servers, language, text, file_path, tab_width)
+            .map_err(|lsp_error| anyhow!("{}; {}", external_error, lsp_error))
+    }
+
+    fn run_external(&self, spec: &FormatterSpec, language: &str, text: &str, file_path: Option<&Path>) -> Result<String> {
+        let file_name = file_path
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|| untitled_name(language));
+
+        let mut command = Command::new(&spec.command);
+        command.args(spec.args.iter().map(|a| a.replace("{file}", &file_name)));
+        if let Some(dir) = file_path.and_then(|p| p.parent()).filter(|d| d.is_dir()) {
+            command.current_dir(dir);
+        }
+
+        run_with_timeout(command, text, self.timeout)
+            .map_err(|e| anyhow!("{} failed: {}", spec.command, e))
+    }
+
+    fn format_with_lsp(&self, servers: &LanguageServers, language: &str, text: &str, file_path: Option<&Path>, tab_width: usize) -> Result<String> {
+        // Servers need a document path, even for untitled buffers
+        let document_path = file_path
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| std::env::temp_dir().join(untitled_name(language)));
+        let server = servers.server_for(language, &document_path)
+            .ok_or_else(|| anyhow!("No language server registered for '{}'", language))?;
+
+        let (reply, edits) = mpsc::channel();
+        if !server.format(document_path, text.to_string(), tab_width, self.timeout, reply) {
+            return Err(anyhow!("The language server for '{}' isn't running", language));
+        }
+        let edits = edits.recv()
+            .map_err(|_| anyhow!("The language server for '{}' stopped", language))??;
+        Ok(lsp_client::apply_text_edits(text, &edits))
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Placeholder file name for an unsaved `language` buffer, with the extension
+/// tools infer the language from
+fn untitled_name(language: &str) -> String {
+    let extension = match language {
+        "rust" => "rs",
+        "python" => "py",
+        "javascript" => "js",
+        "typescript" => "ts",
+        "go" => "go",
+        "c" => "c",
+        "cpp" => "cpp",
+        "json" => "json",
+        "css" => "css",
+        "html" => "html",
+        "markdown" => "md",
+        "yaml" => "yaml",
+        _ => "txt",
+    };
+    format!("untitled.{}", extension)
+}
+
+/// Pipe `input` through a command, killing it if it exceeds `timeout`
+fn run_with_timeout(mut command: Command, input: &str, timeout: Duration) -> Result<String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Feed stdin and drain the outputs on threads so a chatty tool can't deadlock us
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("no stdin"))?;
+    let input = input.to_string();
+    thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let stdout = read_to_end_thread(child.stdout.take());
+    let stderr = read_to_end_thread(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("timed out after {:?}", timeout));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = stderr.join().unwrap_or_default();
+        return Err(anyhow!("{}", String::from_utf8_lossy(&stderr).trim()));
+    }
+    Ok(String::from_utf8(stdout)?)
+}
+
+fn read_to_end_thread(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsaved_buffers_are_named_for_their_language() {
+        let mut registry = FormatterRegistry::new();
+        registry.register("typescript", FormatterSpec::new("echo", &["{file}"]));
+        let formatted = registry.format(&LanguageServers::new(), "typescript", "let a = 1;\n", None, 4).unwrap();
+        assert_eq!(formatted, "untitled.ts\n");
+        assert_eq!(untitled_name("unknown"), "untitled.txt");
+    }
+}
@@ -0,0 +1,557 @@
+use crate::diff::{self, DiffTag};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a hunk changes the base text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HunkKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A changed run of lines between a base text and a newer one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    /// Zero-based line range in the base text
+    pub old: Range<usize>,
+    /// Zero-based line range in the newer text (empty for deletions)
+    pub new: Range<usize>,
+}
+
+impl Hunk {
+    /// Lines of the newer text the hunk is shown on; a deletion is shown
+    /// on the line it sits above
+    pub fn span(&self) -> Range<usize> {
+        self.new.start..self.new.end.max(self.new.start + 1)
+    }
+
+    pub fn touches(&self, line: usize) -> bool {
+        self.span().contains(&line)
+    }
+}
+
+/// Line hunks turning `old` into `new`
+pub fn hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    diff::diff_slices(&old_lines, &new_lines)
+        .into_iter()
+        .filter_map(|op| {
+            let kind = match op.tag {
+                DiffTag::Equal => return None,
+                DiffTag::Insert => HunkKind::Added,
+                DiffTag::Delete => HunkKind::Deleted,
+                DiffTag::Replace => HunkKind::Modified,
+            };
+            Some(Hunk { kind, old: op.old, new: op.new })
+        })
+        .collect()
+}
+
+/// Map a line of the newer text back to the base text through `hunks`
+fn base_line(hunks: &[Hunk], line: usize) -> usize {
+    let mut delta = 0isize;
+    for hunk in hunks {
+        if hunk.new.is_empty() {
+            if hunk.new.start > line {
+                break;
+            }
+        } else if line < hunk.new.start {
+            break;
+        } else if line < hunk.new.end {
+            return hunk.old.start + (line - hunk.new.start).min(hunk.old.len().saturating_sub(1));
+        }
+        delta += hunk.old.len() as isize - hunk.new.len() as isize;
+    }
+    (line as isize + delta).max(0) as usize
+}
+
+/// `base` with the lines of `hunk` replaced by the matching lines of `new`
+fn apply_hunk(base: &str, new: &str, hunk: &Hunk) -> String {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let mut result = String::with_capacity(base.len());
+    base_lines[..hunk.old.start].iter().for_each(|line| result.push_str(line));
+    new_lines[hunk.new.clone()].iter().for_each(|line| result.push_str(line));
+    base_lines[hunk.old.end..].iter().for_each(|line| result.push_str(line));
+    result
+}
+
+/// Byte range of a line range within `text`
+fn line_byte_range(text: &str, lines: &Range<usize>) -> Range<usize> {
+    let mut offsets = vec![0];
+    offsets.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+    let at = |line: usize| offsets.get(line).copied().unwrap_or(text.len());
+    at(lines.start)..at(lines.end)
+}
+
+/// Who last touched a line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlameInfo {
+    /// Abbreviated commit hash
+    pub commit: String,
+    pub author: String,
+    /// Unix time of the commit
+    pub time: i64,
+    pub summary: String,
+    /// The line has changes that aren't committed
+    pub uncommitted: bool,
+}
+
+impl BlameInfo {
+    /// Short annotation, e.g. "Ada Lovelace, 3 days ago • Fix parser"
+    pub fn describe(&self) -> String {
+        if self.uncommitted {
+            return "You, uncommitted changes".to_string();
+        }
+        format!("{}, {} • {}", self.author, relative_time(self.time), self.summary)
+    }
+}
+
+fn relative_time(time: i64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(time);
+    let seconds = (now - time).max(0);
+    let (value, unit) = match seconds {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3600, "hour"),
+        s if s < 30 * 86_400 => (s / 86_400, "day"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    format!("{} {}{} ago", value, unit, if value == 1 { "" } else { "s" })
+}
+
+/// A repository worked on through the `git` CLI
+#[derive(Debug, Clone)]
+pub struct GitRepo {
+    root: PathBuf,
+    git_dir: PathBuf,
+}
+
+impl GitRepo {
+    /// The repository containing `path`, if any
+    pub fn discover(path: &Path) -> Option<Self> {
+        let dir = if path.is_dir() { path } else { path.parent()? };
+        let output = Command::new("git")
+            .arg("-C").arg(dir)
+            .args(["rev-parse", "--show-toplevel", "--absolute-git-dir"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|o| o.status.success())?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        let root = PathBuf::from(lines.next()?);
+        let git_dir = PathBuf::from(lines.next()?);
+        Some(Self { root, git_dir })
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.root).args(args);
+        command
+    }
+
+    /// Run git and return its stdout
+    fn git(&self, args: &[&str]) -> Result<String> {
+        self.git_with_input(args, None)
+    }
+
+    fn git_with_input(&self, args: &[&str], input: Option<&str>) -> Result<String> {
+        let mut child = self.command(args)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            // Written from a thread so a large input can't deadlock against stdout
+            let input = input.to_string();
+            thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow!("git {} failed: {}", args.first().unwrap_or(&""), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Path of `path` relative to the repository root, with `/` separators
+    pub fn relative_path(&self, path: &Path) -> Option<String> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let root = self.root.canonicalize().unwrap_or_else(|_| self.root.clone());
+        let relative = path.strip_prefix(root).ok()?;
+        Some(relative.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"))
+    }
+
+    /// Current branch name, or the short commit when detached
+    pub fn branch(&self) -> Option<String> {
+        let name = self.git(&["rev-parse", "--abbrev-ref", "HEAD"]).ok()?.trim().to_string();
+        if name == "HEAD" {
+            return self.git(&["rev-parse", "--short", "HEAD"]).ok().map(|s| s.trim().to_string());
+        }
+        Some(name)
+    }
+
+    /// File contents at HEAD (`None` if it isn't committed)
+    pub fn head_text(&self, rel_path: &str) -> Option<String> {
+        self.revision_text("HEAD", rel_path)
+    }
+
+    /// File contents at any revision git understands (`HEAD~2`, a branch, ...)
+    pub fn revision_text(&self, revision: &str, rel_path: &str) -> Option<String> {
+        self.git(&["show", &format!("{}:{}", revision, rel_path)]).ok()
+    }
+
+    /// File contents in the index (`None` if it isn't staged or tracked)
+    pub fn index_text(&self, rel_path: &str) -> Option<String> {
+        self.git(&["show", &format!(":{}", rel_path)]).ok()
+    }
+
+    /// Replace the staged contents of a file
+    pub fn write_index(&self, rel_path: &str, text: &str) -> Result<()> {
+        let blob = self.git_with_input(&["hash-object", "-w", "--stdin", &format!("--path={}", rel_path)], Some(text))?;
+        let mode = self.git(&["ls-files", "-s", "--", rel_path]).ok()
+            .and_then(|entry| entry.split_whitespace().next().map(str::to_string))
+            .unwrap_or_else(|| "100644".to_string());
+        self.git(&["update-index", "--add", "--cacheinfo", &format!("{},{},{}", mode, blob.trim(), rel_path)])?;
+        Ok(())
+    }
+
+    /// Drop a file from the index, leaving it untracked
+    pub fn remove_from_index(&self, rel_path: &str) -> Result<()> {
+        self.git(&["rm", "--cached", "--quiet", "--", rel_path])?;
+        Ok(())
+    }
+
+    /// Blame one line of `contents` (the file as currently edited)
+    pub fn blame_line(&self, rel_path: &str, contents: &str, line: usize) -> Result<BlameInfo> {
+        let range = format!("{},+1", line + 1);
+        let output = self.git_with_input(
+            &["blame", "--porcelain", "-L", &range, "--contents", "-", "--", rel_path],
+            Some(contents),
+        )?;
+
+        let mut lines = output.lines();
+        let commit = lines.next()
+            .and_then(|header| header.split_whitespace().next())
+            .ok_or_else(|| anyhow!("Unexpected blame output"))?;
+        let mut info = BlameInfo {
+            commit: commit.chars().take(8).collect(),
+            author: String::new(),
+            time: 0,
+            summary: String::new(),
+            uncommitted: commit.chars().all(|c| c == '0'),
+        };
+        for line in lines {
+            if let Some(author) = line.strip_prefix("author ") {
+                info.author = author.to_string();
+            } else if let Some(time) = line.strip_prefix("author-time ") {
+                info.time = time.parse().unwrap_or(0);
+            } else if let Some(summary) = line.strip_prefix("summary ") {
+                info.summary = summary.to_string();
+            }
+        }
+        Ok(info)
+    }
+
+    /// Modification times of the index and HEAD, to notice outside changes
+    fn stamp(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        let modified = |name: &str| std::fs::metadata(self.git_dir.join(name)).and_then(|m| m.modified()).ok();
+        (modified("index"), modified("HEAD"))
+    }
+}
+
+/// Blame lookup running on a worker thread
+type PendingBlame = ((usize, u64), Mutex<Receiver<Result<BlameInfo>>>);
+
+/// Which base a gutter line differs from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineStatus {
+    pub kind: HunkKind,
+    /// The change is already in the index
+    pub staged: bool,
+}
+
+/// Git state of one open file: its HEAD and index versions and the hunks
+/// between them and the buffer
+pub struct GitFile {
+    repo: GitRepo,
+    rel_path: String,
+    branch: Option<String>,
+    head: Option<String>,
+    index: Option<String>,
+    stamp: (Option<SystemTime>, Option<SystemTime>),
+    /// Buffer against the index: unstaged changes
+    unstaged: Vec<Hunk>,
+    /// Buffer against HEAD: every uncommitted change
+    uncommitted: Vec<Hunk>,
+    /// Index against HEAD: staged changes, in index lines
+    staged: Vec<Hunk>,
+    /// Buffer version the hunks were computed for
+    diffed_version: Option<u64>,
+    /// Blame of `(line, version)`, and a lookup in flight
+    blame: Option<((usize, u64), BlameInfo)>,
+    blame_pending: Option<PendingBlame>,
+}
+
+impl GitFile {
+    /// Track `path` if it's inside a repository
+    pub fn open(path: &Path) -> Option<Self> {
+        let repo = GitRepo::discover(path)?;
+        let rel_path = repo.relative_path(path)?;
+        let mut file = Self {
+            repo,
+            rel_path,
+            branch: None,
+            head: None,
+            index: None,
+            stamp: (None, None),
+            unstaged: Vec::new(),
+            uncommitted: Vec::new(),
+            staged: Vec::new(),
+            diffed_version: None,
+            blame: None,
+            blame_pending: None,
+        };
+        file.reload();
+        Some(file)
+    }
+
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// Re-read HEAD and the index
+    pub fn reload(&mut self) {
+        self.stamp = self.repo.stamp();
+        self.branch = self.repo.branch();
+        self.head = self.repo.head_text(&self.rel_path);
+        self.index = self.repo.index_text(&self.rel_path);
+        self.staged = hunks(self.head.as_deref().unwrap_or(""), self.index.as_deref().unwrap_or(""));
+        self.diffed_version = None;
+        self.blame = None;
+    }
+
+    /// Pick up commits, checkouts and staging done outside the editor, and
+    /// re-diff the buffer if it changed
+    pub fn refresh(&mut self, text: &str, version: u64) {
+        if self.repo.stamp() != self.stamp {
+            self.reload();
+        }
+        if self.diffed_version != Some(version) {
+            let index = self.index.as_deref().or(self.head.as_deref()).unwrap_or("");
+            self.unstaged = hunks(index, text);
+            self.uncommitted = hunks(self.head.as_deref().unwrap_or(""), text);
+            self.diffed_version = Some(version);
+        }
+    }
+
+    /// Unstaged hunks of the buffer against the index
+    pub fn unstaged_hunks(&self) -> &[Hunk] {
+        &self.unstaged
+    }
+
+    /// Staged hunks of the index against HEAD
+    pub fn staged_hunks(&self) -> &[Hunk] {
+        &self.staged
+    }
+
+    /// Gutter status per changed buffer line, as `(line, status)`
+    pub fn line_statuses(&self) -> Vec<(usize, LineStatus)> {
+        let mut statuses = Vec::new();
+        for hunk in &self.uncommitted {
+            let span = hunk.span();
+            let staged = !self.unstaged.iter().any(|u| u.span().start < span.end && span.start < u.span().end);
+            statuses.extend(span.map(|line| (line, LineStatus { kind: hunk.kind, staged })));
+        }
+        statuses
+    }
+
+    /// Stage the unstaged hunk at `line`; returns whether there was one
+    pub fn stage_hunk(&mut self, text: &str, line: usize) -> Result<bool> {
+        let Some(hunk) = self.unstaged.iter().find(|h| h.touches(line)).cloned() else {
+            return Ok(false);
+        };
+        let index = self.index.clone().or_else(|| self.head.clone()).unwrap_or_default();
+        self.repo.write_index(&self.rel_path, &apply_hunk(&index, text, &hunk))?;
+        self.reload();
+        Ok(true)
+    }
+
+    /// Unstage the staged hunk at buffer `line`; returns whether there was one
+    pub fn unstage_hunk(&mut self, line: usize) -> Result<bool> {
+        let index_line = base_line(&self.unstaged, line);
+        let Some(hunk) = self.staged.iter().find(|h| h.touches(index_line)).cloned() else {
+            return Ok(false);
+        };
+        let index = self.index.clone().unwrap_or_default();
+        match &self.head {
+            Some(head) => {
+                // Put HEAD's lines back over the hunk: the hunk reversed
+                let reverse = Hunk { kind: hunk.kind, old: hunk.new.clone(), new: hunk.old.clone() };
+                self.repo.write_index(&self.rel_path, &apply_hunk(&index, head, &reverse))?;
+            }
+            None => self.repo.remove_from_index(&self.rel_path)?,
+        }
+        self.reload();
+        Ok(true)
+    }
+
+    /// Edit that reverts the buffer's unstaged hunk at `line` to the index
+    pub fn revert_hunk(&self, text: &str, line: usize) -> Option<(Range<usize>, String)> {
+        let hunk = self.unstaged.iter().find(|h| h.touches(line))?;
+        let index = self.index.as_deref().or(self.head.as_deref())?;
+        let original = &index[line_byte_range(index, &hunk.old)];
+        Some((line_byte_range(text, &hunk.new), original.to_string()))
+    }
+
+    /// Blame for `line` of the buffer, waiting for git
+    pub fn blame_now(&self, text: &str, line: usize) -> Result<BlameInfo> {
+        self.repo.blame_line(&self.rel_path, text, line)
+    }
+
+    /// Blame for `line` of the buffer, looked up in the background
+    ///
+    /// Returns the cached answer for this line and version, if there is one.
+    pub fn blame(&mut self, text: &str, line: usize, version: u64) -> Option<&BlameInfo> {
+        let key = (line, version);
+        if let Some((pending_key, receiver)) = &mut self.blame_pending {
+            let received = receiver.get_mut().ok().and_then(|r| r.try_recv().ok());
+            if let Some(result) = received {
+                let pending_key = *pending_key;
+                self.blame_pending = None;
+                match result {
+                    Ok(info) => self.blame = Some((pending_key, info)),
+                    Err(e) => log::debug!("Blame failed: {}", e),
+                }
+            }
+        }
+
+        let cached = self.blame.as_ref().is_some_and(|(k, _)| *k == key);
+        let requested = self.blame_pending.as_ref().is_some_and(|(k, _)| *k == key);
+        if !cached && !requested && self.blame_pending.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            let repo = self.repo.clone();
+            let rel_path = self.rel_path.clone();
+            let contents = text.to_string();
+            thread::spawn(move || {
+                let _ = sender.send(repo.blame_line(&rel_path, &contents, line));
+            });
+            self.blame_pending = Some((key, Mutex::new(receiver)));
+        }
+
+        self.blame.as_ref().filter(|(k, _)| *k == key).map(|(_, info)| info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const COMMITTED: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+    const EDITED: &str = "ONE\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nNINE\nten\n";
+
+    /// A throwaway repository with `COMMITTED` in `file.txt`, deleted on drop
+    struct TestRepo {
+        root: PathBuf,
+    }
+
+    impl TestRepo {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("zellij-ide-git-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&root).unwrap();
+            let repo = Self { root };
+            repo.git(&["init", "--quiet"]);
+            fs::write(repo.file(), COMMITTED).unwrap();
+            repo.git(&["add", "file.txt"]);
+            repo.git(&["commit", "--quiet", "-m", "Add file"]);
+            repo
+        }
+
+        fn file(&self) -> PathBuf {
+            self.root.join("file.txt")
+        }
+
+        fn git(&self, args: &[&str]) -> String {
+            let output = Command::new("git")
+                .arg("-C").arg(&self.root)
+                .args(["-c", "user.name=Test Author", "-c", "user.email=test@example.com", "-c", "commit.gpgsign=false"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+    }
+
+    impl Drop for TestRepo {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    /// The repository with `EDITED` on disk, and the file tracked against it
+    fn edited_file() -> (TestRepo, GitFile) {
+        let repo = TestRepo::new();
+        fs::write(repo.file(), EDITED).unwrap();
+        let mut file = GitFile::open(&repo.file()).unwrap();
+        file.refresh(EDITED, 1);
+        assert_eq!(file.unstaged_hunks().len(), 2);
+        (repo, file)
+    }
+
+    #[test]
+    fn stage_and_unstage_one_hunk() {
+        let (repo, mut file) = edited_file();
+
+        assert!(file.stage_hunk(EDITED, 0).unwrap());
+        let cached = repo.git(&["diff", "--cached"]);
+        assert!(cached.contains("-one\n+ONE\n"), "{}", cached);
+        assert!(!cached.contains("NINE"), "{}", cached);
+        file.refresh(EDITED, 2);
+        assert_eq!(file.unstaged_hunks().len(), 1);
+
+        assert!(file.unstage_hunk(0).unwrap());
+        assert_eq!(repo.git(&["diff", "--cached"]), "");
+    }
+
+    #[test]
+    fn revert_one_hunk() {
+        let (_repo, file) = edited_file();
+
+        let (range, original) = file.revert_hunk(EDITED, 8).unwrap();
+        let mut text = EDITED.to_string();
+        text.replace_range(range, &original);
+        assert_eq!(text, "ONE\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n");
+    }
+
+    #[test]
+    fn blame_names_the_committer() {
+        let (_repo, file) = edited_file();
+
+        let committed = file.repo.blame_line(&file.rel_path, EDITED, 1).unwrap();
+        assert_eq!(committed.author, "Test Author");
+        assert_eq!(committed.summary, "Add file");
+        assert!(!committed.uncommitted);
+        assert!(file.repo.blame_line(&file.rel_path, EDITED, 0).unwrap().uncommitted);
+    }
+}
@@ -0,0 +1,1010 @@
+use crate::actor::{Actor, ActorMessage, ActorAPI, ApiMethod, ApiParameter, ApiParams, ApiResult};
+use crate::byte_buffer::ByteBuffer;
+use crate::config::EditorConfig;
+use async_trait::async_trait;
+use egui::{self, Color32, FontId};
+use uuid::Uuid;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+const BYTES_PER_ROW: usize = 16;
+/// Bytes looked at when deciding whether a file is binary
+const SNIFF_LEN: u64 = 8000;
+
+const EDITED_COLOR: Color32 = Color32::from_rgb(230, 160, 60);
+const FOUND_COLOR: Color32 = Color32::from_rgb(200, 170, 40);
+
+/// Whether a file looks binary: it has a NUL byte or invalid UTF-8 near the start
+pub fn is_binary_file(path: &Path) -> bool {
+    let mut head = Vec::new();
+    if File::open(path).and_then(|file| file.take(SNIFF_LEN).read_to_end(&mut head)).is_err() {
+        return false;
+    }
+    // A character cut off at the end of the sample doesn't count
+    head.contains(&0) || std::str::from_utf8(&head).is_err_and(|e| e.error_len().is_some())
+}
+
+/// How a search pattern is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// Hex digits, e.g. `DE AD be ef`
+    Hex,
+    /// UTF-8 text
+    Text,
+}
+
+impl SearchMode {
+    pub fn pattern(self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            SearchMode::Hex => parse_hex(text),
+            SearchMode::Text => Ok(text.as_bytes().to_vec()),
+        }
+    }
+}
+
+/// Bytes from hex digits, ignoring whitespace, commas and `0x` prefixes
+pub fn parse_hex(text: &str) -> Result<Vec<u8>> {
+    let digits: String = text.split([' ', ',', '\t', '\n'])
+        .map(|token| token.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if !digits.is_ascii() {
+        return Err(anyhow!("Not hex: '{}'", text));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(anyhow!("Odd number of hex digits in '{}'", text));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| anyhow!("Not hex: '{}'", text)))
+        .collect()
+}
+
+/// An offset written as decimal, `0x1F` or `1Fh`
+pub fn parse_offset(text: &str) -> Result<usize> {
+    let text = text.trim();
+    let parsed = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        usize::from_str_radix(hex, 16)
+    } else if let Some(hex) = text.strip_suffix(['h', 'H']) {
+        usize::from_str_radix(hex, 16)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|_| anyhow!("Not an offset: '{}'", text))
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+/// One type decoded at the cursor, in both byte orders
+#[derive(Debug, Clone, Serialize)]
+pub struct Inspection {
+    pub name: &'static str,
+    pub little_endian: Option<String>,
+    pub big_endian: Option<String>,
+}
+
+fn decoded<const N: usize>(bytes: &[u8], decode: fn([u8; N]) -> String) -> Option<String> {
+    let array: [u8; N] = bytes.get(..N)?.try_into().ok()?;
+    Some(decode(array))
+}
+
+fn float(value: f64) -> String {
+    if value != 0.0 && !(1e-4..1e9).contains(&value.abs()) {
+        format!("{:e}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Integers and floats starting at the first of `bytes` (at most 8 are used)
+pub fn inspect(bytes: &[u8]) -> Vec<Inspection> {
+    let both = |name, little, big| Inspection { name, little_endian: little, big_endian: big };
+    vec![
+        both("binary", decoded(bytes, |b: [u8; 1]| format!("{:08b}", b[0])), decoded(bytes, |b: [u8; 1]| format!("{:08b}", b[0]))),
+        both("u8", decoded(bytes, |b: [u8; 1]| b[0].to_string()), decoded(bytes, |b: [u8; 1]| b[0].to_string())),
+        both("i8", decoded(bytes, |b: [u8; 1]| (b[0] as i8).to_string()), decoded(bytes, |b: [u8; 1]| (b[0] as i8).to_string())),
+        both("u16", decoded(bytes, |b| u16::from_le_bytes(b).to_string()), decoded(bytes, |b| u16::from_be_bytes(b).to_string())),
+        both("i16", decoded(bytes, |b| i16::from_le_bytes(b).to_string()), decoded(bytes, |b| i16::from_be_bytes(b).to_string())),
+        both("u32", decoded(bytes, |b| u32::from_le_bytes(b).to_string()), decoded(bytes, |b| u32::from_be_bytes(b).to_string())),
+        both("i32", decoded(bytes, |b| i32::from_le_bytes(b).to_string()), decoded(bytes, |b| i32::from_be_bytes(b).to_string())),
+        both("u64", decoded(bytes, |b| u64::from_le_bytes(b).to_string()), decoded(bytes, |b| u64::from_be_bytes(b).to_string())),
+        both("i64", decoded(bytes, |b| i64::from_le_bytes(b).to_string()), decoded(bytes, |b| i64::from_be_bytes(b).to_string())),
+        both("f32", decoded(bytes, |b| float(f32::from_le_bytes(b) as f64)), decoded(bytes, |b| float(f32::from_be_bytes(b) as f64))),
+        both("f64", decoded(bytes, |b| float(f64::from_le_bytes(b))), decoded(bytes, |b| float(f64::from_be_bytes(b)))),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Hex,
+    Ascii,
+}
+
+/// Character shown for a byte in the ASCII column
+fn ascii_char(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }
+}
+
+/// Where the offset, hex and ASCII columns of a row sit, in monospace cells
+struct RowLayout {
+    char_width: f32,
+    /// Digits in the offset column: 8, or 16 past 4 GiB
+    offset_digits: usize,
+}
+
+impl RowLayout {
+    fn new(char_width: f32, len: usize) -> Self {
+        let offset_digits = if len > u32::MAX as usize { 16 } else { 8 };
+        Self { char_width, offset_digits }
+    }
+
+    fn hex_x(&self) -> f32 {
+        (self.offset_digits + 2) as f32 * self.char_width
+    }
+
+    fn ascii_x(&self) -> f32 {
+        self.hex_x() + (BYTES_PER_ROW * 3 + 2) as f32 * self.char_width
+    }
+
+    /// Left edge of the `i`th byte's hex digits, with an extra gap after the eighth
+    fn hex_column(&self, i: usize) -> f32 {
+        self.hex_x() + (i * 3 + i / 8) as f32 * self.char_width
+    }
+
+    fn ascii_column(&self, i: usize) -> f32 {
+        self.ascii_x() + i as f32 * self.char_width
+    }
+
+    fn width(&self) -> f32 {
+        self.ascii_x() + (BYTES_PER_ROW + 1) as f32 * self.char_width
+    }
+
+    /// Byte in the row, and the pane, under `x`
+    fn column_at(&self, x: f32) -> (usize, Pane) {
+        if x >= self.ascii_x() {
+            ((((x - self.ascii_x()) / self.char_width) as usize).min(BYTES_PER_ROW - 1), Pane::Ascii)
+        } else {
+            ((0..BYTES_PER_ROW).rev().find(|&i| x >= self.hex_column(i)).unwrap_or(0), Pane::Hex)
+        }
+    }
+}
+
+/// Hex editor for binary files: offset, hex and ASCII columns over a
+/// memory-mapped piece table, with a data inspector for the bytes at the cursor
+pub struct HexEditorActor {
+    id: Uuid,
+    name: String,
+    path: PathBuf,
+    buffer: ByteBuffer,
+    config: EditorConfig,
+    /// Byte offset of the cursor (the buffer length when past the last byte)
+    cursor: usize,
+    pane: Pane,
+    /// Typing inserts bytes instead of overwriting them
+    insert_mode: bool,
+    /// The high nibble of the byte at the cursor was typed; the low one is next
+    pending_nibble: bool,
+    /// Last search match, highlighted
+    found: Option<Range<usize>>,
+    search_text: String,
+    search_mode: SearchMode,
+    goto_text: String,
+    /// Result of the last search, goto or save, for the status line
+    status: Option<String>,
+    /// Rows shown last frame, for keeping the cursor in view
+    visible_rows: Range<usize>,
+    scroll_to_cursor: bool,
+}
+
+impl HexEditorActor {
+    pub fn open(path: PathBuf, config: EditorConfig) -> Result<Self> {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name,
+            buffer: ByteBuffer::open(&path)?,
+            path,
+            config,
+            cursor: 0,
+            pane: Pane::Hex,
+            insert_mode: false,
+            pending_nibble: false,
+            found: None,
+            search_text: String::new(),
+            search_mode: SearchMode::Hex,
+            goto_text: String::new(),
+            status: None,
+            visible_rows: 0..0,
+            scroll_to_cursor: false,
+        })
+    }
+
+    fn set_cursor(&mut self, offset: usize) {
+        self.cursor = offset.min(self.buffer.len());
+        self.pending_nibble = false;
+        self.buffer.break_undo_group();
+        self.scroll_to_cursor = true;
+    }
+
+    pub fn goto(&mut self, offset: usize) -> Result<()> {
+        if offset > self.buffer.len() {
+            return Err(anyhow!("Offset {:#x} is past the end of the file ({:#x})", offset, self.buffer.len()));
+        }
+        self.set_cursor(offset);
+        Ok(())
+    }
+
+    /// Find the next (or previous) match after the cursor, wrapping around
+    /// the file, and move the cursor to it
+    pub fn find(&mut self, pattern: &[u8], forward: bool) -> Option<usize> {
+        let len = self.buffer.len();
+        let found = if forward {
+            let from = if self.found.as_ref().is_some_and(|found| found.start == self.cursor) { self.cursor + 1 } else { self.cursor };
+            self.buffer.find(pattern, from, true).or_else(|| self.buffer.find(pattern, 0, true))
+        } else {
+            self.buffer.find(pattern, self.cursor, false).or_else(|| self.buffer.find(pattern, len, false))
+        };
+        self.found = found.map(|start| start..start + pattern.len());
+        if let Some(start) = found {
+            self.set_cursor(start);
+        }
+        found
+    }
+
+    /// Search with the toolbar's pattern and mode, reporting the outcome in the status line
+    fn find_from_toolbar(&mut self, forward: bool) {
+        self.status = match self.search_mode.pattern(&self.search_text) {
+            Ok(pattern) if pattern.is_empty() => None,
+            Ok(pattern) => match self.find(&pattern, forward) {
+                Some(offset) => Some(format!("Found at {:#x}", offset)),
+                None => Some("Not found".to_string()),
+            },
+            Err(e) => Some(e.to_string()),
+        };
+    }
+
+    /// Write `bytes` at `offset`, inserting them or overwriting what's there
+    pub fn write_bytes(&mut self, offset: usize, bytes: &[u8], insert: bool) -> Result<()> {
+        if offset > self.buffer.len() {
+            return Err(anyhow!("Offset {:#x} is past the end of the file", offset));
+        }
+        self.buffer.break_undo_group();
+        let end = if insert { offset } else { offset + bytes.len() };
+        self.buffer.replace(offset..end, bytes);
+        self.buffer.break_undo_group();
+        self.found = None;
+        Ok(())
+    }
+
+    /// Type one hex digit into the byte at the cursor
+    fn type_nibble(&mut self, digit: u8) {
+        let cursor = self.cursor;
+        if self.pending_nibble {
+            let byte = self.buffer.get(cursor).unwrap_or(0);
+            self.buffer.replace(cursor..cursor + 1, &[(byte & 0xF0) | digit]);
+            self.pending_nibble = false;
+            self.cursor += 1;
+        } else {
+            if self.insert_mode || cursor >= self.buffer.len() {
+                self.buffer.replace(cursor..cursor, &[digit << 4]);
+            } else {
+                let byte = self.buffer.get(cursor).unwrap_or(0);
+                self.buffer.replace(cursor..cursor + 1, &[(digit << 4) | (byte & 0x0F)]);
+            }
+            self.pending_nibble = true;
+        }
+        self.found = None;
+        self.scroll_to_cursor = true;
+    }
+
+    /// Type a whole byte at the cursor (from the ASCII column or a paste)
+    fn type_bytes(&mut self, bytes: &[u8]) {
+        let cursor = self.cursor;
+        let end = if self.insert_mode { cursor } else { cursor + bytes.len() };
+        self.buffer.replace(cursor..end, bytes);
+        self.cursor += bytes.len();
+        self.pending_nibble = false;
+        self.found = None;
+        self.scroll_to_cursor = true;
+    }
+
+    fn delete(&mut self, backward: bool) {
+        let range = if backward {
+            self.cursor.saturating_sub(1)..self.cursor
+        } else {
+            self.cursor..self.cursor + 1
+        };
+        self.buffer.replace(range.clone(), &[]);
+        self.cursor = range.start.min(self.buffer.len());
+        self.pending_nibble = false;
+        self.found = None;
+        self.scroll_to_cursor = true;
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.buffer.undo() {
+            Some(offset) => {
+                self.set_cursor(offset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.buffer.redo() {
+            Some(offset) => {
+                self.set_cursor(offset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn save(&mut self, path: Option<PathBuf>) -> Result<()> {
+        if let Some(path) = path {
+            self.path = path;
+        }
+        self.buffer.save(&self.path)?;
+        self.cursor = self.cursor.min(self.buffer.len());
+        log::info!("Saved {}", self.path.display());
+        Ok(())
+    }
+
+    fn handle_keys(&mut self, ui: &mut egui::Ui) {
+        use egui::Key;
+        let page = self.visible_rows.len().max(2) - 1;
+        let events = ui.input(|i| i.events.clone());
+        for event in events {
+            match event {
+                egui::Event::Text(text) => {
+                    for c in text.chars() {
+                        match self.pane {
+                            Pane::Hex => if let Some(digit) = c.to_digit(16) {
+                                self.type_nibble(digit as u8);
+                            },
+                            Pane::Ascii => if c.is_ascii() && !c.is_ascii_control() {
+                                self.type_bytes(&[c as u8]);
+                            },
+                        }
+                    }
+                }
+                egui::Event::Paste(text) => {
+                    let bytes = match self.pane {
+                        Pane::Hex => parse_hex(&text),
+                        Pane::Ascii => Ok(text.into_bytes()),
+                    };
+                    match bytes {
+                        Ok(bytes) => {
+                            self.buffer.break_undo_group();
+                            self.type_bytes(&bytes);
+                            self.buffer.break_undo_group();
+                        }
+                        Err(e) => self.status = Some(e.to_string()),
+                    }
+                }
+                egui::Event::Copy => {
+                    let bytes = self.buffer.read(self.cursor..self.cursor + 1);
+                    ui.ctx().copy_text(hex_string(&bytes));
+                }
+                egui::Event::Key { key, pressed: true, modifiers, .. } => {
+                    let row_start = self.cursor - self.cursor % BYTES_PER_ROW;
+                    match key {
+                        Key::ArrowLeft => self.set_cursor(self.cursor.saturating_sub(1)),
+                        Key::ArrowRight => self.set_cursor(self.cursor + 1),
+                        Key::ArrowUp => self.set_cursor(self.cursor.saturating_sub(BYTES_PER_ROW)),
+                        Key::ArrowDown => self.set_cursor(self.cursor + BYTES_PER_ROW),
+                        Key::PageUp => self.set_cursor(self.cursor.saturating_sub(page * BYTES_PER_ROW)),
+                        Key::PageDown => self.set_cursor(self.cursor + page * BYTES_PER_ROW),
+                        Key::Home if modifiers.command => self.set_cursor(0),
+                        Key::End if modifiers.command => self.set_cursor(self.buffer.len()),
+                        Key::Home => self.set_cursor(row_start),
+                        Key::End => self.set_cursor(row_start + BYTES_PER_ROW - 1),
+                        Key::Tab => {
+                            self.pane = if self.pane == Pane::Hex { Pane::Ascii } else { Pane::Hex };
+                            self.pending_nibble = false;
+                        }
+                        Key::Insert => self.insert_mode = !self.insert_mode,
+                        Key::Backspace if self.insert_mode => self.delete(true),
+                        Key::Backspace => self.set_cursor(self.cursor.saturating_sub(1)),
+                        Key::Delete => self.delete(false),
+                        Key::Z if modifiers.command && modifiers.shift => { self.redo(); }
+                        Key::Z if modifiers.command => { self.undo(); }
+                        Key::Y if modifiers.command => { self.redo(); }
+                        Key::S if modifiers.command => {
+                            if let Err(e) = self.save(None) {
+                                self.status = Some(e.to_string());
+                            }
+                        }
+                        Key::F3 => self.find_from_toolbar(!modifiers.shift),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(&self.name);
+            if self.buffer.is_modified() {
+                ui.label("●");
+            }
+            ui.separator();
+            ui.weak(format!("{} bytes", self.buffer.len()));
+            ui.separator();
+            if ui.selectable_label(self.insert_mode, "INS").on_hover_text("Insert instead of overwriting (Insert)").clicked() {
+                self.insert_mode = !self.insert_mode;
+            }
+            ui.separator();
+
+            let goto = ui.add(egui::TextEdit::singleline(&mut self.goto_text)
+                .desired_width(90.0)
+                .hint_text("Go to offset"));
+            if goto.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let result = parse_offset(&self.goto_text).and_then(|offset| self.goto(offset));
+                self.status = result.err().map(|e| e.to_string());
+            }
+            ui.separator();
+
+            egui::ComboBox::from_id_salt(("hex_search_mode", self.id))
+                .width(60.0)
+                .selected_text(match self.search_mode {
+                    SearchMode::Hex => "Hex",
+                    SearchMode::Text => "Text",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.search_mode, SearchMode::Hex, "Hex");
+                    ui.selectable_value(&mut self.search_mode, SearchMode::Text, "Text");
+                });
+            let search = ui.add(egui::TextEdit::singleline(&mut self.search_text)
+                .desired_width(160.0)
+                .hint_text("Find (F3)"));
+            if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.find_from_toolbar(true);
+            }
+            if ui.small_button("▲").on_hover_text("Previous match (Shift+F3)").clicked() {
+                self.find_from_toolbar(false);
+            }
+            if ui.small_button("▼").on_hover_text("Next match (F3)").clicked() {
+                self.find_from_toolbar(true);
+            }
+        });
+    }
+
+    fn show_inspector(&self, ui: &mut egui::Ui) {
+        ui.strong("Data inspector");
+        ui.weak(format!("At {:#x} ({})", self.cursor, self.cursor));
+        ui.add_space(4.0);
+        let bytes = self.buffer.read(self.cursor..self.cursor + 8);
+        egui::Grid::new(("hex_inspector_grid", self.id))
+            .striped(true)
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.strong("Type");
+                ui.strong("Little endian");
+                ui.strong("Big endian");
+                ui.end_row();
+                for row in inspect(&bytes) {
+                    ui.label(row.name);
+                    for value in [row.little_endian, row.big_endian] {
+                        match value {
+                            Some(value) => ui.monospace(value),
+                            None => ui.weak("—"),
+                        };
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_bytes(&mut self, ui: &mut egui::Ui) {
+        let id = egui::Id::new(("hex_editor", self.id));
+        if ui.memory(|m| m.has_focus(id)) {
+            ui.memory_mut(|m| m.set_focus_lock_filter(id, egui::EventFilter {
+                tab: true,
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                escape: false,
+            }));
+            self.handle_keys(ui);
+        }
+
+        let font = FontId::monospace(self.config.font_size);
+        let (char_width, row_height) = ui.fonts(|f| (f.glyph_width(&font, '0'), f.row_height(&font)));
+        let row_step = row_height + ui.spacing().item_spacing.y;
+        let len = self.buffer.len();
+        let layout = RowLayout::new(char_width, len);
+
+        let mut scroll = egui::ScrollArea::both()
+            .id_salt(id)
+            .auto_shrink([false, false]);
+        if std::mem::take(&mut self.scroll_to_cursor) {
+            let row = self.cursor / BYTES_PER_ROW;
+            // Fully visible rows only; the last one shown may be cut off
+            let shown = self.visible_rows.start..self.visible_rows.end.saturating_sub(1).max(self.visible_rows.start + 1);
+            if !shown.contains(&row) {
+                let first = if row < shown.start { row } else { (row + 1).saturating_sub(shown.len()) };
+                scroll = scroll.vertical_scroll_offset(first as f32 * row_step);
+            }
+        }
+
+        let rows = len / BYTES_PER_ROW + 1;
+        let output = scroll.show_rows(ui, row_height, rows, |ui, visible| {
+            self.visible_rows = visible.clone();
+            let start = visible.start * BYTES_PER_ROW;
+            let end = visible.end * BYTES_PER_ROW;
+            let bytes = self.buffer.read(start..end);
+            let edited = self.buffer.edited_ranges(start..end);
+            let visuals = ui.visuals().clone();
+
+            for row in visible {
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(layout.width(), row_height), egui::Sense::hover());
+                let painter = ui.painter();
+                let row_offset = row * BYTES_PER_ROW;
+                painter.text(
+                    rect.min,
+                    egui::Align2::LEFT_TOP,
+                    format!("{:0width$X}", row_offset, width = layout.offset_digits),
+                    font.clone(),
+                    visuals.weak_text_color(),
+                );
+                for i in 0..BYTES_PER_ROW {
+                    let offset = row_offset + i;
+                    if offset > len {
+                        break;
+                    }
+                    let hex_rect = egui::Rect::from_min_size(
+                        egui::pos2(rect.left() + layout.hex_column(i), rect.top()),
+                        egui::vec2(2.0 * char_width, row_height),
+                    );
+                    let ascii_rect = egui::Rect::from_min_size(
+                        egui::pos2(rect.left() + layout.ascii_column(i), rect.top()),
+                        egui::vec2(char_width, row_height),
+                    );
+                    if self.found.as_ref().is_some_and(|found| found.contains(&offset)) {
+                        painter.rect_filled(hex_rect.expand2(egui::vec2(char_width * 0.5, 0.0)), 0.0, FOUND_COLOR.gamma_multiply(0.4));
+                        painter.rect_filled(ascii_rect, 0.0, FOUND_COLOR.gamma_multiply(0.4));
+                    }
+                    if offset == self.cursor {
+                        let (active, other) = match self.pane {
+                            Pane::Hex => (hex_rect, ascii_rect),
+                            Pane::Ascii => (ascii_rect, hex_rect),
+                        };
+                        let active = if self.pending_nibble && self.pane == Pane::Hex {
+                            egui::Rect::from_min_max(egui::pos2(active.center().x, active.top()), active.max)
+                        } else {
+                            active
+                        };
+                        painter.rect_filled(active, 0.0, visuals.selection.bg_fill);
+                        painter.rect_stroke(other, 0.0, visuals.selection.stroke);
+                    }
+                    let Some(&byte) = bytes.get(offset - start) else {
+                        continue;
+                    };
+                    let color = if edited.iter().any(|range| range.contains(&offset)) {
+                        EDITED_COLOR
+                    } else if byte == 0 {
+                        visuals.weak_text_color()
+                    } else {
+                        visuals.text_color()
+                    };
+                    painter.text(hex_rect.min, egui::Align2::LEFT_TOP, format!("{:02X}", byte), font.clone(), color);
+                    painter.text(ascii_rect.min, egui::Align2::LEFT_TOP, ascii_char(byte), font.clone(), color);
+                }
+            }
+        });
+
+        // One widget over the whole view takes clicks and keyboard focus
+        let response = ui.interact(output.inner_rect, id, egui::Sense::click());
+        if response.clicked() {
+            response.request_focus();
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let content = pointer - output.inner_rect.min + output.state.offset;
+                let row = (content.y / row_step) as usize;
+                let (column, pane) = layout.column_at(content.x);
+                self.pane = pane;
+                self.set_cursor(row * BYTES_PER_ROW + column);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Actor for HexEditorActor {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn handle_message(&mut self, _message: ActorMessage) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &egui::Context) {}
+
+    fn render(&mut self, ui: &mut egui::Ui) {
+        self.show_toolbar(ui);
+        ui.separator();
+
+        egui::TopBottomPanel::bottom(egui::Id::new(("hex_status", self.id))).show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("Offset {:#x} ({})", self.cursor, self.cursor));
+                ui.separator();
+                ui.label(if self.insert_mode { "Insert" } else { "Overwrite" });
+                if let Some(status) = &self.status {
+                    ui.separator();
+                    ui.label(status);
+                }
+            });
+        });
+        egui::SidePanel::right(egui::Id::new(("hex_inspector", self.id)))
+            .default_width(280.0)
+            .show_inside(ui, |ui| self.show_inspector(ui));
+        egui::CentralPanel::default().show_inside(ui, |ui| self.show_bytes(ui));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn is_modified(&self) -> bool {
+        self.buffer.is_modified()
+    }
+}
+
+/// An offset parameter given as a number or as text like `0x1F`
+fn offset_param(params: &ApiParams, name: &str) -> Result<Option<usize>> {
+    match params.get_optional::<serde_json::Value>(name) {
+        None => Ok(None),
+        Some(serde_json::Value::Number(n)) => n.as_u64()
+            .map(|n| Some(n as usize))
+            .ok_or_else(|| anyhow!("Parameter '{}' must be a non-negative integer", name)),
+        Some(serde_json::Value::String(text)) => parse_offset(&text).map(Some),
+        Some(_) => Err(anyhow!("Parameter '{}' must be a number or a string", name)),
+    }
+}
+
+impl ActorAPI for HexEditorActor {
+    fn actor_type(&self) -> String {
+        "HexEditorActor".to_string()
+    }
+
+    fn get_api_methods(&self) -> Vec<ApiMethod> {
+        let offset = |description: &str, required| ApiParameter {
+            name: "offset".to_string(),
+            param_type: "number|string".to_string(),
+            description: description.to_string(),
+            required,
+            default_value: None,
+        };
+        vec![
+            ApiMethod {
+                name: "goto_offset".to_string(),
+                description: "Move the cursor to a byte offset".to_string(),
+                parameters: vec![offset("Offset, decimal or hex like 0x1F", true)],
+                return_type: "void".to_string(),
+                category: "navigation".to_string(),
+            },
+            ApiMethod {
+                name: "read_bytes".to_string(),
+                description: "Read bytes as hex".to_string(),
+                parameters: vec![
+                    offset("Where to start (defaults to the cursor)", false),
+                    ApiParameter {
+                        name: "length".to_string(),
+                        param_type: "number".to_string(),
+                        description: "Number of bytes".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::json!(256)),
+                    },
+                ],
+                return_type: "string".to_string(),
+                category: "content".to_string(),
+            },
+            ApiMethod {
+                name: "write_bytes".to_string(),
+                description: "Overwrite or insert bytes given as hex".to_string(),
+                parameters: vec![
+                    offset("Where to write (defaults to the cursor)", false),
+                    ApiParameter {
+                        name: "hex".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Bytes as hex digits, e.g. \"de ad be ef\"".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "insert".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Insert instead of overwriting".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                ],
+                return_type: "void".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "find".to_string(),
+                description: "Find a hex pattern or string after the cursor, wrapping around".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "pattern".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Hex digits or text, depending on the mode".to_string(),
+                        required: true,
+                        default_value: None,
+                    },
+                    ApiParameter {
+                        name: "mode".to_string(),
+                        param_type: "string".to_string(),
+                        description: "hex or text".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::json!("hex")),
+                    },
+                    ApiParameter {
+                        name: "backwards".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Search towards the start of the file".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(false)),
+                    },
+                ],
+                return_type: "number|null".to_string(),
+                category: "search".to_string(),
+            },
+            ApiMethod {
+                name: "find_next".to_string(),
+                description: "Repeat the toolbar search forwards".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "search".to_string(),
+            },
+            ApiMethod {
+                name: "find_previous".to_string(),
+                description: "Repeat the toolbar search backwards".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "search".to_string(),
+            },
+            ApiMethod {
+                name: "inspect".to_string(),
+                description: "Decode integers and floats at the cursor in both byte orders".to_string(),
+                parameters: vec![offset("Where to decode (defaults to the cursor)", false)],
+                return_type: "array".to_string(),
+                category: "content".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_insert_mode".to_string(),
+                description: "Switch between inserting and overwriting bytes".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "undo".to_string(),
+                description: "Undo the last edit".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "redo".to_string(),
+                description: "Redo the last undone edit".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "editing".to_string(),
+            },
+            ApiMethod {
+                name: "save".to_string(),
+                description: "Save the bytes to the file, or to another path".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "Where to save (defaults to the open file)".to_string(),
+                        required: false,
+                        default_value: None,
+                    },
+                ],
+                return_type: "void".to_string(),
+                category: "file".to_string(),
+            },
+            ApiMethod {
+                name: "get_stats".to_string(),
+                description: "Get the file size, cursor and modified state".to_string(),
+                parameters: vec![],
+                return_type: "object".to_string(),
+                category: "info".to_string(),
+            },
+        ]
+    }
+
+    fn execute_api_method(&mut self, method: &str, params: ApiParams) -> Result<ApiResult> {
+        match method {
+            "goto_offset" => {
+                let offset = offset_param(&params, "offset")?.ok_or_else(|| anyhow!("Parameter 'offset' not found"))?;
+                self.goto(offset)?;
+                Ok(ApiResult::Success)
+            },
+            "read_bytes" => {
+                let offset = offset_param(&params, "offset")?.unwrap_or(self.cursor);
+                let length: usize = params.get_optional("length").unwrap_or(256);
+                Ok(ApiResult::Value(serde_json::Value::String(hex_string(&self.buffer.read(offset..offset + length)))))
+            },
+            "write_bytes" => {
+                let offset = offset_param(&params, "offset")?.unwrap_or(self.cursor);
+                let bytes = parse_hex(&params.get::<String>("hex")?)?;
+                self.write_bytes(offset, &bytes, params.get_optional("insert").unwrap_or(false))?;
+                Ok(ApiResult::Success)
+            },
+            "find" => {
+                let mode = params.get_optional("mode").unwrap_or(SearchMode::Hex);
+                let pattern = mode.pattern(&params.get::<String>("pattern")?)?;
+                if pattern.is_empty() {
+                    return Err(anyhow!("Empty search pattern"));
+                }
+                let forward = !params.get_optional("backwards").unwrap_or(false);
+                Ok(ApiResult::Value(serde_json::json!(self.find(&pattern, forward))))
+            },
+            "find_next" => {
+                self.find_from_toolbar(true);
+                Ok(ApiResult::Success)
+            },
+            "find_previous" => {
+                self.find_from_toolbar(false);
+                Ok(ApiResult::Success)
+            },
+            "inspect" => {
+                let offset = offset_param(&params, "offset")?.unwrap_or(self.cursor);
+                Ok(ApiResult::Value(serde_json::to_value(inspect(&self.buffer.read(offset..offset + 8)))?))
+            },
+            "toggle_insert_mode" => {
+                self.insert_mode = !self.insert_mode;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.insert_mode)))
+            },
+            "undo" => Ok(ApiResult::Value(serde_json::Value::Bool(self.undo()))),
+            "redo" => Ok(ApiResult::Value(serde_json::Value::Bool(self.redo()))),
+            "save" => {
+                self.save(params.get_optional::<PathBuf>("path"))?;
+                Ok(ApiResult::Success)
+            },
+            "get_stats" => Ok(ApiResult::Value(serde_json::json!({
+                "size": self.buffer.len(),
+                "cursor": self.cursor,
+                "is_modified": self.buffer.is_modified(),
+                "insert_mode": self.insert_mode,
+            }))),
+            _ => Err(anyhow!("Unknown method: {}", method))
+        }
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        vec![
+            "binary_editing".to_string(),
+            "search".to_string(),
+            "file_operations".to_string(),
+        ]
+    }
+
+    fn get_state(&self) -> HashMap<String, serde_json::Value> {
+        let mut state = HashMap::new();
+        state.insert("file_path".to_string(), serde_json::json!(self.path));
+        state.insert("size".to_string(), serde_json::json!(self.buffer.len()));
+        state.insert("cursor".to_string(), serde_json::json!(self.cursor));
+        state.insert("is_modified".to_string(), serde_json::Value::Bool(self.buffer.is_modified()));
+        state.insert("insert_mode".to_string(), serde_json::Value::Bool(self.insert_mode));
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IdeConfig;
+    use std::fs;
+
+    /// Hex editor over a temporary file holding `bytes`
+    fn editor(bytes: &[u8]) -> HexEditorActor {
+        let path = std::env::temp_dir().join(format!("zellij-ide-hex-{}.bin", Uuid::new_v4()));
+        fs::write(&path, bytes).unwrap();
+        let editor = HexEditorActor::open(path.clone(), IdeConfig::default().editor).unwrap();
+        fs::remove_file(&path).unwrap();
+        editor
+    }
+
+    fn contents(editor: &HexEditorActor) -> Vec<u8> {
+        editor.buffer.read(0..editor.buffer.len())
+    }
+
+    #[test]
+    fn nibbles_edit_the_byte_at_the_cursor() {
+        let mut editor = editor(&[0x12, 0x34, 0x56]);
+        editor.goto(1).unwrap();
+        editor.type_nibble(0xA);
+        assert_eq!((contents(&editor), editor.cursor, editor.pending_nibble), (vec![0x12, 0xA4, 0x56], 1, true));
+        editor.type_nibble(0xB);
+        assert_eq!((contents(&editor), editor.cursor, editor.pending_nibble), (vec![0x12, 0xAB, 0x56], 2, false));
+
+        // Past the end a new byte is started
+        editor.goto(3).unwrap();
+        editor.type_nibble(0xC);
+        editor.type_nibble(0xD);
+        assert_eq!(contents(&editor), [0x12, 0xAB, 0x56, 0xCD]);
+        assert!(editor.buffer.is_modified());
+
+        // Moving the cursor ends the undo step
+        assert!(editor.undo());
+        assert_eq!(contents(&editor), [0x12, 0xAB, 0x56]);
+        assert!(editor.undo());
+        assert_eq!(contents(&editor), [0x12, 0x34, 0x56]);
+        assert!(editor.redo());
+        assert_eq!(contents(&editor), [0x12, 0xAB, 0x56]);
+        assert!(editor.goto(5).is_err());
+    }
+
+    #[test]
+    fn insert_mode_adds_bytes_instead_of_overwriting() {
+        let mut editor = editor(b"abcd");
+        editor.goto(1).unwrap();
+        editor.type_bytes(b"XY");
+        assert_eq!((contents(&editor), editor.cursor), (b"aXYd".to_vec(), 3));
+
+        editor.insert_mode = true;
+        editor.type_bytes(b"-");
+        assert_eq!((contents(&editor), editor.cursor), (b"aXY-d".to_vec(), 4));
+        editor.type_nibble(0x4);
+        editor.type_nibble(0x1);
+        assert_eq!(contents(&editor), b"aXY-Ad");
+        editor.delete(true);
+        assert_eq!((contents(&editor), editor.cursor), (b"aXY-d".to_vec(), 4));
+        editor.delete(false);
+        assert_eq!(contents(&editor), b"aXY-");
+
+        editor.write_bytes(0, b"01", true).unwrap();
+        editor.write_bytes(4, b"!", false).unwrap();
+        assert_eq!(contents(&editor), b"01aX!-");
+        assert!(editor.write_bytes(7, b"?", true).is_err());
+    }
+
+    #[test]
+    fn columns_map_to_bytes() {
+        let layout = RowLayout::new(10.0, 100);
+        assert_eq!(layout.offset_digits, 8);
+        assert_eq!(RowLayout::new(10.0, u32::MAX as usize + 1).offset_digits, 16);
+        assert_eq!((layout.hex_x(), layout.ascii_x()), (100.0, 600.0));
+        // An extra gap splits the hex digits into two groups of eight
+        assert_eq!((layout.hex_column(7), layout.hex_column(8)), (310.0, 350.0));
+        assert_eq!(layout.ascii_column(15), 750.0);
+
+        for i in 0..BYTES_PER_ROW {
+            assert_eq!(layout.column_at(layout.hex_column(i) + 15.0), (i, Pane::Hex));
+            assert_eq!(layout.column_at(layout.ascii_column(i) + 5.0), (i, Pane::Ascii));
+        }
+        // Offsets, gaps and the space past the last byte land on the nearest byte
+        assert_eq!(layout.column_at(0.0), (0, Pane::Hex));
+        assert_eq!(layout.column_at(345.0), (7, Pane::Hex));
+        assert_eq!(layout.column_at(layout.width() + 50.0), (BYTES_PER_ROW - 1, Pane::Ascii));
+
+        assert_eq!([b'a', b' ', b'~', 0, b'\n', 0x7F, 0xE9].map(ascii_char), ['a', ' ', '~', '.', '.', '.', '.']);
+    }
+}
@@ -1,68 +1,414 @@
-use crate::actor::{ActorManager, Actor};
-use crate::view::{SplitDirection};
-use crate::view_system::ViewContainer;
+use crate::actor::{ActorManager, Actor, ApiParams, ApiResult};
+use crate::command_palette::{CommandContext, CommandExecutionResult, CommandPalette, GlobalAction};
+use crate::view::{SplitDirection, ViewManager};
+use crate::view_system::{ViewContainer, ViewSystem};
 use crate::scene_system::SceneSystem;
-use crate::tiling_system::TilingSystem;
+use crate::tiling_system::{TabLabel, TilingSystem};
 use crate::code_editor_actor::CodeEditorActor;
+use crate::hex_editor_actor::{self, HexEditorActor};
+use crate::image_decode;
+use crate::image_preview_actor::ImagePreviewActor;
+use crate::markdown_editor_actor::MarkdownEditorActor;
 use crate::widgets::{WidgetManager, WidgetContext};
 use crate::view_system::Transformable;
 use crate::terminal_actor::TerminalActor;
+use crate::layout::{ActorLayout, Layout, LayoutNode, ViewSystemKind, PROJECT_LAYOUT_FILE};
+use crate::session::{PaneSession, SavedTransform, Session, SESSION_SAVE_INTERVAL};
+use crate::tiling_system::TileDirection;
+use crate::config::{EditorConfig, IdeConfig};
+use crate::keymap::KeymapKind;
+use crate::language_servers::LanguageServers;
+use crate::swap_file::{RecoveryChoice, RecoveryPrompt, SwapStore};
+use crate::floating_panes::FloatingPanes;
 use egui;
+use uuid::Uuid;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Ctrl+1..9 jump to, and Ctrl+Shift+1..9 save, the camera bookmarks in
+/// these slots
+const BOOKMARK_SLOT_KEYS: [egui::Key; 9] = [
+    egui::Key::Num1, egui::Key::Num2, egui::Key::Num3,
+    egui::Key::Num4, egui::Key::Num5, egui::Key::Num6,
+    egui::Key::Num7, egui::Key::Num8, egui::Key::Num9,
+];
+
+/// Consume a press of `key` with exactly `modifiers`, matching the physical
+/// key too since Shift turns the digits into symbols on most layouts
+fn consume_slot_key(input: &mut egui::InputState, modifiers: egui::Modifiers, key: egui::Key) -> bool {
+    let index = input.events.iter().position(|event| matches!(
+        event,
+        egui::Event::Key { key: logical_key, physical_key, pressed: true, modifiers: held, .. }
+            if held.matches_exact(modifiers) && (*logical_key == key || *physical_key == Some(key))
+    ));
+    index.map(|index| input.events.remove(index)).is_some()
+}
 
 /// Main IDE state - combines actors, view system, and widgets
 pub struct IdeState {
     pub actors: ActorManager,
     pub view_container: ViewContainer,
     pub widget_manager: WidgetManager,
+    /// Panes above the view system's layout
+    pub floating: FloatingPanes,
+    /// Runs commands requested by actors (e.g. Vim `:` commands, Emacs `M-x`)
+    command_palette: CommandPalette,
+    editor_config: EditorConfig,
+    /// Language servers every editor shares, one per language and workspace
+    language_servers: LanguageServers,
+    /// Buffers left unsaved by a crashed session, awaiting the user's decision
+    recovery: RecoveryPrompt,
+    /// Closing the window was held off for files with unsaved changes
+    quit_prompt: bool,
+    /// The user chose to quit anyway, so the next close goes through
+    quit_confirmed: bool,
     tab_counter: usize,
+    /// The layout file the IDE started from, which "Save Layout" writes to
+    layout_path: Option<PathBuf>,
+    /// The session the IDE saves itself to
+    session_name: String,
+    last_session_save: Instant,
 }
 
 impl IdeState {
-    pub fn new() -> Self {
-        let mut actors = ActorManager::new();
-
-        // Create view container with scene system (could be swapped for tiling system)
-        let scene_system = Box::new(SceneSystem::new());
-        let mut view_container = ViewContainer::new(scene_system);
-
-        // Add a test terminal actor
-        let terminal_actor = Box::new(TerminalActor::new());
-        let terminal_id = terminal_actor.id();
-        actors.register_actor(terminal_actor);
-        let terminal_view_id = view_container.system_mut().create_view("Terminal".to_string());
-        view_container.system_mut().attach_actor_to_view(terminal_view_id, terminal_id);
-
-        // Set terminal view as active so it shows up
-        view_container.system_mut().set_active_view(terminal_view_id);
-
-        // Add a test code editor actor (but don't set it as active)
-        let code_editor = Box::new(CodeEditorActor::new("main.rs".to_string()));
-        let editor_id = code_editor.id();
-        actors.register_actor(code_editor);
-        let editor_view_id = view_container.system_mut().create_view("Code Editor".to_string());
-        view_container.system_mut().attach_actor_to_view(editor_view_id, editor_id);
+    pub fn new(config: &IdeConfig, layout: &Layout, session_name: &str) -> Self {
+        let mut state = Self::blank(config, session_name);
+        state.apply_layout(layout);
+        state
+    }
+
+    /// Reopen a saved session: its layout, actor states and view transforms
+    pub fn from_session(config: &IdeConfig, session: &Session) -> Self {
+        let mut state = Self::blank(config, &session.name);
+        state.restore_session(session);
+        state
+    }
+
+    /// State without any views or actors yet
+    fn blank(config: &IdeConfig, session_name: &str) -> Self {
+        let swaps = SwapStore::new().map(|store| store.load_all()).unwrap_or_default();
+        if !swaps.is_empty() {
+            log::info!("Found {} recoverable buffers from a previous session", swaps.len());
+        }
 
         Self {
-            actors,
-            view_container,
+            actors: ActorManager::new(),
+            view_container: ViewContainer::new(Box::new(SceneSystem::new())),
             widget_manager: WidgetManager::new(),
-            tab_counter: 1,
+            floating: FloatingPanes::new(),
+            command_palette: CommandPalette::new(),
+            editor_config: config.editor.clone(),
+            language_servers: LanguageServers::new(),
+            recovery: RecoveryPrompt::new(swaps),
+            quit_prompt: false,
+            quit_confirmed: false,
+            tab_counter: 0,
+            layout_path: None,
+            session_name: session_name.to_string(),
+            last_session_save: Instant::now(),
+        }
+    }
+
+    pub fn with_layout_path(mut self, path: PathBuf) -> Self {
+        self.layout_path = Some(path);
+        self
+    }
+
+    /// Replace the view system with `layout`'s, opening its actors, and
+    /// return each pane's view and the actor opened for each of its tabs
+    ///
+    /// Actors a pane's tabs fail to open (e.g. missing files) are logged and
+    /// left out. The first pane becomes active.
+    pub fn apply_layout(&mut self, layout: &Layout) -> Vec<(Uuid, Vec<Option<Uuid>>)> {
+        let mut panes = Vec::new();
+        let mut pane = |pane: &LayoutNode| {
+            let id = Uuid::new_v4();
+            panes.push((id, pane.clone()));
+            id
+        };
+
+        let view_ids: Vec<Uuid> = match layout.view_system {
+            ViewSystemKind::Tiling => {
+                let tree = layout.root.to_tile_tree(&mut pane);
+                let mut tiling = TilingSystem::new();
+                tiling.set_layout(tree);
+                self.view_container = ViewContainer::new(Box::new(tiling));
+                panes.iter().map(|(id, _)| *id).collect()
+            }
+            ViewSystemKind::Scene => {
+                // Scenes have no splits; each pane gets a view of its own
+                layout.root.to_tile_tree(&mut pane);
+                self.view_container = ViewContainer::new(Box::new(SceneSystem::new()));
+                let system = self.view_container.system_mut();
+                let mut view_ids: Vec<Uuid> = system.active_view().into_iter().collect();
+                while view_ids.len() < panes.len() {
+                    view_ids.push(system.create_view(format!("View {}", view_ids.len() + 1)));
+                }
+                view_ids
+            }
+            ViewSystemKind::Nested => {
+                let nested = layout.root.to_view_tree(&mut pane);
+                self.view_container = ViewContainer::new(Box::new(nested));
+                panes.iter().map(|(id, _)| *id).collect()
+            }
+        };
+
+        let mut opened = Vec::new();
+        for ((_, pane), view_id) in panes.iter().zip(view_ids) {
+            let actor_ids: Vec<Option<Uuid>> = pane.tabs.iter().map(|tab| self.open_layout_actor(tab)).collect();
+            let shown = actor_ids.get(pane.active_tab).copied().flatten().or(actor_ids.iter().flatten().next().copied());
+            opened.push((view_id, actor_ids.clone()));
+            if let Some(nested) = self.view_container.as_system_mut::<ViewManager>() {
+                // Each tab has a leaf view of its own
+                for (leaf, tab) in nested.tab_leaves(view_id).into_iter().zip(&actor_ids) {
+                    if let Some(tab) = tab {
+                        nested.attach_actor_to_view(leaf, *tab);
+                    }
+                }
+                continue;
+            }
+            let Some(actor_id) = shown else {
+                continue;
+            };
+            let name = self.actors.get_actor(actor_id).map(|actor| actor.name());
+            if let (Some(tiling), Some(name)) = (self.view_container.as_system_mut::<TilingSystem>(), name) {
+                if let Some(tile) = tiling.get_tile_mut(view_id) {
+                    tile.title = name;
+                }
+            }
+            // Every tab goes into views with tabs; attaching the shown actor
+            // last shows it either way
+            let system = self.view_container.system_mut();
+            for &tab in actor_ids.iter().flatten() {
+                system.attach_actor_to_view(view_id, tab);
+            }
+            system.attach_actor_to_view(view_id, actor_id);
+        }
+
+        // Nested views start on the first leaf shown, leaving tabs as they are
+        let first_view = self.view_container.as_system::<ViewManager>()
+            .and_then(|nested| nested.active_view())
+            .or_else(|| self.view_container.system().get_view_ids().first().copied());
+        if let Some(view_id) = first_view {
+            self.view_container.system_mut().set_active_view(view_id);
+            if let Some(actor_id) = self.view_container.system().get_view_actor(view_id) {
+                self.actors.set_focus(actor_id);
+            }
+        }
+        opened
+    }
+
+    /// Open and register the actor a layout describes
+    fn open_layout_actor(&mut self, actor: &ActorLayout) -> Option<Uuid> {
+        let actor: Box<dyn Actor> = match actor {
+            ActorLayout::Terminal(launch) => Box::new(TerminalActor::launch(launch.clone())),
+            ActorLayout::Editor { file: Some(path) } => match self.actor_for_file(path.clone()) {
+                Ok(actor) => actor,
+                Err(e) => {
+                    log::error!("{}", e);
+                    return None;
+                }
+            },
+            ActorLayout::Editor { file: None } => Box::new(self.untitled_editor()),
+        };
+        let id = actor.id();
+        self.actors.register_actor(actor);
+        Some(id)
+    }
+
+    /// The current arrangement as a layout
+    pub fn capture_layout(&self) -> Layout {
+        self.capture_layout_panes().0
+    }
+
+    /// The current arrangement, and the view of each of its panes in order
+    fn capture_layout_panes(&self) -> (Layout, Vec<Uuid>) {
+        let system = self.view_container.system();
+        let mut pane_views = Vec::new();
+        let mut pane = |view_id: Uuid| {
+            pane_views.push(view_id);
+            let tabs = self.layout_tabs(view_id);
+            let shown = system.get_view_actor(view_id);
+            LayoutNode {
+                active_tab: tabs.iter().position(|(actor, _)| Some(actor.id()) == shown).unwrap_or(0),
+                ..LayoutNode::pane(tabs.into_iter().map(|(_, tab)| tab).collect())
+            }
+        };
+
+        let tiled = self.view_container.as_system::<TilingSystem>()
+            .and_then(TilingSystem::layout)
+            .map(|tree| LayoutNode::from_tile_tree(tree, &mut pane));
+        let nested = self.view_container.as_system::<ViewManager>()
+            .and_then(|nested| LayoutNode::from_view_tree(nested, nested.root()?, &mut pane));
+        let layout = match (tiled, nested) {
+            (Some(root), _) => Layout { view_system: ViewSystemKind::Tiling, root },
+            (_, Some(root)) => Layout { view_system: ViewSystemKind::Nested, root },
+            _ => Layout {
+                view_system: if self.current_view_system() == "scene" { ViewSystemKind::Scene } else { ViewSystemKind::Tiling },
+                root: LayoutNode::split(TileDirection::Horizontal, system.get_view_ids().into_iter().map(&mut pane).collect()),
+            },
+        };
+        (layout, pane_views)
+    }
+
+    /// The actors of `view_id` that a layout can describe, with their tabs
+    fn layout_tabs(&self, view_id: Uuid) -> Vec<(&dyn Actor, ActorLayout)> {
+        self.view_container.system().get_view_actors(view_id).into_iter()
+            .filter_map(|id| self.actors.get_actor(id))
+            .filter_map(|actor| Some((actor, ActorLayout::describe(actor)?)))
+            .collect()
+    }
+
+    /// The current arrangement with each view's transform and each actor's
+    /// state, as the open session
+    pub fn capture_session(&self) -> Session {
+        let scene = self.view_container.as_system::<SceneSystem>();
+        let (layout, pane_views) = self.capture_layout_panes();
+        let panes = pane_views.into_iter()
+            .map(|view_id| PaneSession {
+                transform: scene.and_then(|scene| scene.get_view(view_id)).map(|view| SavedTransform {
+                    zoom: view.transform.zoom,
+                    offset: [view.transform.offset.x, view.transform.offset.y],
+                }),
+                bookmarks: scene.and_then(|scene| scene.get_view(view_id)).map_or_else(Vec::new, |view| view.bookmarks.clone()),
+                states: self.layout_tabs(view_id).into_iter().map(|(actor, _)| actor.get_state()).collect(),
+            })
+            .collect();
+        let mut session = Session::new(&self.session_name, layout, panes);
+        if let Some(scene) = scene {
+            session.canvas_bookmarks = scene.canvas().bookmarks.clone();
+        }
+        session
+    }
+
+    /// Rebuild the views and actors of `session`, putting back the view
+    /// transforms and the editors' selections
+    fn restore_session(&mut self, session: &Session) {
+        let opened = self.apply_layout(&session.layout);
+        for ((view_id, actor_ids), pane) in opened.into_iter().zip(&session.panes) {
+            if let Some(view) = self.view_container.as_system_mut::<SceneSystem>().and_then(|scene| scene.get_view_mut(view_id)) {
+                if let Some(saved) = pane.transform {
+                    let transform = &mut view.transform;
+                    transform.zoom = saved.zoom.clamp(transform.min_zoom, transform.max_zoom);
+                    transform.offset = egui::vec2(saved.offset[0], saved.offset[1]);
+                }
+                view.bookmarks = pane.bookmarks.clone();
+            }
+
+            for (actor_id, state) in actor_ids.into_iter().zip(&pane.states) {
+                let (Some(actor), Some(selections)) = (actor_id.and_then(|id| self.actors.get_actor_mut(id)), state.get("selections")) else {
+                    continue;
+                };
+                if actor.can_handle_method("set_selections") {
+                    let params = ApiParams::new().with_param("selections", selections);
+                    if let Err(e) = actor.execute_api_method("set_selections", params).and_then(ApiResult::into_result) {
+                        log::warn!("Failed to restore the selections of {}: {}", actor.name(), e);
+                    }
+                }
+            }
+        }
+        if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+            scene.canvas_mut().bookmarks = session.canvas_bookmarks.clone();
+        }
+    }
+
+    pub fn session_name(&self) -> &str {
+        &self.session_name
+    }
+
+    /// Write the open session out
+    pub fn save_session(&mut self) -> anyhow::Result<()> {
+        self.last_session_save = Instant::now();
+        self.capture_session().save()
+    }
+
+    /// Save the open session and replace everything with the session saved
+    /// as `name`, or a fresh one if there's none by that name
+    ///
+    /// Refuses while any file has unsaved changes, as switching closes them.
+    pub fn switch_session(&mut self, name: &str) -> anyhow::Result<()> {
+        if name == self.session_name {
+            return Ok(());
+        }
+        let modified = self.actors.actors.iter().find(|actor| actor.is_modified());
+        if let Some(actor) = modified {
+            return Err(anyhow::anyhow!("{} has unsaved changes", actor.name()));
         }
+        let session = Session::load(name)?;
+        self.save_session()?;
+
+        // Dropping the actors closes their files and ends their terminals
+        self.actors = ActorManager::new();
+        self.floating = FloatingPanes::new();
+        self.layout_path = None;
+        self.session_name = name.to_string();
+        match session {
+            Some(session) => self.restore_session(&session),
+            None => {
+                self.apply_layout(&Layout::builtin());
+            }
+        }
+        log::info!("Switched to session '{}'", name);
+        self.save_session()
+    }
+
+    /// Write the current arrangement to the layout file the IDE started
+    /// from, or else the project's, returning where it went
+    pub fn save_layout(&self) -> anyhow::Result<PathBuf> {
+        let path = self.layout_path.clone().unwrap_or_else(|| PathBuf::from(PROJECT_LAYOUT_FILE));
+        self.capture_layout().save(&path)?;
+        Ok(path)
     }
 
     pub fn render(&mut self, ui: &mut egui::Ui) {
+        if self.last_session_save.elapsed() >= SESSION_SAVE_INTERVAL {
+            if let Err(e) = self.save_session() {
+                log::warn!("Failed to save session '{}': {}", self.session_name, e);
+            }
+        }
+
         // Handle keyboard input for view switching
         if ui.input(|i| i.key_pressed(egui::Key::Tab) && i.modifiers.ctrl) {
             self.cycle_active_view();
         }
+        let float_modifiers = egui::Modifiers::COMMAND | egui::Modifiers::ALT;
+        if ui.input_mut(|i| i.modifiers.matches_exact(float_modifiers) && i.consume_key(float_modifiers, egui::Key::W)) {
+            self.toggle_floating_panes();
+        }
+
+        // Quick bookmark slots, run through their palette commands
+        if self.view_container.system().as_bookmarks().is_some() {
+            for (slot, key) in BOOKMARK_SLOT_KEYS.into_iter().enumerate() {
+                let command = if ui.input_mut(|i| consume_slot_key(i, egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, key)) {
+                    format!("bookmark.save_{}", slot + 1)
+                } else if ui.input_mut(|i| consume_slot_key(i, egui::Modifiers::COMMAND, key)) {
+                    format!("bookmark.jump_{}", slot + 1)
+                } else {
+                    continue;
+                };
+                let actor_id = self.actors.focused_actor().unwrap_or_else(Uuid::nil);
+                self.run_palette_command(actor_id, &command, ui.ctx());
+            }
+        }
 
         // Handle view system input
         self.view_container.system_mut().handle_input(ui);
 
+        let labels = || self.actors.actors.iter()
+            .map(|actor| (actor.id(), TabLabel { name: actor.name(), modified: actor.is_modified() }))
+            .collect();
+        if let Some(tiling) = self.view_container.as_system_mut::<TilingSystem>() {
+            tiling.set_tab_labels(labels());
+        } else if let Some(nested) = self.view_container.as_system_mut::<ViewManager>() {
+            nested.set_tab_labels(labels());
+        } else if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+            scene.set_card_titles(self.actors.actors.iter().map(|actor| (actor.id(), actor.name())).collect());
+        }
+
         // Render views using the view system
         // Use a separate scope to avoid borrowing issues
         let actors_ptr = &mut self.actors as *mut ActorManager;
-        self.view_container.system_mut().render_views(ui, &|ui, actor_id| {
+        let render_actor = |ui: &mut egui::Ui, actor_id: Uuid| {
             unsafe {
                 let actor_manager = &mut *actors_ptr;
                 if let Some(actor) = actor_manager.get_actor_mut(actor_id) {
@@ -73,7 +419,28 @@ impl IdeState {
                     });
                 }
             }
+        };
+        self.view_container.system_mut().render_views(ui, &render_actor);
+
+        // Floating panes go over the layout
+        let titles = self.actors.actors.iter().map(|actor| (actor.id(), actor.name())).collect();
+        self.floating.render(ui, &titles, &render_actor);
+        for actor_id in self.floating.take_close_requests() {
+            self.close_floating_pane(actor_id);
+        }
+
+        for (view_id, actor_id) in self.view_container.system_mut().take_close_requests() {
+            self.close_tab(view_id, actor_id);
+        }
+
+        // Clicking into a floating pane, another view or a tab focuses its actor
+        let active_actor = self.floating.focused_actor().or_else(|| {
+            let system = self.view_container.system();
+            system.active_view().and_then(|view_id| system.get_view_actor(view_id))
         });
+        if let Some(actor_id) = active_actor.filter(|&id| self.actors.focused_actor() != Some(id)) {
+            self.actors.set_focus(actor_id);
+        }
 
         // Render widgets on top of everything
         let available_rect = ui.available_rect_before_wrap();
@@ -97,25 +464,355 @@ impl IdeState {
 
         self.widget_manager.render_widgets(ui, &widget_ctx);
 
+        if !self.recovery.is_empty() {
+            self.show_recovery_prompt(ui.ctx());
+        }
+        self.confirm_close(ui.ctx());
+
         // Update all actors
+        let mut palette_commands = Vec::new();
+        let mut word_requests = Vec::new();
+        let mut spawned = Vec::new();
         for actor_idx in 0..self.actors.actors.len() {
             let ctx = ui.ctx().clone();
             if let Some(actor) = self.actors.actors.get_mut(actor_idx) {
                 actor.update(&ctx);
+                let actor_id = actor.id();
+                palette_commands.extend(actor.take_palette_commands().into_iter().map(|command| (actor_id, command)));
+                if actor.take_workspace_words_request() {
+                    word_requests.push(actor_id);
+                }
+                spawned.extend(actor.take_spawned_actors());
             }
         }
+
+        for actor_id in word_requests {
+            let words = self.actors.workspace_words(actor_id);
+            if let Some(actor) = self.actors.get_actor_mut(actor_id) {
+                actor.set_workspace_words(words);
+            }
+        }
+
+        for actor in spawned {
+            self.open_in_new_view(actor);
+        }
+
+        for (actor_id, command) in palette_commands {
+            self.run_palette_command(actor_id, &command, ui.ctx());
+        }
     }
 
-    pub fn new_tab(&mut self) {
+    fn current_view_system(&self) -> &'static str {
+        if self.view_container.as_system::<SceneSystem>().is_some() {
+            "scene"
+        } else if self.view_container.as_system::<ViewManager>().is_some() {
+            "nested"
+        } else {
+            "tiling"
+        }
+    }
+
+    /// Run a palette command on behalf of an actor, by ID or search text
+    ///
+    /// Editor commands without a target apply to the requesting actor.
+    pub fn run_palette_command(&mut self, actor_id: Uuid, query: &str, ctx: &egui::Context) {
+        let result = {
+            let command_ctx = CommandContext {
+                focused_actor: Some(actor_id),
+                view_container: &self.view_container,
+                actor_manager: &self.actors,
+                current_view_system: self.current_view_system().to_string(),
+            };
+            match self.command_palette.resolve_command(query, &command_ctx) {
+                Some(command) => self.command_palette.execute_command(&command.id, &command_ctx),
+                None => {
+                    log::warn!("No command matches '{}'", query);
+                    return;
+                }
+            }
+        };
+
+        match result {
+            Ok(CommandExecutionResult::GlobalAction(action)) => self.execute_global_action(action, ctx),
+            Ok(CommandExecutionResult::ActorAction(target, method)) => {
+                let target = if target.is_nil() { actor_id } else { target };
+                match self.actors.execute_actor_api(target, &method, ApiParams::new()).and_then(ApiResult::into_result) {
+                    Ok(Some(value)) => log::debug!("Command '{}' returned {}", query, value),
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Command '{}' failed: {}", query, e),
+                }
+            }
+            Ok(CommandExecutionResult::ActorCall(target, method, params)) => {
+                match self.actors.execute_actor_api(target, &method, params).and_then(ApiResult::into_result) {
+                    Ok(Some(value)) => log::debug!("Command '{}' returned {}", query, value),
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Command '{}' failed: {}", query, e),
+                }
+            }
+            Ok(CommandExecutionResult::Success) => {}
+            Ok(CommandExecutionResult::Error(e)) => log::warn!("Command '{}' failed: {}", query, e),
+            Ok(other) => log::warn!("Command '{}' was not run: {:?}", query, other),
+            Err(e) => log::warn!("Command '{}' failed: {}", query, e),
+        }
+    }
+
+    fn execute_global_action(&mut self, action: GlobalAction, ctx: &egui::Context) {
+        match action {
+            GlobalAction::NewFile | GlobalAction::NewTab => self.new_tab(),
+            GlobalAction::CloseTab => self.close_active_tab(),
+            GlobalAction::ToggleWidgets => {
+                let enabled = self.widget_manager.is_enabled();
+                self.widget_manager.set_enabled(!enabled);
+            }
+            GlobalAction::SwitchViewSystem(system) => match system.as_str() {
+                "scene" => self.switch_to_scene_system(),
+                "tiling" => self.switch_to_tiling_system(),
+                "nested" => self.switch_to_nested_system(),
+                _ => log::warn!("Unknown view system '{}'", system),
+            },
+            GlobalAction::ResetTransform => {
+                if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+                    scene.reset_transform();
+                } else if let Some(tiling) = self.view_container.as_system_mut::<TilingSystem>() {
+                    tiling.reset_transform();
+                }
+            }
+            GlobalAction::ToggleCanvasMode => {
+                if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+                    scene.toggle_canvas_mode();
+                }
+            }
+            GlobalAction::FocusCard => {
+                if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+                    scene.focus_active_card();
+                }
+            }
+            GlobalAction::FitCanvas => {
+                if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+                    scene.canvas_mut().fit_all();
+                }
+            }
+            GlobalAction::ToggleSnapToGrid => {
+                if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+                    let canvas = scene.canvas_mut();
+                    canvas.snap_to_grid = !canvas.snap_to_grid;
+                }
+            }
+            GlobalAction::ToggleCanvasMinimap => {
+                if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>() {
+                    let canvas = scene.canvas_mut();
+                    canvas.show_minimap = !canvas.show_minimap;
+                }
+            }
+            GlobalAction::SaveBookmark => self.save_bookmark(),
+            GlobalAction::SaveBookmarkSlot(slot) => {
+                if let Some(bookmarks) = self.view_container.system_mut().as_bookmarks_mut() {
+                    if !bookmarks.update_bookmark(slot) {
+                        let name = format!("Bookmark {}", bookmarks.bookmarks().len() + 1);
+                        bookmarks.save_bookmark(name);
+                    }
+                }
+            }
+            GlobalAction::JumpToBookmark(slot) => {
+                let jumped = self.view_container.system_mut().as_bookmarks_mut()
+                    .is_some_and(|bookmarks| bookmarks.jump_to_bookmark(slot));
+                if !jumped {
+                    log::info!("There is no bookmark {}", slot + 1);
+                }
+            }
+            GlobalAction::ExitApplication => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            GlobalAction::ShowCommandPalette => log::info!("The command palette has no window yet"),
+            GlobalAction::SaveSession => match self.save_session() {
+                Ok(()) => log::info!("Saved session '{}'", self.session_name),
+                Err(e) => log::warn!("Failed to save session '{}': {}", self.session_name, e),
+            },
+            GlobalAction::SwitchSession(name) => {
+                if let Err(e) = self.switch_session(&name) {
+                    log::warn!("Can't switch to session '{}': {}", name, e);
+                }
+            }
+            GlobalAction::ToggleFloatingPanes => self.toggle_floating_panes(),
+            GlobalAction::NewFloatingTerminal => self.new_floating_terminal(),
+            GlobalAction::FloatActiveView => self.float_active_actor(),
+            GlobalAction::EmbedFloatingPane => self.embed_floating_pane(),
+            GlobalAction::TogglePinFloatingPane => {
+                self.floating.toggle_pinned();
+            }
+            GlobalAction::SaveLayout => match self.save_layout() {
+                Ok(path) => log::info!("Saved the layout to {}", path.display()),
+                Err(e) => log::warn!("Failed to save the layout: {}", e),
+            },
+            GlobalAction::CyclePaneTab(forward) => {
+                self.view_container.system_mut().cycle_tab(forward);
+            }
+            GlobalAction::FocusPane(_)
+            | GlobalAction::SwapPane(_)
+            | GlobalAction::MovePane(_)
+            | GlobalAction::ResizePane(_)
+            | GlobalAction::TogglePaneZoom => {
+                let Some(tiling) = self.view_container.as_system_mut::<TilingSystem>() else {
+                    log::info!("Pane commands need the tiling view system");
+                    return;
+                };
+                match action {
+                    GlobalAction::FocusPane(direction) => { tiling.focus_direction(direction); }
+                    GlobalAction::SwapPane(direction) => { tiling.swap_with_neighbor(direction); }
+                    GlobalAction::MovePane(direction) => { tiling.move_to_split(direction); }
+                    GlobalAction::ResizePane(direction) => { tiling.resize_active(direction); }
+                    _ => tiling.toggle_zoom(),
+                }
+            }
+        }
+    }
+
+    /// Switch the key binding layer of every open code editor
+    pub fn set_editor_keymap(&mut self, keymap: KeymapKind) {
+        self.editor_config.keymap = keymap;
+        for actor in &mut self.actors.actors {
+            if let Some(editor) = actor.as_any_mut().downcast_mut::<CodeEditorActor>() {
+                editor.set_keymap(keymap);
+            }
+        }
+    }
+
+    /// Hold off closing the window while files have unsaved changes, asking
+    /// whether to save them first
+    ///
+    /// Quitting without saving keeps their swap files, so the recovery prompt
+    /// offers them at the next launch.
+    fn confirm_close(&mut self, ctx: &egui::Context) {
+        let unsaved: Vec<String> = self.actors.actors.iter()
+            .filter(|actor| actor.is_modified())
+            .map(|actor| actor.name())
+            .collect();
+        if ctx.input(|i| i.viewport().close_requested()) && !self.quit_confirmed && !unsaved.is_empty() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.quit_prompt = true;
+        }
+        if !self.quit_prompt {
+            return;
+        }
+
+        let (mut save, mut quit, mut cancel) = (false, false, false);
+        egui::Window::new("Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("These files have unsaved changes:");
+                for name in &unsaved {
+                    ui.label(format!("• {}", name));
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    save = ui.button("Save All and Quit").clicked();
+                    quit = ui.button("Quit Without Saving")
+                        .on_hover_text("Unsaved changes are offered for recovery at the next launch")
+                        .clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if cancel {
+            self.quit_prompt = false;
+        } else if quit || (save && self.save_all()) {
+            self.quit_prompt = false;
+            self.quit_confirmed = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    /// Save every actor with unsaved changes, returning whether they all were
+    fn save_all(&mut self) -> bool {
+        let unsaved: Vec<Uuid> = self.actors.actors.iter()
+            .filter(|actor| actor.is_modified())
+            .map(|actor| actor.id())
+            .collect();
+        let mut saved = true;
+        for actor_id in unsaved {
+            if let Err(e) = self.actors.execute_actor_api(actor_id, "save", ApiParams::new()).and_then(ApiResult::into_result) {
+                log::warn!("{}", e);
+                saved = false;
+            }
+        }
+        saved
+    }
+
+    fn show_recovery_prompt(&mut self, ctx: &egui::Context) {
+        for (choice, swap_path, swap) in self.recovery.show(ctx) {
+            if choice == RecoveryChoice::Recover {
+                let name = swap.name.clone();
+                let editor = CodeEditorActor::from_swap(swap)
+                    .with_config(self.editor_config.clone())
+                    .with_language_servers(self.language_servers.clone());
+                self.open_in_new_view(Box::new(editor));
+                log::info!("Recovered unsaved changes for {}", name);
+            }
+            // The recovered editor journals to its own swap file from now on
+            SwapStore::discard(&swap_path);
+        }
+    }
+
+    /// Open a file in a new editor view (images open in the image preview,
+    /// markdown files get a live preview, other binary files the hex editor)
+    pub fn open_file(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        let actor = self.actor_for_file(path)?;
+        self.open_in_new_view(actor);
+        Ok(())
+    }
+
+    /// The actor that shows the file at `path`
+    fn actor_for_file(&self, path: PathBuf) -> anyhow::Result<Box<dyn Actor>> {
+        if image_decode::is_image_file(&path) {
+            return Ok(Box::new(ImagePreviewActor::open(path)?));
+        }
+        if hex_editor_actor::is_binary_file(&path) {
+            return Ok(Box::new(HexEditorActor::open(path, self.editor_config.clone())?));
+        }
+        let editor = CodeEditorActor::open(path, self.editor_config.clone())?
+            .with_language_servers(self.language_servers.clone());
+        if editor.language() == "markdown" && !editor.is_large_file() {
+            Ok(Box::new(MarkdownEditorActor::new(editor)))
+        } else {
+            Ok(Box::new(editor))
+        }
+    }
+
+    /// An empty editor with the next untitled name
+    fn untitled_editor(&mut self) -> CodeEditorActor {
         self.tab_counter += 1;
+        CodeEditorActor::new(format!("untitled-{}.rs", self.tab_counter))
+            .with_config(self.editor_config.clone())
+            .with_language_servers(self.language_servers.clone())
+    }
 
+    /// Register an actor and show it in a new, active view
+    pub fn open_in_new_view(&mut self, actor: Box<dyn Actor>) {
+        let actor_id = actor.id();
+        let name = actor.name();
+        self.actors.register_actor(actor);
+
+        let view_id = self.view_container.system_mut().create_view(name);
+        self.view_container.system_mut().attach_actor_to_view(view_id, actor_id);
+        self.view_container.system_mut().set_active_view(view_id);
+        self.actors.set_focus(actor_id);
+    }
+
+    pub fn new_tab(&mut self) {
         // Create new editor actor
-        let editor = CodeEditorActor::new(format!("untitled-{}.rs", self.tab_counter));
+        let editor = self.untitled_editor();
         let editor_id = editor.id();
         self.actors.register_actor(Box::new(editor));
 
-        // Replace the current view's actor
-        if let Some(active_view) = self.view_container.system().active_view() {
+        // Add it to the current view (as a new tab where views have tabs)
+        let active_view = self.view_container.system().active_view();
+        let nested_tab = active_view.and_then(|view_id| {
+            let nested = self.view_container.as_system_mut::<ViewManager>()?;
+            let tab = nested.add_tab(view_id, "Tab".to_string())?;
+            nested.set_active_view(tab);
+            Some(tab)
+        });
+        if let Some(active_view) = nested_tab.or(active_view) {
             self.view_container.system_mut().attach_actor_to_view(active_view, editor_id);
             self.actors.set_focus(editor_id);
         }
@@ -138,16 +835,133 @@ impl IdeState {
     // }
 
     pub fn close_active_tab(&mut self) {
-        // Clear the active view
-        if let Some(active_view) = self.view_container.system().active_view() {
-            self.view_container.system_mut().detach_actor_from_view(active_view);
+        let system = self.view_container.system();
+        let Some(view_id) = system.active_view() else {
+            return;
+        };
+        match system.get_view_actor(view_id) {
+            Some(actor_id) => self.close_tab(view_id, actor_id),
+            None => {
+                self.view_container.system_mut().detach_actor_from_view(view_id);
+            }
+        }
+    }
+
+    /// Take `actor_id` out of `view_id`, dropping it unless another view
+    /// still shows it
+    ///
+    /// Refuses while the actor has unsaved changes.
+    pub fn close_tab(&mut self, view_id: Uuid, actor_id: Uuid) {
+        let Some(actor) = self.actors.get_actor(actor_id) else {
+            return;
+        };
+        if actor.is_modified() {
+            log::warn!("{} has unsaved changes; save it before closing", actor.name());
+            return;
+        }
+
+        self.view_container.system_mut().remove_tab(view_id, actor_id);
+        let system = self.view_container.system();
+        let still_shown = system.get_view_ids().into_iter()
+            .any(|id| system.get_view_actors(id).contains(&actor_id))
+            || self.floating.panes().iter().any(|pane| pane.actor_id == actor_id);
+        if !still_shown {
+            self.actors.remove_actor(actor_id);
+        }
+        if let Some(shown) = system.active_view().and_then(|id| system.get_view_actor(id)) {
+            self.actors.set_focus(shown);
+        }
+    }
+
+    /// Show or hide the floating panes, opening a scratch terminal in one
+    /// if there are none
+    pub fn toggle_floating_panes(&mut self) {
+        if self.floating.is_empty() {
+            self.new_floating_terminal();
+        } else {
+            self.floating.toggle_visible();
         }
     }
 
-    pub fn split_active_view(&mut self, _direction: SplitDirection) {
-        // For now, just create a new editor in the current scene
-        // TODO: Implement proper split view support with scene system
-        self.new_tab();
+    /// Open a shell in a new floating pane
+    pub fn new_floating_terminal(&mut self) {
+        let terminal = TerminalActor::new();
+        let terminal_id = terminal.id();
+        self.actors.register_actor(Box::new(terminal));
+        self.floating.open(terminal_id);
+        self.actors.set_focus(terminal_id);
+    }
+
+    /// Move the actor shown in the active view into a floating pane
+    pub fn float_active_actor(&mut self) {
+        let system = self.view_container.system();
+        let Some((view_id, actor_id)) = system.active_view().and_then(|id| Some((id, system.get_view_actor(id)?))) else {
+            return;
+        };
+        self.view_container.system_mut().remove_tab(view_id, actor_id);
+        self.floating.open(actor_id);
+        self.actors.set_focus(actor_id);
+    }
+
+    /// Move the focused floating pane's actor into the active view (as a
+    /// tab where views have tabs)
+    pub fn embed_floating_pane(&mut self) {
+        let Some(actor_id) = self.floating.focused_actor().or(self.floating.panes().last().map(|pane| pane.actor_id)) else {
+            return;
+        };
+        let Some(view_id) = self.view_container.system().active_view() else {
+            return;
+        };
+        self.floating.remove_actor(actor_id);
+        self.view_container.system_mut().attach_actor_to_view(view_id, actor_id);
+        self.actors.set_focus(actor_id);
+    }
+
+    /// Close the floating pane holding `actor_id`, dropping the actor
+    ///
+    /// Refuses while the actor has unsaved changes.
+    pub fn close_floating_pane(&mut self, actor_id: Uuid) {
+        if let Some(actor) = self.actors.get_actor(actor_id).filter(|actor| actor.is_modified()) {
+            log::warn!("{} has unsaved changes; save it before closing", actor.name());
+            return;
+        }
+        if self.floating.remove_actor(actor_id).is_some() {
+            self.actors.remove_actor(actor_id);
+        }
+    }
+
+    /// Split the focused tile or view, opening a new editor in the new half
+    /// (view systems without splits get a new view instead)
+    pub fn split_active_view(&mut self, direction: SplitDirection) {
+        let editor = self.untitled_editor();
+        let editor_id = editor.id();
+        let name = editor.name();
+
+        let active = self.view_container.system().active_view();
+        let tile_id = match active {
+            Some(active) => match self.view_container.as_system_mut::<TilingSystem>() {
+                Some(tiling) => tiling.split_view(active, direction.into(), name),
+                None => self.view_container.as_system_mut::<ViewManager>().and_then(|nested| nested.split_view(active, direction)),
+            },
+            None => None,
+        };
+        let Some(tile_id) = tile_id else {
+            self.open_in_new_view(Box::new(editor));
+            return;
+        };
+
+        self.actors.register_actor(Box::new(editor));
+        self.view_container.system_mut().attach_actor_to_view(tile_id, editor_id);
+        self.view_container.system_mut().set_active_view(tile_id);
+        self.actors.set_focus(editor_id);
+    }
+
+    /// Bookmark the view system's camera under the next free number
+    pub fn save_bookmark(&mut self) {
+        if let Some(bookmarks) = self.view_container.system_mut().as_bookmarks_mut() {
+            let name = format!("Bookmark {}", bookmarks.bookmarks().len() + 1);
+            bookmarks.save_bookmark(name);
+        }
     }
 
     pub fn cycle_active_view(&mut self) {
@@ -172,6 +986,9 @@ impl IdeState {
 
         // Set the next view as active
         self.view_container.system_mut().set_active_view(next_view_id);
+        if let Some(scene) = self.view_container.as_system_mut::<SceneSystem>().filter(|scene| scene.canvas_mode()) {
+            scene.focus_active_card();
+        }
         log::info!("Cycled to view: {:?}", next_view_id);
     }
 
@@ -195,63 +1012,41 @@ impl IdeState {
 
     /// Switch to scene-based view system
     pub fn switch_to_scene_system(&mut self) {
-        // Preserve current actors
-        let current_actors: Vec<_> = self.view_container.system().get_view_ids()
-            .into_iter()
-            .filter_map(|view_id| {
-                self.view_container.system().get_view_actor(view_id)
-            })
-            .collect();
-
-        // Create new scene system
-        let scene_system = Box::new(SceneSystem::new());
-        self.view_container = ViewContainer::new(scene_system);
-
-        // Reattach actors to the new system
-        for (i, actor_id) in current_actors.into_iter().enumerate() {
-            if i == 0 {
-                // Use the default view for the first actor
-                if let Some(default_view) = self.view_container.system().active_view() {
-                    self.view_container.system_mut().attach_actor_to_view(default_view, actor_id);
-                }
-            } else {
-                // Create new views for additional actors
-                let new_view = self.view_container.system_mut().create_view(format!("View {}", i + 1));
-                self.view_container.system_mut().attach_actor_to_view(new_view, actor_id);
-            }
-        }
-
+        self.switch_view_system(Box::new(SceneSystem::new()));
         log::info!("Switched to Scene System");
     }
 
     /// Switch to tiling-based view system
     pub fn switch_to_tiling_system(&mut self) {
+        self.switch_view_system(Box::new(TilingSystem::new()));
+        log::info!("Switched to Tiling System");
+    }
+
+    /// Switch to nested splits and tabs with resizable borders
+    pub fn switch_to_nested_system(&mut self) {
+        self.switch_view_system(Box::new(ViewManager::new()));
+        log::info!("Switched to Nested View System");
+    }
+
+    /// Replace the view system with `system`, moving every actor shown into
+    /// it: the first into its default view, the rest into views of their own
+    fn switch_view_system(&mut self, system: Box<dyn ViewSystem>) {
         // Preserve current actors
         let current_actors: Vec<_> = self.view_container.system().get_view_ids()
             .into_iter()
-            .filter_map(|view_id| {
-                self.view_container.system().get_view_actor(view_id)
-            })
+            .flat_map(|view_id| self.view_container.system().get_view_actors(view_id))
             .collect();
 
-        // Create new tiling system
-        let tiling_system = Box::new(TilingSystem::new());
-        self.view_container = ViewContainer::new(tiling_system);
+        self.view_container = ViewContainer::new(system);
 
         // Reattach actors to the new system
         for (i, actor_id) in current_actors.into_iter().enumerate() {
-            if i == 0 {
-                // Use the default view for the first actor
-                if let Some(default_view) = self.view_container.system().active_view() {
-                    self.view_container.system_mut().attach_actor_to_view(default_view, actor_id);
-                }
-            } else {
-                // Create new tiles for additional actors
-                let new_tile = self.view_container.system_mut().create_view(format!("Tile {}", i + 1));
-                self.view_container.system_mut().attach_actor_to_view(new_tile, actor_id);
-            }
+            let default_view = self.view_container.system().active_view().filter(|_| i == 0);
+            let view_id = match default_view {
+                Some(view_id) => view_id,
+                None => self.view_container.system_mut().create_view(format!("View {}", i + 1)),
+            };
+            self.view_container.system_mut().attach_actor_to_view(view_id, actor_id);
         }
-
-        log::info!("Switched to Tiling System");
     }
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+use anyhow::{Result, anyhow};
+use image::{AnimationDecoder, DynamicImage, ImageDecoder, RgbaImage};
+use image::codecs::{gif::GifDecoder, jpeg::JpegDecoder, png::PngDecoder, webp::WebPDecoder};
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+use std::time::Duration;
+
+/// Animation frames shown for less than this play at the usual browser
+/// default instead, as most "0 delay" GIFs expect
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// Image formats the preview can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Svg,
+}
+
+impl ImageFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpeg => "JPEG",
+            ImageFormat::Gif => "GIF",
+            ImageFormat::WebP => "WebP",
+            ImageFormat::Svg => "SVG",
+        }
+    }
+
+    /// The format from a file's magic bytes, falling back to its extension
+    /// (SVG has no magic; it's only recognized by extension)
+    pub fn detect(path: &Path) -> Option<Self> {
+        let mut head = [0u8; 12];
+        let read = File::open(path)
+            .and_then(|mut file| file.read(&mut head))
+            .unwrap_or(0);
+        Self::from_magic(&head[..read]).or_else(|| Self::from_extension(path))
+    }
+
+    fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+            Some(ImageFormat::WebP)
+        } else {
+            None
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" | "apng" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" | "jpe" | "jfif" => Some(ImageFormat::Jpeg),
+            "gif" => Some(ImageFormat::Gif),
+            "webp" => Some(ImageFormat::WebP),
+            "svg" | "svgz" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a file should open in the image preview rather than as text or bytes
+pub fn is_image_file(path: &Path) -> bool {
+    ImageFormat::detect(path).is_some()
+}
+
+/// One frame of an image, with how long it's shown when animated
+pub struct Frame {
+    pub pixels: RgbaImage,
+    pub delay: Duration,
+}
+
+/// A decoded image: a single frame, or every frame of an animation composed
+/// onto the full canvas
+pub struct DecodedImage {
+    pub format: ImageFormat,
+    /// Size in image pixels (SVG user units for vector images)
+    pub width: u32,
+    pub height: u32,
+    /// Pixel layout of the source, e.g. `Rgba8`
+    pub color_type: String,
+    pub frames: Vec<Frame>,
+    /// The parsed document of an SVG, kept to re-rasterize it when zoomed
+    pub svg: Option<resvg::usvg::Tree>,
+}
+
+impl DecodedImage {
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// Total length of one loop of the animation
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delay).sum()
+    }
+}
+
+/// Decode the image at `path`, sniffing its format
+pub fn decode(path: &Path) -> Result<DecodedImage> {
+    let format = ImageFormat::detect(path)
+        .ok_or_else(|| anyhow!("{} is not a supported image", path.display()))?;
+    let data = fs::read(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    decode_bytes(format, &data).map_err(|e| anyhow!("Failed to decode {}: {}", path.display(), e))
+}
+
+fn decode_bytes(format: ImageFormat, data: &[u8]) -> Result<DecodedImage> {
+    let reader = || BufReader::new(Cursor::new(data));
+    let (color_type, frames) = match format {
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader())?;
+            let color_type = decoder.color_type();
+            if decoder.is_apng()? {
+                (color_type, animation_frames(decoder.apng()?)?)
+            } else {
+                (color_type, still_frame(DynamicImage::from_decoder(decoder)?))
+            }
+        },
+        ImageFormat::Jpeg => {
+            let decoder = JpegDecoder::new(reader())?;
+            (decoder.color_type(), still_frame(DynamicImage::from_decoder(decoder)?))
+        },
+        ImageFormat::Gif => {
+            let decoder = GifDecoder::new(reader())?;
+            (decoder.color_type(), animation_frames(decoder)?)
+        },
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(reader())?;
+            let color_type = decoder.color_type();
+            if decoder.has_animation() {
+                (color_type, animation_frames(decoder)?)
+            } else {
+                (color_type, still_frame(DynamicImage::from_decoder(decoder)?))
+            }
+        },
+        ImageFormat::Svg => return decode_svg(data),
+    };
+    let first = frames.first().ok_or_else(|| anyhow!("the image has no frames"))?;
+    Ok(DecodedImage {
+        format,
+        width: first.pixels.width(),
+        height: first.pixels.height(),
+        color_type: format!("{:?}", color_type),
+        frames,
+        svg: None,
+    })
+}
+
+fn still_frame(image: DynamicImage) -> Vec<Frame> {
+    vec![Frame { pixels: image.into_rgba8(), delay: Duration::ZERO }]
+}
+
+fn animation_frames<'a>(decoder: impl AnimationDecoder<'a>) -> Result<Vec<Frame>> {
+    decoder.into_frames()
+        .map(|frame| {
+            let frame = frame?;
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = Duration::from_secs_f64(numer as f64 / denom.max(1) as f64 / 1000.0);
+            let delay = if delay < MIN_FRAME_DELAY { DEFAULT_FRAME_DELAY } else { delay };
+            Ok(Frame { pixels: frame.into_buffer(), delay })
+        })
+        .collect()
+}
+
+fn decode_svg(data: &[u8]) -> Result<DecodedImage> {
+    let tree = resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())?;
+    let size = tree.size();
+    Ok(DecodedImage {
+        format: ImageFormat::Svg,
+        width: (size.width().ceil() as u32).max(1),
+        height: (size.height().ceil() as u32).max(1),
+        color_type: "Vector".to_string(),
+        frames: vec![Frame { pixels: rasterize_svg(&tree, 1.0)?, delay: Duration::ZERO }],
+        svg: Some(tree),
+    })
+}
+
+/// Render an SVG document at `scale` device pixels per user unit
+pub fn rasterize_svg(tree: &resvg::usvg::Tree, scale: f32) -> Result<RgbaImage> {
+    let size = tree.size();
+    let width = (size.width() * scale).ceil().max(1.0) as u32;
+    let height = (size.height() * scale).ceil().max(1.0) as u32;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| anyhow!("can't render the SVG at {}x{}", width, height))?;
+    resvg::render(tree, resvg::tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia works in premultiplied alpha
+    let mut pixels = Vec::with_capacity(pixmap.pixels().len() * 4);
+    for pixel in pixmap.pixels() {
+        let color = pixel.demultiply();
+        pixels.extend_from_slice(&[color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    RgbaImage::from_raw(width, height, pixels).ok_or_else(|| anyhow!("SVG raster size mismatch"))
+}
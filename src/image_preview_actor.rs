@@ -0,0 +1,665 @@
+use crate::actor::{Actor, ActorMessage, ActorAPI, ApiMethod, ApiParameter, ApiParams, ApiResult};
+use crate::image_decode::{self, DecodedImage, ImageFormat};
+use crate::scene_system::SceneTransform;
+use crate::view_system::{Transformable, TransformInfo};
+use async_trait::async_trait;
+use egui::{self, Color32, ColorImage, Pos2, Rect, Stroke, TextureHandle, TextureOptions, Vec2};
+use image::imageops::{self, FilterType};
+use uuid::Uuid;
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+
+/// Zoom (device pixels per image pixel) from which pixel edges are drawn
+const PIXEL_GRID_ZOOM: f32 = 8.0;
+const ZOOM_STEP: f32 = 1.25;
+/// Largest side an SVG is rasterized at, whatever the zoom
+const MAX_SVG_RASTER: usize = 8192;
+const CHECKER_SIZE: f32 = 8.0;
+
+const GRID_COLOR: Color32 = Color32::from_rgba_premultiplied(40, 40, 40, 90);
+const HOVER_COLOR: Color32 = Color32::from_rgb(240, 240, 240);
+const PICKED_COLOR: Color32 = Color32::from_rgb(230, 160, 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fit {
+    /// Shrink to fit, but never enlarge past actual size
+    Initial,
+    /// Fill the view
+    Exact,
+}
+
+/// Image viewer for PNG, JPEG, GIF, WebP and SVG files: zoom and pan over a
+/// checkerboard, animation playback, a pixel grid when zoomed in and the
+/// color under the pointer
+pub struct ImagePreviewActor {
+    id: Uuid,
+    name: String,
+    path: PathBuf,
+    image: DecodedImage,
+    file_size: u64,
+    /// `zoom` is device pixels per image pixel, `offset` the image center's
+    /// distance from the view center in points
+    transform: SceneTransform,
+    fit: Option<Fit>,
+    /// Textures per frame, created when the frame is first shown
+    textures: Vec<Option<TextureHandle>>,
+    checker: Option<TextureHandle>,
+    /// Device pixels per SVG unit of the current raster
+    svg_scale: f32,
+    frame: usize,
+    playing: bool,
+    /// When the current frame was first shown, in `egui` input time
+    frame_shown_at: Option<f64>,
+    show_pixel_grid: bool,
+    /// Image pixel under the pointer
+    hovered: Option<(u32, u32)>,
+    /// Image pixel last clicked
+    picked: Option<(u32, u32)>,
+    status: Option<String>,
+    /// Where the image was drawn last frame, for zooming from outside `render`
+    view_rect: Rect,
+    pixels_per_point: f32,
+}
+
+impl ImagePreviewActor {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let image = image_decode::decode(&path)?;
+        let playing = image.is_animated();
+        let file_size = std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name,
+            path,
+            textures: image.frames.iter().map(|_| None).collect(),
+            image,
+            file_size,
+            transform: SceneTransform { min_zoom: 0.01, max_zoom: 256.0, ..SceneTransform::new() },
+            fit: Some(Fit::Initial),
+            checker: None,
+            svg_scale: 1.0,
+            frame: 0,
+            playing,
+            frame_shown_at: None,
+            show_pixel_grid: true,
+            hovered: None,
+            picked: None,
+            status: None,
+            view_rect: Rect::NOTHING,
+            pixels_per_point: 1.0,
+        })
+    }
+
+    fn image_size(&self) -> Vec2 {
+        Vec2::new(self.image.width as f32, self.image.height as f32)
+    }
+
+    /// Zoom by `factor`, keeping `anchor` (in points from the view center) in place
+    pub fn zoom_by(&mut self, factor: f32, anchor: Vec2) {
+        self.fit = None;
+        self.transform.zoom_around(factor, anchor);
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom_by(zoom / self.transform.zoom, Vec2::ZERO);
+    }
+
+    /// Fit the whole image in the view (applied on the next frame, when the
+    /// view's size is known)
+    pub fn fit_to_view(&mut self) {
+        self.fit = Some(Fit::Exact);
+    }
+
+    /// One image pixel per device pixel, centered
+    pub fn actual_size(&mut self) {
+        self.fit = None;
+        self.transform.reset();
+    }
+
+    fn apply_fit(&mut self, fit: Fit) {
+        let size = self.image_size().max(Vec2::splat(1.0));
+        let available = self.view_rect.size() * self.pixels_per_point;
+        let mut zoom = (available.x / size.x).min(available.y / size.y);
+        if fit == Fit::Initial {
+            zoom = zoom.min(1.0);
+        }
+        self.transform.zoom = zoom.clamp(self.transform.min_zoom, self.transform.max_zoom);
+        self.transform.offset = Vec2::ZERO;
+    }
+
+    pub fn set_frame(&mut self, frame: usize) -> Result<()> {
+        if frame >= self.image.frames.len() {
+            return Err(anyhow!("Frame {} is out of range (the image has {})", frame, self.image.frames.len()));
+        }
+        self.frame = frame;
+        self.frame_shown_at = None;
+        Ok(())
+    }
+
+    fn step_frame(&mut self, forward: bool) {
+        let count = self.image.frames.len();
+        self.playing = false;
+        self.frame = if forward { (self.frame + 1) % count } else { (self.frame + count - 1) % count };
+    }
+
+    pub fn toggle_playback(&mut self) -> bool {
+        self.playing = !self.playing && self.image.is_animated();
+        self.frame_shown_at = None;
+        self.playing
+    }
+
+    /// Color of an image pixel in the current frame
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.image.width || y >= self.image.height {
+            return None;
+        }
+        let pixels = &self.image.frames[self.frame].pixels;
+        // An SVG raster has its own resolution
+        let scale = if self.image.svg.is_some() { self.svg_scale } else { 1.0 };
+        let px = ((x as f32 + 0.5) * scale) as u32;
+        let py = ((y as f32 + 0.5) * scale) as u32;
+        Some(pixels.get_pixel(px.min(pixels.width() - 1), py.min(pixels.height() - 1)).0)
+    }
+
+    fn image_rect(&self) -> Rect {
+        let size = self.image_size() * self.transform.zoom / self.pixels_per_point;
+        Rect::from_center_size(self.view_rect.center() + self.transform.offset, size)
+    }
+
+    fn pixel_at(&self, pos: Pos2) -> Option<(u32, u32)> {
+        let rect = self.image_rect();
+        let at = (pos - rect.min) / rect.size() * self.image_size();
+        (rect.contains(pos) && at.x >= 0.0 && at.y >= 0.0)
+            .then(|| ((at.x as u32).min(self.image.width.saturating_sub(1)), (at.y as u32).min(self.image.height.saturating_sub(1))))
+    }
+
+    /// Re-render an SVG when the zoom moved far from the scale it was last
+    /// rendered at, so it stays sharp
+    fn update_svg_raster(&mut self, max_texture_side: usize) {
+        let Some(tree) = &self.image.svg else {
+            return;
+        };
+        let longest = self.image.width.max(self.image.height).max(1) as f32;
+        let limit = max_texture_side.min(MAX_SVG_RASTER) as f32 / longest;
+        let wanted = self.transform.zoom.clamp(0.1_f32.min(limit), limit);
+        let ratio = wanted / self.svg_scale;
+        if (0.8..=1.25).contains(&ratio) {
+            return;
+        }
+        match image_decode::rasterize_svg(tree, wanted) {
+            Ok(pixels) => {
+                self.image.frames[0].pixels = pixels;
+                self.textures[0] = None;
+                self.svg_scale = wanted;
+            },
+            Err(e) => self.status = Some(e.to_string()),
+        }
+    }
+
+    fn texture(&mut self, ctx: &egui::Context, max_texture_side: usize) -> &TextureHandle {
+        let frame = &self.image.frames[self.frame];
+        let (pixels, options) = (&frame.pixels, if self.image.svg.is_some() {
+            TextureOptions::LINEAR
+        } else {
+            // Crisp pixels when zoomed in, smooth when zoomed out
+            TextureOptions { magnification: egui::TextureFilter::Nearest, ..TextureOptions::LINEAR }
+        });
+        self.textures[self.frame].get_or_insert_with(|| {
+            let (width, height) = pixels.dimensions();
+            let longest = width.max(height) as usize;
+            let image = if longest > max_texture_side {
+                let scale = max_texture_side as f32 / longest as f32;
+                let scaled = imageops::resize(pixels, ((width as f32 * scale) as u32).max(1), ((height as f32 * scale) as u32).max(1), FilterType::Triangle);
+                ColorImage::from_rgba_unmultiplied([scaled.width() as usize, scaled.height() as usize], scaled.as_raw())
+            } else {
+                ColorImage::from_rgba_unmultiplied([width as usize, height as usize], pixels.as_raw())
+            };
+            ctx.load_texture(format!("image_preview_{}_{}", self.id, self.frame), image, options)
+        })
+    }
+
+    /// Two-by-two checkerboard, repeated behind transparent images
+    fn checker(&mut self, ctx: &egui::Context) -> egui::TextureId {
+        self.checker.get_or_insert_with(|| {
+            let (light, dark) = (Color32::from_gray(200), Color32::from_gray(150));
+            let image = ColorImage { size: [2, 2], pixels: vec![light, dark, dark, light] };
+            ctx.load_texture("image_preview_checker", image, TextureOptions {
+                magnification: egui::TextureFilter::Nearest,
+                minification: egui::TextureFilter::Nearest,
+                wrap_mode: egui::TextureWrapMode::Repeat,
+                ..TextureOptions::default()
+            })
+        }).id()
+    }
+
+    fn advance_animation(&mut self, ctx: &egui::Context) {
+        if !self.playing || !self.image.is_animated() {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let mut shown_at = *self.frame_shown_at.get_or_insert(now);
+        let mut delay = self.image.frames[self.frame].delay.as_secs_f64();
+        while now - shown_at >= delay {
+            shown_at += delay;
+            self.frame = (self.frame + 1) % self.image.frames.len();
+            delay = self.image.frames[self.frame].delay.as_secs_f64();
+        }
+        // After a long stall, restart the frame rather than racing to catch up
+        if now - shown_at > self.image.duration().as_secs_f64() {
+            shown_at = now;
+        }
+        self.frame_shown_at = Some(shown_at);
+        ctx.request_repaint_after(Duration::from_secs_f64(delay - (now - shown_at)));
+    }
+
+    fn pick(&mut self, ctx: &egui::Context, pixel: (u32, u32)) {
+        self.picked = Some(pixel);
+        if let Some(color) = self.pixel(pixel.0, pixel.1) {
+            let hex = color_hex(color);
+            ctx.copy_text(hex.clone());
+            self.status = Some(format!("Copied {}", hex));
+        }
+    }
+
+    fn handle_input(&mut self, ui: &egui::Ui, response: &egui::Response) {
+        if response.clicked() {
+            response.request_focus();
+            if let Some(pixel) = response.interact_pointer_pos().and_then(|pos| self.pixel_at(pos)) {
+                self.pick(ui.ctx(), pixel);
+            }
+        }
+        if response.dragged() {
+            self.pan(response.drag_delta());
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let (zoom_delta, scroll) = ui.input(|i| (i.zoom_delta(), i.smooth_scroll_delta));
+            let factor = zoom_delta * (scroll.y / 200.0).exp();
+            if factor != 1.0 {
+                self.zoom_by(factor, pointer - self.view_rect.center());
+            }
+        }
+        if !response.has_focus() {
+            return;
+        }
+        let pressed = |key| ui.input(|i| i.key_pressed(key));
+        if pressed(egui::Key::Plus) || pressed(egui::Key::Equals) {
+            self.zoom_by(ZOOM_STEP, Vec2::ZERO);
+        }
+        if pressed(egui::Key::Minus) {
+            self.zoom_by(1.0 / ZOOM_STEP, Vec2::ZERO);
+        }
+        if pressed(egui::Key::Num0) {
+            self.fit_to_view();
+        }
+        if pressed(egui::Key::Num1) {
+            self.actual_size();
+        }
+        if pressed(egui::Key::G) {
+            self.show_pixel_grid = !self.show_pixel_grid;
+        }
+        if self.image.is_animated() {
+            if pressed(egui::Key::Space) {
+                self.toggle_playback();
+            }
+            if pressed(egui::Key::ArrowRight) {
+                self.step_frame(true);
+            }
+            if pressed(egui::Key::ArrowLeft) {
+                self.step_frame(false);
+            }
+        }
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(&self.name).on_hover_text(self.path.display().to_string());
+            ui.separator();
+            ui.weak(format!("{} · {} × {}", self.image.format.name(), self.image.width, self.image.height));
+            ui.separator();
+            if ui.small_button("−").on_hover_text("Zoom out (-)").clicked() {
+                self.zoom_by(1.0 / ZOOM_STEP, Vec2::ZERO);
+            }
+            ui.monospace(format!("{:>5.0}%", self.transform.zoom * 100.0));
+            if ui.small_button("+").on_hover_text("Zoom in (+)").clicked() {
+                self.zoom_by(ZOOM_STEP, Vec2::ZERO);
+            }
+            if ui.button("Fit").on_hover_text("Fit to view (0)").clicked() {
+                self.fit_to_view();
+            }
+            if ui.button("1:1").on_hover_text("Actual size (1)").clicked() {
+                self.actual_size();
+            }
+            if self.image.svg.is_none() {
+                ui.checkbox(&mut self.show_pixel_grid, "Pixel grid")
+                    .on_hover_text(format!("Pixel edges from {}00% zoom (G)", PIXEL_GRID_ZOOM));
+            }
+            if self.image.is_animated() {
+                ui.separator();
+                if ui.small_button("⏮").on_hover_text("Previous frame (←)").clicked() {
+                    self.step_frame(false);
+                }
+                if ui.small_button(if self.playing { "⏸" } else { "▶" }).on_hover_text("Play or pause (Space)").clicked() {
+                    self.toggle_playback();
+                }
+                if ui.small_button("⏭").on_hover_text("Next frame (→)").clicked() {
+                    self.step_frame(true);
+                }
+                ui.weak(format!("Frame {}/{}", self.frame + 1, self.image.frames.len()));
+            }
+        });
+    }
+
+    fn show_status(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for (label, pixel) in [("", self.hovered), ("Picked ", self.picked)] {
+                let Some((x, y)) = pixel else {
+                    continue;
+                };
+                let Some(color) = self.pixel(x, y) else {
+                    continue;
+                };
+                let (swatch, _) = ui.allocate_exact_size(Vec2::splat(12.0), egui::Sense::hover());
+                ui.painter().rect_filled(swatch, 2.0, Color32::from_rgba_unmultiplied(color[0], color[1], color[2], color[3]));
+                ui.painter().rect_stroke(swatch, 2.0, Stroke::new(1.0, ui.visuals().weak_text_color()));
+                ui.monospace(format!("{}{}, {}  {}  rgba({}, {}, {}, {})", label, x, y, color_hex(color), color[0], color[1], color[2], color[3]));
+                ui.separator();
+            }
+            ui.weak(format!("{} · {}", self.image.color_type, format_size(self.file_size)));
+            if let Some(status) = &self.status {
+                ui.separator();
+                ui.label(status);
+            }
+        });
+    }
+
+    fn show_image(&mut self, ui: &mut egui::Ui) {
+        let max_texture_side = ui.ctx().input(|i| i.max_texture_side);
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        self.view_rect = rect;
+        self.pixels_per_point = ui.ctx().pixels_per_point();
+        if let Some(fit) = self.fit {
+            self.apply_fit(fit);
+        }
+        self.handle_input(ui, &response);
+        self.advance_animation(ui.ctx());
+        self.update_svg_raster(max_texture_side);
+        self.hovered = response.hover_pos().and_then(|pos| self.pixel_at(pos));
+
+        let image_rect = self.image_rect();
+        let painter = ui.painter_at(rect);
+        let checker = self.checker(ui.ctx());
+        let repeats = image_rect.size() / (2.0 * CHECKER_SIZE);
+        painter.image(checker, image_rect, Rect::from_min_max(Pos2::ZERO, repeats.to_pos2()), Color32::WHITE);
+        let texture = self.texture(ui.ctx(), max_texture_side).id();
+        painter.image(texture, image_rect, Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)), Color32::WHITE);
+
+        if self.image.svg.is_some() || self.transform.zoom < PIXEL_GRID_ZOOM {
+            return;
+        }
+        let pixel = image_rect.width() / self.image.width as f32;
+        let pixel_rect = |(x, y): (u32, u32)| Rect::from_min_size(image_rect.min + Vec2::new(x as f32, y as f32) * pixel, Vec2::splat(pixel));
+        if self.show_pixel_grid {
+            let visible = image_rect.intersect(rect);
+            let stroke = Stroke::new(1.0 / self.pixels_per_point, GRID_COLOR);
+            let first = ((visible.min - image_rect.min) / pixel).floor();
+            let last = ((visible.max - image_rect.min) / pixel).ceil();
+            for x in first.x as u32..=last.x as u32 {
+                let x = image_rect.min.x + x as f32 * pixel;
+                painter.vline(x, visible.y_range(), stroke);
+            }
+            for y in first.y as u32..=last.y as u32 {
+                let y = image_rect.min.y + y as f32 * pixel;
+                painter.hline(visible.x_range(), y, stroke);
+            }
+        }
+        if let Some(hovered) = self.hovered {
+            painter.rect_stroke(pixel_rect(hovered), 0.0, Stroke::new(1.0, HOVER_COLOR));
+        }
+        if let Some(picked) = self.picked {
+            painter.rect_stroke(pixel_rect(picked), 0.0, Stroke::new(2.0, PICKED_COLOR));
+        }
+    }
+
+    fn metadata(&self) -> serde_json::Value {
+        serde_json::json!({
+            "format": self.image.format.name(),
+            "width": self.image.width,
+            "height": self.image.height,
+            "color_type": self.image.color_type,
+            "file_size": self.file_size,
+            "frame_count": self.image.frames.len(),
+            "animated": self.image.is_animated(),
+            "duration_ms": self.image.duration().as_millis() as u64,
+            "vector": self.image.format == ImageFormat::Svg,
+        })
+    }
+}
+
+fn color_hex(color: [u8; 4]) -> String {
+    if color[3] == 255 {
+        format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", color[0], color[1], color[2], color[3])
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+#[async_trait]
+impl Actor for ImagePreviewActor {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn handle_message(&mut self, _message: ActorMessage) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &egui::Context) {}
+
+    fn render(&mut self, ui: &mut egui::Ui) {
+        self.show_toolbar(ui);
+        ui.separator();
+
+        egui::TopBottomPanel::bottom(egui::Id::new(("image_status", self.id)))
+            .show_inside(ui, |ui| self.show_status(ui));
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
+            .show_inside(ui, |ui| self.show_image(ui));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Transformable for ImagePreviewActor {
+    fn zoom(&mut self, factor: f32, center: Option<Pos2>) {
+        let anchor = center.map_or(Vec2::ZERO, |center| center - self.view_rect.center());
+        self.zoom_by(factor, anchor);
+    }
+
+    fn pan(&mut self, delta: Vec2) {
+        self.fit = None;
+        self.transform.pan_with_axis_lock(delta, true);
+    }
+
+    fn reset_transform(&mut self) {
+        self.fit_to_view();
+    }
+
+    fn get_transform_info(&self) -> TransformInfo {
+        TransformInfo {
+            zoom: self.transform.zoom,
+            pan_offset: self.transform.offset,
+            can_zoom: true,
+            can_pan: true,
+        }
+    }
+}
+
+impl ActorAPI for ImagePreviewActor {
+    fn actor_type(&self) -> String {
+        "ImagePreviewActor".to_string()
+    }
+
+    fn get_api_methods(&self) -> Vec<ApiMethod> {
+        let number = |name: &str, description: &str| ApiParameter {
+            name: name.to_string(),
+            param_type: "number".to_string(),
+            description: description.to_string(),
+            required: true,
+            default_value: None,
+        };
+        let view = |name: &str, description: &str| ApiMethod {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters: vec![],
+            return_type: "void".to_string(),
+            category: "view".to_string(),
+        };
+        vec![
+            view("zoom_in", "Zoom in"),
+            view("zoom_out", "Zoom out"),
+            view("fit_to_view", "Fit the whole image in the view"),
+            view("actual_size", "Show one image pixel per screen pixel"),
+            ApiMethod {
+                name: "set_zoom".to_string(),
+                description: "Set the zoom (1.0 is actual size)".to_string(),
+                parameters: vec![number("zoom", "Screen pixels per image pixel")],
+                return_type: "void".to_string(),
+                category: "view".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_pixel_grid".to_string(),
+                description: "Show or hide pixel edges when zoomed in".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "view".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_playback".to_string(),
+                description: "Play or pause an animated image".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "animation".to_string(),
+            },
+            ApiMethod {
+                name: "set_frame".to_string(),
+                description: "Show a frame of an animated image".to_string(),
+                parameters: vec![number("frame", "Frame index, from 0")],
+                return_type: "void".to_string(),
+                category: "animation".to_string(),
+            },
+            ApiMethod {
+                name: "get_pixel".to_string(),
+                description: "Color of a pixel in the current frame".to_string(),
+                parameters: vec![number("x", "Column"), number("y", "Row")],
+                return_type: "object".to_string(),
+                category: "content".to_string(),
+            },
+            ApiMethod {
+                name: "get_metadata".to_string(),
+                description: "Format, size, color type and animation details".to_string(),
+                parameters: vec![],
+                return_type: "object".to_string(),
+                category: "content".to_string(),
+            },
+        ]
+    }
+
+    fn execute_api_method(&mut self, method: &str, params: ApiParams) -> Result<ApiResult> {
+        match method {
+            "zoom_in" => {
+                self.zoom_by(ZOOM_STEP, Vec2::ZERO);
+                Ok(ApiResult::Success)
+            },
+            "zoom_out" => {
+                self.zoom_by(1.0 / ZOOM_STEP, Vec2::ZERO);
+                Ok(ApiResult::Success)
+            },
+            "fit_to_view" => {
+                self.fit_to_view();
+                Ok(ApiResult::Success)
+            },
+            "actual_size" => {
+                self.actual_size();
+                Ok(ApiResult::Success)
+            },
+            "set_zoom" => {
+                let zoom: f32 = params.get("zoom")?;
+                if zoom.is_nan() || zoom <= 0.0 {
+                    return Err(anyhow!("Zoom must be positive"));
+                }
+                self.set_zoom(zoom);
+                Ok(ApiResult::Success)
+            },
+            "toggle_pixel_grid" => {
+                self.show_pixel_grid = !self.show_pixel_grid;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.show_pixel_grid)))
+            },
+            "toggle_playback" => Ok(ApiResult::Value(serde_json::Value::Bool(self.toggle_playback()))),
+            "set_frame" => {
+                self.playing = false;
+                self.set_frame(params.get("frame")?)?;
+                Ok(ApiResult::Success)
+            },
+            "get_pixel" => {
+                let (x, y) = (params.get("x")?, params.get("y")?);
+                let color = self.pixel(x, y)
+                    .ok_or_else(|| anyhow!("Pixel {}, {} is outside the {}x{} image", x, y, self.image.width, self.image.height))?;
+                Ok(ApiResult::Value(serde_json::json!({
+                    "x": x,
+                    "y": y,
+                    "hex": color_hex(color),
+                    "rgba": color,
+                })))
+            },
+            "get_metadata" => Ok(ApiResult::Value(self.metadata())),
+            _ => Err(anyhow!("Unknown method: {}", method))
+        }
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        vec![
+            "image_preview".to_string(),
+            "zoom".to_string(),
+            "pan".to_string(),
+        ]
+    }
+
+    fn get_state(&self) -> HashMap<String, serde_json::Value> {
+        let mut state = HashMap::new();
+        state.insert("file_path".to_string(), serde_json::json!(self.path));
+        state.insert("metadata".to_string(), self.metadata());
+        state.insert("zoom".to_string(), serde_json::json!(self.transform.zoom));
+        state.insert("pan_offset".to_string(), serde_json::json!([self.transform.offset.x, self.transform.offset.y]));
+        state.insert("frame".to_string(), serde_json::json!(self.frame));
+        state.insert("playing".to_string(), serde_json::Value::Bool(self.playing));
+        state.insert("show_pixel_grid".to_string(), serde_json::Value::Bool(self.show_pixel_grid));
+        state.insert("hovered_pixel".to_string(), serde_json::json!(self.hovered));
+        state.insert("picked_color".to_string(), serde_json::json!(self.picked
+            .and_then(|(x, y)| self.pixel(x, y))
+            .map(color_hex)));
+        state
+    }
+}
@@ -0,0 +1,453 @@
+use crate::editor_buffer::EditorBuffer;
+use crate::emacs_keymap::EmacsKeymap;
+use crate::vim_keymap::VimKeymap;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+
+/// Which key binding layer the code editor uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapKind {
+    /// Plain text widget bindings
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl KeymapKind {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "default" | "none" => Ok(Self::Default),
+            "vim" => Ok(Self::Vim),
+            "emacs" => Ok(Self::Emacs),
+            _ => Err(anyhow!("Unknown keymap '{}' (expected default, vim or emacs)", name)),
+        }
+    }
+
+    /// Instantiate the layer (`None` for the default bindings)
+    pub fn create(self) -> Option<Box<dyn Keymap>> {
+        match self {
+            Self::Default => None,
+            Self::Vim => Some(Box::new(VimKeymap::new())),
+            Self::Emacs => Some(Box::new(EmacsKeymap::new())),
+        }
+    }
+}
+
+/// A key, independent of egui so keymaps can be driven headlessly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Escape,
+    Backspace,
+    Delete,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// A key together with its modifiers
+///
+/// Shift is already folded into `Char` keys (`A`, `<`, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    pub key: Key,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl KeyPress {
+    pub fn new(key: Key) -> Self {
+        Self { key, ctrl: false, alt: false }
+    }
+
+    /// The plain character typed, if this is an unmodified character key
+    pub fn plain_char(&self) -> Option<char> {
+        match self.key {
+            Key::Char(c) if !self.ctrl && !self.alt => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn is_ctrl(&self, c: char) -> bool {
+        self.ctrl && !self.alt && self.key == Key::Char(c)
+    }
+
+    pub fn is_alt(&self, c: char) -> bool {
+        self.alt && !self.ctrl && self.key == Key::Char(c)
+    }
+}
+
+/// Parse Vim-style key notation, e.g. `d2w`, `ihello<Esc>` or `<C-x><C-s>`
+///
+/// Supported names: `<Esc>`, `<CR>`/`<Enter>`, `<BS>`, `<Del>`, `<Tab>`,
+/// `<Space>`, `<lt>`, arrows (`<Left>`...), `<Home>`, `<End>`, `<PageUp>`,
+/// `<PageDown>`, with `C-`/`M-`/`A-` modifier prefixes.
+pub fn parse_keys(keys: &str) -> Result<Vec<KeyPress>> {
+    let mut result = Vec::new();
+    let mut chars = keys.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            result.push(KeyPress::new(Key::Char(c)));
+            continue;
+        }
+
+        let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+        let mut press = KeyPress::new(Key::Char('<'));
+        let mut rest = name.as_str();
+        loop {
+            if let Some(r) = rest.strip_prefix("C-") {
+                press.ctrl = true;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("M-").or_else(|| rest.strip_prefix("A-")) {
+                press.alt = true;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+
+        press.key = match rest.to_lowercase().as_str() {
+            "esc" => Key::Escape,
+            "cr" | "enter" | "return" => Key::Enter,
+            "bs" | "backspace" => Key::Backspace,
+            "del" | "delete" => Key::Delete,
+            "tab" => Key::Tab,
+            "space" => Key::Char(' '),
+            "lt" => Key::Char('<'),
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            _ => {
+                let mut name_chars = rest.chars();
+                match (name_chars.next(), name_chars.next()) {
+                    (Some(c), None) => Key::Char(c),
+                    _ => return Err(anyhow!("Unknown key <{}>", name)),
+                }
+            }
+        };
+        result.push(press);
+    }
+
+    Ok(result)
+}
+
+/// Convert an egui key event into a key press for the keymap
+///
+/// Unmodified printable keys are skipped: they arrive as `Event::Text`.
+pub fn key_press_from_egui(key: egui::Key, modifiers: egui::Modifiers) -> Option<KeyPress> {
+    let named = match key {
+        egui::Key::Enter => Some(Key::Enter),
+        egui::Key::Escape => Some(Key::Escape),
+        egui::Key::Backspace => Some(Key::Backspace),
+        egui::Key::Delete => Some(Key::Delete),
+        egui::Key::Tab => Some(Key::Tab),
+        egui::Key::ArrowLeft => Some(Key::Left),
+        egui::Key::ArrowRight => Some(Key::Right),
+        egui::Key::ArrowUp => Some(Key::Up),
+        egui::Key::ArrowDown => Some(Key::Down),
+        egui::Key::Home => Some(Key::Home),
+        egui::Key::End => Some(Key::End),
+        egui::Key::PageUp => Some(Key::PageUp),
+        egui::Key::PageDown => Some(Key::PageDown),
+        _ => None,
+    };
+    if let Some(key) = named {
+        return Some(KeyPress { key, ctrl: modifiers.ctrl, alt: modifiers.alt });
+    }
+
+    if !modifiers.ctrl && !modifiers.alt {
+        return None;
+    }
+    let c = match key {
+        egui::Key::Space => ' ',
+        _ => {
+            let mut symbol = key.symbol_or_name().chars();
+            match (symbol.next(), symbol.next()) {
+                (Some(c), None) => c.to_ascii_lowercase(),
+                _ => return None,
+            }
+        }
+    };
+    // egui reports the unshifted key; fold shift in for the bindings that need it
+    let c = match (c, modifiers.shift) {
+        (',', true) => '<',
+        ('.', true) => '>',
+        ('/', true) => '?',
+        ('-', true) => '_',
+        (c, true) if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+        (c, _) => c,
+    };
+    Some(KeyPress { key: Key::Char(c), ctrl: modifiers.ctrl, alt: modifiers.alt })
+}
+
+/// Something a keymap needs the host editor to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapRequest {
+    Save,
+    /// Run a command palette command, by id or search text
+    PaletteCommand(String),
+    /// Put text on the system clipboard
+    CopyToClipboard(String),
+}
+
+/// Whether a keymap consumed a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOutcome {
+    Handled,
+    /// Let the default text widget bindings handle it
+    Ignored,
+}
+
+/// Editor state a keymap operates on
+pub struct KeymapContext<'a> {
+    pub buffer: &'a mut EditorBuffer,
+    /// Selection anchor (byte offset); equals `cursor` when nothing is selected
+    pub anchor: usize,
+    pub cursor: usize,
+    pub tab_width: usize,
+    pub requests: Vec<KeymapRequest>,
+}
+
+impl<'a> KeymapContext<'a> {
+    pub fn new(buffer: &'a mut EditorBuffer, anchor: usize, cursor: usize, tab_width: usize) -> Self {
+        Self { buffer, anchor, cursor, tab_width, requests: Vec::new() }
+    }
+
+    pub fn text(&self) -> &str {
+        self.buffer.text()
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        self.anchor = cursor;
+    }
+
+    /// Replace `range` and leave the cursor after the new text
+    pub fn replace(&mut self, range: std::ops::Range<usize>, text: &str) {
+        let end = range.start + text.len();
+        self.buffer.apply_edits(vec![(range, text.to_string())]);
+        self.set_cursor(end);
+    }
+
+    /// Insert typed text at the cursor, merging with the previous typing for undo
+    pub fn type_text(&mut self, text: &str) {
+        let at = self.cursor;
+        self.buffer.replace_range(at..at, text);
+        self.set_cursor(at + text.len());
+    }
+}
+
+/// A modal or chorded key binding layer over the editor buffer
+///
+/// Keymaps only touch the buffer and selection through `KeymapContext`, so
+/// they can be driven headlessly by feeding key presses.
+pub trait Keymap: Send + Sync {
+    fn handle_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome;
+
+    /// Mode indicator for the editor header, e.g. "-- INSERT --"
+    fn status(&self) -> String;
+
+    /// Whether typed text and pastes should reach the buffer at all
+    fn accepts_text(&self) -> bool {
+        true
+    }
+
+    /// Whether the cursor should be drawn as a block over the next character
+    fn block_cursor(&self) -> bool {
+        false
+    }
+
+    /// Selection to display for the logical `(anchor, cursor)`
+    fn display_selection(&self, _text: &str, anchor: usize, cursor: usize) -> (usize, usize) {
+        (anchor, cursor)
+    }
+}
+
+// Text navigation helpers shared by the keymaps. All offsets are byte offsets
+// on char boundaries.
+
+pub fn next_char(text: &str, pos: usize) -> usize {
+    text[pos..].chars().next().map(|c| pos + c.len_utf8()).unwrap_or(pos)
+}
+
+pub fn prev_char(text: &str, pos: usize) -> usize {
+    text[..pos].chars().next_back().map(|c| pos - c.len_utf8()).unwrap_or(pos)
+}
+
+pub fn char_at(text: &str, pos: usize) -> Option<char> {
+    text[pos..].chars().next()
+}
+
+pub fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// Offset of the newline ending the line (or the end of the text)
+pub fn line_end(text: &str, pos: usize) -> usize {
+    text[pos..].find('\n').map(|i| pos + i).unwrap_or(text.len())
+}
+
+pub fn first_non_blank(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    let end = line_end(text, start);
+    text[start..end].find(|c: char| c != ' ' && c != '\t').map(|i| start + i).unwrap_or(end)
+}
+
+/// Column of `pos` within its line, in chars
+pub fn column(text: &str, pos: usize) -> usize {
+    text[line_start(text, pos)..pos].chars().count()
+}
+
+/// Offset at `column` chars into the line containing `line_pos`, clamped to its end
+pub fn offset_at_column(text: &str, line_pos: usize, column: usize) -> usize {
+    let start = line_start(text, line_pos);
+    let end = line_end(text, start);
+    text[start..end].char_indices().nth(column).map(|(i, _)| start + i).unwrap_or(end)
+}
+
+/// Start of the line `delta` lines away from `pos` (clamped to the text)
+pub fn line_offset(text: &str, pos: usize, delta: isize) -> usize {
+    let mut start = line_start(text, pos);
+    if delta >= 0 {
+        for _ in 0..delta {
+            let end = line_end(text, start);
+            if end >= text.len() {
+                break;
+            }
+            start = end + 1;
+        }
+    } else {
+        for _ in 0..delta.unsigned_abs() {
+            if start == 0 {
+                break;
+            }
+            start = line_start(text, start - 1);
+        }
+    }
+    start
+}
+
+/// Start of the zero-based line `line` (clamped to the last line)
+pub fn line_at(text: &str, line: usize) -> usize {
+    line_offset(text, 0, line as isize)
+}
+
+/// Character class used for word motions: 0 whitespace, 1 word, 2 punctuation
+///
+/// With `big_word` everything that isn't whitespace is one class.
+pub fn char_class(c: char, big_word: bool) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if big_word || c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+/// Start of the next word (Vim `w`)
+pub fn word_forward(text: &str, pos: usize, big_word: bool) -> usize {
+    let mut chars = text[pos..].char_indices().map(|(i, c)| (pos + i, c)).peekable();
+    let Some((_, first)) = chars.next() else {
+        return pos;
+    };
+    let class = char_class(first, big_word);
+    let mut crossed_newline = first == '\n';
+    // Skip the rest of the current word
+    while let Some(&(_, c)) = chars.peek() {
+        if class == 0 || char_class(c, big_word) != class {
+            break;
+        }
+        chars.next();
+    }
+    // Skip whitespace, stopping at an empty line
+    while let Some(&(i, c)) = chars.peek() {
+        if !c.is_whitespace() {
+            return i;
+        }
+        if c == '\n' {
+            if crossed_newline {
+                return i;
+            }
+            crossed_newline = true;
+        }
+        chars.next();
+    }
+    text.len()
+}
+
+/// End of the current or next word (Vim `e`), on its last character
+pub fn word_end(text: &str, pos: usize, big_word: bool) -> usize {
+    let mut chars = text[pos..].char_indices().map(|(i, c)| (pos + i, c)).skip(1).peekable();
+    while chars.peek().is_some_and(|&(_, c)| c.is_whitespace()) {
+        chars.next();
+    }
+    let Some((mut end, first)) = chars.next() else {
+        return pos;
+    };
+    let class = char_class(first, big_word);
+    for (i, c) in chars {
+        if char_class(c, big_word) != class {
+            break;
+        }
+        end = i;
+    }
+    end
+}
+
+/// Start of the current or previous word (Vim `b`)
+pub fn word_backward(text: &str, pos: usize, big_word: bool) -> usize {
+    let mut chars = text[..pos].char_indices().rev().peekable();
+    while chars.peek().is_some_and(|&(_, c)| c.is_whitespace()) {
+        chars.next();
+    }
+    let Some((mut start, first)) = chars.next() else {
+        return 0;
+    };
+    let class = char_class(first, big_word);
+    for (i, c) in chars {
+        if char_class(c, big_word) != class {
+            break;
+        }
+        start = i;
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_key_notation() {
+        let keys = parse_keys("d2w<Esc><C-x><M-d><lt><Space>").unwrap();
+        let ctrl_x = KeyPress { ctrl: true, ..KeyPress::new(Key::Char('x')) };
+        let alt_d = KeyPress { alt: true, ..KeyPress::new(Key::Char('d')) };
+        assert_eq!(keys, vec![
+            KeyPress::new(Key::Char('d')),
+            KeyPress::new(Key::Char('2')),
+            KeyPress::new(Key::Char('w')),
+            KeyPress::new(Key::Escape),
+            ctrl_x,
+            alt_d,
+            KeyPress::new(Key::Char('<')),
+            KeyPress::new(Key::Char(' ')),
+        ]);
+        assert!(parse_keys("<Nope>").is_err());
+    }
+}
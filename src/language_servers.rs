@@ -0,0 +1,276 @@
+use crate::lsp_client::{LanguageServerRegistry, LanguageServerSpec, LspClient};
+use anyhow::anyhow;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Files and directories marking the top of a project, nearest first wins
+const ROOT_MARKERS: [&str; 8] = [
+    "Cargo.toml",
+    "package.json",
+    "tsconfig.json",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "compile_commands.json",
+    ".git",
+];
+
+/// How long a server gets to start and answer `initialize`
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a completion request may take before it's given up on
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The project directory `path` belongs to: the nearest one above it with a
+/// root marker, or else the directory it's in
+pub fn workspace_root(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(path);
+    dir.ancestors()
+        .find(|dir| ROOT_MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// Items a server offered at a position, or `None` if it had nothing
+pub struct CompletionReply {
+    pub path: PathBuf,
+    /// Where the word being completed started, as sent with the request
+    pub word_start: usize,
+    pub response: Option<lsp_types::CompletionResponse>,
+}
+
+enum ServerRequest {
+    Complete {
+        path: PathBuf,
+        text: String,
+        position: lsp_types::Position,
+        word_start: usize,
+        reply: Sender<CompletionReply>,
+    },
+    Format {
+        path: PathBuf,
+        text: String,
+        tab_width: usize,
+        timeout: Duration,
+        reply: Sender<anyhow::Result<Vec<lsp_types::TextEdit>>>,
+    },
+}
+
+/// Language servers shared by every editor: one per language and workspace,
+/// started on first use and running until the last handle is dropped
+#[derive(Clone, Default)]
+pub struct LanguageServers {
+    registry: LanguageServerRegistry,
+    servers: Arc<Mutex<HashMap<(String, PathBuf), LanguageServer>>>,
+}
+
+impl LanguageServers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The server for `language` in the workspace of `path`, starting it if
+    /// it isn't running, or `None` if no server is registered for `language`
+    pub fn server_for(&self, language: &str, path: &Path) -> Option<LanguageServer> {
+        let spec = self.registry.get(language)?;
+        let root = workspace_root(path);
+        let mut servers = self.servers.lock().ok()?;
+        let server = servers.entry((language.to_string(), root.clone()))
+            .or_insert_with(|| LanguageServer::spawn(spec.clone(), language.to_string(), root));
+        Some(server.clone())
+    }
+}
+
+/// Handle to the background thread owning one running language server
+///
+/// A server that failed to start or died stops accepting requests; it isn't
+/// restarted, so a missing server doesn't get respawned on every keystroke.
+#[derive(Clone)]
+pub struct LanguageServer {
+    requests: Sender<ServerRequest>,
+}
+
+impl LanguageServer {
+    fn spawn(spec: LanguageServerSpec, language: String, root: PathBuf) -> Self {
+        let (requests, request_rx) = mpsc::channel::<ServerRequest>();
+
+        thread::spawn(move || {
+            let mut client = match LspClient::start(&spec, Some(&root), START_TIMEOUT) {
+                Ok(client) => client,
+                Err(e) => {
+                    log::info!("Language server for {} in {} is unavailable: {}", language, root.display(), e);
+                    return;
+                }
+            };
+            log::info!("Started {} for {} in {}", spec.command, language, root.display());
+            let mut versions: HashMap<PathBuf, i32> = HashMap::new();
+
+            'serve: while let Ok(request) = request_rx.recv() {
+                let mut queued: Vec<ServerRequest> = vec![request];
+                queued.extend(request_rx.try_iter());
+                let keep = requests_to_serve(&queued);
+                for (_, request) in queued.into_iter().enumerate().filter(|(i, _)| keep.contains(i)) {
+                    if serve(&mut client, &mut versions, &language, request).is_err() {
+                        break 'serve;
+                    }
+                }
+            }
+            client.shutdown();
+        });
+
+        Self { requests }
+    }
+
+    /// Ask for completions at `position` in `text`, the contents of `path`,
+    /// with the reply sent to `reply`; false if the server isn't running
+    pub fn complete(&self, path: PathBuf, text: String, position: lsp_types::Position, word_start: usize, reply: Sender<CompletionReply>) -> bool {
+        self.requests.send(ServerRequest::Complete { path, text, position, word_start, reply }).is_ok()
+    }
+
+    /// Ask for edits formatting `text`, the contents of `path`, with the
+    /// reply sent to `reply`; false if the server isn't running
+    pub fn format(
+        &self,
+        path: PathBuf,
+        text: String,
+        tab_width: usize,
+        timeout: Duration,
+        reply: Sender<anyhow::Result<Vec<lsp_types::TextEdit>>>,
+    ) -> bool {
+        self.requests.send(ServerRequest::Format { path, text, tab_width, timeout, reply }).is_ok()
+    }
+}
+
+/// The indices of the requests in `queued` (oldest first) worth answering:
+/// every format request, but only the newest completion request for each
+/// file, since the editor has moved on from the others
+fn requests_to_serve(queued: &[ServerRequest]) -> Vec<usize> {
+    let mut newest: HashMap<&Path, usize> = HashMap::new();
+    for (i, request) in queued.iter().enumerate() {
+        if let ServerRequest::Complete { path, .. } = request {
+            newest.insert(path, i);
+        }
+    }
+    (0..queued.len())
+        .filter(|i| match &queued[*i] {
+            ServerRequest::Complete { path, .. } => newest.get(path.as_path()) == Some(i),
+            ServerRequest::Format { .. } => true,
+        })
+        .collect()
+}
+
+/// Answer `request`, failing only if the server can no longer be talked to
+fn serve(client: &mut LspClient, versions: &mut HashMap<PathBuf, i32>, language: &str, request: ServerRequest) -> anyhow::Result<()> {
+    match request {
+        ServerRequest::Complete { path, text, position, word_start, reply } => {
+            let Ok(uri) = lsp_types::Url::from_file_path(&path) else {
+                return Ok(());
+            };
+            sync_document(client, versions, language, &path, &uri, &text)?;
+            let response = client.request("textDocument/completion", json!({
+                "textDocument": { "uri": uri },
+                "position": position,
+            }), COMPLETION_TIMEOUT)
+                .ok()
+                .and_then(|result| serde_json::from_value::<Option<lsp_types::CompletionResponse>>(result).ok().flatten());
+            // The editor may have closed since asking
+            let _ = reply.send(CompletionReply { path, word_start, response });
+        }
+        ServerRequest::Format { path, text, tab_width, timeout, reply } => {
+            let Ok(uri) = lsp_types::Url::from_file_path(&path) else {
+                let _ = reply.send(Err(anyhow!("Invalid document path {:?}", path)));
+                return Ok(());
+            };
+            sync_document(client, versions, language, &path, &uri, &text)?;
+            let edits = client.request("textDocument/formatting", json!({
+                "textDocument": { "uri": uri },
+                "options": { "tabSize": tab_width, "insertSpaces": true },
+            }), timeout)
+                .map(|result| serde_json::from_value::<Option<Vec<lsp_types::TextEdit>>>(result).ok().flatten().unwrap_or_default());
+            let _ = reply.send(edits);
+        }
+    }
+    Ok(())
+}
+
+/// Open `path` on the server with `text`, or send `text` as its new contents
+fn sync_document(
+    client: &mut LspClient,
+    versions: &mut HashMap<PathBuf, i32>,
+    language: &str,
+    path: &Path,
+    uri: &lsp_types::Url,
+    text: &str,
+) -> anyhow::Result<()> {
+    match versions.get_mut(path) {
+        Some(version) => {
+            *version += 1;
+            client.notify("textDocument/didChange", json!({
+                "textDocument": { "uri": uri, "version": *version },
+                "contentChanges": [{ "text": text }],
+            }))
+        }
+        None => {
+            versions.insert(path.to_path_buf(), 1);
+            client.notify("textDocument/didOpen", json!({
+                "textDocument": { "uri": uri, "languageId": language, "version": 1, "text": text }
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn workspace_root_is_the_nearest_marked_directory() {
+        let base = std::env::temp_dir().join(format!("zellij-ide-root-{}", uuid::Uuid::new_v4()));
+        let nested = base.join("crates/core/src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(base.join("Cargo.toml"), "").unwrap();
+        fs::write(base.join("crates/core/Cargo.toml"), "").unwrap();
+
+        assert_eq!(workspace_root(&nested.join("lib.rs")), base.join("crates/core"));
+        assert_eq!(workspace_root(&base.join("build.rs")), base);
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn only_the_newest_completion_per_file_is_served() {
+        let (reply, _) = mpsc::channel();
+        let (format_reply, _) = mpsc::channel();
+        let complete = |path: &str| ServerRequest::Complete {
+            path: PathBuf::from(path),
+            text: String::new(),
+            position: lsp_types::Position::new(0, 0),
+            word_start: 0,
+            reply: reply.clone(),
+        };
+        let format = |path: &str| ServerRequest::Format {
+            path: PathBuf::from(path),
+            text: String::new(),
+            tab_width: 4,
+            timeout: Duration::from_secs(1),
+            reply: format_reply.clone(),
+        };
+
+        let queued = [
+            complete("/a.rs"),
+            format("/a.rs"),
+            complete("/b.rs"),
+            complete("/a.rs"),
+            format("/a.rs"),
+            complete("/c.rs"),
+            complete("/b.rs"),
+        ];
+        assert_eq!(requests_to_serve(&queued), [1, 3, 4, 5, 6]);
+        assert_eq!(requests_to_serve(&queued[..1]), [0]);
+        assert_eq!(requests_to_serve(&[]), Vec::<usize>::new());
+    }
+}
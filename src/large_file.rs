@@ -0,0 +1,197 @@
+use anyhow::{Result, anyhow};
+use egui::{self, FontId};
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+/// Lines kept above a line scrolled to
+const CONTEXT_LINES: usize = 3;
+
+/// A read-only, memory-mapped file that lays out only the lines and columns in view
+///
+/// Used for files above the editor's large-file threshold, where laying out
+/// the whole text every frame (as `TextEdit` does) would stall the UI.
+pub struct LargeFile {
+    data: Mmap,
+    /// Byte offset where each line starts
+    line_starts: Vec<usize>,
+    /// Length in bytes of the longest line, for the horizontal scroll extent
+    longest_line: usize,
+    /// Line the user clicked, highlighted and copied with Ctrl+C
+    selected_line: Option<usize>,
+    /// Line to bring into view on the next frame
+    scroll_to: Option<usize>,
+}
+
+impl LargeFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+        // SAFETY: the map is only ever read. Another process truncating the
+        // file meanwhile can still fault reads, as with any mapped viewer.
+        let data = unsafe { Mmap::map(&file) }
+            .map_err(|e| anyhow!("Failed to map {}: {}", path.display(), e))?;
+
+        let mut line_starts = vec![0];
+        let mut longest_line = 0;
+        for (i, _) in data.iter().enumerate().filter(|(_, &b)| b == b'\n') {
+            longest_line = longest_line.max(i - line_starts[line_starts.len() - 1]);
+            line_starts.push(i + 1);
+        }
+        longest_line = longest_line.max(data.len() - line_starts[line_starts.len() - 1]);
+
+        Ok(Self {
+            data,
+            line_starts,
+            longest_line,
+            selected_line: None,
+            scroll_to: None,
+        })
+    }
+
+    /// Size in bytes
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Bytes of a line, without its line ending
+    fn line_bytes(&self, line: usize) -> &[u8] {
+        let start = self.line_starts[line];
+        let end = self.line_starts.get(line + 1).map_or(self.data.len(), |next| next - 1);
+        let bytes = &self.data[start..end];
+        bytes.strip_suffix(b"\r").unwrap_or(bytes)
+    }
+
+    /// The whole file, with invalid UTF-8 replaced
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.data)
+    }
+
+    /// Select a zero-based line and scroll it into view
+    pub fn go_to_line(&mut self, line: usize) {
+        let line = line.min(self.line_count() - 1);
+        self.selected_line = Some(line);
+        self.scroll_to = Some(line);
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, id: egui::Id, font_size: f32, show_line_numbers: bool) {
+        let font = FontId::monospace(font_size);
+        let (char_width, row_height) = ui.fonts(|f| (f.glyph_width(&font, '0'), f.row_height(&font)));
+        let gutter = if show_line_numbers {
+            (self.line_count().to_string().len() + 2) as f32 * char_width
+        } else {
+            char_width
+        };
+        let content_size = egui::vec2(
+            gutter + (self.longest_line + 2) as f32 * char_width,
+            self.line_count() as f32 * row_height,
+        );
+
+        let has_focus = ui.memory(|m| m.has_focus(id));
+        if let Some(line) = self.selected_line.filter(|_| has_focus) {
+            if ui.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Copy))) {
+                ui.ctx().copy_text(String::from_utf8_lossy(self.line_bytes(line)).into_owned());
+            }
+            let (up, down) = ui.input(|i| (i.key_pressed(egui::Key::ArrowUp), i.key_pressed(egui::Key::ArrowDown)));
+            if up || down {
+                let line = if up { line.saturating_sub(1) } else { line + 1 };
+                self.go_to_line(line);
+            }
+        }
+
+        let mut scroll = egui::ScrollArea::both()
+            .id_salt(id)
+            .auto_shrink([false, false]);
+        if let Some(line) = self.scroll_to.take() {
+            scroll = scroll.vertical_scroll_offset(line.saturating_sub(CONTEXT_LINES) as f32 * row_height);
+        }
+        scroll.show_viewport(ui, |ui, viewport| {
+            let origin = ui.max_rect().min;
+            let content_rect = egui::Rect::from_min_size(origin, content_size);
+            ui.allocate_rect(content_rect, egui::Sense::hover());
+            let response = ui.interact(content_rect, id, egui::Sense::click());
+            if response.clicked() {
+                response.request_focus();
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let line = ((pointer.y - origin.y) / row_height) as usize;
+                    self.selected_line = Some(line.min(self.line_count() - 1));
+                }
+            }
+
+            let first = (viewport.min.y / row_height).floor() as usize;
+            let last = ((viewport.max.y / row_height).ceil() as usize).min(self.line_count());
+            // Only the visible columns of each line are laid out, so a
+            // minified file's single huge line costs no more than a short one
+            let first_column = ((viewport.min.x - gutter).max(0.0) / char_width) as usize;
+            let columns = (viewport.width() / char_width).ceil() as usize + 1;
+
+            let painter = ui.painter();
+            let visuals = ui.visuals();
+            for line in first..last {
+                let y = origin.y + line as f32 * row_height;
+                if self.selected_line == Some(line) {
+                    let rect = egui::Rect::from_x_y_ranges(content_rect.x_range(), y..=y + row_height);
+                    painter.rect_filled(rect, 0.0, visuals.selection.bg_fill.gamma_multiply(0.5));
+                }
+                let bytes = self.line_bytes(line);
+                painter.text(
+                    egui::pos2(origin.x + gutter + first_column as f32 * char_width, y),
+                    egui::Align2::LEFT_TOP,
+                    String::from_utf8_lossy(&bytes[visible_columns(bytes, first_column, columns)]),
+                    font.clone(),
+                    visuals.text_color(),
+                );
+            }
+
+            if show_line_numbers {
+                // Painted over the text and pinned while scrolling sideways
+                let left = origin.x + viewport.min.x;
+                let gutter_rect = egui::Rect::from_x_y_ranges(left..=left + gutter - char_width * 0.5, ui.clip_rect().y_range());
+                painter.rect_filled(gutter_rect, 0.0, visuals.extreme_bg_color);
+                for line in first..last {
+                    painter.text(
+                        egui::pos2(left + gutter - char_width * 1.5, origin.y + line as f32 * row_height),
+                        egui::Align2::RIGHT_TOP,
+                        (line + 1).to_string(),
+                        font.clone(),
+                        visuals.weak_text_color(),
+                    );
+                }
+            }
+        });
+    }
+}
+
+/// Byte range of `columns` chars of a UTF-8 line from char `first_column` on
+fn visible_columns(bytes: &[u8], first_column: usize, columns: usize) -> Range<usize> {
+    // Byte offset of the `n`th char of `bytes`, or its length past the end
+    let offset = |bytes: &[u8], n: usize| bytes.iter()
+        .enumerate()
+        .filter(|(_, &b)| b & 0xC0 != 0x80)
+        .nth(n)
+        .map_or(bytes.len(), |(i, _)| i);
+    let start = offset(bytes, first_column);
+    start..start + offset(&bytes[start..], columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_columns_count_chars_not_bytes() {
+        let line = "añb→c😀d".as_bytes();
+        let slice = |first, columns| std::str::from_utf8(&line[visible_columns(line, first, columns)]).unwrap();
+        assert_eq!(slice(0, 3), "añb");
+        assert_eq!(slice(2, 3), "b→c");
+        assert_eq!(slice(5, 10), "😀d");
+        assert_eq!(slice(7, 3), "");
+        assert_eq!(slice(20, 3), "");
+    }
+}
@@ -0,0 +1,476 @@
+/*!
+# Layout Files
+
+A layout describes the view system, the split tree and the actors to open
+in each pane, in TOML:
+
+```toml
+view_system = "tiling"
+
+[root]
+split = "horizontal"
+
+[[root.panes]]
+size = 30
+[[root.panes.tabs]]
+type = "terminal"
+cwd = "server"
+command = "cargo"
+args = ["watch", "-x", "run"]
+
+[[root.panes]]
+size = 70
+split = "vertical"
+
+[[root.panes.panes]]
+[[root.panes.panes.tabs]]
+type = "editor"
+file = "src/main.rs"
+
+[[root.panes.panes]]
+[[root.panes.panes.tabs]]
+type = "editor"
+file = "README.md"
+```
+
+- A node with `panes` is a split, laying them out side by side
+  (`"horizontal"`, the default) or stacked (`"vertical"`).
+- `size` is a pane's share of its split, relative to its siblings' sizes;
+  panes without one get an even share.
+- A node without `panes` is a pane. Its `tabs` are the actors it holds,
+  with `active_tab` (an index, 0 by default) shown first.
+- A node with `tab_group` shows one of its layouts at a time, as tabs,
+  `active_tab` first. Only the nested view system (`"nested"`) has tabs of
+  splits; the others open just the one shown.
+- Editors open their `file` (images, binary and markdown files get their
+  own viewers), or an empty buffer without one. Relative paths are resolved
+  against the directory the IDE was started in.
+
+The IDE loads `--layout <path>` at startup, or else the session last saved
+in the current directory, or else `.zellij-ide/layout.toml` there. "Save Layout" writes the current arrangement back
+to the file it came from.
+*/
+
+use crate::actor::Actor;
+use crate::terminal_actor::{TerminalActor, TerminalLaunch};
+use crate::tiling_system::{TileDirection, TileNode};
+use crate::view::{View, ViewManager, ViewType};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The project's layout file, relative to the directory the IDE starts in
+pub const PROJECT_LAYOUT_FILE: &str = ".zellij-ide/layout.toml";
+
+/// Which view system a layout arranges its panes with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViewSystemKind {
+    #[default]
+    Tiling,
+    Scene,
+    /// Splits and tabs of views, where tabs can hold splits
+    Nested,
+}
+
+/// A whole layout file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layout {
+    #[serde(default)]
+    pub view_system: ViewSystemKind,
+    pub root: LayoutNode,
+}
+
+/// A split or a pane of a layout
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutNode {
+    /// Share of the parent split, relative to the sibling panes' sizes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<f32>,
+    /// How a split lays out its panes (horizontal when not given)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<TileDirection>,
+    /// Index into `tabs` (or `tab_group`) of the one shown
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub active_tab: usize,
+    /// The children of a split
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub panes: Vec<LayoutNode>,
+    /// The actors in a pane
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tabs: Vec<ActorLayout>,
+    /// Layouts shown one at a time as tabs, in nested views
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tab_group: Vec<LayoutNode>,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// An actor to open in a pane
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ActorLayout {
+    Terminal(TerminalLaunch),
+    Editor {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<PathBuf>,
+    },
+}
+
+impl ActorLayout {
+    /// How to reopen `actor`, for the kinds of actors layouts can describe
+    pub fn describe(actor: &dyn Actor) -> Option<Self> {
+        if let Some(terminal) = actor.as_any().downcast_ref::<TerminalActor>() {
+            // Reopen in the directory the shell was left in
+            let launch = TerminalLaunch { cwd: terminal.current_dir(), ..terminal.launch_spec().clone() };
+            return Some(ActorLayout::Terminal(launch));
+        }
+        match actor.actor_type().as_str() {
+            "CodeEditorActor" | "MarkdownEditorActor" | "HexEditorActor" | "ImagePreviewActor" => {
+                let file = actor.get_state().remove("file_path")
+                    .and_then(|path| serde_json::from_value(path).ok());
+                Some(ActorLayout::Editor { file })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Layout {
+    pub fn parse(text: &str) -> Result<Self> {
+        let layout: Layout = toml::from_str(text)?;
+        layout.root.validate()?;
+        Ok(layout)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| anyhow!("Invalid layout {}: {}", path.display(), e))
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_toml()?)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// What the IDE opens without a layout file: a terminal beside an empty
+    /// editor, in the scene view system
+    pub fn builtin() -> Self {
+        Self {
+            view_system: ViewSystemKind::Scene,
+            root: LayoutNode::split(TileDirection::Horizontal, vec![
+                LayoutNode::pane(vec![ActorLayout::Terminal(TerminalLaunch::default())]),
+                LayoutNode::pane(vec![ActorLayout::Editor { file: None }]),
+            ]),
+        }
+    }
+
+    /// The layout file to open at startup: `layout_arg` if given, else the
+    /// project's layout file if there is one
+    pub fn startup_path(layout_arg: Option<PathBuf>) -> Option<PathBuf> {
+        layout_arg.or_else(|| Some(PathBuf::from(PROJECT_LAYOUT_FILE)).filter(|path| path.is_file()))
+    }
+}
+
+impl LayoutNode {
+    pub fn pane(tabs: Vec<ActorLayout>) -> Self {
+        Self { tabs, ..Self::default() }
+    }
+
+    pub fn split(direction: TileDirection, panes: Vec<LayoutNode>) -> Self {
+        Self { split: Some(direction), panes, ..Self::default() }
+    }
+
+    pub fn tab_group(active_tab: usize, tab_group: Vec<LayoutNode>) -> Self {
+        Self { active_tab, tab_group, ..Self::default() }
+    }
+
+    pub fn is_pane(&self) -> bool {
+        self.panes.is_empty() && self.tab_group.is_empty()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(size) = self.size {
+            if size.is_nan() || size <= 0.0 {
+                return Err(anyhow!("pane size must be positive, not {}", size));
+            }
+        }
+        if self.is_pane() {
+            if self.split.is_some() {
+                return Err(anyhow!("a split needs panes"));
+            }
+            if !self.tabs.is_empty() && self.active_tab >= self.tabs.len() {
+                return Err(anyhow!("active_tab {} is out of range for {} tabs", self.active_tab, self.tabs.len()));
+            }
+            return Ok(());
+        }
+        if !self.tabs.is_empty() {
+            return Err(anyhow!("a node can't have both panes and tabs"));
+        }
+        if !self.tab_group.is_empty() {
+            if !self.panes.is_empty() || self.split.is_some() {
+                return Err(anyhow!("a tab group can't be a split"));
+            }
+            if self.active_tab >= self.tab_group.len() {
+                return Err(anyhow!("active_tab {} is out of range for {} tabs", self.active_tab, self.tab_group.len()));
+            }
+        }
+        self.panes.iter().chain(&self.tab_group).try_for_each(LayoutNode::validate)
+    }
+
+    /// The split tree for this node, calling `leaf` for each pane in layout
+    /// order for its tile's ID
+    ///
+    /// Splits of more than two panes become a chain of splits, each giving
+    /// its first half the panes before the last. Tiles have no tabs of
+    /// splits, so only the shown layout of a tab group is kept.
+    pub fn to_tile_tree(&self, leaf: &mut dyn FnMut(&LayoutNode) -> Uuid) -> TileNode {
+        if let Some(shown) = self.tab_group.get(self.active_tab) {
+            return shown.to_tile_tree(leaf);
+        }
+        if self.is_pane() {
+            return TileNode::Leaf(leaf(self));
+        }
+        let direction = self.split.unwrap_or(TileDirection::Horizontal);
+        let mut panes = self.panes.iter();
+        let first = panes.next().expect("splits have panes");
+        let mut tree = first.to_tile_tree(leaf);
+        let mut size = first.size.unwrap_or(1.0);
+        for pane in panes {
+            let pane_size = pane.size.unwrap_or(1.0);
+            tree = TileNode::Split {
+                direction,
+                ratio: size / (size + pane_size),
+                first: Box::new(tree),
+                second: Box::new(pane.to_tile_tree(leaf)),
+            };
+            size += pane_size;
+        }
+        tree
+    }
+
+    /// The layout of a split tree, calling `leaf` for each tile's pane
+    ///
+    /// Chains of splits in the same direction become one split of their
+    /// panes, undoing `to_tile_tree`.
+    pub fn from_tile_tree(tree: &TileNode, leaf: &mut dyn FnMut(Uuid) -> LayoutNode) -> Self {
+        let TileNode::Split { direction, .. } = tree else {
+            return leaf(tree.leaves()[0]);
+        };
+
+        let mut chain = Vec::new();
+        collect_chain(tree, *direction, 1.0, &mut chain);
+        let panes = chain.into_iter()
+            .map(|(node, share)| LayoutNode {
+                // Percentages, rounded so saved files stay readable
+                size: Some((share * 1000.0).round() / 10.0),
+                ..Self::from_tile_tree(node, leaf)
+            })
+            .collect();
+        Self::split(*direction, panes)
+    }
+
+    /// The nested views for this node, calling `leaf` for each pane in
+    /// layout order for its view's ID
+    ///
+    /// A pane with several tabs becomes a tab container with a leaf view for
+    /// each.
+    pub fn to_view_tree(&self, leaf: &mut dyn FnMut(&LayoutNode) -> Uuid) -> ViewManager {
+        let mut views = Vec::new();
+        let root = self.push_views(&mut views, leaf);
+        ViewManager::from_views(views, root)
+    }
+
+    fn push_views(&self, views: &mut Vec<View>, leaf: &mut dyn FnMut(&LayoutNode) -> Uuid) -> Uuid {
+        let view_type = if !self.tab_group.is_empty() {
+            let children = self.tab_group.iter().map(|node| node.push_views(views, leaf)).collect();
+            ViewType::Tabs { children, active_tab: self.active_tab }
+        } else if !self.panes.is_empty() {
+            let total = self.panes.iter().map(|pane| pane.size.unwrap_or(1.0)).sum::<f32>();
+            let ratios = self.panes.iter().map(|pane| pane.size.unwrap_or(1.0) / total).collect();
+            let children = self.panes.iter().map(|pane| pane.push_views(views, leaf)).collect();
+            let direction = self.split.unwrap_or(TileDirection::Horizontal).into();
+            ViewType::Split { direction, children, ratios }
+        } else if self.tabs.len() > 1 {
+            let children: Vec<Uuid> = self.tabs.iter().map(|_| View::leaf("Tab".to_string())).map(|tab| {
+                let id = tab.id;
+                views.push(tab);
+                id
+            }).collect();
+            ViewType::Tabs { children, active_tab: self.active_tab }
+        } else {
+            ViewType::Leaf
+        };
+        let title = match view_type {
+            ViewType::Leaf => "View",
+            ViewType::Tabs { .. } => "Tabs",
+            ViewType::Split { .. } => "Split",
+        };
+        let id = if self.is_pane() { leaf(self) } else { Uuid::new_v4() };
+        views.push(View { id, view_type, ..View::leaf(title.to_string()) });
+        id
+    }
+
+    /// The layout of nested view `id`, calling `leaf` for each pane: a leaf
+    /// view, or a tab container of leaf views
+    ///
+    /// Tab containers holding splits become tab groups, keeping every tab.
+    pub fn from_view_tree(views: &ViewManager, id: Uuid, leaf: &mut dyn FnMut(Uuid) -> LayoutNode) -> Option<Self> {
+        let is_leaf = |id: Uuid| views.get_view(id).is_some_and(|view| matches!(view.view_type, ViewType::Leaf));
+        match &views.get_view(id)?.view_type {
+            ViewType::Leaf => Some(leaf(id)),
+            ViewType::Tabs { children, .. } if children.iter().all(|&child| is_leaf(child)) => Some(leaf(id)),
+            ViewType::Tabs { children, active_tab } => {
+                let tab_group = children.iter()
+                    .map(|&child| Self::from_view_tree(views, child, leaf))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Self::tab_group(*active_tab, tab_group))
+            }
+            ViewType::Split { direction, children, ratios } => {
+                let total = ratios.iter().sum::<f32>().max(f32::EPSILON);
+                let panes = children.iter().zip(ratios)
+                    .filter_map(|(&child, ratio)| Some(LayoutNode {
+                        size: Some((ratio / total * 1000.0).round() / 10.0),
+                        ..Self::from_view_tree(views, child, leaf)?
+                    }))
+                    .collect();
+                Some(Self::split((*direction).into(), panes))
+            }
+        }
+    }
+}
+
+/// The panes of a left-nested chain of `direction` splits under `node`,
+/// with their shares of it
+fn collect_chain<'a>(node: &'a TileNode, direction: TileDirection, share: f32, chain: &mut Vec<(&'a TileNode, f32)>) {
+    match node {
+        TileNode::Split { direction: node_direction, ratio, first, second } if *node_direction == direction => {
+            collect_chain(first, direction, share * ratio, chain);
+            chain.push((second, share * (1.0 - ratio)));
+        }
+        _ => chain.push((node, share)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const LAYOUT: &str = r#"
+view_system = "scene"
+
+[root]
+split = "horizontal"
+
+[[root.panes]]
+size = 30
+[[root.panes.tabs]]
+type = "terminal"
+cwd = "server"
+command = "cargo"
+args = ["watch", "-x", "run"]
+
+[[root.panes]]
+size = 70
+split = "vertical"
+
+[[root.panes.panes]]
+active_tab = 1
+[[root.panes.panes.tabs]]
+type = "editor"
+file = "src/main.rs"
+[[root.panes.panes.tabs]]
+type = "editor"
+
+[[root.panes.panes]]
+[[root.panes.panes.tabs]]
+type = "editor"
+file = "README.md"
+"#;
+
+    fn editor(file: &str) -> ActorLayout {
+        ActorLayout::Editor { file: Some(PathBuf::from(file)) }
+    }
+
+    #[test]
+    fn parse_and_to_toml_round_trip() {
+        let layout = Layout::parse(LAYOUT).unwrap();
+        assert_eq!(layout.view_system, ViewSystemKind::Scene);
+        assert_eq!(layout.root.panes.len(), 2);
+        assert_eq!(layout.root.panes[1].panes[0].active_tab, 1);
+
+        let reparsed = Layout::parse(&layout.to_toml().unwrap()).unwrap();
+        assert_eq!(reparsed, layout);
+    }
+
+    #[test]
+    fn even_thirds_survive_a_tile_tree() {
+        let pane = |file: &str| LayoutNode { size: Some(33.3), ..LayoutNode::pane(vec![editor(file)]) };
+        let root = LayoutNode::split(TileDirection::Horizontal, vec![pane("a.rs"), pane("b.rs"), pane("c.rs")]);
+
+        let mut panes = HashMap::new();
+        let tree = root.to_tile_tree(&mut |node| {
+            let id = Uuid::new_v4();
+            panes.insert(id, node.clone());
+            id
+        });
+        assert_eq!(tree.leaves().len(), 3);
+
+        let restored = LayoutNode::from_tile_tree(&tree, &mut |id| panes[&id].clone());
+        assert_eq!(restored, root);
+    }
+
+    #[test]
+    fn nested_tab_groups_keep_every_tab() {
+        let pane = |file: &str| LayoutNode::pane(vec![editor(file)]);
+        let half = |node: LayoutNode| LayoutNode { size: Some(50.0), ..node };
+        let root = LayoutNode::tab_group(1, vec![
+            LayoutNode { active_tab: 1, ..LayoutNode::pane(vec![editor("a.rs"), editor("b.rs")]) },
+            LayoutNode::split(TileDirection::Vertical, vec![half(pane("c.rs")), half(pane("d.rs"))]),
+        ]);
+
+        let mut panes = HashMap::new();
+        let views = root.to_view_tree(&mut |node| {
+            let id = Uuid::new_v4();
+            panes.insert(id, node.clone());
+            id
+        });
+        assert_eq!(panes.len(), 3);
+
+        let restored = LayoutNode::from_view_tree(&views, views.root().unwrap(), &mut |id| panes[&id].clone());
+        assert_eq!(restored, Some(root));
+    }
+
+    #[test]
+    fn invalid_trees_are_rejected() {
+        let cases = [
+            ("[root]\nsplit = \"vertical\"\n", "a split needs panes"),
+            ("[root]\nactive_tab = 1\n[[root.tabs]]\ntype = \"editor\"\n", "out of range"),
+            (
+                "[root]\n[[root.tabs]]\ntype = \"editor\"\n[[root.panes]]\n[[root.panes.tabs]]\ntype = \"editor\"\n",
+                "both panes and tabs",
+            ),
+            (
+                "[root]\nactive_tab = 2\n[[root.tab_group]]\n[[root.tab_group.tabs]]\ntype = \"editor\"\n",
+                "out of range",
+            ),
+        ];
+        for (text, expected) in cases {
+            let error = Layout::parse(text).unwrap_err().to_string();
+            assert!(error.contains(expected), "{:?} gave {:?}", text, error);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How to launch a language server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageServerSpec {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl LanguageServerSpec {
+    pub fn new(command: &str, args: &[&str]) -> Self {
+        Self {
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+}
+
+/// Language servers keyed by editor language name
+#[derive(Debug, Clone)]
+pub struct LanguageServerRegistry {
+    servers: HashMap<String, LanguageServerSpec>,
+}
+
+impl LanguageServerRegistry {
+    pub fn new() -> Self {
+        let mut servers = HashMap::new();
+        servers.insert("rust".to_string(), LanguageServerSpec::new("rust-analyzer", &[]));
+        servers.insert("python".to_string(), LanguageServerSpec::new("pylsp", &[]));
+        servers.insert("go".to_string(), LanguageServerSpec::new("gopls", &[]));
+        servers.insert("c".to_string(), LanguageServerSpec::new("clangd", &[]));
+        servers.insert("cpp".to_string(), LanguageServerSpec::new("clangd", &[]));
+        for lang in ["javascript", "typescript"] {
+            servers.insert(lang.to_string(), LanguageServerSpec::new("typescript-language-server", &["--stdio"]));
+        }
+        Self { servers }
+    }
+
+    pub fn get(&self, language: &str) -> Option<&LanguageServerSpec> {
+        self.servers.get(language)
+    }
+}
+
+impl Default for LanguageServerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Minimal synchronous LSP client speaking JSON-RPC over stdio
+///
+/// Intended for short-lived, one-shot requests (formatting, completion)
+/// where every call is bounded by a timeout.
+pub struct LspClient {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: i64,
+}
+
+impl LspClient {
+    /// Spawn the server and perform the `initialize` handshake
+    pub fn start(spec: &LanguageServerSpec, root: Option<&Path>, timeout: Duration) -> Result<Self> {
+        let mut command = Command::new(&spec.command);
+        command.args(&spec.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        if let Some(root) = root {
+            command.current_dir(root);
+        }
+
+        let mut child = command.spawn()
+            .map_err(|e| anyhow!("Failed to start language server '{}': {}", spec.command, e))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("Language server has no stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("Language server has no stdout"))?;
+
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Some(message) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = Self { child, stdin, messages, next_id: 1 };
+
+        let root_uri = root.and_then(|r| lsp_types::Url::from_directory_path(r).ok());
+        client.request("initialize", json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "capabilities": {},
+        }), timeout)?;
+        client.notify("initialized", json!({}))?;
+
+        Ok(client)
+    }
+
+    /// Send a request and wait for its response
+    pub fn request(&mut self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let message = self.messages.recv_timeout(remaining)
+                .map_err(|_| anyhow!("Language server timed out on '{}'", method))?;
+
+            // Server-to-client requests must be answered or some servers stall
+            if message.get("method").is_some() {
+                if let Some(request_id) = message.get("id") {
+                    let reply = json!({ "jsonrpc": "2.0", "id": request_id, "result": Value::Null });
+                    self.send(&reply)?;
+                }
+                continue;
+            }
+
+            if message.get("id") == Some(&json!(id)) {
+                if let Some(error) = message.get("error") {
+                    return Err(anyhow!("Language server error on '{}': {}", method, error));
+                }
+                return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+            }
+        }
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    /// Politely shut the server down, killing it if it doesn't comply
+    pub fn shutdown(mut self) {
+        let _ = self.request("shutdown", Value::Null, Duration::from_millis(500));
+        let _ = self.notify("exit", Value::Null);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    fn send(&mut self, message: &Value) -> Result<()> {
+        let body = serde_json::to_string(message)?;
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+}
+
+/// Read one `Content-Length` framed message
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(len) = header.strip_prefix("Content-Length:") {
+            content_length = len.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0u8; content_length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// Convert an LSP position (UTF-16 columns) into a byte offset
+pub fn position_to_offset(text: &str, position: lsp_types::Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line = text[line_start..].split('\n').next().unwrap_or("");
+    let mut utf16 = 0;
+    for (i, ch) in line.char_indices() {
+        if utf16 >= position.character as usize {
+            return line_start + i;
+        }
+        utf16 += ch.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// Apply LSP text edits to a string
+pub fn apply_text_edits(text: &str, edits: &[lsp_types::TextEdit]) -> String {
+    let mut ranges: Vec<_> = edits.iter()
+        .map(|edit| {
+            let start = position_to_offset(text, edit.range.start);
+            let end = position_to_offset(text, edit.range.end).max(start);
+            (start..end, edit.new_text.as_str())
+        })
+        .collect();
+    ranges.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+    let mut result = text.to_string();
+    for (range, new_text) in ranges {
+        result.replace_range(range, new_text);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use lsp_types::{Position, Range, TextEdit};
+
+    /// LSP position (UTF-16 columns) of byte `offset`
+    fn position_of(text: &str, offset: usize) -> Position {
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            line: text[..offset].matches('\n').count() as u32,
+            character: text[line_start..offset].encode_utf16().count() as u32,
+        }
+    }
+
+    #[test]
+    fn positions_count_utf16_columns() {
+        let text = "ab\n😀x\n";
+        assert_eq!(position_to_offset(text, Position::new(1, 2)), 7);
+        assert_eq!(position_to_offset(text, Position::new(1, 99)), 8);
+        assert_eq!(position_to_offset(text, Position::new(9, 0)), text.len());
+    }
+
+    #[test]
+    fn text_edits_round_trip_as_lsp_edits() {
+        let old = "fn main(){\n  let é=1;\n}\n";
+        let new = "fn main() {\n    let é = 1;\n}\n";
+        let edits: Vec<TextEdit> = diff::text_edits(old, new).into_iter()
+            .map(|(range, new_text)| TextEdit {
+                range: Range::new(position_of(old, range.start), position_of(old, range.end)),
+                new_text,
+            })
+            .collect();
+        assert_eq!(apply_text_edits(old, &edits), new);
+    }
+}
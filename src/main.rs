@@ -5,17 +5,48 @@ mod panels;
 mod code_editor_actor;
 mod config;
 mod terminal_actor;
-mod scene_view;
 mod view_system;
 mod scene_system;
 mod tiling_system;
 mod widgets;
+mod byte_buffer;
 mod command_palette;
+mod completion;
+mod diff;
+mod diff_actor;
+mod editor_buffer;
+mod find_bar;
+mod emacs_keymap;
+mod floating_panes;
+mod formatter;
+mod git;
+mod hex_editor_actor;
+mod image_decode;
+mod image_preview_actor;
+mod keymap;
+mod language_servers;
+mod large_file;
+mod layout;
+mod lsp_client;
+mod markdown;
+mod markdown_editor_actor;
+mod merge;
+mod minimap;
+mod scene_canvas;
+mod session;
+mod snippet;
+mod swap_file;
+mod syntax_tree;
+mod text_search;
+mod vim_keymap;
 
 use eframe::egui;
 use env_logger;
 use ide_state::IdeState;
 use config::IdeConfig;
+use keymap::KeymapKind;
+use layout::Layout;
+use session::{Session, DEFAULT_SESSION};
 
 fn main() -> eframe::Result<()> {
     env_logger::init();
@@ -41,6 +72,8 @@ fn main() -> eframe::Result<()> {
 struct IdeApp {
     state: IdeState,
     config: IdeConfig,
+    /// Name typed into the File > Session menu
+    new_session_name: String,
 }
 
 impl IdeApp {
@@ -50,9 +83,67 @@ impl IdeApp {
             IdeConfig::default()
         });
 
+        // `--layout <path>` picks the layout and `--session <name>` the
+        // session (by default, the working directory's); other arguments
+        // are files
+        let mut layout_arg = None;
+        let mut session_name = std::env::current_dir()
+            .map_or_else(|_| DEFAULT_SESSION.to_string(), |dir| Session::default_name(&dir));
+        let mut files = Vec::new();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--layout" {
+                layout_arg = args.next().map(Into::into);
+            } else if let Some(path) = arg.strip_prefix("--layout=") {
+                layout_arg = Some(path.into());
+            } else if arg == "--session" {
+                if let Some(name) = args.next() {
+                    session_name = name;
+                }
+            } else if let Some(name) = arg.strip_prefix("--session=") {
+                session_name = name.to_string();
+            } else {
+                files.push(arg);
+            }
+        }
+
+        // An explicit layout wins over the project's saved session, which
+        // wins over its layout file
+        let session = if layout_arg.is_some() {
+            None
+        } else {
+            Session::load(&session_name).unwrap_or_else(|e| {
+                log::error!("{}", e);
+                None
+            })
+        };
+        let mut state = match session {
+            Some(session) => IdeState::from_session(&config, &session),
+            None => {
+                let loaded = Layout::startup_path(layout_arg).and_then(|path| match Layout::load(&path) {
+                    Ok(layout) => Some((layout, path)),
+                    Err(e) => {
+                        log::error!("{}", e);
+                        None
+                    }
+                });
+                match loaded {
+                    Some((layout, path)) => IdeState::new(&config, &layout, &session_name).with_layout_path(path),
+                    None => IdeState::new(&config, &Layout::builtin(), &session_name),
+                }
+            }
+        };
+        // Files named on the command line open in their own views
+        for path in files {
+            if let Err(e) = state.open_file(path.into()) {
+                log::error!("{}", e);
+            }
+        }
+
         Self {
-            state: IdeState::new(),
+            state,
             config,
+            new_session_name: String::new(),
         }
     }
 }
@@ -100,6 +191,37 @@ impl eframe::App for IdeApp {
                         self.state.close_active_tab();
                     }
                     ui.separator();
+                    ui.menu_button("Session", |ui| {
+                        ui.label(format!("Current: {}", self.state.session_name()));
+                        if ui.button("Save Session").clicked() {
+                            if let Err(e) = self.state.save_session() {
+                                log::warn!("Failed to save the session: {}", e);
+                            }
+                        }
+                        ui.separator();
+                        for name in Session::list() {
+                            let current = name == self.state.session_name();
+                            if ui.selectable_label(current, &name).clicked() && !current {
+                                if let Err(e) = self.state.switch_session(&name) {
+                                    log::warn!("Can't switch to session '{}': {}", name, e);
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.new_session_name);
+                            let name = self.new_session_name.trim().to_string();
+                            if ui.add_enabled(!name.is_empty(), egui::Button::new("New")).clicked() {
+                                match self.state.switch_session(&name) {
+                                    Ok(()) => self.new_session_name.clear(),
+                                    Err(e) => log::warn!("Can't switch to session '{}': {}", name, e),
+                                }
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                    ui.separator();
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
@@ -112,6 +234,84 @@ impl eframe::App for IdeApp {
                     if ui.button("Split Vertical").clicked() {
                         self.state.split_active_view(panels::SplitDirection::Vertical);
                     }
+                    if let Some(tiling) = self.state.view_container.as_system_mut::<tiling_system::TilingSystem>() {
+                        let mut zoomed = tiling.zoomed_tile().is_some();
+                        if ui.checkbox(&mut zoomed, "Zoom Pane").on_hover_text("Ctrl+Alt+F").changed() {
+                            tiling.toggle_zoom();
+                        }
+                    }
+                    if ui.button("Save Layout").clicked() {
+                        match self.state.save_layout() {
+                            Ok(path) => log::info!("Saved the layout to {}", path.display()),
+                            Err(e) => log::warn!("Failed to save the layout: {}", e),
+                        }
+                    }
+                    ui.separator();
+                    ui.menu_button("Floating Panes", |ui| {
+                        let mut visible = self.state.floating.is_visible();
+                        if ui.checkbox(&mut visible, "Show Floating Panes").changed() {
+                            self.state.floating.toggle_visible();
+                        }
+                        if ui.button("New Floating Terminal").clicked() {
+                            self.state.new_floating_terminal();
+                        }
+                        if ui.button("Float Active View").clicked() {
+                            self.state.float_active_actor();
+                        }
+                        if ui.button("Embed Floating Pane").clicked() {
+                            self.state.embed_floating_pane();
+                        }
+                        if ui.button("Pin Floating Pane").clicked() {
+                            self.state.floating.toggle_pinned();
+                        }
+                    });
+                    if let Some(scene) = self.state.view_container.as_system_mut::<scene_system::SceneSystem>() {
+                        ui.menu_button("Canvas", |ui| {
+                            let mut canvas_mode = scene.canvas_mode();
+                            if ui.checkbox(&mut canvas_mode, "Canvas Mode").changed() {
+                                scene.toggle_canvas_mode();
+                            }
+                            let canvas = scene.canvas_mut();
+                            ui.checkbox(&mut canvas.snap_to_grid, "Snap to Grid");
+                            ui.checkbox(&mut canvas.show_minimap, "Minimap");
+                            if ui.button("Focus Card").clicked() {
+                                scene.focus_active_card();
+                            }
+                            if ui.button("Fit All Cards").clicked() {
+                                scene.canvas_mut().fit_all();
+                            }
+                        });
+                    }
+                    let mut save_bookmark = false;
+                    if let Some(bookmarks) = self.state.view_container.system_mut().as_bookmarks_mut() {
+                        ui.menu_button("Bookmarks", |ui| {
+                            let mut removed = None;
+                            for index in 0..bookmarks.bookmarks().len() {
+                                ui.horizontal(|ui| {
+                                    if ui.button(format!("{}", index + 1)).on_hover_text("Jump to this bookmark").clicked() {
+                                        bookmarks.jump_to_bookmark(index);
+                                    }
+                                    let mut name = bookmarks.bookmarks()[index].name.clone();
+                                    if ui.text_edit_singleline(&mut name).changed() {
+                                        bookmarks.rename_bookmark(index, name);
+                                    }
+                                    if ui.small_button("Update").clicked() {
+                                        bookmarks.update_bookmark(index);
+                                    }
+                                    if ui.small_button("×").clicked() {
+                                        removed = Some(index);
+                                    }
+                                });
+                            }
+                            if let Some(index) = removed {
+                                bookmarks.remove_bookmark(index);
+                            }
+                            save_bookmark = ui.button("Save Bookmark").clicked();
+                        });
+                    }
+                    if save_bookmark {
+                        self.state.save_bookmark();
+                    }
                     ui.separator();
                     ui.menu_button("View System", |ui| {
                         if ui.button("Scene System").clicked() {
@@ -120,6 +320,9 @@ impl eframe::App for IdeApp {
                         if ui.button("Tiling System").clicked() {
                             self.state.switch_to_tiling_system();
                         }
+                        if ui.button("Nested Views").clicked() {
+                            self.state.switch_to_nested_system();
+                        }
                     });
                     ui.separator();
                     ui.menu_button("Widgets", |ui| {
@@ -162,9 +365,27 @@ impl eframe::App for IdeApp {
                         }
                     }
 
+                    ui.separator();
+                    ui.menu_button("Keymap", |ui| {
+                        let current = self.config.editor.keymap;
+                        for (kind, label) in [
+                            (KeymapKind::Default, "Default"),
+                            (KeymapKind::Vim, "Vim"),
+                            (KeymapKind::Emacs, "Emacs"),
+                        ] {
+                            if ui.radio(current == kind, label).clicked() && current != kind {
+                                self.state.set_editor_keymap(kind);
+                                if let Err(e) = self.config.set_keymap(kind) {
+                                    log::error!("Failed to save keymap: {}", e);
+                                }
+                            }
+                        }
+                    });
+
                     ui.separator();
                     if ui.button("Reset to Defaults").clicked() {
                         self.config = IdeConfig::default();
+                        self.state.set_editor_keymap(self.config.editor.keymap);
                         if let Err(e) = self.config.save() {
                             log::error!("Failed to save default config: {}", e);
                         }
@@ -180,4 +401,10 @@ impl eframe::App for IdeApp {
                 self.state.render(ui);
             });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Err(e) = self.state.save_session() {
+            log::error!("Failed to save session '{}': {}", self.state.session_name(), e);
+        }
+    }
 }
\ No newline at end of file
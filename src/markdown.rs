@@ -0,0 +1,712 @@
+use egui::{self, Color32, FontId};
+use egui::text::{LayoutJob, TextFormat};
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// syntect theme for code blocks, in the preview and in exported HTML
+const CODE_THEME: &str = "base16-ocean.dark";
+
+/// Styling of a run of inline text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub strike: bool,
+    pub code: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String, Style),
+    Image { alt: String, src: String },
+    LineBreak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListMarker {
+    Bullet,
+    Number(u64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockKind {
+    Heading { level: usize, inlines: Vec<Inline> },
+    Paragraph(Vec<Inline>),
+    /// One list item; nesting is given by `depth` rather than by containment
+    ListItem { depth: usize, marker: ListMarker, checked: Option<bool>, inlines: Vec<Inline> },
+    Code { language: String, code: String },
+    Quote(Vec<Block>),
+    Table { align: Vec<Align>, header: Vec<Vec<Inline>>, rows: Vec<Vec<Vec<Inline>>> },
+    Rule,
+}
+
+/// A top-level element and the zero-based source lines it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub lines: Range<usize>,
+}
+
+/// Opening fence of a fenced code block (three or more backticks or tildes)
+fn fence(line: &str) -> Option<&str> {
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.len() - line.trim_start_matches(c).len();
+    (len >= 3).then(|| &line[..len])
+}
+
+/// Level and text of an ATX heading (`## Title`)
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = rest.trim();
+    // Optional closing hashes
+    let stripped = text.trim_end_matches('#');
+    let text = if stripped.is_empty() || stripped.ends_with(' ') { stripped.trim_end() } else { text };
+    Some((level, text))
+}
+
+/// `---`, `***` or `___`, optionally spaced out
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_'].iter().any(|&c| compact.chars().all(|x| x == c))
+}
+
+/// Marker and content of a list item line (leading indentation already removed)
+fn list_marker(line: &str) -> Option<(ListMarker, &str)> {
+    if let Some(rest) = line.strip_prefix(['-', '*', '+']) {
+        return (rest.is_empty() || rest.starts_with([' ', '\t'])).then(|| (ListMarker::Bullet, rest.trim_start()));
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let rest = line[digits..].strip_prefix(['.', ')'])?;
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some((ListMarker::Number(line[..digits].parse().ok()?), rest.trim_start()))
+}
+
+/// Width of a line's leading whitespace, tabs counting as four columns
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Whether `line` begins a block other than a paragraph, ending the paragraph before it
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    fence(trimmed).is_some()
+        || atx_heading(trimmed).is_some()
+        || is_rule(trimmed)
+        || trimmed.starts_with('>')
+        || list_marker(trimmed).is_some_and(|(_, content)| !content.is_empty())
+}
+
+/// Cells of a table row, with the outer pipes removed
+fn table_cells(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').filter(|l| !l.ends_with('\\')).unwrap_or(line);
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '|' if !escaped => {
+                cells.push(line[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.push(line[start..].trim());
+    cells
+}
+
+/// Column alignments if `line` is a table's delimiter row (`| :--- | ---: |`)
+fn delimiter_row(line: &str) -> Option<Vec<Align>> {
+    if !line.contains('-') {
+        return None;
+    }
+    table_cells(line)
+        .into_iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Align::Center,
+                (true, false) => Align::Left,
+                (false, true) => Align::Right,
+                (false, false) => Align::None,
+            })
+        })
+        .collect()
+}
+
+/// Parse markdown into blocks
+///
+/// Covers the CommonMark block structure people actually write (headings,
+/// paragraphs, lists, task lists, fenced and indented code, quotes, rules)
+/// plus GitHub-style tables. Lists are flattened into items with a depth.
+pub fn parse(text: &str) -> Vec<Block> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut blocks: Vec<Block> = Vec::new();
+    // Indentation of the enclosing list items, outermost first
+    let mut list_indents: Vec<usize> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        let indent = indent_width(line);
+        let start = i;
+        if trimmed.is_empty() {
+            i += 1;
+            continue;
+        }
+        let in_list = matches!(blocks.last(), Some(Block { kind: BlockKind::ListItem { .. }, .. }));
+        if !in_list {
+            list_indents.clear();
+        }
+
+        let kind = if let Some(open) = fence(trimmed) {
+            let language = trimmed[open.len()..].split_whitespace().next().unwrap_or_default().to_string();
+            let mut code = String::new();
+            i += 1;
+            while i < lines.len() {
+                let close = lines[i].trim();
+                if close.starts_with(open) && close.trim_start_matches(open.chars().next().unwrap_or('`')).is_empty() {
+                    i += 1;
+                    break;
+                }
+                code.push_str(lines[i]);
+                code.push('\n');
+                i += 1;
+            }
+            BlockKind::Code { language, code }
+        } else if indent >= 4 && !in_list {
+            let mut code = String::new();
+            while i < lines.len() && (indent_width(lines[i]) >= 4 || lines[i].trim().is_empty()) {
+                let mut skipped = 0;
+                let content = lines[i].trim_start_matches(|c: char| {
+                    skipped += if c == '\t' { 4 } else { 1 };
+                    c.is_whitespace() && skipped <= 4
+                });
+                code.push_str(content);
+                code.push('\n');
+                i += 1;
+            }
+            let code = code.trim_end_matches('\n').to_string() + "\n";
+            BlockKind::Code { language: String::new(), code }
+        } else if let Some((level, text)) = atx_heading(trimmed) {
+            i += 1;
+            BlockKind::Heading { level, inlines: parse_inlines(text) }
+        } else if is_rule(trimmed) {
+            i += 1;
+            BlockKind::Rule
+        } else if trimmed.starts_with('>') {
+            let mut inner = String::new();
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                let content = &lines[i].trim_start()[1..];
+                inner.push_str(content.strip_prefix(' ').unwrap_or(content));
+                inner.push('\n');
+                i += 1;
+            }
+            let mut quoted = parse(&inner);
+            for block in &mut quoted {
+                block.lines = block.lines.start + start..block.lines.end + start;
+            }
+            BlockKind::Quote(quoted)
+        } else if let Some((marker, content)) = list_marker(trimmed) {
+            while list_indents.last().is_some_and(|&outer| outer > indent) {
+                list_indents.pop();
+            }
+            if list_indents.last().is_none_or(|&outer| outer < indent) {
+                list_indents.push(indent);
+            }
+            let (checked, content) = match content.get(..4) {
+                Some("[ ] ") => (Some(false), &content[4..]),
+                Some("[x] " | "[X] ") => (Some(true), &content[4..]),
+                _ => (None, content),
+            };
+            let mut text = content.to_string();
+            i += 1;
+            // Continuation lines, lazily or properly indented
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines[i]) {
+                text.push(' ');
+                text.push_str(lines[i].trim());
+                i += 1;
+            }
+            BlockKind::ListItem {
+                depth: list_indents.len() - 1,
+                marker,
+                checked,
+                inlines: parse_inlines(&text),
+            }
+        } else if let Some(align) = lines.get(i + 1).filter(|_| line.contains('|')).and_then(|next| delimiter_row(next)) {
+            let row = |line: &str| -> Vec<Vec<Inline>> {
+                let mut cells: Vec<_> = table_cells(line).into_iter().map(parse_inlines).collect();
+                cells.resize(align.len(), Vec::new());
+                cells
+            };
+            let header = row(line);
+            i += 2;
+            let mut rows = Vec::new();
+            while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+                rows.push(row(lines[i]));
+                i += 1;
+            }
+            BlockKind::Table { align, header, rows }
+        } else {
+            let mut text = String::new();
+            let mut setext = None;
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                let current = lines[i].trim();
+                if i > start {
+                    if !current.is_empty() && current.chars().all(|c| c == '=') {
+                        setext = Some(1);
+                    } else if !current.is_empty() && current.chars().all(|c| c == '-') {
+                        setext = Some(2);
+                    }
+                    if setext.is_some() {
+                        i += 1;
+                        break;
+                    }
+                    if starts_block(lines[i]) {
+                        break;
+                    }
+                    // Two trailing spaces or a backslash force a line break
+                    let previous = lines[i - 1];
+                    text.push(if previous.ends_with("  ") || previous.ends_with('\\') { '\n' } else { ' ' });
+                }
+                text.push_str(current.strip_suffix('\\').unwrap_or(current));
+                i += 1;
+            }
+            match setext {
+                Some(level) => BlockKind::Heading { level, inlines: parse_inlines(&text) },
+                None => BlockKind::Paragraph(parse_inlines(&text)),
+            }
+        };
+        blocks.push(Block { kind, lines: start..i });
+    }
+    blocks
+}
+
+/// Parse inline markup: emphasis, strikethrough, code spans, links, images and autolinks
+pub fn parse_inlines(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    inlines_into(text, &Style::default(), &mut inlines);
+    inlines
+}
+
+/// Label, destination and total length of a `[label](destination "title")` at the start of `text`
+fn link_parts(text: &str) -> Option<(&str, String, usize)> {
+    let mut depth = 0;
+    let mut label_end = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    label_end = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let label_end = label_end?;
+    let rest = text[label_end + 1..].strip_prefix('(')?;
+    let close = rest.find(')')?;
+    let destination = rest[..close].split_whitespace().next().unwrap_or_default();
+    let destination = destination.trim_start_matches('<').trim_end_matches('>');
+    Some((&text[1..label_end], destination.to_string(), label_end + 2 + close + 1))
+}
+
+/// Offset of the delimiter closing an emphasis span that starts right after
+/// an opening `delimiter` at the start of `text`
+fn closing_delimiter(text: &str, delimiter: &str) -> Option<usize> {
+    if text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let single = delimiter.len() == 1;
+    let mut from = 0;
+    while let Some(found) = text[from..].find(delimiter) {
+        let at = from + found;
+        let after = &text[at + delimiter.len()..];
+        // A single `*` doesn't close on half of a `**`
+        if single && after.starts_with(delimiter) {
+            from = at + 2;
+            continue;
+        }
+        let intraword = delimiter.starts_with('_') && after.starts_with(char::is_alphanumeric);
+        if at > 0 && !text[..at].ends_with(char::is_whitespace) && !intraword {
+            return Some(at);
+        }
+        from = at + delimiter.len();
+    }
+    None
+}
+
+fn inlines_into(text: &str, style: &Style, inlines: &mut Vec<Inline>) {
+    let mut plain = String::new();
+    let flush = |plain: &mut String, inlines: &mut Vec<Inline>| {
+        if !plain.is_empty() {
+            inlines.push(Inline::Text(std::mem::take(plain), style.clone()));
+        }
+    };
+
+    let mut i = 0;
+    'scan: while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        match c {
+            '\\' => {
+                if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                    plain.push(escaped);
+                    i += 1 + escaped.len_utf8();
+                    continue;
+                }
+            }
+            '\n' => {
+                flush(&mut plain, inlines);
+                inlines.push(Inline::LineBreak);
+                i += 1;
+                continue;
+            }
+            '`' => {
+                let ticks = &rest[..rest.len() - rest.trim_start_matches('`').len()];
+                if let Some(end) = rest[ticks.len()..].find(ticks) {
+                    flush(&mut plain, inlines);
+                    let code = rest[ticks.len()..ticks.len() + end].trim();
+                    inlines.push(Inline::Text(code.to_string(), Style { code: true, ..style.clone() }));
+                    i += 2 * ticks.len() + end;
+                    continue;
+                }
+            }
+            '!' if rest.starts_with("![") => {
+                if let Some((alt, src, len)) = link_parts(&rest[1..]) {
+                    flush(&mut plain, inlines);
+                    inlines.push(Inline::Image { alt: alt.to_string(), src });
+                    i += 1 + len;
+                    continue;
+                }
+            }
+            '[' => {
+                if let Some((label, url, len)) = link_parts(rest) {
+                    flush(&mut plain, inlines);
+                    inlines_into(label, &Style { link: Some(url), ..style.clone() }, inlines);
+                    i += len;
+                    continue;
+                }
+            }
+            '<' => {
+                if let Some(end) = rest.find('>') {
+                    let url = &rest[1..end];
+                    let is_url = ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme));
+                    if is_url && !url.contains(char::is_whitespace) {
+                        flush(&mut plain, inlines);
+                        inlines.push(Inline::Text(url.to_string(), Style { link: Some(url.to_string()), ..style.clone() }));
+                        i += end + 1;
+                        continue;
+                    }
+                }
+            }
+            '*' | '_' | '~' => {
+                let after_word = text[..i].ends_with(char::is_alphanumeric);
+                for delimiter in ["**", "__", "~~", "*", "_"] {
+                    if !rest.starts_with(delimiter) || (delimiter.starts_with('_') && after_word) {
+                        continue;
+                    }
+                    let inner = &rest[delimiter.len()..];
+                    if let Some(end) = closing_delimiter(inner, delimiter) {
+                        flush(&mut plain, inlines);
+                        let mut inner_style = style.clone();
+                        match delimiter {
+                            "**" | "__" => inner_style.bold = true,
+                            "~~" => inner_style.strike = true,
+                            _ => inner_style.italic = true,
+                        }
+                        inlines_into(&inner[..end], &inner_style, inlines);
+                        i += 2 * delimiter.len() + end;
+                        continue 'scan;
+                    }
+                }
+            }
+            _ => {}
+        }
+        plain.push(c);
+        i += c.len_utf8();
+    }
+    flush(&mut plain, inlines);
+}
+
+/// Text of inlines without markup, e.g. for heading anchors and outlines
+pub fn plain_text(inlines: &[Inline]) -> String {
+    inlines.iter()
+        .map(|inline| match inline {
+            Inline::Text(text, _) => text.as_str(),
+            Inline::Image { alt, .. } => alt.as_str(),
+            Inline::LineBreak => " ",
+        })
+        .collect()
+}
+
+/// GitHub-style anchor for a heading
+pub fn slug(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn syntaxes() -> &'static (SyntaxSet, ThemeSet) {
+    static SYNTAXES: OnceLock<(SyntaxSet, ThemeSet)> = OnceLock::new();
+    SYNTAXES.get_or_init(|| (SyntaxSet::load_defaults_newlines(), ThemeSet::load_defaults()))
+}
+
+fn find_syntax<'a>(syntaxes: &'a SyntaxSet, language: &str) -> Option<&'a SyntaxReference> {
+    if language.is_empty() {
+        return None;
+    }
+    syntaxes.find_syntax_by_token(language)
+}
+
+/// Background of highlighted code blocks
+pub fn code_background() -> Color32 {
+    let (_, themes) = syntaxes();
+    themes.themes[CODE_THEME].settings.background
+        .map_or(Color32::from_gray(30), |c| Color32::from_rgb(c.r, c.g, c.b))
+}
+
+/// Lay out a code block, highlighted by syntect when the language is known
+pub fn code_layout_job(code: &str, language: &str, font_size: f32) -> LayoutJob {
+    let (syntaxes, themes) = syntaxes();
+    let theme = &themes.themes[CODE_THEME];
+    let default_color = theme.settings.foreground
+        .map_or(Color32::from_gray(200), |c| Color32::from_rgb(c.r, c.g, c.b));
+    let format = |color, italics| TextFormat {
+        font_id: FontId::monospace(font_size),
+        color,
+        italics,
+        ..Default::default()
+    };
+    let code = code.strip_suffix('\n').unwrap_or(code);
+
+    let mut job = LayoutJob::default();
+    let Some(syntax) = find_syntax(syntaxes, language) else {
+        job.append(code, 0.0, format(default_color, false));
+        return job;
+    };
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    for line in LinesWithEndings::from(code) {
+        match highlighter.highlight_line(line, syntaxes) {
+            Ok(ranges) => {
+                for (style, piece) in ranges {
+                    let color = Color32::from_rgb(style.foreground.r, style.foreground.g, style.foreground.b);
+                    job.append(piece, 0.0, format(color, style.font_style.contains(FontStyle::ITALIC)));
+                }
+            }
+            Err(_) => job.append(line, 0.0, format(default_color, false)),
+        }
+    }
+    job
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn inlines_html(inlines: &[Inline], html: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text, style) => {
+                let tags: Vec<&str> = [
+                    (style.bold, "strong"),
+                    (style.italic, "em"),
+                    (style.strike, "del"),
+                    (style.code, "code"),
+                ].into_iter().filter(|(on, _)| *on).map(|(_, tag)| tag).collect();
+                if let Some(url) = &style.link {
+                    let _ = write!(html, "<a href=\"{}\">", escape_html(url));
+                }
+                tags.iter().for_each(|tag| { let _ = write!(html, "<{}>", tag); });
+                html.push_str(&escape_html(text));
+                tags.iter().rev().for_each(|tag| { let _ = write!(html, "</{}>", tag); });
+                if style.link.is_some() {
+                    html.push_str("</a>");
+                }
+            }
+            Inline::Image { alt, src } => {
+                let _ = write!(html, "<img src=\"{}\" alt=\"{}\">", escape_html(src), escape_html(alt));
+            }
+            Inline::LineBreak => html.push_str("<br>\n"),
+        }
+    }
+}
+
+/// Highlighted `<pre>` for a code block (plain when the language is unknown)
+fn code_html(code: &str, language: &str) -> String {
+    let (syntaxes, themes) = syntaxes();
+    find_syntax(syntaxes, language)
+        .and_then(|syntax| syntect::html::highlighted_html_for_string(code, syntaxes, syntax, &themes.themes[CODE_THEME]).ok())
+        .unwrap_or_else(|| format!("<pre><code>{}</code></pre>\n", escape_html(code)))
+}
+
+fn blocks_html(blocks: &[Block], html: &mut String) {
+    // Lists still open, innermost last, with whether each is ordered
+    let mut lists: Vec<(usize, bool)> = Vec::new();
+    let close_list = |html: &mut String, ordered: bool| {
+        html.push_str(if ordered { "</li>\n</ol>\n" } else { "</li>\n</ul>\n" });
+    };
+
+    for block in blocks {
+        if let BlockKind::ListItem { depth, marker, checked, inlines } = &block.kind {
+            let ordered = matches!(marker, ListMarker::Number(_));
+            while let Some(&(open_depth, open_ordered)) = lists.last() {
+                if open_depth > *depth || (open_depth == *depth && open_ordered != ordered) {
+                    close_list(html, open_ordered);
+                    lists.pop();
+                } else {
+                    break;
+                }
+            }
+            if lists.last().is_some_and(|(open_depth, _)| open_depth == depth) {
+                html.push_str("</li>\n");
+            } else {
+                match marker {
+                    ListMarker::Number(start) if *start != 1 => { let _ = writeln!(html, "<ol start=\"{}\">", start); }
+                    ListMarker::Number(_) => html.push_str("<ol>\n"),
+                    ListMarker::Bullet => html.push_str("<ul>\n"),
+                }
+                lists.push((*depth, ordered));
+            }
+            html.push_str("<li>");
+            match checked {
+                Some(true) => html.push_str("<input type=\"checkbox\" checked disabled> "),
+                Some(false) => html.push_str("<input type=\"checkbox\" disabled> "),
+                None => {}
+            }
+            inlines_html(inlines, html);
+            continue;
+        }
+        while let Some((_, ordered)) = lists.pop() {
+            close_list(html, ordered);
+        }
+
+        match &block.kind {
+            BlockKind::Heading { level, inlines } => {
+                let _ = write!(html, "<h{} id=\"{}\">", level, escape_html(&slug(&plain_text(inlines))));
+                inlines_html(inlines, html);
+                let _ = writeln!(html, "</h{}>", level);
+            }
+            BlockKind::Paragraph(inlines) => {
+                html.push_str("<p>");
+                inlines_html(inlines, html);
+                html.push_str("</p>\n");
+            }
+            BlockKind::Code { language, code } => html.push_str(&code_html(code, language)),
+            BlockKind::Quote(blocks) => {
+                html.push_str("<blockquote>\n");
+                blocks_html(blocks, html);
+                html.push_str("</blockquote>\n");
+            }
+            BlockKind::Table { align, header, rows } => {
+                let cell = |html: &mut String, tag: &str, column: usize, inlines: &[Inline]| {
+                    match align.get(column) {
+                        Some(Align::Left) => { let _ = write!(html, "<{} style=\"text-align: left\">", tag); }
+                        Some(Align::Center) => { let _ = write!(html, "<{} style=\"text-align: center\">", tag); }
+                        Some(Align::Right) => { let _ = write!(html, "<{} style=\"text-align: right\">", tag); }
+                        _ => { let _ = write!(html, "<{}>", tag); }
+                    }
+                    inlines_html(inlines, html);
+                    let _ = write!(html, "</{}>", tag);
+                };
+                html.push_str("<table>\n<thead>\n<tr>");
+                for (column, inlines) in header.iter().enumerate() {
+                    cell(html, "th", column, inlines);
+                }
+                html.push_str("</tr>\n</thead>\n<tbody>\n");
+                for row in rows {
+                    html.push_str("<tr>");
+                    for (column, inlines) in row.iter().enumerate() {
+                        cell(html, "td", column, inlines);
+                    }
+                    html.push_str("</tr>\n");
+                }
+                html.push_str("</tbody>\n</table>\n");
+            }
+            BlockKind::Rule => html.push_str("<hr>\n"),
+            BlockKind::ListItem { .. } => unreachable!("list items are handled above"),
+        }
+    }
+    while let Some((_, ordered)) = lists.pop() {
+        close_list(html, ordered);
+    }
+}
+
+/// Render parsed markdown as the body of an HTML page
+pub fn body_html(blocks: &[Block]) -> String {
+    let mut html = String::new();
+    blocks_html(blocks, &mut html);
+    html
+}
+
+/// A standalone HTML document for parsed markdown
+pub fn to_html(blocks: &[Block], title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        HTML_STYLE,
+        body_html(blocks),
+    )
+}
+
+const HTML_STYLE: &str = "\
+body { max-width: 50em; margin: 2em auto; padding: 0 1em; font-family: sans-serif; line-height: 1.5; }
+pre { padding: 0.8em; overflow-x: auto; border-radius: 4px; }
+code { font-family: monospace; }
+blockquote { margin-left: 0; padding-left: 1em; border-left: 4px solid #ccc; color: #555; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.7em; }
+img { max-width: 100%; }
+";
@@ -0,0 +1,653 @@
+use crate::actor::{Actor, ActorMessage, ActorAPI, ApiMethod, ApiParameter, ApiParams, ApiResult};
+use crate::code_editor_actor::CodeEditorActor;
+use crate::editor_buffer::{LineIndex, TextPosition};
+use crate::image_decode;
+use crate::markdown::{self, Block, BlockKind, Inline, ListMarker};
+use crate::syntax_tree::DocumentSymbol;
+use async_trait::async_trait;
+use egui::{self, Color32, FontId, Stroke, TextureHandle, TextureOptions};
+use egui::text::{LayoutJob, TextFormat};
+use uuid::Uuid;
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+
+/// Heading font sizes relative to body text, by level
+const HEADING_SCALE: [f32; 6] = [2.0, 1.6, 1.35, 1.15, 1.0, 0.9];
+
+/// Markdown editor: the code editor on the left and a rendered preview on
+/// the right, kept scrolled to the same place
+pub struct MarkdownEditorActor {
+    editor: CodeEditorActor,
+    /// Parsed document, and the buffer version it was parsed from
+    blocks: Vec<Block>,
+    parsed_version: Option<u64>,
+    show_preview: bool,
+    scroll_sync: bool,
+    /// `(source line, preview offset)` of each top-level block's top, from
+    /// the last frame, bracketed by the start and end of the document
+    block_tops: Vec<(usize, f32)>,
+    /// Vertical offset to apply to the preview on the next frame
+    preview_scroll_request: Option<f32>,
+    /// Editor line and preview offset after the last sync, to tell which side moved
+    last_sync: (f32, f32),
+    /// Textures for local images by `src` (`None` if the image couldn't be loaded)
+    images: HashMap<String, Option<TextureHandle>>,
+    /// Highlighted code blocks by a hash of their language and code
+    code_jobs: HashMap<u64, LayoutJob>,
+}
+
+impl MarkdownEditorActor {
+    pub fn new(editor: CodeEditorActor) -> Self {
+        let mut actor = Self {
+            editor,
+            blocks: Vec::new(),
+            parsed_version: None,
+            show_preview: true,
+            scroll_sync: true,
+            block_tops: Vec::new(),
+            preview_scroll_request: None,
+            last_sync: (0.0, 0.0),
+            images: HashMap::new(),
+            code_jobs: HashMap::new(),
+        };
+        actor.reparse();
+        actor
+    }
+
+    /// Reparse the source if it changed, and refresh the heading outline
+    fn reparse(&mut self) {
+        let version = self.editor.version();
+        if self.parsed_version == Some(version) {
+            return;
+        }
+        self.parsed_version = Some(version);
+        let text = self.editor.get_content();
+        self.blocks = markdown::parse(text);
+        let outline = heading_outline(text, &self.blocks);
+        self.editor.set_outline(outline);
+
+        let used: Vec<u64> = self.blocks.iter()
+            .filter_map(|block| match &block.kind {
+                BlockKind::Code { language, code } => Some(code_key(language, code)),
+                _ => None,
+            })
+            .collect();
+        self.code_jobs.retain(|key, _| used.contains(key));
+    }
+
+    /// The document as HTML, either a standalone page or just the body
+    pub fn html(&self, full_document: bool) -> String {
+        if full_document {
+            markdown::to_html(&self.blocks, &self.editor.name())
+        } else {
+            markdown::body_html(&self.blocks)
+        }
+    }
+
+    /// Write the document as an HTML page, by default next to the markdown
+    /// file with an `.html` extension
+    pub fn export_html(&mut self, path: Option<PathBuf>) -> Result<PathBuf> {
+        self.reparse();
+        let path = path
+            .or_else(|| self.editor.file_path().map(|file| file.with_extension("html")))
+            .ok_or_else(|| anyhow!("No export path given and the document has never been saved"))?;
+        fs::write(&path, self.html(true))
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        log::info!("Exported {} to {}", self.editor.name(), path.display());
+        Ok(path)
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.show_preview, "Preview");
+            ui.add_enabled_ui(self.show_preview, |ui| {
+                ui.toggle_value(&mut self.scroll_sync, "Sync scroll");
+            });
+            ui.separator();
+            if ui.button(egui::RichText::new("B").strong()).on_hover_text("Bold").clicked() {
+                self.editor.wrap_selection("**", "**");
+            }
+            if ui.button(egui::RichText::new("I").italics()).on_hover_text("Italic").clicked() {
+                self.editor.wrap_selection("*", "*");
+            }
+            if ui.button(egui::RichText::new("</>").monospace()).on_hover_text("Inline code").clicked() {
+                self.editor.wrap_selection("`", "`");
+            }
+            ui.separator();
+            let export = ui.add_enabled(self.editor.file_path().is_some(), egui::Button::new("Export HTML"))
+                .on_hover_text("Write an .html file next to this one");
+            if export.clicked() {
+                if let Err(e) = self.export_html(None) {
+                    log::warn!("{}", e);
+                }
+            }
+        });
+    }
+
+    /// Draw the rendered document, returning the preview's scroll offset
+    fn show_preview(&mut self, ui: &mut egui::Ui) -> f32 {
+        let mut scroll = egui::ScrollArea::vertical()
+            .id_salt(("markdown_preview", self.editor.id()))
+            .auto_shrink([false, false]);
+        if let Some(offset) = self.preview_scroll_request.take() {
+            scroll = scroll.vertical_scroll_offset(offset.max(0.0));
+        }
+
+        let blocks = std::mem::take(&mut self.blocks);
+        let output = scroll.show(ui, |ui| {
+            ui.add_space(4.0);
+            let top = ui.cursor().top();
+            let mut tops = vec![(0, 0.0)];
+            // Running number of ordered list items, by depth
+            let mut numbers: Vec<Option<u64>> = Vec::new();
+            for block in &blocks {
+                tops.push((block.lines.start, ui.cursor().top() - top));
+                self.show_block(ui, block, &mut numbers);
+            }
+            tops.push((self.editor.get_content().lines().count(), ui.cursor().top() - top));
+            tops
+        });
+        self.blocks = blocks;
+        self.block_tops = output.inner;
+        output.state.offset.y
+    }
+
+    fn show_blocks(&mut self, ui: &mut egui::Ui, blocks: &[Block]) {
+        let mut numbers = Vec::new();
+        for block in blocks {
+            self.show_block(ui, block, &mut numbers);
+        }
+    }
+
+    fn show_block(&mut self, ui: &mut egui::Ui, block: &Block, numbers: &mut Vec<Option<u64>>) {
+        let size = self.editor.font_size();
+        if !matches!(block.kind, BlockKind::ListItem { .. }) {
+            numbers.clear();
+        }
+        match &block.kind {
+            BlockKind::Heading { level, inlines } => {
+                ui.add_space(size * 0.4);
+                self.show_inlines(ui, inlines, size * HEADING_SCALE[level - 1], true);
+                if *level <= 2 {
+                    ui.separator();
+                }
+            }
+            BlockKind::Paragraph(inlines) => self.show_inlines(ui, inlines, size, false),
+            BlockKind::ListItem { depth, marker, checked, inlines } => {
+                numbers.resize(depth + 1, None);
+                let label = match (checked, marker) {
+                    (Some(true), _) => "☑".to_string(),
+                    (Some(false), _) => "☐".to_string(),
+                    (None, ListMarker::Bullet) => "•".to_string(),
+                    (None, ListMarker::Number(start)) => {
+                        let number = numbers[*depth].map_or(*start, |previous| previous + 1);
+                        numbers[*depth] = Some(number);
+                        format!("{}.", number)
+                    }
+                };
+                if !matches!(marker, ListMarker::Number(_)) {
+                    numbers[*depth] = None;
+                }
+                ui.horizontal_top(|ui| {
+                    ui.add_space(*depth as f32 * size * 1.5);
+                    ui.label(egui::RichText::new(label).size(size));
+                    ui.vertical(|ui| self.show_inlines(ui, inlines, size, false));
+                });
+                return;
+            }
+            BlockKind::Code { language, code } => {
+                let job = self.code_jobs.entry(code_key(language, code))
+                    .or_insert_with(|| markdown::code_layout_job(code, language, size * 0.9))
+                    .clone();
+                egui::Frame::none()
+                    .fill(markdown::code_background())
+                    .inner_margin(8.0)
+                    .rounding(4.0)
+                    .show(ui, |ui| {
+                        ui.set_min_width(ui.available_width());
+                        egui::ScrollArea::horizontal()
+                            .id_salt(("markdown_code", block.lines.start))
+                            .show(ui, |ui| ui.add(egui::Label::new(job).extend()));
+                    });
+            }
+            BlockKind::Quote(quoted) => {
+                let rect = ui.indent(("markdown_quote", block.lines.start), |ui| self.show_blocks(ui, quoted)).response.rect;
+                let bar = Stroke::new(3.0, ui.visuals().weak_text_color());
+                ui.painter().vline(rect.left() - size * 0.6, rect.y_range(), bar);
+            }
+            BlockKind::Table { header, rows, .. } => {
+                egui::Grid::new(("markdown_table", block.lines.start))
+                    .striped(true)
+                    .spacing([size, size * 0.3])
+                    .show(ui, |ui| {
+                        for cell in header {
+                            ui.vertical(|ui| self.show_inlines(ui, cell, size, true));
+                        }
+                        ui.end_row();
+                        for row in rows {
+                            for cell in row {
+                                ui.vertical(|ui| self.show_inlines(ui, cell, size, false));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            }
+            BlockKind::Rule => {
+                ui.separator();
+            }
+        }
+        ui.add_space(size * 0.5);
+    }
+
+    /// Draw a run of inline content, images on their own rows between the text
+    fn show_inlines(&mut self, ui: &mut egui::Ui, inlines: &[Inline], size: f32, strong: bool) {
+        let mut start = 0;
+        for (i, inline) in inlines.iter().enumerate() {
+            if let Inline::Image { alt, src } = inline {
+                show_text(ui, &inlines[start..i], size, strong);
+                self.show_image(ui, alt, src);
+                start = i + 1;
+            }
+        }
+        show_text(ui, &inlines[start..], size, strong);
+    }
+
+    fn show_image(&mut self, ui: &mut egui::Ui, alt: &str, src: &str) {
+        let base_dir = self.editor.file_path().and_then(Path::parent).map(Path::to_path_buf);
+        let texture = self.images.entry(src.to_string())
+            .or_insert_with(|| {
+                load_image(ui.ctx(), base_dir.as_deref(), src)
+                    .map_err(|e| log::warn!("Can't show image {}: {}", src, e))
+                    .ok()
+            });
+        match texture {
+            Some(texture) => {
+                let mut size = texture.size_vec2();
+                if size.x > ui.available_width() {
+                    size *= ui.available_width() / size.x;
+                }
+                let response = ui.image(egui::load::SizedTexture::new(texture.id(), size));
+                if !alt.is_empty() {
+                    response.on_hover_text(alt);
+                }
+            }
+            None => {
+                ui.weak(format!("🖼 {}", if alt.is_empty() { src } else { alt }));
+            }
+        }
+    }
+
+    /// Scroll whichever side the user isn't scrolling to match the other
+    ///
+    /// The preview leads while the pointer is over it; otherwise the editor
+    /// does, so moving the cursor with the keyboard also moves the preview.
+    fn sync_scroll(&mut self, preview_hovered: bool, preview_offset: f32) {
+        let editor_line = self.editor.first_visible_line();
+        let (last_line, last_offset) = self.last_sync;
+        self.last_sync = (editor_line, preview_offset);
+        if !self.scroll_sync || self.block_tops.len() < 2 {
+            return;
+        }
+
+        if preview_hovered {
+            if (preview_offset - last_offset).abs() > 0.5 {
+                let points: Vec<(f32, f32)> = self.block_tops.iter().map(|(line, y)| (*y, *line as f32)).collect();
+                let line = interpolate(&points, preview_offset);
+                self.editor.scroll_to_line(line);
+                self.last_sync.0 = line;
+            }
+        } else if (editor_line - last_line).abs() > 0.01 {
+            let points: Vec<(f32, f32)> = self.block_tops.iter().map(|(line, y)| (*line as f32, *y)).collect();
+            let offset = interpolate(&points, editor_line);
+            self.preview_scroll_request = Some(offset);
+            self.last_sync.1 = offset;
+        }
+    }
+}
+
+/// Lay out inline text as one wrapped label, with clickable links
+fn show_text(ui: &mut egui::Ui, inlines: &[Inline], size: f32, strong: bool) {
+    if inlines.is_empty() {
+        return;
+    }
+    let visuals = ui.visuals();
+    let mut job = LayoutJob::default();
+    let mut links: Vec<(Range<usize>, String)> = Vec::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text, style) => {
+                let color = if style.link.is_some() {
+                    visuals.hyperlink_color
+                } else if style.bold || strong {
+                    visuals.strong_text_color()
+                } else {
+                    visuals.text_color()
+                };
+                let format = TextFormat {
+                    font_id: if style.code { FontId::monospace(size * 0.9) } else { FontId::proportional(size) },
+                    color,
+                    background: if style.code { visuals.code_bg_color } else { Color32::TRANSPARENT },
+                    italics: style.italic,
+                    underline: if style.link.is_some() { Stroke::new(1.0, color) } else { Stroke::NONE },
+                    strikethrough: if style.strike { Stroke::new(1.0, color) } else { Stroke::NONE },
+                    ..Default::default()
+                };
+                let start = job.text.len();
+                job.append(text, 0.0, format);
+                if let Some(url) = &style.link {
+                    links.push((start..job.text.len(), url.clone()));
+                }
+            }
+            Inline::LineBreak => job.append("\n", 0.0, TextFormat::simple(FontId::proportional(size), visuals.text_color())),
+            Inline::Image { .. } => {}
+        }
+    }
+    job.wrap.max_width = ui.available_width();
+
+    let (pos, galley, response) = egui::Label::new(job)
+        .selectable(false)
+        .sense(egui::Sense::click())
+        .layout_in_ui(ui);
+    ui.painter().galley(pos, galley.clone(), ui.visuals().text_color());
+
+    let Some(pointer) = response.hover_pos().filter(|_| !links.is_empty()) else {
+        return;
+    };
+    let index = galley.cursor_from_pos(pointer - pos).ccursor.index;
+    let byte = galley.text().char_indices().nth(index).map_or(galley.text().len(), |(byte, _)| byte);
+    if let Some((_, url)) = links.iter().find(|(range, _)| range.contains(&byte)) {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        let clicked = response.clicked();
+        response.on_hover_text(url);
+        if clicked && (url.contains("://") || url.starts_with("mailto:")) {
+            ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+        }
+    }
+}
+
+/// Read a local image (`src` relative to the document's directory) into a texture
+fn load_image(ctx: &egui::Context, base_dir: Option<&Path>, src: &str) -> Result<TextureHandle> {
+    let local = src.strip_prefix("file://").unwrap_or(src);
+    if local.contains("://") {
+        return Err(anyhow!("only local images are shown"));
+    }
+    let path = match base_dir {
+        Some(dir) if Path::new(local).is_relative() => dir.join(local),
+        _ => PathBuf::from(local),
+    };
+    let image = image_decode::decode(&path)?
+        .frames
+        .swap_remove(0)
+        .pixels;
+    let size = [image.width() as usize, image.height() as usize];
+    let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Ok(ctx.load_texture(src, image, TextureOptions::LINEAR))
+}
+
+fn code_key(language: &str, code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (language, code).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Piecewise-linear map through `points` (sorted by x), flat past either end
+fn interpolate(points: &[(f32, f32)], x: f32) -> f32 {
+    let next = points.partition_point(|(px, _)| *px <= x);
+    match (next.checked_sub(1).map(|i| points[i]), points.get(next)) {
+        (Some((x0, y0)), Some(&(x1, y1))) if x1 > x0 => y0 + (x - x0) / (x1 - x0) * (y1 - y0),
+        (Some((_, y0)), _) => y0,
+        (None, Some(&(_, y1))) => y1,
+        (None, None) => 0.0,
+    }
+}
+
+/// Headings as an outline, each spanning until the next heading of the same
+/// or a higher level
+fn heading_outline(text: &str, blocks: &[Block]) -> Vec<DocumentSymbol> {
+    let index = LineIndex::new(text);
+    let headings: Vec<(usize, String, usize)> = blocks.iter()
+        .filter_map(|block| match &block.kind {
+            BlockKind::Heading { level, inlines } => Some((*level, markdown::plain_text(inlines), block.lines.start)),
+            _ => None,
+        })
+        .collect();
+
+    let mut outline = Vec::new();
+    // Levels of the headings enclosing the current one
+    let mut parents: Vec<usize> = Vec::new();
+    for (i, (level, name, line)) in headings.iter().enumerate() {
+        while parents.last().is_some_and(|parent| parent >= level) {
+            parents.pop();
+        }
+        let start = index.line_start(*line).unwrap_or(0);
+        let end = headings[i + 1..].iter()
+            .find(|(next_level, _, _)| next_level <= level)
+            .and_then(|(_, _, next_line)| index.line_start(*next_line))
+            .unwrap_or(text.len());
+        let line_end = index.line_start(line + 1).map_or(text.len(), |next| next - 1);
+        outline.push(DocumentSymbol {
+            name: name.clone(),
+            kind: format!("h{}", level),
+            range: start..end,
+            name_range: start..line_end,
+            position: TextPosition { line: *line, column: 0 },
+            depth: parents.len(),
+        });
+        parents.push(*level);
+    }
+    outline
+}
+
+#[async_trait]
+impl Actor for MarkdownEditorActor {
+    fn id(&self) -> Uuid {
+        self.editor.id()
+    }
+
+    fn name(&self) -> String {
+        self.editor.name()
+    }
+
+    async fn handle_message(&mut self, message: ActorMessage) -> anyhow::Result<()> {
+        self.editor.handle_message(message).await
+    }
+
+    fn update(&mut self, ctx: &egui::Context) {
+        self.editor.update(ctx);
+        self.reparse();
+    }
+
+    fn render(&mut self, ui: &mut egui::Ui) {
+        self.reparse();
+        self.show_toolbar(ui);
+        if !self.show_preview {
+            self.editor.render(ui);
+            return;
+        }
+
+        let rect = ui.available_rect_before_wrap();
+        let middle = rect.center().x;
+        let editor_rect = egui::Rect::from_min_max(rect.min, egui::pos2(middle - 2.0, rect.max.y));
+        let preview_rect = egui::Rect::from_min_max(egui::pos2(middle + 6.0, rect.min.y), rect.max);
+
+        let mut editor_ui = ui.new_child(egui::UiBuilder::new().max_rect(editor_rect).layout(*ui.layout()));
+        self.editor.render(&mut editor_ui);
+        let mut preview_ui = ui.new_child(egui::UiBuilder::new().max_rect(preview_rect).layout(*ui.layout()));
+        let preview_offset = self.show_preview(&mut preview_ui);
+        ui.painter().vline(middle + 2.0, rect.y_range(), ui.visuals().widgets.noninteractive.bg_stroke);
+        ui.advance_cursor_after_rect(rect);
+
+        let preview_hovered = ui.rect_contains_pointer(preview_rect);
+        self.sync_scroll(preview_hovered, preview_offset);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn take_palette_commands(&mut self) -> Vec<String> {
+        self.editor.take_palette_commands()
+    }
+
+    fn document_symbols(&self) -> Vec<DocumentSymbol> {
+        self.editor.document_symbols().to_vec()
+    }
+
+    fn completion_words(&self) -> Vec<String> {
+        Actor::completion_words(&self.editor)
+    }
+
+    fn take_workspace_words_request(&mut self) -> bool {
+        self.editor.take_workspace_words_request()
+    }
+
+    fn set_workspace_words(&mut self, words: Vec<String>) {
+        self.editor.set_workspace_words(words);
+    }
+
+    fn take_spawned_actors(&mut self) -> Vec<Box<dyn Actor>> {
+        self.editor.take_spawned_actors()
+    }
+
+    fn is_modified(&self) -> bool {
+        self.editor.is_modified()
+    }
+}
+
+impl ActorAPI for MarkdownEditorActor {
+    fn actor_type(&self) -> String {
+        "MarkdownEditorActor".to_string()
+    }
+
+    fn get_api_methods(&self) -> Vec<ApiMethod> {
+        let mut methods = vec![
+            ApiMethod {
+                name: "export_html".to_string(),
+                description: "Export the document as an HTML page".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "path".to_string(),
+                        param_type: "string".to_string(),
+                        description: "File to write (defaults to the markdown file with an .html extension)".to_string(),
+                        required: false,
+                        default_value: None,
+                    },
+                ],
+                return_type: "string".to_string(),
+                category: "markdown".to_string(),
+            },
+            ApiMethod {
+                name: "get_html".to_string(),
+                description: "Get the document rendered as HTML".to_string(),
+                parameters: vec![
+                    ApiParameter {
+                        name: "full_document".to_string(),
+                        param_type: "boolean".to_string(),
+                        description: "Return a standalone page rather than just the body".to_string(),
+                        required: false,
+                        default_value: Some(serde_json::Value::Bool(true)),
+                    },
+                ],
+                return_type: "string".to_string(),
+                category: "markdown".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_preview".to_string(),
+                description: "Show or hide the rendered preview".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "markdown".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_scroll_sync".to_string(),
+                description: "Turn scroll synchronization between editor and preview on or off".to_string(),
+                parameters: vec![],
+                return_type: "boolean".to_string(),
+                category: "markdown".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_bold".to_string(),
+                description: "Make the selection bold, or plain if it already is".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "markdown".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_italic".to_string(),
+                description: "Make the selection italic, or plain if it already is".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "markdown".to_string(),
+            },
+            ApiMethod {
+                name: "toggle_inline_code".to_string(),
+                description: "Format the selection as inline code, or plain if it already is".to_string(),
+                parameters: vec![],
+                return_type: "void".to_string(),
+                category: "markdown".to_string(),
+            },
+        ];
+        methods.extend(self.editor.get_api_methods());
+        methods
+    }
+
+    fn execute_api_method(&mut self, method: &str, params: ApiParams) -> Result<ApiResult> {
+        match method {
+            "export_html" => {
+                let path = self.export_html(params.get_optional::<PathBuf>("path"))?;
+                Ok(ApiResult::Value(serde_json::Value::String(path.display().to_string())))
+            },
+            "get_html" => {
+                self.reparse();
+                let full_document = params.get_optional("full_document").unwrap_or(true);
+                Ok(ApiResult::Value(serde_json::Value::String(self.html(full_document))))
+            },
+            "toggle_preview" => {
+                self.show_preview = !self.show_preview;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.show_preview)))
+            },
+            "toggle_scroll_sync" => {
+                self.scroll_sync = !self.scroll_sync;
+                Ok(ApiResult::Value(serde_json::Value::Bool(self.scroll_sync)))
+            },
+            "toggle_bold" => {
+                self.editor.wrap_selection("**", "**");
+                Ok(ApiResult::Success)
+            },
+            "toggle_italic" => {
+                self.editor.wrap_selection("*", "*");
+                Ok(ApiResult::Success)
+            },
+            "toggle_inline_code" => {
+                self.editor.wrap_selection("`", "`");
+                Ok(ApiResult::Success)
+            },
+            _ => self.editor.execute_api_method(method, params),
+        }
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        let mut capabilities = self.editor.get_capabilities();
+        capabilities.push("markdown_preview".to_string());
+        capabilities.push("html_export".to_string());
+        capabilities
+    }
+
+    fn get_state(&self) -> HashMap<String, serde_json::Value> {
+        let mut state = self.editor.get_state();
+        state.insert("show_preview".to_string(), serde_json::Value::Bool(self.show_preview));
+        state.insert("scroll_sync".to_string(), serde_json::Value::Bool(self.scroll_sync));
+        state.insert("block_count".to_string(), serde_json::json!(self.blocks.len()));
+        state
+    }
+}
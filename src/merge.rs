@@ -0,0 +1,469 @@
+use crate::diff::{self, DiffTag};
+use crate::editor_buffer::TextChange;
+use egui::{self, Color32, FontId};
+use egui::text::{LayoutJob, TextFormat};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+const OURS_COLOR: Color32 = Color32::from_rgb(70, 130, 200);
+const BASE_COLOR: Color32 = Color32::from_rgb(140, 140, 140);
+const THEIRS_COLOR: Color32 = Color32::from_rgb(80, 170, 90);
+
+/// Which version of a conflicted block to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Ours followed by theirs
+    Both,
+    /// The common ancestor (only with diff3-style markers)
+    Base,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 4] = [Resolution::Ours, Resolution::Theirs, Resolution::Both, Resolution::Base];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Resolution::Ours => "Accept ours",
+            Resolution::Theirs => "Accept theirs",
+            Resolution::Both => "Accept both",
+            Resolution::Base => "Accept base",
+        }
+    }
+}
+
+/// A `<<<<<<<` ... `>>>>>>>` block left in a file by a merge or rebase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    /// Byte range of the whole block, markers included
+    pub range: Range<usize>,
+    /// Byte ranges of each version's text, between the markers
+    pub ours: Range<usize>,
+    pub base: Option<Range<usize>>,
+    pub theirs: Range<usize>,
+    /// Names after the `<<<<<<<` and `>>>>>>>` markers (e.g. `HEAD`, a commit)
+    pub ours_label: String,
+    pub theirs_label: String,
+    /// Zero-based lines of the `<<<<<<<`, `|||||||`, `=======` and `>>>>>>>` markers
+    pub start_line: usize,
+    pub base_line: Option<usize>,
+    pub separator_line: usize,
+    pub end_line: usize,
+}
+
+impl Conflict {
+    /// Lines the block covers, markers included
+    pub fn lines(&self) -> Range<usize> {
+        self.start_line..self.end_line + 1
+    }
+
+    /// Lines of the `<<<<<<<`, `|||||||`, `=======` and `>>>>>>>` markers
+    pub fn marker_lines(&self) -> [Option<usize>; 4] {
+        [Some(self.start_line), self.base_line, Some(self.separator_line), Some(self.end_line)]
+    }
+
+    /// Move what comes after `offset` by `delta` bytes, and the marker
+    /// lines after `line` by `line_delta`, for an edit made there
+    fn shift(&mut self, offset: usize, delta: isize, line: usize, line_delta: isize) {
+        let moved = |pos: usize| if pos > offset { pos.saturating_add_signed(delta) } else { pos };
+        let moved_range = |range: &Range<usize>| moved(range.start)..moved(range.end);
+        let moved_line = |marker: usize| if marker > line { marker.saturating_add_signed(line_delta) } else { marker };
+        self.range = moved_range(&self.range);
+        self.ours = moved_range(&self.ours);
+        self.base = self.base.as_ref().map(moved_range);
+        self.theirs = moved_range(&self.theirs);
+        self.start_line = moved_line(self.start_line);
+        self.base_line = self.base_line.map(moved_line);
+        self.separator_line = moved_line(self.separator_line);
+        self.end_line = moved_line(self.end_line);
+    }
+
+    pub fn ours<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.ours.clone()]
+    }
+
+    pub fn base<'a>(&self, text: &'a str) -> Option<&'a str> {
+        self.base.clone().map(|range| &text[range])
+    }
+
+    pub fn theirs<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.theirs.clone()]
+    }
+
+    /// Text replacing the whole block for a resolution (`None` for `Base`
+    /// when the markers don't include the common ancestor)
+    pub fn resolved(&self, text: &str, resolution: Resolution) -> Option<String> {
+        match resolution {
+            Resolution::Ours => Some(self.ours(text).to_string()),
+            Resolution::Theirs => Some(self.theirs(text).to_string()),
+            Resolution::Base => self.base(text).map(str::to_string),
+            Resolution::Both => {
+                let mut both = self.ours(text).to_string();
+                if !both.is_empty() && !both.ends_with('\n') {
+                    both.push('\n');
+                }
+                both.push_str(self.theirs(text));
+                Some(both)
+            }
+        }
+    }
+}
+
+/// Text after a conflict marker, if `line` is one (seven marker characters,
+/// then a space or the end of the line)
+fn marker(line: &str, marker: char) -> Option<&str> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let rest = line.strip_prefix(&marker.to_string().repeat(7))?;
+    if rest.is_empty() || rest.starts_with(' ') {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+fn is_marker(line: &str) -> bool {
+    ['<', '|', '=', '>'].into_iter().any(|c| marker(line, c).is_some())
+}
+
+/// Whether a line overlapping `range` of `text` is a conflict marker
+fn has_marker_line(text: &str, range: Range<usize>) -> bool {
+    let (start, end) = (range.start.min(text.len()), range.end.min(text.len()));
+    let start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[end..].find('\n').map_or(text.len(), |i| end + i + 1);
+    text[start..end].split_inclusive('\n').any(is_marker)
+}
+
+/// Conflict blocks of a buffer, kept up to date edit by edit
+///
+/// Edits away from the markers only move the blocks; the text is scanned
+/// again when one may have made or broken a block.
+#[derive(Debug, Clone, Default)]
+pub struct Conflicts {
+    blocks: Vec<Conflict>,
+    /// Some `<<<<<<<` starts no complete block, so a marker anywhere after
+    /// it may still complete one
+    dangling: bool,
+}
+
+impl Conflicts {
+    pub fn new(text: &str) -> Self {
+        let blocks = find_conflicts(text);
+        let openers = if text.contains("<<<<<<<") {
+            text.split_inclusive('\n').filter(|line| marker(line, '<').is_some()).count()
+        } else {
+            0
+        };
+        Self { dangling: openers != blocks.len(), blocks }
+    }
+
+    pub fn blocks(&self) -> &[Conflict] {
+        &self.blocks
+    }
+
+    /// Follow `changes` (oldest first), which turned the text the blocks
+    /// were found in into `text`
+    pub fn update(&mut self, text: &str, changes: &[TextChange]) {
+        // Where the text of each change ended up
+        let mut changed: Vec<Range<usize>> = Vec::new();
+        for change in changes {
+            let touched = change.start_point.0..=change.old_end_point.0;
+            let touches_marker = self.blocks.iter()
+                .any(|block| block.marker_lines().iter().flatten().any(|line| touched.contains(line)));
+            if self.dangling || touches_marker {
+                *self = Self::new(text);
+                return;
+            }
+
+            let delta = change.new_end as isize - change.old_end as isize;
+            let line_delta = change.new_end_point.0 as isize - change.old_end_point.0 as isize;
+            for block in &mut self.blocks {
+                block.shift(change.start, delta, change.start_point.0, line_delta);
+            }
+            for range in &mut changed {
+                let start = if range.start <= change.start { range.start } else { range.start.max(change.old_end).saturating_add_signed(delta) };
+                let end = if range.end <= change.start { range.end } else { range.end.max(change.old_end).saturating_add_signed(delta) };
+                *range = start..end;
+            }
+            changed.push(change.start..change.new_end);
+        }
+        if changed.into_iter().any(|range| has_marker_line(text, range)) {
+            *self = Self::new(text);
+        }
+    }
+}
+
+/// Every complete conflict block in `text`, in order
+///
+/// Malformed blocks (e.g. a missing `>>>>>>>`) are skipped.
+pub fn find_conflicts(text: &str) -> Vec<Conflict> {
+    if !text.contains("<<<<<<<") {
+        return Vec::new();
+    }
+
+    struct Open {
+        start: usize,
+        start_line: usize,
+        ours_label: String,
+        ours: Range<usize>,
+        base: Option<(usize, Range<usize>)>,
+        separator: Option<(usize, usize)>,
+    }
+
+    let mut conflicts = Vec::new();
+    let mut open: Option<Open> = None;
+    let mut offset = 0;
+    for (line_no, line) in text.split_inclusive('\n').enumerate() {
+        let line_end = offset + line.len();
+        if let Some(label) = marker(line, '<') {
+            // A new block abandons an unterminated one
+            open = Some(Open {
+                start: offset,
+                start_line: line_no,
+                ours_label: label.to_string(),
+                ours: line_end..line_end,
+                base: None,
+                separator: None,
+            });
+        } else if let Some(block) = &mut open {
+            if block.separator.is_none() && block.base.is_none() && marker(line, '|').is_some() {
+                block.ours.end = offset;
+                block.base = Some((line_no, line_end..line_end));
+            } else if block.separator.is_none() && marker(line, '=').is_some_and(str::is_empty) {
+                match &mut block.base {
+                    Some((_, base)) => base.end = offset,
+                    None => block.ours.end = offset,
+                }
+                block.separator = Some((line_no, line_end));
+            } else if let (Some((separator_line, theirs_start)), Some(label)) = (block.separator, marker(line, '>')) {
+                conflicts.push(Conflict {
+                    range: block.start..line_end,
+                    ours: block.ours.clone(),
+                    base: block.base.as_ref().map(|(_, base)| base.clone()),
+                    theirs: theirs_start..offset,
+                    ours_label: block.ours_label.clone(),
+                    theirs_label: label.to_string(),
+                    start_line: block.start_line,
+                    base_line: block.base.as_ref().map(|(line, _)| *line),
+                    separator_line,
+                    end_line: line_no,
+                });
+                open = None;
+            }
+        }
+        offset = line_end;
+    }
+    conflicts
+}
+
+/// Background tint for each line of a conflict block, markers stronger than contents
+pub fn line_tints(conflict: &Conflict) -> Vec<(usize, Color32)> {
+    let markers = conflict.marker_lines();
+    conflict.lines()
+        .map(|line| {
+            let color = if line < conflict.base_line.unwrap_or(conflict.separator_line) {
+                OURS_COLOR
+            } else if line <= conflict.separator_line {
+                BASE_COLOR
+            } else {
+                THEIRS_COLOR
+            };
+            let alpha = if markers.contains(&Some(line)) { 0.35 } else { 0.12 };
+            (line, color.gamma_multiply(alpha))
+        })
+        .collect()
+}
+
+/// Changed line ranges and word ranges of `side` compared with `base`, per line of `side`
+fn changes_against(base: &str, side: &str) -> Vec<(bool, Vec<Range<usize>>)> {
+    let base_lines: Vec<&str> = base.lines().collect();
+    let side_lines: Vec<&str> = side.lines().collect();
+    let mut changes = vec![(false, Vec::new()); side_lines.len()];
+    for op in diff::diff_slices(&base_lines, &side_lines) {
+        if op.tag == DiffTag::Equal {
+            continue;
+        }
+        for (k, line) in op.new.clone().enumerate() {
+            let words = match op.tag {
+                DiffTag::Replace if k < op.old.len() => diff::word_diff(base_lines[op.old.start + k], side_lines[line]).1,
+                _ => Vec::new(),
+            };
+            changes[line] = (true, words);
+        }
+    }
+    changes
+}
+
+/// Lay out one version, highlighting what changed relative to `base`
+fn pane_job(text: &str, base: Option<&str>, color: Color32, font: &FontId, text_color: Color32) -> LayoutJob {
+    let changes = base.map(|base| changes_against(base, text)).unwrap_or_default();
+    let line_bg = color.gamma_multiply(0.15);
+    let word_bg = color.gamma_multiply(0.45);
+    let mut job = LayoutJob::default();
+    for (i, line) in text.lines().enumerate() {
+        let (changed, words) = changes.get(i).cloned().unwrap_or_default();
+        let format = |background| TextFormat {
+            font_id: font.clone(),
+            color: text_color,
+            background,
+            ..Default::default()
+        };
+        let background = if changed { line_bg } else { Color32::TRANSPARENT };
+        let mut pos = 0;
+        for word in words {
+            job.append(&line[pos..word.start], 0.0, format(background));
+            job.append(&line[word.clone()], 0.0, format(word_bg));
+            pos = word.end;
+        }
+        job.append(&line[pos..], 0.0, format(background));
+        job.append("\n", 0.0, format(Color32::TRANSPARENT));
+    }
+    job
+}
+
+/// Three-pane view of one conflict: ours, the common ancestor and theirs,
+/// each side highlighted against the base (or against the other side when
+/// the markers don't include one)
+///
+/// Returns the resolution the user picked, if any.
+pub fn show_merge_panes(ui: &mut egui::Ui, text: &str, conflict: &Conflict, font_size: f32) -> Option<Resolution> {
+    let font = FontId::monospace(font_size);
+    let text_color = ui.visuals().text_color();
+    let ours = conflict.ours(text);
+    let theirs = conflict.theirs(text);
+    let base = conflict.base(text);
+
+    let mut picked = None;
+    ui.columns(3, |columns| {
+        let panes = [
+            (format!("Ours ({})", conflict.ours_label), Some(ours), base.or(Some(theirs)), OURS_COLOR, Resolution::Ours),
+            ("Base".to_string(), base, None, BASE_COLOR, Resolution::Base),
+            (format!("Theirs ({})", conflict.theirs_label), Some(theirs), base.or(Some(ours)), THEIRS_COLOR, Resolution::Theirs),
+        ];
+        for (ui, (title, pane, against, color, resolution)) in columns.iter_mut().zip(panes) {
+            ui.horizontal(|ui| {
+                ui.colored_label(color, title);
+                if ui.add_enabled(pane.is_some(), egui::Button::new("Accept").small()).clicked() {
+                    picked = Some(resolution);
+                }
+            });
+            egui::ScrollArea::both()
+                .id_salt(("merge_pane", resolution.label()))
+                .max_height(font_size * 12.0)
+                .show(ui, |ui| match pane {
+                    Some(pane) => {
+                        ui.label(pane_job(pane, against, color, &font, text_color));
+                    }
+                    None => {
+                        ui.weak("No base version; set merge.conflictStyle to diff3 to see it");
+                    }
+                });
+        }
+    });
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_buffer::EditorBuffer;
+
+    /// Replace the first `find` in the buffer and check the tracked blocks
+    /// against a fresh scan
+    fn edit(buffer: &mut EditorBuffer, conflicts: &mut Conflicts, find: &str, replace: &str) {
+        let start = buffer.text().find(find).unwrap();
+        buffer.replace_range(start..start + find.len(), replace);
+        let changes = buffer.take_changes();
+        conflicts.update(buffer.text(), &changes);
+        assert_eq!(conflicts.blocks(), find_conflicts(buffer.text()), "{}", buffer.text());
+    }
+
+    #[test]
+    fn conflicts_follow_edits() {
+        let mut buffer = EditorBuffer::new("fn main() {\n<<<<<<< HEAD\n    ours();\n=======\n    theirs();\n>>>>>>> topic\n}\n\
+            <<<<<<< HEAD\na\n||||||| base\nb\n=======\nc\n>>>>>>> topic\n".to_string());
+        let mut conflicts = Conflicts::new(buffer.text());
+        assert_eq!(conflicts.blocks().len(), 2);
+
+        // Away from the markers
+        edit(&mut buffer, &mut conflicts, "fn main", "// header\n\nfn main");
+        edit(&mut buffer, &mut conflicts, "ours();", "ours();\n    more();");
+        edit(&mut buffer, &mut conflicts, "}\n", "}\n\nfn other() {}\n");
+        edit(&mut buffer, &mut conflicts, "\nb\n", "\nbase\n\n");
+        // Breaking and restoring a separator
+        edit(&mut buffer, &mut conflicts, "=======\n    theirs", "-======\n    theirs");
+        assert_eq!(conflicts.blocks().len(), 1);
+        edit(&mut buffer, &mut conflicts, "-======", "=======");
+        // A new block typed after the others
+        let end = buffer.text().len();
+        buffer.replace_range(end..end, "<<<<<<< x\n1\n=======\n2\n>>>>>>> y\n");
+        let changes = buffer.take_changes();
+        conflicts.update(buffer.text(), &changes);
+        assert_eq!(conflicts.blocks(), find_conflicts(buffer.text()));
+        assert_eq!(conflicts.blocks().len(), 3);
+    }
+
+    #[test]
+    fn two_way_markers() {
+        let text = "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\nb\n";
+        let conflicts = find_conflicts(text);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(&text[conflict.range.clone()], "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n");
+        assert_eq!((conflict.ours(text), conflict.theirs(text), conflict.base(text)), ("ours\n", "theirs\n", None));
+        assert_eq!((conflict.ours_label.as_str(), conflict.theirs_label.as_str()), ("HEAD", "topic"));
+        assert_eq!(conflict.marker_lines(), [Some(1), None, Some(3), Some(5)]);
+    }
+
+    #[test]
+    fn diff3_markers() {
+        let text = "<<<<<<< HEAD\nours\n||||||| merged common ancestors\nbase\n=======\ntheirs\n>>>>>>> topic\n";
+        let conflicts = find_conflicts(text);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!((conflict.ours(text), conflict.base(text), conflict.theirs(text)), ("ours\n", Some("base\n"), "theirs\n"));
+        assert_eq!(conflict.marker_lines(), [Some(0), Some(2), Some(4), Some(6)]);
+    }
+
+    #[test]
+    fn unterminated_blocks_are_not_conflicts() {
+        assert!(find_conflicts("<<<<<<< HEAD\nours\n=======\ntheirs\n").is_empty());
+        // A new block abandons the one left open
+        let text = "<<<<<<< HEAD\nlost\n=======\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n";
+        let conflicts = find_conflicts(text);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].ours(text), "ours\n");
+        assert_eq!(conflicts[0].start_line, 3);
+        // Lookalikes are not markers
+        assert!(find_conflicts("<<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>>topic\n").is_empty());
+    }
+
+    #[test]
+    fn crlf_markers() {
+        let text = "<<<<<<< HEAD\r\nours\r\n=======\r\ntheirs\r\n>>>>>>> topic\r\n";
+        let conflicts = find_conflicts(text);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!((conflict.ours(text), conflict.theirs(text)), ("ours\r\n", "theirs\r\n"));
+        assert_eq!((conflict.ours_label.as_str(), conflict.theirs_label.as_str()), ("HEAD", "topic"));
+        assert_eq!(conflict.range, 0..text.len());
+    }
+
+    #[test]
+    fn resolutions() {
+        let text = "<<<<<<< HEAD\nours\n||||||| base\nbase\n=======\ntheirs\n>>>>>>> topic\n";
+        let conflict = &find_conflicts(text)[0];
+        assert_eq!(conflict.resolved(text, Resolution::Ours).as_deref(), Some("ours\n"));
+        assert_eq!(conflict.resolved(text, Resolution::Theirs).as_deref(), Some("theirs\n"));
+        assert_eq!(conflict.resolved(text, Resolution::Base).as_deref(), Some("base\n"));
+        assert_eq!(conflict.resolved(text, Resolution::Both).as_deref(), Some("ours\ntheirs\n"));
+
+        // No base without diff3 markers, and an empty side keeps both clean
+        let text = "<<<<<<< HEAD\n=======\ntheirs\n>>>>>>> topic\n";
+        let conflict = &find_conflicts(text)[0];
+        assert_eq!(conflict.resolved(text, Resolution::Base), None);
+        assert_eq!(conflict.resolved(text, Resolution::Ours).as_deref(), Some(""));
+        assert_eq!(conflict.resolved(text, Resolution::Both).as_deref(), Some("theirs\n"));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::syntax_tree::TokenClass;
+use egui::{self, Color32, ColorImage, TextureHandle, TextureOptions};
+use std::ops::Range;
+
+/// Width of the minimap column beside the editor
+pub const WIDTH: f32 = 90.0;
+/// Characters per minimap row; the rest of longer lines is cut off
+const COLUMNS: usize = 120;
+/// Pixel rows per text line (one drawn, one gap)
+const LINE_PIXELS: usize = 2;
+/// Tallest texture drawn; longer files share pixel rows between lines
+const MAX_HEIGHT: usize = 8192;
+
+/// The part of the file the editor shows, in lines
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub first_line: f32,
+    pub visible_lines: f32,
+}
+
+/// Zoomed-out, syntax-colored overview of a buffer, drawn into a texture
+/// that's only redrawn when the buffer changes
+pub struct Minimap {
+    id: egui::Id,
+    texture: Option<TextureHandle>,
+    /// Buffer version the texture was drawn for
+    version: Option<u64>,
+    line_count: usize,
+}
+
+impl Minimap {
+    pub fn new(id: egui::Id) -> Self {
+        Self {
+            id,
+            texture: None,
+            version: None,
+            line_count: 1,
+        }
+    }
+
+    fn redraw(&mut self, ctx: &egui::Context, text: &str, tokens: &[(Range<usize>, TokenClass)]) {
+        self.line_count = text.split_inclusive('\n').count().max(1);
+        let height = (self.line_count * LINE_PIXELS).min(MAX_HEIGHT);
+        let mut image = ColorImage::new([COLUMNS, height], Color32::TRANSPARENT);
+
+        let mut token = 0;
+        let mut line_start = 0;
+        for (line_no, line) in text.split_inclusive('\n').enumerate() {
+            let y = line_no * height / self.line_count;
+            let mut column = 0;
+            for (i, c) in line.char_indices() {
+                if column >= COLUMNS {
+                    break;
+                }
+                if c == '\t' {
+                    column += 4;
+                    continue;
+                }
+                if !c.is_whitespace() {
+                    let offset = line_start + i;
+                    while tokens.get(token).is_some_and(|(range, _)| range.end <= offset) {
+                        token += 1;
+                    }
+                    let class = tokens.get(token)
+                        .filter(|(range, _)| range.start <= offset)
+                        .map(|(_, class)| *class);
+                    image.pixels[y * COLUMNS + column] = token_color(class);
+                }
+                column += 1;
+            }
+            line_start += line.len();
+        }
+
+        match &mut self.texture {
+            Some(texture) => texture.set(image, TextureOptions::LINEAR),
+            None => self.texture = Some(ctx.load_texture("minimap", image, TextureOptions::LINEAR)),
+        }
+    }
+
+    /// Draw the overview in `rect`, scrolled along with the editor
+    ///
+    /// Returns the line to center the editor on when the map is clicked or dragged.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: egui::Rect,
+        text: &str,
+        tokens: &[(Range<usize>, TokenClass)],
+        version: u64,
+        viewport: Viewport,
+    ) -> Option<f32> {
+        if self.version != Some(version) {
+            self.redraw(ui.ctx(), text, tokens);
+            self.version = Some(version);
+        }
+        let texture = self.texture.as_ref()?;
+
+        // Taller maps scroll so the viewport marker stays inside the column
+        let map_height = texture.size()[1] as f32;
+        let line_height = map_height / self.line_count as f32;
+        let scrollable_lines = (self.line_count as f32 - viewport.visible_lines).max(1.0);
+        let fraction = (viewport.first_line / scrollable_lines).clamp(0.0, 1.0);
+        let scroll = fraction * (map_height - rect.height()).max(0.0);
+
+        let shown = map_height.min(rect.height());
+        let painter = ui.painter_at(rect);
+        painter.image(
+            texture.id(),
+            egui::Rect::from_min_size(rect.min, egui::vec2(rect.width(), shown)),
+            egui::Rect::from_min_max(egui::pos2(0.0, scroll / map_height), egui::pos2(1.0, (scroll + shown) / map_height)),
+            Color32::WHITE,
+        );
+
+        let marker = egui::Rect::from_x_y_ranges(
+            rect.x_range(),
+            rect.top() + viewport.first_line * line_height - scroll
+                ..=rect.top() + (viewport.first_line + viewport.visible_lines) * line_height - scroll,
+        );
+        let response = ui.interact(rect, self.id, egui::Sense::click_and_drag());
+        let alpha = if response.hovered() || response.dragged() { 40 } else { 22 };
+        painter.rect_filled(marker, 0.0, Color32::from_white_alpha(alpha));
+        painter.vline(rect.left(), rect.y_range(), ui.visuals().widgets.noninteractive.bg_stroke);
+
+        if !(response.clicked() || response.dragged()) {
+            return None;
+        }
+        let pointer = response.interact_pointer_pos()?;
+        let line = (pointer.y - rect.top() + scroll) / line_height;
+        Some(line.clamp(0.0, self.line_count as f32))
+    }
+}
+
+fn token_color(class: Option<TokenClass>) -> Color32 {
+    match class {
+        Some(TokenClass::Keyword) => Color32::from_rgb(198, 120, 221),
+        Some(TokenClass::String) => Color32::from_rgb(152, 195, 121),
+        Some(TokenClass::Comment) => Color32::from_rgb(92, 99, 112),
+        Some(TokenClass::Number) => Color32::from_rgb(209, 154, 102),
+        Some(TokenClass::Type) => Color32::from_rgb(229, 192, 123),
+        None => Color32::from_gray(150),
+    }
+}
@@ -0,0 +1,384 @@
+use crate::scene_system::{CameraMotion, SceneTransform, SceneView};
+use crate::view_system::CameraBookmark;
+use egui::{self, Pos2, Rect, Vec2};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// World-space size of a newly placed card
+const CARD_SIZE: Vec2 = Vec2::new(640.0, 420.0);
+
+/// World-space gap between cards placed by default
+const CARD_GAP: f32 = 40.0;
+
+/// Cards placed by default per row
+const CARD_COLUMNS: usize = 3;
+
+const MIN_CARD_SIZE: Vec2 = Vec2::new(160.0, 100.0);
+
+/// Screen height of a card's title bar, whatever the zoom
+const CARD_TITLE_HEIGHT: f32 = 22.0;
+
+/// Below this zoom cards show only their title, as actors can't be read
+const MIN_CONTENT_ZOOM: f32 = 0.3;
+
+const MINIMAP_SIZE: Vec2 = Vec2::new(200.0, 140.0);
+
+/// Screen margin left around a card focused by the camera
+const FOCUS_MARGIN: f32 = 32.0;
+
+const DEFAULT_GRID_SIZE: f32 = 40.0;
+
+/// Where the camera was asked to go, resolved once the viewport is known
+#[derive(Debug, Clone, Copy)]
+enum CameraRequest {
+    FocusCard(Uuid),
+    FitAll,
+}
+
+/// Every scene view as a card in one shared world space, arranged by
+/// dragging title bars and seen through a single pannable, zoomable camera
+pub struct Canvas {
+    pub camera: SceneTransform,
+    /// Each view's card, in world coordinates
+    cards: HashMap<Uuid, Rect>,
+    pub snap_to_grid: bool,
+    pub grid_size: f32,
+    pub show_minimap: bool,
+    pub motion: CameraMotion,
+    pub bookmarks: Vec<CameraBookmark>,
+    camera_request: Option<CameraRequest>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Self {
+            camera: SceneTransform::new(),
+            cards: HashMap::new(),
+            snap_to_grid: true,
+            grid_size: DEFAULT_GRID_SIZE,
+            show_minimap: true,
+            motion: CameraMotion::default(),
+            bookmarks: Vec::new(),
+            camera_request: None,
+        }
+    }
+
+    pub fn card(&self, view_id: Uuid) -> Option<Rect> {
+        self.cards.get(&view_id).copied()
+    }
+
+    pub fn remove_card(&mut self, view_id: Uuid) {
+        self.cards.remove(&view_id);
+    }
+
+    /// Animate the camera to fit the card of `view_id`
+    pub fn focus_card(&mut self, view_id: Uuid) {
+        self.camera_request = Some(CameraRequest::FocusCard(view_id));
+    }
+
+    /// Animate the camera to fit every card
+    pub fn fit_all(&mut self) {
+        self.camera_request = Some(CameraRequest::FitAll);
+    }
+
+    /// `rect` with its corner and size rounded to the grid, if snapping
+    fn snap(&self, rect: Rect) -> Rect {
+        if !self.snap_to_grid {
+            return rect;
+        }
+        let grid = self.grid_size;
+        let min = (rect.min.to_vec2() / grid).round() * grid;
+        let size = ((rect.size() / grid).round() * grid).max(Vec2::splat(grid));
+        Rect::from_min_size(min.to_pos2(), size)
+    }
+
+    /// The first free slot of the default grid placement
+    fn free_slot(&self) -> Rect {
+        (0..)
+            .map(|index| {
+                let column = (index % CARD_COLUMNS) as f32;
+                let row = (index / CARD_COLUMNS) as f32;
+                let min = Pos2::new(column * (CARD_SIZE.x + CARD_GAP), row * (CARD_SIZE.y + CARD_GAP));
+                Rect::from_min_size(min, CARD_SIZE)
+            })
+            .find(|slot| !self.cards.values().any(|card| card.intersects(*slot)))
+            .expect("the grid has unbounded slots")
+    }
+
+    /// Give every view a card and drop the cards of views that are gone
+    fn sync_cards(&mut self, views: &[SceneView]) {
+        self.cards.retain(|id, _| views.iter().any(|view| view.id == *id));
+        for view in views {
+            if !self.cards.contains_key(&view.id) {
+                let slot = self.free_slot();
+                self.cards.insert(view.id, slot);
+            }
+        }
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        self.cards.values().copied().reduce(|a, b| a.union(b))
+    }
+
+    fn to_screen(&self, world: Rect, center: Pos2) -> Rect {
+        Rect::from_min_max(
+            self.camera.scene_to_viewport(world.min, center),
+            self.camera.scene_to_viewport(world.max, center),
+        )
+    }
+
+    /// Draw the cards of `views` and handle the camera and arranging input,
+    /// making a pressed card the active view
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        views: &[SceneView],
+        active_view: &mut Option<Uuid>,
+        titles: &HashMap<Uuid, String>,
+        render_actor: &dyn Fn(&mut egui::Ui, Uuid),
+    ) {
+        self.sync_cards(views);
+
+        let viewport = ui.available_rect_before_wrap();
+        let center = viewport.center();
+        let background = ui.allocate_rect(viewport, egui::Sense::click_and_drag());
+        let now = ui.input(|i| i.time);
+
+        if let Some(request) = self.camera_request.take() {
+            let target = match request {
+                CameraRequest::FocusCard(id) => self.card(id),
+                CameraRequest::FitAll => self.bounds(),
+            };
+            if let Some(target) = target {
+                self.motion.animate_to(self.camera.fitted(target, viewport, FOCUS_MARGIN));
+            }
+        }
+
+        // Zooming works over cards too, scrolling pans only over the background
+        let hover = ui.input(|i| i.pointer.hover_pos()).filter(|&pos| ui.rect_contains_pointer(viewport) && viewport.contains(pos));
+        if let Some(pos) = hover {
+            let zoom_delta = ui.input(|i| i.zoom_delta());
+            if zoom_delta != 1.0 {
+                self.motion.stop();
+                let anchor = pos - center;
+                self.camera.zoom_around(zoom_delta, anchor);
+            } else if !self.cards.values().any(|card| self.to_screen(*card, center).contains(pos)) {
+                let scroll = ui.input(|i| i.smooth_scroll_delta);
+                if scroll != Vec2::ZERO {
+                    self.motion.stop();
+                    self.camera.pan(scroll);
+                }
+            }
+        }
+        if background.dragged() {
+            self.motion.stop();
+            self.camera.pan(background.drag_delta());
+        }
+        if background.drag_stopped() {
+            self.motion.fling(ui.input(|i| i.pointer.velocity()));
+        }
+        if background.double_clicked() {
+            self.fit_all();
+        }
+
+        let dt = ui.input(|i| i.stable_dt);
+        if self.motion.update(&mut self.camera, now, dt) {
+            ui.ctx().request_repaint();
+        }
+        if let Some(bounds) = self.bounds() {
+            self.camera.keep_in_view(bounds, viewport);
+        }
+
+        let painter = ui.painter_at(viewport);
+        painter.rect_filled(viewport, 0.0, egui::Color32::from_gray(24));
+        self.paint_grid(&painter, viewport);
+
+        // Back to front, the active card last so it is drawn on top
+        let mut order: Vec<&SceneView> = views.iter().filter(|view| Some(view.id) != *active_view).collect();
+        order.extend(views.iter().filter(|view| Some(view.id) == *active_view));
+
+        let pressed_at = ui.input(|i| if i.pointer.any_pressed() { i.pointer.interact_pos() } else { None })
+            .filter(|&pos| ui.ctx().layer_id_at(pos).is_none_or(|layer| layer == ui.layer_id()));
+        let mut pressed_card = None;
+
+        for view in order {
+            let Some(world) = self.card(view.id) else { continue };
+            let screen = self.to_screen(world, center);
+            if !screen.intersects(viewport) {
+                continue;
+            }
+            if pressed_at.is_some_and(|pos| screen.contains(pos)) {
+                pressed_card = Some(view.id);
+            }
+
+            let title = titles.get(&view.id).map_or(view.title.as_str(), String::as_str);
+            if let Some(rect) = self.render_card(ui, view, screen, viewport, title, Some(view.id) == *active_view, render_actor) {
+                self.cards.insert(view.id, rect);
+            }
+        }
+
+        if let Some(view_id) = pressed_card {
+            *active_view = Some(view_id);
+        }
+
+        if self.show_minimap {
+            self.render_minimap(ui, viewport, *active_view);
+        }
+    }
+
+    /// Faint grid lines, sparser when zoomed out so they never crowd
+    fn paint_grid(&self, painter: &egui::Painter, viewport: Rect) {
+        let mut step = self.grid_size;
+        while step * self.camera.zoom < 12.0 {
+            step *= 4.0;
+        }
+        let visible = self.camera.visible_scene_rect(viewport);
+        let center = viewport.center();
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(34));
+
+        let mut x = (visible.min.x / step).floor() * step;
+        while x <= visible.max.x {
+            let screen_x = self.camera.scene_to_viewport(Pos2::new(x, 0.0), center).x;
+            painter.vline(screen_x, viewport.y_range(), stroke);
+            x += step;
+        }
+        let mut y = (visible.min.y / step).floor() * step;
+        while y <= visible.max.y {
+            let screen_y = self.camera.scene_to_viewport(Pos2::new(0.0, y), center).y;
+            painter.hline(viewport.x_range(), screen_y, stroke);
+            y += step;
+        }
+    }
+
+    /// Draw one card at `screen`, returning its new world rect if it was
+    /// moved or resized
+    #[allow(clippy::too_many_arguments)]
+    fn render_card(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &SceneView,
+        screen: Rect,
+        viewport: Rect,
+        title: &str,
+        active: bool,
+        render_actor: &dyn Fn(&mut egui::Ui, Uuid),
+    ) -> Option<Rect> {
+        let world = self.card(view.id)?;
+        let zoom = self.camera.zoom;
+        let painter = ui.painter_at(viewport);
+        let accent = egui::Color32::from_rgb(100, 150, 255);
+        let mut moved = None;
+
+        painter.add(egui::epaint::Shadow {
+            offset: Vec2::new(0.0, 4.0),
+            blur: 12.0,
+            spread: 0.0,
+            color: egui::Color32::from_black_alpha(100),
+        }.as_shape(screen, 4.0));
+        painter.rect_filled(screen, 4.0, egui::Color32::from_gray(40));
+
+        // Title bar: drag to arrange, double-click to focus
+        let bar = Rect::from_min_size(screen.min, Vec2::new(screen.width(), CARD_TITLE_HEIGHT.min(screen.height())));
+        painter.rect_filled(bar, egui::Rounding { nw: 4.0, ne: 4.0, sw: 0.0, se: 0.0 }, egui::Color32::from_gray(if active { 65 } else { 52 }));
+        let id = ui.id().with(("canvas_card", view.id));
+        let drag = ui.interact(bar.intersect(viewport), id.with("title"), egui::Sense::click_and_drag());
+        if drag.dragged() {
+            moved = Some(world.translate(drag.drag_delta() / zoom));
+        }
+        if drag.drag_stopped() {
+            moved = Some(self.snap(moved.unwrap_or(world)));
+        }
+        if drag.double_clicked() {
+            self.focus_card(view.id);
+        }
+        if drag.hovered() || drag.dragged() {
+            ui.ctx().set_cursor_icon(if drag.dragged() { egui::CursorIcon::Grabbing } else { egui::CursorIcon::Grab });
+        }
+        painter.with_clip_rect(bar.intersect(viewport)).text(
+            Pos2::new(bar.min.x + 8.0, bar.center().y),
+            egui::Align2::LEFT_CENTER,
+            title,
+            egui::FontId::proportional(13.0),
+            egui::Color32::from_gray(if active { 240 } else { 180 }),
+        );
+
+        // Claims clicks in the body's gaps, so they don't reach the canvas
+        let body = Rect::from_min_max(Pos2::new(screen.min.x, bar.max.y), screen.max).shrink(4.0);
+        ui.interact(screen.intersect(viewport), id.with("body"), egui::Sense::click());
+        if zoom >= MIN_CONTENT_ZOOM && body.is_positive() {
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(body), |ui| {
+                ui.set_clip_rect(body.intersect(viewport));
+                match view.actor_id {
+                    Some(actor_id) => render_actor(ui, actor_id),
+                    None => {
+                        ui.centered_and_justified(|ui| {
+                            ui.label("Empty Card");
+                        });
+                    }
+                }
+            });
+        } else if body.is_positive() {
+            painter.with_clip_rect(body.intersect(viewport)).text(
+                body.center(),
+                egui::Align2::CENTER_CENTER,
+                title,
+                egui::FontId::proportional(14.0),
+                egui::Color32::from_gray(150),
+            );
+        }
+
+        // Resize grip in the bottom-right corner
+        let grip = Rect::from_min_max(screen.max - Vec2::splat(12.0), screen.max);
+        let resize = ui.interact(grip.intersect(viewport), id.with("resize"), egui::Sense::drag());
+        if resize.dragged() {
+            let max = world.max + resize.drag_delta() / zoom;
+            moved = Some(Rect::from_min_max(world.min, max.max(world.min + MIN_CARD_SIZE)));
+        }
+        if resize.drag_stopped() {
+            moved = Some(self.snap(moved.unwrap_or(world)));
+        }
+        if resize.hovered() || resize.dragged() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeNwSe);
+        }
+        for offset in [4.0, 8.0] {
+            painter.line_segment(
+                [Pos2::new(screen.max.x - offset, screen.max.y - 2.0), Pos2::new(screen.max.x - 2.0, screen.max.y - offset)],
+                egui::Stroke::new(1.0, egui::Color32::from_gray(110)),
+            );
+        }
+
+        painter.rect_stroke(screen, 4.0, egui::Stroke::new(if active { 2.0 } else { 1.0 }, if active { accent } else { egui::Color32::from_gray(70) }));
+        moved
+    }
+
+    /// An overview of every card and the visible area in the bottom-right
+    /// corner; clicking or dragging in it centers the camera there
+    fn render_minimap(&mut self, ui: &mut egui::Ui, viewport: Rect, active_view: Option<Uuid>) {
+        let Some(cards) = self.bounds() else { return };
+        let map = Rect::from_min_size(viewport.max - MINIMAP_SIZE - Vec2::splat(12.0), MINIMAP_SIZE);
+        if !viewport.contains_rect(map) {
+            return;
+        }
+
+        let visible = self.camera.visible_scene_rect(viewport);
+        let world = cards.union(visible).expand(cards.size().max_elem() * 0.05);
+        let scale = (map.width() / world.width()).min(map.height() / world.height());
+        let to_map = |pos: Pos2| map.center() + (pos - world.center()) * scale;
+
+        let painter = ui.painter_at(map);
+        painter.rect_filled(map, 4.0, egui::Color32::from_black_alpha(170));
+        for (id, card) in &self.cards {
+            let color = if Some(*id) == active_view { egui::Color32::from_rgb(100, 150, 255) } else { egui::Color32::from_gray(110) };
+            painter.rect_filled(Rect::from_min_max(to_map(card.min), to_map(card.max)), 1.0, color);
+        }
+        painter.rect_stroke(Rect::from_min_max(to_map(visible.min), to_map(visible.max)), 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+        painter.rect_stroke(map, 4.0, egui::Stroke::new(1.0, egui::Color32::from_gray(80)));
+
+        let response = ui.interact(map, ui.id().with("canvas_minimap"), egui::Sense::click_and_drag());
+        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.is_pointer_button_down_on()) {
+            let target = world.center() + (pos - map.center()) / scale;
+            self.motion.stop();
+            self.camera.offset = -target.to_vec2() * self.camera.zoom;
+        }
+    }
+}
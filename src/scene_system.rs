@@ -1,5 +1,6 @@
-use crate::view_system::{ViewSystem, Transformable, TransformInfo};
-use egui::{self, Vec2, Pos2};
+use crate::scene_canvas::Canvas;
+use crate::view_system::{CameraBookmark, ViewSystem, Transformable, TransformBookmarks, TransformInfo};
+use egui::{self, Vec2, Pos2, Rect};
 use uuid::Uuid;
 use std::collections::HashMap;
 use std::any::Any;
@@ -15,8 +16,14 @@ pub struct SceneTransform {
     pub min_zoom: f32,
     /// Maximum zoom level
     pub max_zoom: f32,
+    /// Points of content kept on screen however far it is panned, or
+    /// `None` to let it leave the viewport entirely
+    pub pan_margin: Option<f32>,
 }
 
+/// Points of content panning leaves on screen by default
+pub const DEFAULT_PAN_MARGIN: f32 = 64.0;
+
 impl Default for SceneTransform {
     fn default() -> Self {
         Self {
@@ -24,6 +31,7 @@ impl Default for SceneTransform {
             offset: Vec2::ZERO,
             min_zoom: 0.1,
             max_zoom: 10.0,
+            pan_margin: Some(DEFAULT_PAN_MARGIN),
         }
     }
 }
@@ -38,6 +46,19 @@ impl SceneTransform {
         self.zoom = (self.zoom * zoom_delta).clamp(self.min_zoom, self.max_zoom);
     }
 
+    /// Apply zoom change keeping `anchor` (relative to the point the offset
+    /// is measured from) in place
+    pub fn zoom_around(&mut self, zoom_delta: f32, anchor: Vec2) {
+        let old_zoom = self.zoom;
+        self.zoom_fixed_pan(zoom_delta);
+        self.offset = anchor - (anchor - self.offset) * (self.zoom / old_zoom);
+    }
+
+    /// Pan the view by a delta in viewport coordinates
+    pub fn pan(&mut self, delta: Vec2) {
+        self.offset += delta;
+    }
+
     /// Pan with axis locking - restricts to one axis unless free_pan is true
     pub fn pan_with_axis_lock(&mut self, delta: Vec2, free_pan: bool) {
         if free_pan {
@@ -53,11 +74,176 @@ impl SceneTransform {
         }
     }
 
+    /// Transform a point from scene coordinates to viewport coordinates
+    pub fn scene_to_viewport(&self, scene_pos: Pos2, viewport_center: Pos2) -> Pos2 {
+        let transformed = scene_pos.to_vec2() * self.zoom + self.offset;
+        (transformed + viewport_center.to_vec2()).to_pos2()
+    }
+
+    /// Transform a point from viewport coordinates to scene coordinates
+    pub fn viewport_to_scene(&self, viewport_pos: Pos2, viewport_center: Pos2) -> Pos2 {
+        let relative_to_center = viewport_pos - viewport_center;
+        let scene_coords = (relative_to_center - self.offset) / self.zoom;
+        scene_coords.to_pos2()
+    }
+
+    /// Get the visible scene rectangle in scene coordinates
+    pub fn visible_scene_rect(&self, viewport_rect: Rect) -> Rect {
+        let top_left = self.viewport_to_scene(viewport_rect.min, viewport_rect.center());
+        let bottom_right = self.viewport_to_scene(viewport_rect.max, viewport_rect.center());
+        Rect::from_min_max(top_left, bottom_right)
+    }
+
     /// Reset transform to default
     pub fn reset(&mut self) {
         self.zoom = 1.0;
         self.offset = Vec2::ZERO;
     }
+
+    pub fn bookmark(&self, name: String) -> CameraBookmark {
+        CameraBookmark { name, zoom: self.zoom, offset: [self.offset.x, self.offset.y] }
+    }
+
+    /// A copy of this transform at `bookmark`'s zoom and pan
+    pub fn at_bookmark(&self, bookmark: &CameraBookmark) -> Self {
+        Self {
+            zoom: bookmark.zoom.clamp(self.min_zoom, self.max_zoom),
+            offset: Vec2::new(bookmark.offset[0], bookmark.offset[1]),
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this transform reset to default
+    pub fn reset_target(&self) -> Self {
+        let mut target = self.clone();
+        target.reset();
+        target
+    }
+
+    /// Pull the offset back so that at least `pan_margin` points of
+    /// `content` (in scene coordinates) stay inside `viewport_rect`
+    pub fn keep_in_view(&mut self, content: Rect, viewport_rect: Rect) {
+        let Some(margin) = self.pan_margin else { return };
+        let center = viewport_rect.center();
+        let margin = Vec2::splat(margin).min(content.size() * self.zoom).min(viewport_rect.size());
+        let low = viewport_rect.min + margin - center.to_vec2() - content.max.to_vec2() * self.zoom;
+        let high = viewport_rect.max - margin - center.to_vec2() - content.min.to_vec2() * self.zoom;
+        self.offset = Vec2::new(
+            self.offset.x.clamp(low.x.min(high.x), high.x.max(low.x)),
+            self.offset.y.clamp(low.y.min(high.y), high.y.max(low.y)),
+        );
+    }
+
+    /// A copy of this transform that fits `scene_rect` inside `viewport_rect`,
+    /// leaving `margin` of the viewport free around it
+    pub fn fitted(&self, scene_rect: Rect, viewport_rect: Rect, margin: f32) -> Self {
+        let room = (viewport_rect.size() - Vec2::splat(margin * 2.0)).max(Vec2::splat(1.0));
+        let size = scene_rect.size().max(Vec2::splat(1.0));
+        let zoom = (room.x / size.x).min(room.y / size.y).clamp(self.min_zoom, self.max_zoom);
+        Self {
+            zoom,
+            offset: -scene_rect.center().to_vec2() * zoom,
+            ..self.clone()
+        }
+    }
+}
+
+/// Seconds a camera animation takes
+pub const TRANSFORM_ANIMATION_DURATION: f64 = 0.35;
+
+/// A transform easing from one zoom and offset to another
+#[derive(Debug, Clone)]
+pub struct TransformAnimation {
+    from_zoom: f32,
+    from_offset: Vec2,
+    to_zoom: f32,
+    to_offset: Vec2,
+    start: f64,
+}
+
+impl TransformAnimation {
+    pub fn new(from: &SceneTransform, to: &SceneTransform, now: f64) -> Self {
+        Self {
+            from_zoom: from.zoom,
+            from_offset: from.offset,
+            to_zoom: to.zoom,
+            to_offset: to.offset,
+            start: now,
+        }
+    }
+
+    /// Move `transform` to where the animation is at `now`, returning
+    /// whether it has finished
+    pub fn apply(&self, transform: &mut SceneTransform, now: f64) -> bool {
+        let t = ((now - self.start) / TRANSFORM_ANIMATION_DURATION).clamp(0.0, 1.0) as f32;
+        let eased = 1.0 - (1.0 - t).powi(3);
+        // Zoom moves geometrically so zooming in and out feel equally fast
+        transform.zoom = self.from_zoom * (self.to_zoom / self.from_zoom).powf(eased);
+        transform.offset = self.from_offset + (self.to_offset - self.from_offset) * eased;
+        t >= 1.0
+    }
+}
+
+/// How quickly a flung transform slows down, per second
+const KINETIC_FRICTION: f32 = 5.0;
+
+/// Fling speeds below this many points per second stop the transform
+const MIN_FLING_SPEED: f32 = 20.0;
+
+/// Where a transform goes on its own: easing to a target, or coasting and
+/// slowing down after a drag is released
+#[derive(Debug, Default)]
+pub struct CameraMotion {
+    /// Waiting for the frame time to start animating towards
+    target: Option<SceneTransform>,
+    animation: Option<TransformAnimation>,
+    /// Points per second
+    velocity: Vec2,
+}
+
+impl CameraMotion {
+    pub fn animate_to(&mut self, target: SceneTransform) {
+        self.stop();
+        self.target = Some(target);
+    }
+
+    /// Keep panning at `velocity` after a drag, unless it is too slow to notice
+    pub fn fling(&mut self, velocity: Vec2) {
+        self.stop();
+        if velocity.length() >= MIN_FLING_SPEED {
+            self.velocity = velocity;
+        }
+    }
+
+    /// Stop moving, as the user took over the transform
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.animation = None;
+        self.velocity = Vec2::ZERO;
+    }
+
+    /// Move `transform` on to `now`, `dt` seconds after the last frame,
+    /// returning whether it is still moving
+    pub fn update(&mut self, transform: &mut SceneTransform, now: f64, dt: f32) -> bool {
+        if let Some(target) = self.target.take() {
+            self.animation = Some(TransformAnimation::new(transform, &target, now));
+        }
+        if let Some(animation) = &self.animation {
+            if animation.apply(transform, now) {
+                self.animation = None;
+            }
+            return true;
+        }
+        if self.velocity == Vec2::ZERO {
+            return false;
+        }
+        transform.pan(self.velocity * dt);
+        self.velocity *= (-KINETIC_FRICTION * dt).exp();
+        if self.velocity.length() < MIN_FLING_SPEED {
+            self.velocity = Vec2::ZERO;
+        }
+        true
+    }
 }
 
 /// Scene-based view
@@ -67,6 +253,7 @@ pub struct SceneView {
     pub title: String,
     pub transform: SceneTransform,
     pub actor_id: Option<Uuid>,
+    pub bookmarks: Vec<CameraBookmark>,
 }
 
 impl SceneView {
@@ -76,6 +263,7 @@ impl SceneView {
             title,
             transform: SceneTransform::new(),
             actor_id: None,
+            bookmarks: Vec::new(),
         }
     }
 }
@@ -85,6 +273,7 @@ impl SceneView {
 struct ViewInteractionState {
     is_dragging: bool,
     last_mouse_pos: Option<Pos2>,
+    motion: CameraMotion,
 }
 
 /// Scene-based view system implementation
@@ -92,6 +281,12 @@ pub struct SceneSystem {
     views: Vec<SceneView>,
     active_view: Option<Uuid>,
     interaction_state: HashMap<Uuid, ViewInteractionState>,
+    /// Show every view as a card on the canvas instead of the active one alone
+    canvas_mode: bool,
+    canvas: Canvas,
+    card_titles: HashMap<Uuid, String>,
+    /// Where the views were drawn on the last frame
+    viewport: Rect,
 }
 
 impl SceneSystem {
@@ -106,9 +301,68 @@ impl SceneSystem {
             views: vec![root_view],
             active_view: Some(root_id),
             interaction_state,
+            canvas_mode: false,
+            canvas: Canvas::new(),
+            card_titles: HashMap::new(),
+            viewport: Rect::NOTHING,
+        }
+    }
+
+    pub fn canvas_mode(&self) -> bool {
+        self.canvas_mode
+    }
+
+    /// Switch between the canvas and showing the active view alone,
+    /// fitting every card when the canvas is entered
+    pub fn toggle_canvas_mode(&mut self) {
+        self.canvas_mode = !self.canvas_mode;
+        if self.canvas_mode {
+            self.canvas.fit_all();
+        }
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    pub fn canvas_mut(&mut self) -> &mut Canvas {
+        &mut self.canvas
+    }
+
+    /// Animate the canvas camera to fit the active view's card
+    pub fn focus_active_card(&mut self) {
+        if let Some(view_id) = self.active_view {
+            self.canvas.focus_card(view_id);
         }
     }
 
+    /// The transform zoom and pan commands apply to, and how it is moving:
+    /// the canvas camera in canvas mode, otherwise the active view's
+    fn active_camera(&mut self) -> Option<(&mut SceneTransform, &mut CameraMotion)> {
+        if self.canvas_mode {
+            let canvas = &mut self.canvas;
+            return Some((&mut canvas.camera, &mut canvas.motion));
+        }
+        let view_id = self.active_view?;
+        let view = self.views.iter_mut().find(|v| v.id == view_id)?;
+        let interaction = self.interaction_state.entry(view_id).or_default();
+        Some((&mut view.transform, &mut interaction.motion))
+    }
+
+    /// The bookmarks of the camera `active_camera` returns
+    fn active_bookmarks_mut(&mut self) -> Option<&mut Vec<CameraBookmark>> {
+        if self.canvas_mode {
+            return Some(&mut self.canvas.bookmarks);
+        }
+        let view_id = self.active_view?;
+        self.get_view_mut(view_id).map(|view| &mut view.bookmarks)
+    }
+
+    /// Titles for the canvas cards, by actor
+    pub fn set_card_titles(&mut self, titles: HashMap<Uuid, String>) {
+        self.card_titles = titles;
+    }
+
     pub fn get_view(&self, id: Uuid) -> Option<&SceneView> {
         self.views.iter().find(|v| v.id == id)
     }
@@ -126,24 +380,25 @@ impl SceneSystem {
     ) {
         let available_rect = ui.available_rect_before_wrap();
         let response = ui.allocate_rect(available_rect, egui::Sense::click_and_drag());
+        self.viewport = available_rect;
 
-        // Check if Cmd key is held for free panning (this locks zoom)
+        // Check if Cmd key is held for free panning
         let cmd_held = ui.ctx().input(|i| i.modifiers.command);
 
-        // Handle pinch gestures for zoom (disabled during free pan mode)
-        let zoom_factor = if response.hovered() && !cmd_held {
+        // Handle pinch and Ctrl+wheel zoom, anchored at the pointer (locked
+        // while free panning)
+        let zoom_factor = if response.hovered() && !(cmd_held && response.dragged()) {
             let zoom_delta = ui.ctx().input(|i| i.zoom_delta());
             if zoom_delta != 1.0 {
-                Some(zoom_delta)
+                response.hover_pos().map(|pos| (zoom_delta, pos - available_rect.center()))
             } else { None }
         } else { None };
 
         // Handle panning input
         let mut pan_delta = None;
 
-        // Scroll wheel panning (when not zooming and cmd not held)
-        // Note: When Cmd is held, we rely on drag panning for free pan
-        if response.hovered() && zoom_factor.is_none() && !cmd_held {
+        // Scroll wheel panning (when not zooming)
+        if response.hovered() && zoom_factor.is_none() {
             let scroll_delta = ui.ctx().input(|i| i.smooth_scroll_delta);
             if scroll_delta != egui::Vec2::ZERO {
                 pan_delta = Some((-scroll_delta, false)); // Always axis-locked for scroll
@@ -151,37 +406,55 @@ impl SceneSystem {
         }
 
         // Click and drag panning
+        let interaction = self.interaction_state.entry(view_id).or_default();
         if response.dragged() {
             if let Some(mouse_pos) = response.interact_pointer_pos() {
-                let last_pos = self.interaction_state.get(&view_id)
-                    .and_then(|s| s.last_mouse_pos);
-
-                if let Some(last_pos) = last_pos {
+                if let Some(last_pos) = interaction.last_mouse_pos {
                     pan_delta = Some((mouse_pos - last_pos, cmd_held));
                 }
 
                 // Update interaction state
-                let interaction = self.interaction_state.entry(view_id).or_default();
                 interaction.last_mouse_pos = Some(mouse_pos);
                 interaction.is_dragging = true;
             }
         } else {
-            // Clear interaction state when not dragging
-            if let Some(interaction) = self.interaction_state.get_mut(&view_id) {
-                interaction.is_dragging = false;
-                interaction.last_mouse_pos = None;
+            // Keep coasting in the direction of the released drag
+            if interaction.is_dragging {
+                let velocity = ui.ctx().input(|i| i.pointer.velocity());
+                let velocity = match (cmd_held, velocity.x.abs() > velocity.y.abs()) {
+                    (true, _) => velocity,
+                    (false, true) => Vec2::new(velocity.x, 0.0),
+                    (false, false) => Vec2::new(0.0, velocity.y),
+                };
+                interaction.motion.fling(velocity);
             }
+
+            // Clear interaction state when not dragging
+            interaction.is_dragging = false;
+            interaction.last_mouse_pos = None;
+        }
+        if zoom_factor.is_some() || pan_delta.is_some() {
+            interaction.motion.stop();
         }
 
         // Apply transformations
-        if let Some(view) = self.get_view_mut(view_id) {
-            if let Some(zoom_delta) = zoom_factor {
-                view.transform.zoom_fixed_pan(zoom_delta);
+        let (now, dt) = ui.ctx().input(|i| (i.time, i.stable_dt));
+        if let Some(view) = self.views.iter_mut().find(|v| v.id == view_id) {
+            if let Some((zoom_delta, anchor)) = zoom_factor {
+                view.transform.zoom_around(zoom_delta, anchor);
             }
 
             if let Some((delta, free_pan)) = pan_delta {
                 view.transform.pan_with_axis_lock(delta, free_pan);
             }
+
+            if interaction.motion.update(&mut view.transform, now, dt) {
+                ui.ctx().request_repaint();
+            }
+
+            // The content fills the viewport at zoom 1
+            let content = Rect::from_center_size(Pos2::ZERO, available_rect.size());
+            view.transform.keep_in_view(content, available_rect);
         }
 
         // Get transform for rendering
@@ -246,6 +519,14 @@ impl ViewSystem for SceneSystem {
     }
 
     fn render_views(&mut self, ui: &mut egui::Ui, render_actor: &dyn Fn(&mut egui::Ui, Uuid)) {
+        if self.canvas_mode {
+            let titles = self.views.iter()
+                .filter_map(|view| Some((view.id, self.card_titles.get(&view.actor_id?)?.clone())))
+                .collect();
+            self.canvas.render(ui, &self.views, &mut self.active_view, &titles, render_actor);
+            return;
+        }
+
         if let Some(active_view_id) = self.active_view {
             let actor_id = self.get_view_actor(active_view_id);
 
@@ -285,6 +566,7 @@ impl ViewSystem for SceneSystem {
         if let Some(pos) = self.views.iter().position(|v| v.id == view_id) {
             self.views.remove(pos);
             self.interaction_state.remove(&view_id);
+            self.canvas.remove_card(view_id);
 
             // If we removed the active view, set a new active view
             if self.active_view == Some(view_id) {
@@ -301,6 +583,14 @@ impl ViewSystem for SceneSystem {
         self.views.iter().map(|v| v.id).collect()
     }
 
+    fn as_bookmarks(&self) -> Option<&dyn TransformBookmarks> {
+        Some(self)
+    }
+
+    fn as_bookmarks_mut(&mut self) -> Option<&mut dyn TransformBookmarks> {
+        Some(self)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -311,31 +601,38 @@ impl ViewSystem for SceneSystem {
 }
 
 impl Transformable for SceneSystem {
-    fn zoom(&mut self, factor: f32, _center: Option<Pos2>) {
-        if let Some(active_view_id) = self.active_view {
-            if let Some(view) = self.get_view_mut(active_view_id) {
-                view.transform.zoom_fixed_pan(factor);
-            }
+    fn zoom(&mut self, factor: f32, center: Option<Pos2>) {
+        let viewport = self.viewport;
+        let anchor = center.filter(|_| viewport.is_positive()).map_or(Vec2::ZERO, |center| center - viewport.center());
+        if let Some((transform, motion)) = self.active_camera() {
+            motion.stop();
+            transform.zoom_around(factor, anchor);
         }
     }
 
     fn pan(&mut self, delta: Vec2) {
-        if let Some(active_view_id) = self.active_view {
-            if let Some(view) = self.get_view_mut(active_view_id) {
-                view.transform.pan_with_axis_lock(delta, false);
-            }
+        let canvas_mode = self.canvas_mode;
+        if let Some((transform, motion)) = self.active_camera() {
+            motion.stop();
+            transform.pan_with_axis_lock(delta, canvas_mode);
         }
     }
 
     fn reset_transform(&mut self) {
-        if let Some(active_view_id) = self.active_view {
-            if let Some(view) = self.get_view_mut(active_view_id) {
-                view.transform.reset();
-            }
+        if let Some((transform, motion)) = self.active_camera() {
+            motion.animate_to(transform.reset_target());
         }
     }
 
     fn get_transform_info(&self) -> TransformInfo {
+        if self.canvas_mode {
+            return TransformInfo {
+                zoom: self.canvas.camera.zoom,
+                pan_offset: self.canvas.camera.offset,
+                can_zoom: true,
+                can_pan: true,
+            };
+        }
         if let Some(active_view_id) = self.active_view {
             if let Some(view) = self.get_view(active_view_id) {
                 return TransformInfo {
@@ -354,4 +651,171 @@ impl Transformable for SceneSystem {
             can_pan: false,
         }
     }
-}
\ No newline at end of file
+}
+
+impl TransformBookmarks for SceneSystem {
+    fn bookmarks(&self) -> &[CameraBookmark] {
+        if self.canvas_mode {
+            return &self.canvas.bookmarks;
+        }
+        self.active_view
+            .and_then(|view_id| self.get_view(view_id))
+            .map_or(&[], |view| &view.bookmarks)
+    }
+
+    fn save_bookmark(&mut self, name: String) -> Option<usize> {
+        let bookmark = self.active_camera()?.0.bookmark(name);
+        let bookmarks = self.active_bookmarks_mut()?;
+        bookmarks.push(bookmark);
+        Some(bookmarks.len() - 1)
+    }
+
+    fn update_bookmark(&mut self, index: usize) -> bool {
+        let Some(name) = self.bookmarks().get(index).map(|bookmark| bookmark.name.clone()) else {
+            return false;
+        };
+        let Some(bookmark) = self.active_camera().map(|(transform, _)| transform.bookmark(name)) else {
+            return false;
+        };
+        self.active_bookmarks_mut().is_some_and(|bookmarks| {
+            bookmarks[index] = bookmark;
+            true
+        })
+    }
+
+    fn rename_bookmark(&mut self, index: usize, name: String) -> bool {
+        match self.active_bookmarks_mut().and_then(|bookmarks| bookmarks.get_mut(index)) {
+            Some(bookmark) => {
+                bookmark.name = name;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove_bookmark(&mut self, index: usize) -> bool {
+        match self.active_bookmarks_mut() {
+            Some(bookmarks) if index < bookmarks.len() => {
+                bookmarks.remove(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn jump_to_bookmark(&mut self, index: usize) -> bool {
+        let Some(bookmark) = self.bookmarks().get(index).cloned() else {
+            return false;
+        };
+        match self.active_camera() {
+            Some((transform, motion)) => {
+                motion.animate_to(transform.at_bookmark(&bookmark));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < 1e-3
+    }
+
+    #[test]
+    fn zooming_keeps_the_anchor_in_place() {
+        let center = Pos2::new(400.0, 300.0);
+        let mut transform = SceneTransform { offset: Vec2::new(30.0, -20.0), ..Default::default() };
+        for (delta, anchor) in [(2.0, Vec2::new(120.0, -80.0)), (0.25, Vec2::new(-300.0, 10.0)), (1000.0, Vec2::ZERO), (1e-4, Vec2::new(5.0, 5.0))] {
+            let under_anchor = transform.viewport_to_scene(center + anchor, center);
+            transform.zoom_around(delta, anchor);
+            assert!(close(transform.scene_to_viewport(under_anchor, center).to_vec2(), (center + anchor).to_vec2()), "{:?}", transform);
+        }
+        // Zoom stays within its limits
+        assert_eq!(transform.zoom, transform.min_zoom);
+        transform.zoom_around(1e6, Vec2::ZERO);
+        assert_eq!(transform.zoom, transform.max_zoom);
+    }
+
+    #[test]
+    fn a_fling_slows_down_and_stops() {
+        let mut transform = SceneTransform::new();
+        let mut motion = CameraMotion::default();
+        let dt = 1.0 / 60.0;
+        motion.fling(Vec2::new(1000.0, 0.0));
+
+        let mut steps = Vec::new();
+        let mut now = 0.0;
+        loop {
+            let before = transform.offset.x;
+            now += dt as f64;
+            if !motion.update(&mut transform, now, dt) {
+                break;
+            }
+            steps.push(transform.offset.x - before);
+            assert!(steps.len() < 600, "never stopped");
+        }
+        assert!(steps.windows(2).all(|pair| pair[1] < pair[0] && pair[1] > 0.0), "{:?}", steps);
+        // Coasting covers about the distance friction allows, one frame late
+        let coasted = transform.offset.x;
+        assert!(coasted > 150.0 && coasted < 1000.0 / KINETIC_FRICTION + 1000.0 * dt, "{}", coasted);
+        assert_eq!(transform.offset.y, 0.0);
+        assert!(!motion.update(&mut transform, 100.0, dt));
+
+        // Too slow to notice, or taken over by the user
+        motion.fling(Vec2::new(MIN_FLING_SPEED / 2.0, 0.0));
+        assert!(!motion.update(&mut transform, 100.0, dt));
+        motion.fling(Vec2::new(0.0, 500.0));
+        motion.stop();
+        assert!(!motion.update(&mut transform, 100.0, dt));
+    }
+
+    #[test]
+    fn an_animation_reaches_its_target() {
+        let mut transform = SceneTransform::new();
+        let target = SceneTransform { zoom: 4.0, offset: Vec2::new(-200.0, 50.0), ..Default::default() };
+        let mut motion = CameraMotion::default();
+        motion.animate_to(target.clone());
+        assert!(motion.update(&mut transform, 10.0, 0.0));
+        assert_eq!(transform.zoom, 1.0);
+        assert!(motion.update(&mut transform, 10.0 + TRANSFORM_ANIMATION_DURATION / 2.0, 0.1));
+        assert!(transform.zoom > 1.0 && transform.zoom < 4.0);
+        assert!(motion.update(&mut transform, 10.0 + TRANSFORM_ANIMATION_DURATION, 0.1));
+        assert_eq!((transform.zoom, transform.offset), (target.zoom, target.offset));
+        assert!(!motion.update(&mut transform, 11.0, 0.1));
+    }
+
+    #[test]
+    fn keep_in_view_leaves_a_margin_of_content_on_screen() {
+        let viewport = Rect::from_min_size(Pos2::ZERO, Vec2::new(800.0, 600.0));
+        let content = Rect::from_min_size(Pos2::ZERO, Vec2::new(1000.0, 1000.0));
+        let visible = |transform: &SceneTransform| transform.visible_scene_rect(viewport).intersect(content).size();
+
+        // Panned within the content, nothing changes
+        let mut transform = SceneTransform { offset: Vec2::new(-300.0, -200.0), ..Default::default() };
+        transform.keep_in_view(content, viewport);
+        assert_eq!(transform.offset, Vec2::new(-300.0, -200.0));
+
+        // Flung far off any side, a margin of the content comes back
+        for offset in [Vec2::new(1e5, 0.0), Vec2::new(-1e5, 0.0), Vec2::new(0.0, 1e5), Vec2::new(-1e5, -1e5)] {
+            let mut transform = SceneTransform { offset, ..Default::default() };
+            transform.keep_in_view(content, viewport);
+            let size = visible(&transform);
+            assert!(size.min_elem() >= DEFAULT_PAN_MARGIN - 1e-3, "{:?} left {:?}", offset, size);
+            assert!(close(size.min(Vec2::splat(DEFAULT_PAN_MARGIN)), Vec2::splat(DEFAULT_PAN_MARGIN)), "{:?}", size);
+        }
+
+        // The margin is in screen points, so zoomed out it covers more content
+        let mut transform = SceneTransform { zoom: 0.5, offset: Vec2::new(1e5, 0.0), ..Default::default() };
+        transform.keep_in_view(content, viewport);
+        assert!((visible(&transform).x - DEFAULT_PAN_MARGIN / 0.5).abs() < 1e-3);
+
+        // Without a margin the content may leave entirely
+        let mut transform = SceneTransform { offset: Vec2::new(1e5, 0.0), pan_margin: None, ..Default::default() };
+        transform.keep_in_view(content, viewport);
+        assert_eq!(transform.offset.x, 1e5);
+    }
+}
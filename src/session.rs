@@ -0,0 +1,155 @@
+use crate::layout::Layout;
+use crate::view_system::CameraBookmark;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the open session is written out while the IDE runs
+pub const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The session used when none is named and the working directory is unknown
+pub const DEFAULT_SESSION: &str = "default";
+
+/// A snapshot of the IDE to restore on the next launch: the layout (view
+/// system, split tree and the actors in each pane) plus what the layout
+/// format doesn't cover
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    /// Seconds since the Unix epoch
+    pub saved_at: u64,
+    pub layout: Layout,
+    /// One per pane, in layout order
+    #[serde(default)]
+    pub panes: Vec<PaneSession>,
+    /// Bookmarks of the scene canvas camera
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub canvas_bookmarks: Vec<CameraBookmark>,
+}
+
+/// Per-pane state of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaneSession {
+    /// Zoom and pan of a scene view
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<SavedTransform>,
+    /// Camera bookmarks of a scene view
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<CameraBookmark>,
+    /// `get_state` of the actor in each tab
+    #[serde(default)]
+    pub states: Vec<HashMap<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedTransform {
+    pub zoom: f32,
+    pub offset: [f32; 2],
+}
+
+impl Session {
+    pub fn new(name: &str, layout: Layout, panes: Vec<PaneSession>) -> Self {
+        let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        Self { name: name.to_string(), saved_at, layout, panes, canvas_bookmarks: Vec::new() }
+    }
+
+    /// The session a project opens when none is named, so each directory
+    /// the IDE is started in restores its own files: the directory's name
+    /// and a hash of its full path, e.g. `zellij-ide-1a2b3c4d`
+    pub fn default_name(dir: &Path) -> String {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let base: String = dir.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+            .trim_start_matches('.')
+            .chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+            .collect();
+        // FNV-1a, which unlike the std hasher stays the same across releases
+        let hash = dir.to_string_lossy().bytes()
+            .fold(0x811c_9dc5_u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+        let base = if base.is_empty() { DEFAULT_SESSION } else { &base };
+        format!("{}-{:08x}", base, hash)
+    }
+
+    /// Where sessions are kept
+    pub fn dir() -> Result<PathBuf> {
+        let mut path = dirs::config_dir()
+            .ok_or_else(|| anyhow!("Could not find config directory"))?;
+        path.push("zellij-ide");
+        path.push("sessions");
+        Ok(path)
+    }
+
+    fn path(name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '));
+        if !valid {
+            return Err(anyhow!("Invalid session name '{}'", name));
+        }
+        Ok(Self::dir()?.join(format!("{}.json", name)))
+    }
+
+    /// The session saved as `name`, if there is one
+    pub fn load(name: &str) -> Result<Option<Self>> {
+        let path = Self::path(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let session = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid session {}: {}", path.display(), e))?;
+        Ok(Some(session))
+    }
+
+    /// Write the session, replacing the file only once it's fully written
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path(&self.name)?;
+        fs::create_dir_all(Self::dir()?)?;
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to write {}: {}", temp.display(), e))?;
+        fs::rename(&temp, &path)
+            .map_err(|e| anyhow!("Failed to replace {}: {}", path.display(), e))
+    }
+
+    /// Names of the saved sessions, alphabetically
+    pub fn list() -> Vec<String> {
+        let Ok(entries) = Self::dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_names_are_valid_and_per_directory() {
+        let a = Session::default_name(Path::new("/work/my project"));
+        let b = Session::default_name(Path::new("/other/my project"));
+        assert!(a.starts_with("my_project-"), "{}", a);
+        assert_ne!(a, b);
+        assert_eq!(a, Session::default_name(Path::new("/work/my project")));
+        for name in [a, b, Session::default_name(Path::new("/")), Session::default_name(Path::new("/tmp/.hidden"))] {
+            assert!(Session::path(&name).is_ok(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use crate::editor_buffer::TextChange;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// A snippet from the library
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    /// What the user types to get it offered
+    pub prefix: String,
+    /// TextMate/VSCode snippet syntax (`$1`, `${2:default}`, `${3|a,b|}`, `$0`, `$TM_FILENAME`)
+    pub body: String,
+    pub description: String,
+}
+
+impl Snippet {
+    pub fn new(prefix: &str, body: &str, description: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            body: body.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Snippets keyed by editor language (`"*"` applies everywhere)
+#[derive(Debug, Clone, Default)]
+pub struct SnippetLibrary {
+    snippets: HashMap<String, Vec<Snippet>>,
+}
+
+impl SnippetLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in snippets plus the user's, from `<config>/zellij-ide/snippets`
+    pub fn load() -> Self {
+        let mut library = Self::builtin();
+        if let Some(mut dir) = dirs::config_dir() {
+            dir.push("zellij-ide");
+            dir.push("snippets");
+            if dir.is_dir() {
+                if let Err(e) = library.load_dir(&dir) {
+                    log::warn!("Failed to load snippets from {}: {}", dir.display(), e);
+                }
+            }
+        }
+        library
+    }
+
+    pub fn builtin() -> Self {
+        let mut library = Self::new();
+        library.add("rust", Snippet::new("fn", "fn ${1:name}(${2}) ${3:-> ${4:()} }{\n\t$0\n}", "Function"));
+        library.add("rust", Snippet::new("test", "#[test]\nfn ${1:name}() {\n\t$0\n}", "Test function"));
+        library.add("rust", Snippet::new("struct", "struct ${1:Name} {\n\t${2:field}: ${3:Type},\n}", "Struct"));
+        library.add("rust", Snippet::new("impl", "impl ${1:Type} {\n\t$0\n}", "Impl block"));
+        library.add("rust", Snippet::new("match", "match ${1:value} {\n\t${2:_} => ${3:todo!()},\n}", "Match expression"));
+        library.add("rust", Snippet::new("for", "for ${1:item} in ${2:iter} {\n\t$0\n}", "For loop"));
+        library.add("rust", Snippet::new("iflet", "if let ${1:Some(value)} = ${2:option} {\n\t$0\n}", "If let"));
+        library.add("rust", Snippet::new("derive", "#[derive(${1|Debug,Clone,Default|})]", "Derive attribute"));
+        library.add("python", Snippet::new("def", "def ${1:name}(${2}):\n\t${0:pass}", "Function"));
+        library.add("python", Snippet::new("class", "class ${1:Name}:\n\tdef __init__(self${2}):\n\t\t${0:pass}", "Class"));
+        library.add("python", Snippet::new("for", "for ${1:item} in ${2:items}:\n\t${0:pass}", "For loop"));
+        library.add("python", Snippet::new("ifmain", "if __name__ == \"__main__\":\n\t${0:main()}", "Main guard"));
+        library.add("javascript", Snippet::new("function", "function ${1:name}(${2}) {\n\t$0\n}", "Function"));
+        library.add("javascript", Snippet::new("arrow", "(${1}) => {\n\t$0\n}", "Arrow function"));
+        library.add("javascript", Snippet::new("for", "for (let ${1:i} = 0; $1 < ${2:length}; $1++) {\n\t$0\n}", "For loop"));
+        library.add("javascript", Snippet::new("log", "console.log($1);", "Log to the console"));
+        library
+    }
+
+    pub fn add(&mut self, language: &str, snippet: Snippet) {
+        self.snippets.entry(language.to_string()).or_default().push(snippet);
+    }
+
+    /// Load VSCode-style snippet files: `<language>.json`, or `global.json` for all languages
+    pub fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let language = if stem == "global" { "*" } else { stem };
+            let file: HashMap<String, SnippetDefinition> = serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            for (name, definition) in file {
+                let body = definition.body.into_vec().join("\n");
+                let description = definition.description.unwrap_or(name);
+                for prefix in definition.prefix.into_vec() {
+                    self.add(language, Snippet::new(&prefix, &body, &description));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Snippets offered in an editor for `language`
+    pub fn for_language<'a>(&'a self, language: &str) -> impl Iterator<Item = &'a Snippet> {
+        let language = language.to_lowercase();
+        self.snippets.iter()
+            .filter(move |(lang, _)| **lang == language || lang.as_str() == "*")
+            .flat_map(|(_, snippets)| snippets.iter())
+    }
+}
+
+/// One entry of a VSCode snippet file
+#[derive(Deserialize)]
+struct SnippetDefinition {
+    prefix: OneOrMany,
+    body: OneOrMany,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+/// A placeholder position; every range is edited together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabStop {
+    pub ranges: Vec<Range<usize>>,
+    /// Options of a `${1|a,b|}` choice
+    pub choices: Vec<String>,
+}
+
+/// Snippet body resolved to plain text
+#[derive(Debug, Clone)]
+pub struct ExpandedSnippet {
+    pub text: String,
+    /// Tab stops in visiting order, ending with `$0` (the end of the text if absent)
+    pub tab_stops: Vec<TabStop>,
+}
+
+/// Expand a snippet body for insertion
+///
+/// Continuation lines get `indent` prepended and tabs become `unit`; `variables`
+/// resolves `$NAME` / `${NAME:default}`.
+pub fn expand(body: &str, indent: &str, unit: &str, variables: &HashMap<String, String>) -> ExpandedSnippet {
+    let mut parser = SnippetParser {
+        chars: body.chars().collect(),
+        pos: 0,
+        text: String::new(),
+        stops: BTreeMap::new(),
+        indent,
+        unit,
+        variables,
+    };
+    parser.parse_until(None);
+
+    let final_stop = parser.stops.remove(&0);
+    let mut tab_stops: Vec<TabStop> = parser.stops.into_values().collect();
+    let end = parser.text.len();
+    tab_stops.push(final_stop.unwrap_or(TabStop { ranges: std::iter::once(end..end).collect(), choices: Vec::new() }));
+    ExpandedSnippet { text: parser.text, tab_stops }
+}
+
+struct SnippetParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    text: String,
+    stops: BTreeMap<u32, TabStop>,
+    indent: &'a str,
+    unit: &'a str,
+    variables: &'a HashMap<String, String>,
+}
+
+impl SnippetParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn push(&mut self, c: char) {
+        match c {
+            '\n' => {
+                self.text.push('\n');
+                self.text.push_str(self.indent);
+            }
+            '\t' => self.text.push_str(self.unit),
+            c => self.text.push(c),
+        }
+    }
+
+    /// Parse until an unescaped `terminator` (consumed) or the end of input
+    fn parse_until(&mut self, terminator: Option<char>) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                _ if Some(c) == terminator => return,
+                '\\' => match self.peek() {
+                    Some(next @ ('$' | '}' | '\\')) => {
+                        self.pos += 1;
+                        self.push(next);
+                    }
+                    _ => self.push('\\'),
+                },
+                '$' => self.parse_dollar(),
+                c => self.push(c),
+            }
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn add_stop(&mut self, index: u32, range: Range<usize>, choices: Vec<String>) {
+        let stop = self.stops.entry(index).or_insert_with(|| TabStop { ranges: Vec::new(), choices: Vec::new() });
+        stop.ranges.push(range);
+        if stop.choices.is_empty() {
+            stop.choices = choices;
+        }
+    }
+
+    /// Mirrors of a placeholder repeat its default text
+    fn default_for(&self, index: u32) -> Option<String> {
+        let range = self.stops.get(&index)?.ranges.first()?.clone();
+        Some(self.text[range].to_string())
+    }
+
+    fn parse_dollar(&mut self) {
+        let start = self.text.len();
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                let index = self.take_while(|c| c.is_ascii_digit()).parse().unwrap_or(0);
+                self.insert_mirror(index, start);
+            }
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c == '_' || c.is_ascii_alphanumeric());
+                if let Some(value) = self.variables.get(&name) {
+                    self.text.push_str(value);
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    let index = self.take_while(|c| c.is_ascii_digit()).parse().unwrap_or(0);
+                    match self.peek() {
+                        Some(':') => {
+                            self.pos += 1;
+                            self.parse_until(Some('}'));
+                            self.add_stop(index, start..self.text.len(), Vec::new());
+                        }
+                        Some('|') => {
+                            self.pos += 1;
+                            let choices = self.parse_choices();
+                            if let Some(first) = choices.first() {
+                                self.text.push_str(first);
+                            }
+                            self.add_stop(index, start..self.text.len(), choices);
+                        }
+                        _ => {
+                            // `${1}`, or a transform we don't support
+                            self.skip_to_brace();
+                            self.insert_mirror(index, start);
+                        }
+                    }
+                } else {
+                    let name = self.take_while(|c| c == '_' || c.is_ascii_alphanumeric());
+                    let value = self.variables.get(&name).cloned();
+                    if self.peek() == Some(':') {
+                        self.pos += 1;
+                        self.parse_until(Some('}'));
+                        if let Some(value) = value {
+                            // The default is only used when the variable is unset
+                            self.text.truncate(start);
+                            self.stops.values_mut().for_each(|stop| stop.ranges.retain(|r| r.end <= start));
+                            self.stops.retain(|_, stop| !stop.ranges.is_empty());
+                            self.text.push_str(&value);
+                        }
+                    } else {
+                        self.skip_to_brace();
+                        if let Some(value) = value {
+                            self.text.push_str(&value);
+                        }
+                    }
+                }
+            }
+            _ => self.text.push('$'),
+        }
+    }
+
+    fn insert_mirror(&mut self, index: u32, start: usize) {
+        if let Some(default) = self.default_for(index) {
+            self.text.push_str(&default);
+        }
+        self.add_stop(index, start..self.text.len(), Vec::new());
+    }
+
+    fn skip_to_brace(&mut self) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '}' {
+                return;
+            }
+        }
+    }
+
+    /// Options of `${1|one,two|}`, after the opening `|`
+    fn parse_choices(&mut self) -> Vec<String> {
+        let mut choices = vec![String::new()];
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' => {
+                    if let Some(next) = self.peek() {
+                        self.pos += 1;
+                        choices.last_mut().unwrap().push(next);
+                    }
+                }
+                ',' => choices.push(String::new()),
+                '|' if self.peek() == Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                c => choices.last_mut().unwrap().push(c),
+            }
+        }
+        choices
+    }
+}
+
+/// Tab-stop navigation through an inserted snippet
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    tab_stops: Vec<TabStop>,
+    current: usize,
+}
+
+impl SnippetSession {
+    /// Session for a snippet whose text was inserted at `offset`
+    pub fn new(snippet: &ExpandedSnippet, offset: usize) -> Self {
+        let tab_stops = snippet.tab_stops.iter()
+            .map(|stop| TabStop {
+                ranges: stop.ranges.iter().map(|r| r.start + offset..r.end + offset).collect(),
+                choices: stop.choices.clone(),
+            })
+            .collect();
+        Self { tab_stops, current: 0 }
+    }
+
+    pub fn current(&self) -> &TabStop {
+        &self.tab_stops[self.current]
+    }
+
+    /// Whether the current stop is the final one (`$0`)
+    pub fn is_finished(&self) -> bool {
+        self.current + 1 >= self.tab_stops.len()
+    }
+
+    pub fn next(&mut self) -> &TabStop {
+        self.current = (self.current + 1).min(self.tab_stops.len() - 1);
+        self.current()
+    }
+
+    pub fn previous(&mut self) -> &TabStop {
+        self.current = self.current.saturating_sub(1);
+        self.current()
+    }
+
+    /// Byte range spanned by all the tab stops
+    pub fn span(&self) -> Range<usize> {
+        let ranges = self.tab_stops.iter().flat_map(|stop| stop.ranges.iter());
+        let start = ranges.clone().map(|r| r.start).min().unwrap_or(0);
+        let end = ranges.map(|r| r.end).max().unwrap_or(0);
+        start..end
+    }
+
+    /// Keep the tab stops on their text as the buffer changes
+    pub fn apply_change(&mut self, change: &TextChange) {
+        let delta = change.new_end as isize - change.old_end as isize;
+        let shift = |offset: usize| (offset as isize + delta).max(0) as usize;
+        for range in self.tab_stops.iter_mut().flat_map(|stop| stop.ranges.iter_mut()) {
+            if change.old_end <= range.start && change.start < range.start {
+                *range = shift(range.start)..shift(range.end);
+            } else if change.start >= range.start && change.old_end <= range.end {
+                // Typing inside (or at the edges of) the placeholder grows it
+                range.end = shift(range.end);
+            } else if change.start < range.end {
+                // Overlapping edits clamp the placeholder to what survives
+                let start = range.start.min(change.start);
+                range.end = shift(range.end.max(change.old_end));
+                range.start = start;
+            }
+        }
+    }
+}
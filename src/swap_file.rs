@@ -0,0 +1,218 @@
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Unsaved contents of an editor buffer, journaled so they survive a crash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapFile {
+    /// Display name of the buffer
+    pub name: String,
+    /// File the buffer belongs to (`None` for untitled buffers)
+    pub file_path: Option<PathBuf>,
+    pub language: String,
+    pub content: String,
+    /// Unix time in seconds when the swap file was written
+    pub written_at: u64,
+}
+
+impl SwapFile {
+    pub fn new(name: &str, file_path: Option<PathBuf>, language: &str, content: &str) -> Self {
+        let written_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            name: name.to_string(),
+            file_path,
+            language: language.to_string(),
+            content: content.to_string(),
+            written_at,
+        }
+    }
+
+    /// Human readable age, e.g. "5 minutes ago"
+    pub fn age(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let seconds = now.saturating_sub(self.written_at);
+        match seconds {
+            0..=59 => "just now".to_string(),
+            60..=3599 => format!("{} minutes ago", seconds / 60),
+            3600..=86399 => format!("{} hours ago", seconds / 3600),
+            _ => format!("{} days ago", seconds / 86400),
+        }
+    }
+}
+
+/// Directory of swap files, one per dirty editor buffer
+///
+/// Editors remove their swap file when the buffer is saved or closed without
+/// changes, so anything left over at launch was lost in a crash or left
+/// unsaved on quit.
+#[derive(Debug, Clone)]
+pub struct SwapStore {
+    dir: PathBuf,
+}
+
+impl SwapStore {
+    /// Store in the IDE config directory
+    pub fn new() -> Result<Self> {
+        let mut dir = dirs::config_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
+        dir.push("zellij-ide");
+        dir.push("swap");
+        Ok(Self::at(dir))
+    }
+
+    /// Store in `dir`
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path_for(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.swap", id))
+    }
+
+    /// Write the swap file for an editor, replacing the previous one atomically
+    pub fn write(&self, id: Uuid, swap: &SwapFile) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(id);
+        let tmp = path.with_extension("swap.tmp");
+        fs::write(&tmp, serde_json::to_vec(swap)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, id: Uuid) {
+        let path = self.path_for(id);
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove swap file {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Swap files left behind by a previous session, oldest first
+    ///
+    /// Swaps whose contents already match the file on disk are deleted, since
+    /// there is nothing to recover from them.
+    pub fn load_all(&self) -> Vec<(PathBuf, SwapFile)> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut swaps: Vec<(PathBuf, SwapFile)> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "swap"))
+            .filter_map(|path| {
+                let swap = fs::read(&path).ok()
+                    .and_then(|bytes| serde_json::from_slice::<SwapFile>(&bytes).ok());
+                if swap.is_none() {
+                    log::warn!("Ignoring unreadable swap file {}", path.display());
+                }
+                swap.map(|swap| (path, swap))
+            })
+            .filter(|(path, swap)| {
+                let on_disk = swap.file_path.as_ref().and_then(|p| fs::read_to_string(p).ok());
+                if on_disk.as_deref() == Some(swap.content.as_str()) {
+                    Self::discard(path);
+                    return false;
+                }
+                true
+            })
+            .collect();
+
+        swaps.sort_by_key(|(_, swap)| swap.written_at);
+        swaps
+    }
+
+    /// Delete a swap file returned by `load_all`
+    pub fn discard(path: &Path) {
+        if let Err(e) = fs::remove_file(path) {
+            log::warn!("Failed to remove swap file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// What the user chose for a recoverable buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryChoice {
+    Recover,
+    Discard,
+}
+
+/// Window offering to restore buffers left behind by a crash
+pub struct RecoveryPrompt {
+    swaps: Vec<(PathBuf, SwapFile)>,
+}
+
+impl RecoveryPrompt {
+    pub fn new(swaps: Vec<(PathBuf, SwapFile)>) -> Self {
+        Self { swaps }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.swaps.is_empty()
+    }
+
+    /// Render the prompt and return the buffers the user decided on
+    ///
+    /// Decided entries are removed from the prompt; the caller is responsible
+    /// for deleting their swap files once handled.
+    pub fn show(&mut self, ctx: &egui::Context) -> Vec<(RecoveryChoice, PathBuf, SwapFile)> {
+        let mut choices: Vec<(usize, RecoveryChoice)> = Vec::new();
+
+        egui::Window::new("Recover Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("These buffers had unsaved changes when the IDE last exited unexpectedly.");
+                ui.separator();
+
+                egui::Grid::new("recovery_grid").striped(true).show(ui, |ui| {
+                    for (i, (_, swap)) in self.swaps.iter().enumerate() {
+                        let location = swap.file_path.as_ref()
+                            .map(|p| p.display().to_string())
+                            .unwrap_or_else(|| "untitled".to_string());
+                        ui.label(&swap.name).on_hover_text(location);
+                        ui.label(swap.age());
+                        if ui.button("Recover").clicked() {
+                            choices.push((i, RecoveryChoice::Recover));
+                        }
+                        if ui.button("Discard").clicked() {
+                            choices.push((i, RecoveryChoice::Discard));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Recover All").clicked() {
+                        choices = (0..self.swaps.len()).map(|i| (i, RecoveryChoice::Recover)).collect();
+                    }
+                    if ui.button("Discard All").clicked() {
+                        choices = (0..self.swaps.len()).map(|i| (i, RecoveryChoice::Discard)).collect();
+                    }
+                });
+            });
+
+        // Remove back to front so indices stay valid
+        choices.sort_by_key(|(i, _)| std::cmp::Reverse(*i));
+        choices.dedup_by_key(|(i, _)| *i);
+        let mut decided: Vec<_> = choices.into_iter()
+            .map(|(i, choice)| {
+                let (path, swap) = self.swaps.remove(i);
+                (choice, path, swap)
+            })
+            .collect();
+        decided.reverse();
+        decided
+    }
+}
@@ -0,0 +1,358 @@
+use crate::editor_buffer::{LineIndex, TextChange, TextPosition};
+use serde::{Serialize, Deserialize};
+use std::ops::Range;
+use tree_sitter::{InputEdit, Language, Node, Parser, Point, Tree};
+
+/// Grammar plus the node kinds the structural features care about
+struct LanguageSpec {
+    language: fn() -> Language,
+    /// Nodes whose contents are indented one level
+    indent_kinds: &'static [&'static str],
+    /// Nodes that show up in the outline
+    symbol_kinds: &'static [(&'static str, &'static str)],
+    /// Whether a trailing `:` opens an indented block (Python)
+    colon_blocks: bool,
+}
+
+const RUST: LanguageSpec = LanguageSpec {
+    language: || tree_sitter_rust::LANGUAGE.into(),
+    indent_kinds: &[
+        "block", "declaration_list", "field_declaration_list", "enum_variant_list",
+        "field_initializer_list", "match_block", "arguments", "parameters",
+        "array_expression", "tuple_expression", "use_list", "token_tree",
+    ],
+    symbol_kinds: &[
+        ("function_item", "fn"), ("function_signature_item", "fn"), ("struct_item", "struct"),
+        ("enum_item", "enum"), ("union_item", "union"), ("trait_item", "trait"),
+        ("impl_item", "impl"), ("mod_item", "mod"), ("const_item", "const"),
+        ("static_item", "static"), ("type_item", "type"), ("macro_definition", "macro"),
+    ],
+    colon_blocks: false,
+};
+
+const PYTHON: LanguageSpec = LanguageSpec {
+    language: || tree_sitter_python::LANGUAGE.into(),
+    indent_kinds: &[
+        "argument_list", "parameters", "list", "dictionary", "set", "tuple",
+        "parenthesized_expression", "list_comprehension", "dictionary_comprehension",
+    ],
+    symbol_kinds: &[("function_definition", "def"), ("class_definition", "class")],
+    colon_blocks: true,
+};
+
+const JAVASCRIPT: LanguageSpec = LanguageSpec {
+    language: || tree_sitter_javascript::LANGUAGE.into(),
+    indent_kinds: &[
+        "statement_block", "class_body", "object", "array", "arguments", "formal_parameters",
+        "switch_body", "named_imports", "object_pattern", "array_pattern", "template_substitution",
+    ],
+    symbol_kinds: &[
+        ("function_declaration", "function"), ("generator_function_declaration", "function"),
+        ("class_declaration", "class"), ("method_definition", "method"),
+    ],
+    colon_blocks: false,
+};
+
+fn spec_for(language: &str) -> Option<&'static LanguageSpec> {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" => Some(&PYTHON),
+        "javascript" | "js" | "jsx" => Some(&JAVASCRIPT),
+        _ => None,
+    }
+}
+
+const BRACKETS: [(&str, &str); 3] = [("(", ")"), ("[", "]"), ("{", "}")];
+
+/// Coarse lexical class of a token, for overview coloring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Keyword,
+    String,
+    Comment,
+    Number,
+    Type,
+}
+
+impl TokenClass {
+    /// Class of a leaf (or string/comment) node, if it's worth coloring
+    fn of(node: Node) -> Option<Self> {
+        let kind = node.kind();
+        if kind.contains("comment") {
+            Some(Self::Comment)
+        } else if kind.contains("string") || kind == "char_literal" {
+            Some(Self::String)
+        } else if kind.contains("integer") || kind.contains("float") || kind == "number" {
+            Some(Self::Number)
+        } else if kind == "type_identifier" || kind == "primitive_type" {
+            Some(Self::Type)
+        } else if !node.is_named() && kind.chars().all(|c| c.is_ascii_alphabetic() || c == '_') {
+            Some(Self::Keyword)
+        } else {
+            None
+        }
+    }
+}
+
+/// An entry of the document outline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSymbol {
+    pub name: String,
+    /// Short keyword for the kind of item, e.g. "fn" or "class"
+    pub kind: String,
+    /// Byte range of the whole item
+    pub range: Range<usize>,
+    /// Byte range of the name
+    pub name_range: Range<usize>,
+    /// Where the name starts
+    pub position: TextPosition,
+    /// Nesting level (0 for top-level items)
+    pub depth: usize,
+}
+
+/// Incrementally maintained tree-sitter parse of an editor buffer
+pub struct SyntaxTree {
+    spec: &'static LanguageSpec,
+    parser: Parser,
+    tree: Option<Tree>,
+}
+
+impl SyntaxTree {
+    /// Parser for `language`, or `None` if there is no grammar for it
+    pub fn for_language(language: &str) -> Option<Self> {
+        let spec = spec_for(language)?;
+        let mut parser = Parser::new();
+        if let Err(e) = parser.set_language(&(spec.language)()) {
+            log::warn!("Failed to load {} grammar: {}", language, e);
+            return None;
+        }
+        Some(Self { spec, parser, tree: None })
+    }
+
+    /// Bring the tree up to date with `text`, reusing the old tree for the
+    /// unchanged parts; returns whether it was reparsed
+    pub fn update(&mut self, text: &str, changes: &[TextChange]) -> bool {
+        if let Some(tree) = &mut self.tree {
+            if changes.is_empty() {
+                return false;
+            }
+            for change in changes {
+                tree.edit(&InputEdit {
+                    start_byte: change.start,
+                    old_end_byte: change.old_end,
+                    new_end_byte: change.new_end,
+                    start_position: point(change.start_point),
+                    old_end_position: point(change.old_end_point),
+                    new_end_position: point(change.new_end_point),
+                });
+            }
+        }
+        self.tree = self.parser.parse(text, self.tree.as_ref());
+        true
+    }
+
+    fn root(&self) -> Option<Node<'_>> {
+        self.tree.as_ref().map(|tree| tree.root_node())
+    }
+
+    /// Smallest syntax node strictly containing `range`
+    pub fn expand_selection(&self, range: Range<usize>) -> Option<Range<usize>> {
+        let mut node = self.root()?.descendant_for_byte_range(range.start, range.end)?;
+        loop {
+            let node_range = node.byte_range();
+            if node.is_named() && node_range.start <= range.start && node_range.end >= range.end && node_range != range {
+                return Some(node_range);
+            }
+            node = node.parent()?;
+        }
+    }
+
+    /// Indentation for a line break inserted at `offset`
+    ///
+    /// Returns the indent of the new line and, when the break lands right
+    /// before a closing bracket, the indent that bracket should move to.
+    pub fn newline_indent(&self, text: &str, offset: usize, unit: &str) -> (String, Option<String>) {
+        let before = &text[line_start(text, offset)..offset];
+        let after = text[offset..].trim_start_matches([' ', '\t']);
+
+        let enclosing = self.root().and_then(|root| {
+            let mut node = root.descendant_for_byte_range(offset, offset)?;
+            loop {
+                if self.spec.indent_kinds.contains(&node.kind())
+                    && node.start_byte() < offset
+                    && offset < node.end_byte()
+                {
+                    return Some(node);
+                }
+                node = node.parent()?;
+            }
+        });
+
+        if let Some(node) = enclosing {
+            let base = indentation(text, node.start_byte());
+            let next = text.len() - after.len();
+            let closes_here = next + 1 == node.end_byte() && after.starts_with([')', ']', '}']);
+            return (format!("{}{}", base, unit), closes_here.then_some(base));
+        }
+
+        let current = indentation(text, offset);
+        let trimmed = before.trim_end();
+        let opens = trimmed.ends_with(['{', '[', '('])
+            || (self.spec.colon_blocks && trimmed.ends_with(':'));
+        if opens {
+            (format!("{}{}", current, unit), None)
+        } else {
+            (current, None)
+        }
+    }
+
+    /// The bracket pair touching `offset` (the bracket after it, else the one before)
+    pub fn matching_brackets(&self, offset: usize) -> Option<(Range<usize>, Range<usize>)> {
+        let root = self.root()?;
+        let candidates = [Some(offset), offset.checked_sub(1)];
+        candidates.into_iter().flatten().find_map(|at| {
+            let leaf = root.descendant_for_byte_range(at, at + 1)?;
+            let (open, close) = BRACKETS.iter().find(|(o, c)| leaf.kind() == *o || leaf.kind() == *c)?;
+            if leaf.is_named() || leaf.start_byte() != at {
+                return None;
+            }
+            let parent = leaf.parent()?;
+            let mut cursor = parent.walk();
+            let siblings: Vec<Node> = parent.children(&mut cursor).collect();
+            let other = if leaf.kind() == *open {
+                siblings.iter().rev().find(|n| n.kind() == *close)?
+            } else {
+                siblings.iter().find(|n| n.kind() == *open)?
+            };
+            if other.is_missing() {
+                return None;
+            }
+            Some((leaf.byte_range(), other.byte_range()))
+        })
+    }
+
+    /// Every bracket with its nesting depth, in document order
+    pub fn bracket_depths(&self) -> Vec<(Range<usize>, usize)> {
+        let Some(root) = self.root() else {
+            return Vec::new();
+        };
+        let mut brackets = Vec::new();
+        let mut depth = 0usize;
+        let mut cursor = root.walk();
+        loop {
+            let node = cursor.node();
+            if !node.is_named() && !node.is_missing() && node.child_count() == 0 {
+                if BRACKETS.iter().any(|(open, _)| node.kind() == *open) {
+                    brackets.push((node.byte_range(), depth));
+                    depth += 1;
+                } else if BRACKETS.iter().any(|(_, close)| node.kind() == *close) {
+                    depth = depth.saturating_sub(1);
+                    brackets.push((node.byte_range(), depth));
+                }
+            }
+
+            if cursor.goto_first_child() || cursor.goto_next_sibling() {
+                continue;
+            }
+            loop {
+                if !cursor.goto_parent() {
+                    return brackets;
+                }
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Keywords, literals, comments and type names in document order
+    pub fn tokens(&self) -> Vec<(Range<usize>, TokenClass)> {
+        let Some(root) = self.root() else {
+            return Vec::new();
+        };
+        let mut tokens = Vec::new();
+        let mut cursor = root.walk();
+        loop {
+            let node = cursor.node();
+            let class = TokenClass::of(node);
+            if let Some(class) = class {
+                tokens.push((node.byte_range(), class));
+            }
+
+            // Strings and comments are colored whole, without their parts
+            let descend = !matches!(class, Some(TokenClass::String | TokenClass::Comment));
+            if (descend && cursor.goto_first_child()) || cursor.goto_next_sibling() {
+                continue;
+            }
+            loop {
+                if !cursor.goto_parent() {
+                    return tokens;
+                }
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Document outline in document order, nested items following their parent
+    pub fn symbols(&self, text: &str) -> Vec<DocumentSymbol> {
+        let Some(root) = self.root() else {
+            return Vec::new();
+        };
+        let index = LineIndex::new(text);
+        let mut symbols = Vec::new();
+        let mut stack = vec![(root, 0usize)];
+
+        while let Some((node, depth)) = stack.pop() {
+            let mut child_depth = depth;
+            if let Some((_, kind)) = self.spec.symbol_kinds.iter().find(|(k, _)| node.kind() == *k) {
+                if let Some((name, name_range)) = symbol_name(node, text) {
+                    symbols.push(DocumentSymbol {
+                        name,
+                        kind: kind.to_string(),
+                        range: node.byte_range(),
+                        position: index.position(text, name_range.start),
+                        name_range,
+                        depth,
+                    });
+                    child_depth += 1;
+                }
+            }
+
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.named_children(&mut cursor).collect();
+            // Reversed so the stack pops them in document order
+            stack.extend(children.into_iter().rev().map(|child| (child, child_depth)));
+        }
+        symbols
+    }
+}
+
+fn point((row, column): (usize, usize)) -> Point {
+    Point { row, column }
+}
+
+/// Display name of an outline item and the source range naming it
+fn symbol_name(node: Node, text: &str) -> Option<(String, Range<usize>)> {
+    if node.kind() == "impl_item" {
+        let ty = node.child_by_field_name("type")?;
+        let name = match node.child_by_field_name("trait") {
+            Some(tr) => format!("{} for {}", &text[tr.byte_range()], &text[ty.byte_range()]),
+            None => text[ty.byte_range()].to_string(),
+        };
+        return Some((name, ty.byte_range()));
+    }
+    let name = node.child_by_field_name("name")?;
+    Some((text[name.byte_range()].to_string(), name.byte_range()))
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// Leading whitespace of the line containing `offset`
+fn indentation(text: &str, offset: usize) -> String {
+    let start = line_start(text, offset);
+    text[start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect()
+}
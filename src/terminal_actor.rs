@@ -8,7 +8,22 @@ use std::thread;
 use std::io::{Read, Write};
 use portable_pty::{CommandBuilder, PtySize, native_pty_system};
 use vte::{Parser, Perform};
+use serde::{Deserialize, Serialize};
 use serde_json;
+use std::path::PathBuf;
+
+/// What a terminal runs, and where
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerminalLaunch {
+    /// Program to run instead of the shell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Working directory (the IDE's own by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+}
 
 /// High-performance terminal emulator actor using VTE parser
 /// Supports full ANSI escape sequences and terminal features
@@ -22,7 +37,10 @@ pub struct TerminalActor {
     // VTE parser for escape sequences
     parser: Arc<Mutex<Parser>>,
 
+    launch: TerminalLaunch,
+
     // PTY handling
+    child: Option<Box<dyn portable_pty::Child + Send + Sync>>,
     pty_master: Option<Arc<Mutex<Box<dyn portable_pty::MasterPty + Send>>>>,
     writer: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
 
@@ -435,6 +453,11 @@ impl Perform for TerminalPerformer {
 
 impl TerminalActor {
     pub fn new() -> Self {
+        Self::launch(TerminalLaunch::default())
+    }
+
+    /// A terminal running `launch`'s command, or the shell
+    pub fn launch(launch: TerminalLaunch) -> Self {
         let id = Uuid::new_v4();
         let colors = TerminalColors::default();
 
@@ -449,6 +472,8 @@ impl TerminalActor {
             name: "Terminal".to_string(),
             terminal_grid,
             parser,
+            launch,
+            child: None,
             pty_master: None,
             writer: None,
             font_size: 14.0,
@@ -476,9 +501,13 @@ impl TerminalActor {
         let pair = pty_system.openpty(pty_size).expect("Failed to open PTY");
 
         // Spawn shell
-        let cmd = CommandBuilder::new("/bin/bash");
-        let _child = pair.slave.spawn_command(cmd)
-            .expect("Failed to spawn shell");
+        let mut cmd = CommandBuilder::new(self.launch.command.as_deref().unwrap_or("/bin/bash"));
+        cmd.args(&self.launch.args);
+        if let Some(cwd) = &self.launch.cwd {
+            cmd.cwd(cwd);
+        }
+        self.child = Some(pair.slave.spawn_command(cmd)
+            .expect("Failed to spawn shell"));
 
         // Set up reader thread with VTE parser
         let mut reader = pair.master.try_clone_reader()
@@ -510,6 +539,17 @@ impl TerminalActor {
         self.pty_master = Some(Arc::new(Mutex::new(pair.master)));
     }
 
+    pub fn launch_spec(&self) -> &TerminalLaunch {
+        &self.launch
+    }
+
+    /// The shell's working directory now, where the system exposes it,
+    /// else the one it started in
+    pub fn current_dir(&self) -> Option<PathBuf> {
+        let pid = self.child.as_ref().and_then(|child| child.process_id());
+        pid.and_then(process_cwd).or_else(|| self.launch.cwd.clone())
+    }
+
     pub fn write_to_terminal(&mut self, text: &str) {
         if let Some(writer_arc) = &self.writer {
             if let Ok(mut writer) = writer_arc.lock() {
@@ -538,6 +578,15 @@ impl TerminalActor {
     }
 }
 
+impl Drop for TerminalActor {
+    fn drop(&mut self) {
+        // Closing the terminal ends what runs in it
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+        }
+    }
+}
+
 #[async_trait]
 impl Actor for TerminalActor {
     fn id(&self) -> Uuid {
@@ -835,10 +884,46 @@ impl ActorAPI for TerminalActor {
             state.insert("cursor_y".to_string(), serde_json::Value::Number(serde_json::Number::from(grid.cursor.y)));
         }
 
+        state.insert("command".to_string(), serde_json::json!(self.launch.command));
+        state.insert("args".to_string(), serde_json::json!(self.launch.args));
+        state.insert("cwd".to_string(), serde_json::json!(self.current_dir()));
         state.insert("font_size".to_string(), serde_json::json!(self.font_size));
         state.insert("auto_scroll".to_string(), serde_json::Value::Bool(self.auto_scroll));
         state.insert("scroll_offset".to_string(), serde_json::Value::Number(serde_json::Number::from(self.scroll_offset)));
 
         state
     }
-}
\ No newline at end of file
+}
+
+/// Working directory of process `pid`
+#[cfg(target_os = "linux")]
+fn process_cwd(pid: u32) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/{}/cwd", pid)).ok()
+}
+
+/// Working directory of process `pid`
+#[cfg(target_os = "macos")]
+fn process_cwd(pid: u32) -> Option<PathBuf> {
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut info = std::mem::MaybeUninit::<libc::proc_vnodepathinfo>::zeroed();
+    let size = std::mem::size_of::<libc::proc_vnodepathinfo>() as libc::c_int;
+    // SAFETY: the buffer is the size of the struct PROC_PIDVNODEPATHINFO fills
+    let written = unsafe {
+        libc::proc_pidinfo(pid as libc::c_int, libc::PROC_PIDVNODEPATHINFO, 0, info.as_mut_ptr().cast(), size)
+    };
+    if written != size {
+        return None;
+    }
+    // SAFETY: fully written by the call above, and zeroed before it
+    let info = unsafe { info.assume_init() };
+    // SAFETY: `vip_path` is a NUL-terminated MAXPATHLEN buffer
+    let path = unsafe { CStr::from_ptr(info.pvi_cdir.vip_path.as_ptr().cast()) };
+    Some(PathBuf::from(OsStr::from_bytes(path.to_bytes()))).filter(|path| path.is_absolute())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn process_cwd(_pid: u32) -> Option<PathBuf> {
+    None
+}
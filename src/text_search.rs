@@ -0,0 +1,195 @@
+use anyhow::{Result, anyhow};
+use regex::{Regex, RegexBuilder};
+use serde::{Serialize, Deserialize};
+use std::ops::Range;
+
+/// Options controlling how a query is matched
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only match whole words
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+/// A compiled query that can be run against any text
+pub struct Search {
+    regex: Regex,
+    options: SearchOptions,
+}
+
+impl Search {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self> {
+        if query.is_empty() {
+            return Err(anyhow!("Search query is empty"));
+        }
+
+        let pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        // Word boundaries in the pattern, so a match glued to a longer word
+        // doesn't hide a whole word overlapping it
+        let pattern = if options.whole_word {
+            format!(r"\b(?:{})\b", pattern)
+        } else {
+            pattern
+        };
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| anyhow!("Invalid search pattern: {}", e))?;
+
+        Ok(Self { regex, options })
+    }
+
+    /// Byte ranges of all non-empty matches, in order
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex.find_iter(text)
+            .map(|m| m.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// Text that should replace the match at `range`
+    ///
+    /// In regex mode `$1`/`${name}` refer to capture groups. With
+    /// `preserve_case` the replacement follows the casing of the matched text.
+    pub fn replacement_for(&self, text: &str, range: &Range<usize>, replacement: &str, preserve_case: bool) -> String {
+        let mut expanded = String::new();
+        match self.regex.captures_at(text, range.start).filter(|c| c.get(0).map(|m| m.range()) == Some(range.clone())) {
+            Some(captures) if self.options.regex => captures.expand(replacement, &mut expanded),
+            _ => expanded.push_str(replacement),
+        }
+
+        if preserve_case {
+            apply_case(&text[range.clone()], &expanded)
+        } else {
+            expanded
+        }
+    }
+
+    /// Replacement edits for every match, as `(range, replacement)` pairs
+    pub fn replace_all(&self, text: &str, replacement: &str, preserve_case: bool) -> Vec<(Range<usize>, String)> {
+        self.find_all(text)
+            .into_iter()
+            .map(|range| {
+                let new_text = self.replacement_for(text, &range, replacement, preserve_case);
+                (range, new_text)
+            })
+            .collect()
+    }
+}
+
+/// Re-case `replacement` to follow the casing pattern of `matched`
+pub fn apply_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return replacement.to_string();
+    }
+
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if letters.iter().all(|c| c.is_lowercase()) {
+        replacement.to_lowercase()
+    } else if letters[0].is_uppercase() {
+        let mut chars = replacement.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// Positions of `ranges` after applying `edits` (both sorted, non-overlapping)
+///
+/// Used to report where replacements ended up in the new text.
+pub fn ranges_after_edits(edits: &[(Range<usize>, String)]) -> Vec<Range<usize>> {
+    let mut delta: isize = 0;
+    edits.iter()
+        .map(|(range, text)| {
+            let start = (range.start as isize + delta) as usize;
+            delta += text.len() as isize - range.len() as isize;
+            start..start + text.len()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(query: &str, options: SearchOptions, text: &str) -> Vec<Range<usize>> {
+        Search::new(query, options).unwrap().find_all(text)
+    }
+
+    #[test]
+    fn find_all_honors_the_options() {
+        let text = "Foo foo food _foo foo_bar\nfoo";
+        assert_eq!(find("foo", SearchOptions::default(), text), [0..3, 4..7, 8..11, 14..17, 18..21, 26..29]);
+        let case_sensitive = SearchOptions { case_sensitive: true, ..Default::default() };
+        assert_eq!(find("Foo", case_sensitive, text), [Range { start: 0, end: 3 }]);
+        let whole_word = SearchOptions { whole_word: true, ..Default::default() };
+        assert_eq!(find("foo", whole_word.clone(), text), [0..3, 4..7, 26..29]);
+        // A rejected longer match doesn't hide a whole word inside it
+        assert_eq!(find("a+", SearchOptions { regex: true, ..whole_word }, "aab a"), [Range { start: 4, end: 5 }]);
+
+        let regex = SearchOptions { regex: true, ..Default::default() };
+        assert_eq!(find(r"^fo+", regex.clone(), text), [0..3, 26..29]);
+        assert_eq!(find("x*", regex.clone(), text), Vec::<Range<usize>>::new());
+        assert!(Search::new("(", regex).is_err());
+        assert!(Search::new("", SearchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn find_all_reports_byte_offsets() {
+        let text = "café Café naïve";
+        assert_eq!(find("café", SearchOptions::default(), text), [0..5, 6..11]);
+        assert_eq!(find("naïve", SearchOptions { whole_word: true, ..Default::default() }, text), [Range { start: 12, end: 18 }]);
+        assert_eq!(find("caf", SearchOptions { whole_word: true, ..Default::default() }, text), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn replacements_expand_capture_groups() {
+        let text = "let width = 1; let height = 2;";
+        let search = Search::new(r"let (?P<name>\w+) = (\d)", SearchOptions { regex: true, ..Default::default() }).unwrap();
+        assert_eq!(search.replace_all(text, "const ${name}: u32 = $2", false), [
+            (0..13, "const width: u32 = 1".to_string()),
+            (15..29, "const height: u32 = 2".to_string()),
+        ]);
+
+        // Outside regex mode `$1` is literal
+        let plain = Search::new("width", SearchOptions::default()).unwrap();
+        assert_eq!(plain.replacement_for(text, &(4..9), "$1", false), "$1");
+    }
+
+    #[test]
+    fn replacements_can_follow_the_matched_case() {
+        assert_eq!(apply_case("FOO", "bar"), "BAR");
+        assert_eq!(apply_case("foo", "BaR"), "bar");
+        assert_eq!(apply_case("Foo", "bar"), "Bar");
+        assert_eq!(apply_case("fOO", "bar"), "bar");
+        assert_eq!(apply_case("A", "bar"), "Bar");
+        assert_eq!(apply_case("42", "bar"), "bar");
+
+        let search = Search::new("color", SearchOptions::default()).unwrap();
+        let edits = search.replace_all("Color color COLOR", "colour", true);
+        let replaced: Vec<&str> = edits.iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(replaced, ["Colour", "colour", "COLOUR"]);
+    }
+
+    #[test]
+    fn ranges_follow_earlier_edits() {
+        let edits = [(0..3, "a".to_string()), (5..6, "four".to_string()), (8..10, String::new())];
+        assert_eq!(ranges_after_edits(&edits), [0..1, 3..7, 9..9]);
+    }
+}
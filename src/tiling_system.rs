@@ -1,22 +1,325 @@
+use crate::view::SplitDirection;
 use crate::view_system::{ViewSystem, Transformable, TransformInfo};
 use egui::{self, Vec2, Pos2, Rect};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::collections::HashMap;
 use std::any::Any;
 
 /// Tiling layout direction
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TileDirection {
+    /// Side by side (left/right)
     Horizontal,
+    /// Stacked (top/bottom)
     Vertical,
 }
 
+impl From<SplitDirection> for TileDirection {
+    fn from(direction: SplitDirection) -> Self {
+        match direction {
+            SplitDirection::Horizontal => TileDirection::Horizontal,
+            SplitDirection::Vertical => TileDirection::Vertical,
+        }
+    }
+}
+
+impl From<TileDirection> for SplitDirection {
+    fn from(direction: TileDirection) -> Self {
+        match direction {
+            TileDirection::Horizontal => SplitDirection::Horizontal,
+            TileDirection::Vertical => SplitDirection::Vertical,
+        }
+    }
+}
+
+/// A direction to look for a neighboring tile in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaneDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl PaneDirection {
+    pub const ALL: [PaneDirection; 4] = [PaneDirection::Left, PaneDirection::Right, PaneDirection::Up, PaneDirection::Down];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaneDirection::Left => "left",
+            PaneDirection::Right => "right",
+            PaneDirection::Up => "up",
+            PaneDirection::Down => "down",
+        }
+    }
+
+    /// The split direction that lays tiles out along this direction
+    fn axis(self) -> TileDirection {
+        match self {
+            PaneDirection::Left | PaneDirection::Right => TileDirection::Horizontal,
+            PaneDirection::Up | PaneDirection::Down => TileDirection::Vertical,
+        }
+    }
+
+    /// Whether this points toward the first half of a split
+    fn is_backward(self) -> bool {
+        matches!(self, PaneDirection::Left | PaneDirection::Up)
+    }
+}
+
+/// How far one resize step moves a divider
+const RESIZE_STEP: f32 = 40.0;
+
+/// A node of the binary split tree tiles are laid out by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TileNode {
+    Leaf(Uuid),
+    Split {
+        direction: TileDirection,
+        /// Share of the space (excluding the divider) given to `first`
+        ratio: f32,
+        first: Box<TileNode>,
+        second: Box<TileNode>,
+    },
+}
+
+impl TileNode {
+    /// Tile IDs in layout order (left to right, top to bottom)
+    pub fn leaves(&self) -> Vec<Uuid> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves(&self, leaves: &mut Vec<Uuid>) {
+        match self {
+            TileNode::Leaf(id) => leaves.push(*id),
+            TileNode::Split { first, second, .. } => {
+                first.collect_leaves(leaves);
+                second.collect_leaves(leaves);
+            }
+        }
+    }
+
+    fn first_leaf(&self) -> Uuid {
+        match self {
+            TileNode::Leaf(id) => *id,
+            TileNode::Split { first, .. } => first.first_leaf(),
+        }
+    }
+
+    /// Replace the leaf `target` with a split of it and `new_id`, which goes
+    /// first (left or top) when `before` is set
+    fn split_leaf(&mut self, target: Uuid, new_id: Uuid, direction: TileDirection, before: bool) -> bool {
+        match self {
+            TileNode::Leaf(id) if *id == target => {
+                *self = TileNode::split(direction, TileNode::Leaf(target), TileNode::Leaf(new_id), before);
+                true
+            }
+            TileNode::Leaf(_) => false,
+            TileNode::Split { first, second, .. } => {
+                first.split_leaf(target, new_id, direction, before) || second.split_leaf(target, new_id, direction, before)
+            }
+        }
+    }
+
+    /// An even split of `node` and `new`, with `new` first when `before` is set
+    fn split(direction: TileDirection, node: TileNode, new: TileNode, before: bool) -> TileNode {
+        let (first, second) = if before { (new, node) } else { (node, new) };
+        TileNode::Split { direction, ratio: 0.5, first: Box::new(first), second: Box::new(second) }
+    }
+
+    /// Exchange the places of two tiles
+    fn swap_leaves(&mut self, a: Uuid, b: Uuid) {
+        match self {
+            TileNode::Leaf(id) if *id == a => *id = b,
+            TileNode::Leaf(id) if *id == b => *id = a,
+            TileNode::Leaf(_) => {}
+            TileNode::Split { first, second, .. } => {
+                first.swap_leaves(a, b);
+                second.swap_leaves(a, b);
+            }
+        }
+    }
+
+    /// Path from this node to the leaf `target` (`true` = second child)
+    fn path_to(&self, target: Uuid) -> Option<Vec<bool>> {
+        match self {
+            TileNode::Leaf(id) => (*id == target).then(Vec::new),
+            TileNode::Split { first, second, .. } => {
+                let (child, mut path) = first.path_to(target).map(|path| (false, path))
+                    .or_else(|| second.path_to(target).map(|path| (true, path)))?;
+                path.insert(0, child);
+                Some(path)
+            }
+        }
+    }
+
+    /// The tree without the leaf `target` (its sibling takes the split's place)
+    fn remove_leaf(self, target: Uuid) -> Option<TileNode> {
+        match self {
+            TileNode::Leaf(id) if id == target => None,
+            TileNode::Leaf(_) => Some(self),
+            TileNode::Split { direction, ratio, first, second } => {
+                match (first.remove_leaf(target), second.remove_leaf(target)) {
+                    (Some(first), Some(second)) => Some(TileNode::Split {
+                        direction,
+                        ratio,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                    }),
+                    (Some(node), None) | (None, Some(node)) => Some(node),
+                    (None, None) => None,
+                }
+            }
+        }
+    }
+
+    /// The first tile of the subtree that would take `target`'s place if it
+    /// were removed
+    fn sibling_leaf(&self, target: Uuid) -> Option<Uuid> {
+        match self {
+            TileNode::Leaf(_) => None,
+            TileNode::Split { first, second, .. } => match (first.as_ref(), second.as_ref()) {
+                (TileNode::Leaf(id), sibling) | (sibling, TileNode::Leaf(id)) if *id == target => Some(sibling.first_leaf()),
+                _ => first.sibling_leaf(target).or_else(|| second.sibling_leaf(target)),
+            },
+        }
+    }
+
+    fn node_at_mut(&mut self, path: &[bool]) -> Option<&mut TileNode> {
+        match (path.split_first(), self) {
+            (None, node) => Some(node),
+            (Some((&second_child, rest)), TileNode::Split { first, second, .. }) => {
+                if second_child { second.node_at_mut(rest) } else { first.node_at_mut(rest) }
+            }
+            (Some(_), TileNode::Leaf(_)) => None,
+        }
+    }
+
+    /// Smallest size the subtree can be laid out in without shrinking any
+    /// tile below `min_tile`
+    fn min_size(&self, min_tile: Vec2, gap: f32) -> Vec2 {
+        match self {
+            TileNode::Leaf(_) => min_tile,
+            TileNode::Split { direction, first, second, .. } => {
+                let (a, b) = (first.min_size(min_tile, gap), second.min_size(min_tile, gap));
+                match direction {
+                    TileDirection::Horizontal => Vec2::new(a.x + gap + b.x, a.y.max(b.y)),
+                    TileDirection::Vertical => Vec2::new(a.x.max(b.x), a.y + gap + b.y),
+                }
+            }
+        }
+    }
+}
+
+/// A draggable gap between the two halves of a split, from the last layout
+#[derive(Debug, Clone)]
+struct Divider {
+    /// Path from the root to the split (`true` = second child)
+    path: Vec<bool>,
+    direction: TileDirection,
+    rect: Rect,
+    /// The rect the split was laid out in
+    parent: Rect,
+    /// Bounds the split's ratio is kept within so both halves fit
+    min_ratio: f32,
+    max_ratio: f32,
+}
+
+impl Divider {
+    /// The ratio putting the divider's center at `pos`
+    fn ratio_at(&self, pos: Pos2, gap: f32) -> f32 {
+        let (offset, extent) = match self.direction {
+            TileDirection::Horizontal => (pos.x - self.parent.min.x, self.parent.width()),
+            TileDirection::Vertical => (pos.y - self.parent.min.y, self.parent.height()),
+        };
+        let available = (extent - gap).max(1.0);
+        ((offset - gap / 2.0) / available).clamp(self.min_ratio, self.max_ratio)
+    }
+}
+
+impl TileNode {
+    /// Lay the subtree out in `rect`, recording each tile's rect and each
+    /// split's divider
+    fn layout(
+        &self,
+        rect: Rect,
+        gap: f32,
+        min_tile: Vec2,
+        rects: &mut HashMap<Uuid, Rect>,
+        dividers: &mut Vec<Divider>,
+        path: &mut Vec<bool>,
+    ) {
+        let TileNode::Split { direction, ratio, first, second } = self else {
+            if let TileNode::Leaf(id) = self {
+                rects.insert(*id, rect);
+            }
+            return;
+        };
+
+        let axis = |size: Vec2| match direction {
+            TileDirection::Horizontal => size.x,
+            TileDirection::Vertical => size.y,
+        };
+        let available = (axis(rect.size()) - gap).max(0.0);
+        let min_first = axis(first.min_size(min_tile, gap));
+        let min_second = axis(second.min_size(min_tile, gap));
+        // Too small for both minimums: share the space in their proportion
+        let (min_ratio, max_ratio) = if available > 0.0 && available >= min_first + min_second {
+            (min_first / available, 1.0 - min_second / available)
+        } else {
+            let ratio = min_first / (min_first + min_second);
+            (ratio, ratio)
+        };
+        let first_extent = available * ratio.clamp(min_ratio, max_ratio);
+
+        let (first_rect, divider_rect, second_rect) = match direction {
+            TileDirection::Horizontal => {
+                let x = rect.min.x + first_extent;
+                (
+                    Rect::from_min_max(rect.min, Pos2::new(x, rect.max.y)),
+                    Rect::from_min_max(Pos2::new(x, rect.min.y), Pos2::new(x + gap, rect.max.y)),
+                    Rect::from_min_max(Pos2::new(x + gap, rect.min.y), rect.max),
+                )
+            }
+            TileDirection::Vertical => {
+                let y = rect.min.y + first_extent;
+                (
+                    Rect::from_min_max(rect.min, Pos2::new(rect.max.x, y)),
+                    Rect::from_min_max(Pos2::new(rect.min.x, y), Pos2::new(rect.max.x, y + gap)),
+                    Rect::from_min_max(Pos2::new(rect.min.x, y + gap), rect.max),
+                )
+            }
+        };
+        dividers.push(Divider {
+            path: path.clone(),
+            direction: *direction,
+            rect: divider_rect,
+            parent: rect,
+            min_ratio,
+            max_ratio,
+        });
+
+        path.push(false);
+        first.layout(first_rect, gap, min_tile, rects, dividers, path);
+        path.pop();
+        path.push(true);
+        second.layout(second_rect, gap, min_tile, rects, dividers, path);
+        path.pop();
+    }
+}
+
 /// A tile in the tiling system
 #[derive(Debug, Clone)]
 pub struct Tile {
     pub id: Uuid,
     pub title: String,
-    pub actor_id: Option<Uuid>,
+    /// Actors in the tile's tabs, in tab bar order
+    pub tabs: Vec<Uuid>,
+    pub active_tab: usize,
     pub rect: Rect,
     pub is_focused: bool,
 }
@@ -26,29 +329,206 @@ impl Tile {
         Self {
             id: Uuid::new_v4(),
             title,
-            actor_id: None,
+            tabs: Vec::new(),
+            active_tab: 0,
             rect,
             is_focused: false,
         }
     }
+
+    /// The actor of the tab shown
+    pub fn actor_id(&self) -> Option<Uuid> {
+        self.tabs.get(self.active_tab).copied()
+    }
+
+    /// Show `actor_id`, in a new tab after the shown one if it has none yet
+    fn add_tab(&mut self, actor_id: Uuid) {
+        match self.tabs.iter().position(|&id| id == actor_id) {
+            Some(index) => self.active_tab = index,
+            None => {
+                let index = if self.tabs.is_empty() { 0 } else { self.active_tab + 1 };
+                self.tabs.insert(index, actor_id);
+                self.active_tab = index;
+            }
+        }
+    }
+
+    /// Take `actor_id`'s tab out, returning where it was; the tab after a
+    /// removed shown tab (or before it, for the last) is shown instead
+    fn remove_tab(&mut self, actor_id: Uuid) -> Option<usize> {
+        let index = self.tabs.iter().position(|&id| id == actor_id)?;
+        self.tabs.remove(index);
+        if index < self.active_tab || self.active_tab >= self.tabs.len() {
+            self.active_tab = self.active_tab.saturating_sub(1);
+        }
+        Some(index)
+    }
+}
+
+/// Height of the tab bar along the top of each tile with tabs
+pub const TAB_BAR_HEIGHT: f32 = 24.0;
+
+/// What a tab shows for its actor
+#[derive(Debug, Clone, Default)]
+pub struct TabLabel {
+    pub name: String,
+    /// Whether the actor has unsaved changes
+    pub modified: bool,
+}
+
+impl TabLabel {
+    pub fn named(name: &str) -> Self {
+        Self { name: name.to_string(), modified: false }
+    }
+}
+
+/// Where a tile's tabs were drawn on the last frame, for dropping dragged
+/// tabs on
+struct TabBar {
+    tile: Uuid,
+    rect: Rect,
+    tabs: Vec<Rect>,
+}
+
+/// What was done to a tab bar this frame, by tab index
+#[derive(Debug, Default)]
+pub struct TabBarResponse {
+    /// Where each tab was drawn
+    pub tabs: Vec<Rect>,
+    pub selected: Option<usize>,
+    /// The tab whose close button or middle click closed it
+    pub closed: Option<usize>,
+    pub drag_started: Option<usize>,
+}
+
+/// A tab bar along the top of `rect`, with a tab per `(key, label)` and
+/// the tab at `active` shown as selected
+///
+/// Dragged tabs are left to the caller to follow, with
+/// [`paint_dragged_tab`] while the pointer is down.
+pub fn tab_bar(ui: &mut egui::Ui, rect: Rect, id: egui::Id, tabs: &[(Uuid, TabLabel)], active: usize, focused: bool) -> TabBarResponse {
+    let bar = Rect::from_min_size(rect.min, Vec2::new(rect.width(), TAB_BAR_HEIGHT));
+    let painter = ui.painter().with_clip_rect(bar.intersect(ui.clip_rect()));
+    painter.line_segment(
+        [bar.left_bottom(), bar.right_bottom()],
+        egui::Stroke::new(1.0, egui::Color32::from_gray(80)),
+    );
+
+    let font = egui::FontId::proportional(13.0);
+    let mut x = bar.min.x + 2.0;
+    let mut response = TabBarResponse::default();
+    for (index, (key, label)) in tabs.iter().enumerate() {
+        let is_active = index == active;
+        let text_color = if is_active { egui::Color32::WHITE } else { egui::Color32::from_gray(170) };
+        let galley = painter.layout_no_wrap(label.name.clone(), font.clone(), text_color);
+
+        let rect = Rect::from_min_size(egui::pos2(x, bar.min.y + 2.0), Vec2::new(galley.size().x + 34.0, TAB_BAR_HEIGHT - 2.0));
+        x = rect.max.x + 2.0;
+        response.tabs.push(rect);
+        let id = id.with(key);
+        let tab = ui.interact(rect, id, egui::Sense::click_and_drag());
+        let close_rect = Rect::from_center_size(egui::pos2(rect.max.x - 12.0, rect.center().y), Vec2::splat(14.0));
+        let close = ui.interact(close_rect, id.with("close"), egui::Sense::click());
+
+        if tab.clicked() {
+            response.selected = Some(index);
+        }
+        if tab.middle_clicked() || close.clicked() {
+            response.closed = Some(index);
+        }
+        if tab.drag_started() {
+            response.drag_started = Some(index);
+        }
+
+        let fill = if is_active {
+            egui::Color32::from_gray(if focused { 70 } else { 55 })
+        } else if tab.hovered() {
+            egui::Color32::from_gray(50)
+        } else {
+            egui::Color32::from_gray(35)
+        };
+        painter.rect_filled(rect, egui::Rounding { nw: 3.0, ne: 3.0, sw: 0.0, se: 0.0 }, fill);
+        if is_active && focused {
+            painter.line_segment([rect.left_top(), rect.right_top()], egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 150, 255)));
+        }
+        painter.galley(egui::pos2(rect.min.x + 8.0, rect.center().y - galley.size().y / 2.0), galley, text_color);
+
+        // Unsaved changes show as a dot that turns into the close button on hover
+        if close.hovered() {
+            painter.rect_filled(close_rect, 2.0, egui::Color32::from_gray(90));
+        }
+        let (glyph, glyph_color) = if label.modified && !close.hovered() {
+            ("●", egui::Color32::from_rgb(230, 180, 80))
+        } else if tab.hovered() || close.hovered() || is_active {
+            ("×", text_color)
+        } else {
+            ("", text_color)
+        };
+        painter.text(close_rect.center(), egui::Align2::CENTER_CENTER, glyph, font.clone(), glyph_color);
+    }
+    response
+}
+
+/// Follow the pointer at `pos` with a tab named `name`, marking where it
+/// goes if dropped: `index` among the `tabs` of the tab bar atop a rect
+pub fn paint_dragged_tab(ui: &egui::Ui, name: &str, pos: Pos2, drop: Option<(Rect, &[Rect], usize)>) {
+    ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+    let accent = egui::Color32::from_rgb(100, 150, 255);
+    let painter = ui.ctx().layer_painter(egui::LayerId::new(egui::Order::Tooltip, ui.id().with("dragged_tab")));
+    if let Some((rect, tabs, index)) = drop {
+        // A marker where the tab will go
+        let x = match (tabs.get(index), tabs.last()) {
+            (Some(tab), _) => tab.min.x - 1.0,
+            (None, Some(tab)) => tab.max.x + 1.0,
+            (None, None) => rect.min.x + 2.0,
+        };
+        painter.line_segment(
+            [egui::pos2(x, rect.min.y + 2.0), egui::pos2(x, rect.min.y + TAB_BAR_HEIGHT)],
+            egui::Stroke::new(2.0, accent),
+        );
+    }
+    let galley = painter.layout_no_wrap(name.to_string(), egui::FontId::proportional(13.0), egui::Color32::WHITE);
+    let rect = Rect::from_min_size(pos + Vec2::new(12.0, 8.0), galley.size() + Vec2::new(16.0, 8.0));
+    painter.rect(rect, 3.0, egui::Color32::from_gray(70), egui::Stroke::new(1.0, accent));
+    painter.galley(rect.min + Vec2::new(8.0, 4.0), galley, egui::Color32::WHITE);
 }
 
-/// Tiling-based view system (placeholder implementation)
-/// This demonstrates how we can have different view management approaches
+/// Tiling view system: tiles laid out by a binary split tree, like Zellij
+/// panes, with draggable dividers between them
 pub struct TilingSystem {
     tiles: HashMap<Uuid, Tile>,
+    root: Option<TileNode>,
     active_tile: Option<Uuid>,
+    /// The tile shown alone over the whole area, if one is zoomed
+    zoomed_tile: Option<Uuid>,
+    /// Direction new views are appended in
     layout_direction: TileDirection,
     tile_margin: f32,
+    min_tile_size: Vec2,
+    dividers: Vec<Divider>,
+    tab_labels: HashMap<Uuid, TabLabel>,
+    tab_bars: Vec<TabBar>,
+    /// The tile and actor of the tab being dragged
+    dragged_tab: Option<(Uuid, Uuid)>,
+    /// Tabs whose close button was clicked, as `(tile, actor)`
+    close_requests: Vec<(Uuid, Uuid)>,
 }
 
 impl TilingSystem {
     pub fn new() -> Self {
         let mut system = Self {
             tiles: HashMap::new(),
+            root: None,
             active_tile: None,
+            zoomed_tile: None,
             layout_direction: TileDirection::Horizontal,
             tile_margin: 4.0,
+            min_tile_size: Vec2::new(120.0, 80.0),
+            dividers: Vec::new(),
+            tab_labels: HashMap::new(),
+            tab_bars: Vec::new(),
+            dragged_tab: None,
+            close_requests: Vec::new(),
         };
 
         // Create initial tile
@@ -66,38 +546,357 @@ impl TilingSystem {
         self.tile_margin = margin;
     }
 
-    fn recalculate_layout(&mut self, available_rect: Rect) {
-        let tile_count = self.tiles.len();
-        if tile_count == 0 {
-            return;
+    /// The split tree, if there are any tiles
+    pub fn layout(&self) -> Option<&TileNode> {
+        self.root.as_ref()
+    }
+
+    /// Replace the split tree, adding tiles for its new leaves and dropping
+    /// those no longer in it
+    pub fn set_layout(&mut self, root: TileNode) {
+        let leaves = root.leaves();
+        self.tiles.retain(|id, _| leaves.contains(id));
+        for &id in &leaves {
+            self.tiles.entry(id).or_insert_with(|| Tile { id, ..Tile::new("Tile".to_string(), Rect::ZERO) });
+        }
+        self.root = Some(root);
+        self.zoomed_tile = self.zoomed_tile.filter(|id| leaves.contains(id));
+        match self.active_tile.filter(|id| leaves.contains(id)).or(leaves.first().copied()) {
+            Some(active) => {
+                self.set_active_view(active);
+            }
+            None => self.active_tile = None,
         }
+    }
 
-        let total_margin = self.tile_margin * (tile_count as f32 - 1.0);
+    /// Split `view_id` in two, putting a new tile after it (right of or
+    /// below it), and return the new tile's ID
+    pub fn split_view(&mut self, view_id: Uuid, direction: TileDirection, title: String) -> Option<Uuid> {
+        if !self.tiles.contains_key(&view_id) {
+            return None;
+        }
+        let tile = Tile::new(title, Rect::ZERO);
+        let id = tile.id;
+        if !self.root.as_mut()?.split_leaf(view_id, id, direction, false) {
+            return None;
+        }
+        self.tiles.insert(id, tile);
+        Some(id)
+    }
 
-        let tiles: Vec<_> = self.tiles.values_mut().collect();
+    /// The nearest tile in `direction` from `view_id`, by tile geometry,
+    /// preferring the one sharing the longest edge with it
+    pub fn neighbor(&self, view_id: Uuid, direction: PaneDirection) -> Option<Uuid> {
+        let from = self.tiles.get(&view_id)?.rect;
+        self.tiles.values()
+            .filter(|tile| tile.id != view_id)
+            .filter_map(|tile| {
+                let rect = tile.rect;
+                let (gap, overlap) = match direction {
+                    PaneDirection::Left => (from.min.x - rect.max.x, from.y_range().intersection(rect.y_range())),
+                    PaneDirection::Right => (rect.min.x - from.max.x, from.y_range().intersection(rect.y_range())),
+                    PaneDirection::Up => (from.min.y - rect.max.y, from.x_range().intersection(rect.x_range())),
+                    PaneDirection::Down => (rect.min.y - from.max.y, from.x_range().intersection(rect.x_range())),
+                };
+                (gap >= -0.5 && overlap.span() > 0.0).then_some((tile.id, gap, overlap.span()))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(b.2.total_cmp(&a.2)))
+            .map(|(id, _, _)| id)
+    }
 
-        match self.layout_direction {
-            TileDirection::Horizontal => {
-                let tile_width = (available_rect.width() - total_margin) / tile_count as f32;
-                for (i, tile) in tiles.into_iter().enumerate() {
-                    let x = available_rect.min.x + (tile_width + self.tile_margin) * i as f32;
-                    tile.rect = Rect::from_min_size(
-                        Pos2::new(x, available_rect.min.y),
-                        Vec2::new(tile_width, available_rect.height())
-                    );
+    /// Move focus to the neighbor of the focused tile in `direction`
+    pub fn focus_direction(&mut self, direction: PaneDirection) -> bool {
+        match self.active_tile.and_then(|active| self.neighbor(active, direction)) {
+            Some(neighbor) => self.set_active_view(neighbor),
+            None => false,
+        }
+    }
+
+    /// Exchange the focused tile with its neighbor in `direction`; focus
+    /// moves along with it
+    pub fn swap_with_neighbor(&mut self, direction: PaneDirection) -> bool {
+        let Some(active) = self.active_tile else {
+            return false;
+        };
+        let (Some(neighbor), Some(root)) = (self.neighbor(active, direction), self.root.as_mut()) else {
+            return false;
+        };
+        root.swap_leaves(active, neighbor);
+        true
+    }
+
+    /// Take the focused tile out of the layout and split its neighbor in
+    /// `direction` with it, or put it along that whole edge if there's none
+    pub fn move_to_split(&mut self, direction: PaneDirection) -> bool {
+        let Some(active) = self.active_tile else {
+            return false;
+        };
+        let neighbor = self.neighbor(active, direction);
+        let Some(root) = self.root.take() else {
+            return false;
+        };
+        let Some(mut rest) = root.clone().remove_leaf(active) else {
+            // The only tile has nowhere to go
+            self.root = Some(root);
+            return false;
+        };
+
+        let before = direction.is_backward();
+        match neighbor {
+            Some(neighbor) => {
+                rest.split_leaf(neighbor, active, direction.axis(), before);
+            }
+            None => rest = TileNode::split(direction.axis(), rest, TileNode::Leaf(active), before),
+        }
+        self.root = Some(rest);
+        true
+    }
+
+    /// Move the divider nearest the focused tile along `direction`'s axis
+    /// one step that way
+    pub fn resize_active(&mut self, direction: PaneDirection) -> bool {
+        let (Some(active), Some(root)) = (self.active_tile, self.root.as_mut()) else {
+            return false;
+        };
+        let Some(path) = root.path_to(active) else {
+            return false;
+        };
+        for depth in (0..path.len()).rev() {
+            let split_path = &path[..depth];
+            let Some(TileNode::Split { direction: axis, ratio, .. }) = root.node_at_mut(split_path) else {
+                continue;
+            };
+            if *axis != direction.axis() {
+                continue;
+            }
+
+            // Steps are in points, so use the size the split last had
+            let divider = self.dividers.iter().find(|divider| divider.path == split_path);
+            let (step, min_ratio, max_ratio) = match divider {
+                Some(divider) => {
+                    let extent = match axis {
+                        TileDirection::Horizontal => divider.parent.width(),
+                        TileDirection::Vertical => divider.parent.height(),
+                    };
+                    (RESIZE_STEP / (extent - self.tile_margin).max(1.0), divider.min_ratio, divider.max_ratio)
                 }
+                None => (0.05, 0.0, 1.0),
+            };
+            let step = if direction.is_backward() { -step } else { step };
+            *ratio = (*ratio + step).clamp(min_ratio, max_ratio);
+            return true;
+        }
+        false
+    }
+
+    /// Show the focused tile alone over the whole area, or go back to the
+    /// layout if it already is
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed_tile = match self.zoomed_tile {
+            Some(_) => None,
+            None => self.active_tile,
+        };
+    }
+
+    pub fn zoomed_tile(&self) -> Option<Uuid> {
+        self.zoomed_tile
+    }
+
+    /// Names and unsaved-changes markers for the tabs' actors
+    pub fn set_tab_labels(&mut self, labels: HashMap<Uuid, TabLabel>) {
+        self.tab_labels = labels;
+    }
+
+    /// The actors in `view_id`'s tabs, in tab bar order
+    pub fn tabs(&self, view_id: Uuid) -> &[Uuid] {
+        self.tiles.get(&view_id).map_or(&[], |tile| &tile.tabs)
+    }
+
+    pub fn select_tab(&mut self, view_id: Uuid, index: usize) -> bool {
+        match self.tiles.get_mut(&view_id) {
+            Some(tile) if index < tile.tabs.len() => {
+                tile.active_tab = index;
+                true
             }
-            TileDirection::Vertical => {
-                let tile_height = (available_rect.height() - total_margin) / tile_count as f32;
-                for (i, tile) in tiles.into_iter().enumerate() {
-                    let y = available_rect.min.y + (tile_height + self.tile_margin) * i as f32;
-                    tile.rect = Rect::from_min_size(
-                        Pos2::new(available_rect.min.x, y),
-                        Vec2::new(available_rect.width(), tile_height)
-                    );
+            _ => false,
+        }
+    }
+
+    /// Move `actor_id`'s tab from `from_view` to `index` in `to_view`'s tab
+    /// bar, showing and focusing it there
+    ///
+    /// A tile whose last tab is dragged to another tile goes away.
+    pub fn move_tab(&mut self, from_view: Uuid, actor_id: Uuid, to_view: Uuid, index: usize) -> bool {
+        let already_there = self.tiles.get(&to_view).map(|tile| from_view != to_view && tile.tabs.contains(&actor_id));
+        if already_there != Some(false) {
+            return false;
+        }
+        let Some(old_index) = self.tiles.get_mut(&from_view).and_then(|tile| tile.remove_tab(actor_id)) else {
+            return false;
+        };
+        let emptied = self.tiles.get(&from_view).is_some_and(|tile| tile.tabs.is_empty());
+        if let Some(tile) = self.tiles.get_mut(&to_view) {
+            // Dropping past its old place in the same bar shifts the tabs after it
+            let index = if from_view == to_view && old_index < index { index - 1 } else { index };
+            let index = index.min(tile.tabs.len());
+            tile.tabs.insert(index, actor_id);
+            tile.active_tab = index;
+        }
+        if emptied && from_view != to_view {
+            self.remove_view(from_view);
+        }
+        self.set_active_view(to_view);
+        true
+    }
+
+    fn recalculate_layout(&mut self, available_rect: Rect) {
+        self.dividers.clear();
+        let Some(root) = &self.root else {
+            return;
+        };
+
+        let mut rects = HashMap::new();
+        root.layout(available_rect, self.tile_margin, self.min_tile_size, &mut rects, &mut self.dividers, &mut Vec::new());
+        for (id, rect) in rects {
+            if let Some(tile) = self.tiles.get_mut(&id) {
+                tile.rect = rect;
+            }
+        }
+    }
+
+    /// Drag, hover and double-click (to even out) handling for the dividers
+    fn interact_dividers(&mut self, ui: &mut egui::Ui) {
+        for divider in self.dividers.clone() {
+            let id = ui.id().with(("tile_divider", &divider.path));
+            let response = ui.interact(divider.rect.expand(2.0), id, egui::Sense::click_and_drag());
+
+            let mut ratio = None;
+            if response.double_clicked() {
+                ratio = Some(0.5_f32.clamp(divider.min_ratio, divider.max_ratio));
+            } else if response.dragged() {
+                ratio = response.interact_pointer_pos().map(|pos| divider.ratio_at(pos, self.tile_margin));
+            }
+            if let Some(new_ratio) = ratio {
+                if let Some(TileNode::Split { ratio, .. }) = self.root.as_mut().and_then(|root| root.node_at_mut(&divider.path)) {
+                    *ratio = new_ratio;
                 }
             }
+
+            if response.hovered() || response.dragged() {
+                ui.ctx().set_cursor_icon(match divider.direction {
+                    TileDirection::Horizontal => egui::CursorIcon::ResizeHorizontal,
+                    TileDirection::Vertical => egui::CursorIcon::ResizeVertical,
+                });
+                ui.painter().rect_filled(divider.rect, 0.0, egui::Color32::from_rgb(100, 150, 255));
+            }
+        }
+    }
+
+    /// The tab bar of `tile`: clicking a tab shows it, its close button or a
+    /// middle click closes it, and dragging moves it within or between bars
+    fn render_tab_bar(&mut self, ui: &mut egui::Ui, tile: &Tile) {
+        if tile.tabs.is_empty() {
+            return;
+        }
+        let tabs: Vec<(Uuid, TabLabel)> = tile.tabs.iter()
+            .map(|&actor_id| (actor_id, self.tab_labels.get(&actor_id).cloned().unwrap_or_else(|| TabLabel::named("Tab"))))
+            .collect();
+        let response = tab_bar(ui, tile.rect, ui.id().with(("tile_tab", tile.id)), &tabs, tile.active_tab, tile.is_focused);
+
+        if let Some(index) = response.closed {
+            self.close_requests.push((tile.id, tile.tabs[index]));
+        }
+        if let Some(index) = response.drag_started {
+            self.dragged_tab = Some((tile.id, tile.tabs[index]));
+        }
+        if let Some(index) = response.selected {
+            self.select_tab(tile.id, index);
+            self.set_active_view(tile.id);
+        }
+        self.tab_bars.push(TabBar { tile: tile.id, rect: tile.rect, tabs: response.tabs });
+    }
+
+    /// Where a tab from `from_view` dropped at `pos` goes: the tile under
+    /// it and the place in its tab bar (the end, when dropped on another
+    /// tile's content)
+    fn tab_drop_target(&self, from_view: Uuid, pos: Pos2) -> Option<(Uuid, usize)> {
+        let bar = self.tab_bars.iter().rev().find(|bar| bar.rect.contains(pos))?;
+        if pos.y < bar.rect.min.y + TAB_BAR_HEIGHT {
+            Some((bar.tile, bar.tabs.iter().filter(|rect| rect.center().x < pos.x).count()))
+        } else {
+            (bar.tile != from_view).then_some((bar.tile, bar.tabs.len()))
+        }
+    }
+
+    /// Follow the pointer with the dragged tab, and move it where it's dropped
+    fn drag_tab(&mut self, ui: &mut egui::Ui) {
+        let Some((from_view, actor_id)) = self.dragged_tab else {
+            return;
+        };
+        let (pos, down) = ui.input(|i| (i.pointer.interact_pos(), i.pointer.primary_down()));
+        let target = pos.and_then(|pos| self.tab_drop_target(from_view, pos));
+        if !down {
+            self.dragged_tab = None;
+            if let Some((to_view, index)) = target {
+                self.move_tab(from_view, actor_id, to_view, index);
+            }
+            return;
+        }
+
+        let Some(pos) = pos else {
+            return;
+        };
+        let drop = target.and_then(|(to_view, index)| {
+            let bar = self.tab_bars.iter().find(|bar| bar.tile == to_view)?;
+            Some((bar.rect, bar.tabs.as_slice(), index))
+        });
+        let name = self.tab_labels.get(&actor_id).map_or("Tab", |label| label.name.as_str());
+        paint_dragged_tab(ui, name, pos, drop);
+    }
+
+    fn render_tile(ui: &mut egui::Ui, tile: &Tile, render_actor: &dyn Fn(&mut egui::Ui, Uuid)) {
+        // Draw tile background
+        let fill_color = if tile.is_focused {
+            egui::Color32::from_gray(60)
+        } else {
+            egui::Color32::from_gray(40)
+        };
+
+        ui.painter().rect_filled(
+            tile.rect,
+            egui::Rounding::same(2.0),
+            fill_color
+        );
+
+        // Draw tile border
+        let stroke_color = if tile.is_focused {
+            egui::Color32::from_rgb(100, 150, 255)
+        } else {
+            egui::Color32::from_gray(80)
+        };
+
+        ui.painter().rect_stroke(
+            tile.rect,
+            egui::Rounding::same(2.0),
+            egui::Stroke::new(1.0, stroke_color)
+        );
+
+        // Render tile content, below the tab bar
+        let mut content_rect = tile.rect;
+        if !tile.tabs.is_empty() {
+            content_rect.min.y = (content_rect.min.y + TAB_BAR_HEIGHT).min(content_rect.max.y);
         }
+        let content_rect = content_rect.shrink(4.0);
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(content_rect), |ui| {
+            ui.set_clip_rect(content_rect.intersect(ui.clip_rect()));
+            if let Some(actor_id) = tile.actor_id() {
+                render_actor(ui, actor_id);
+            } else {
+                ui.centered_and_justified(|ui| {
+                    ui.label(&tile.title);
+                });
+            }
+        });
     }
 
     pub fn get_tile(&self, id: Uuid) -> Option<&Tile> {
@@ -110,10 +909,27 @@ impl TilingSystem {
 }
 
 impl ViewSystem for TilingSystem {
+    /// Add a tile at the end of the layout, in the layout direction, sized
+    /// evenly with the tiles already in a row there
     fn create_view(&mut self, title: String) -> Uuid {
         let tile = Tile::new(title, Rect::ZERO);
         let id = tile.id;
         self.tiles.insert(id, tile);
+        self.root = Some(match self.root.take() {
+            None => TileNode::Leaf(id),
+            Some(root) => {
+                let count = match &root {
+                    TileNode::Split { direction, .. } if *direction == self.layout_direction => root.leaves().len(),
+                    _ => 1,
+                };
+                TileNode::Split {
+                    direction: self.layout_direction,
+                    ratio: count as f32 / (count + 1) as f32,
+                    first: Box::new(root),
+                    second: Box::new(TileNode::Leaf(id)),
+                }
+            }
+        });
         id
     }
 
@@ -132,32 +948,65 @@ impl ViewSystem for TilingSystem {
             if let Some(tile) = self.tiles.get_mut(&view_id) {
                 tile.is_focused = true;
                 self.active_tile = Some(view_id);
+                // A zoomed layout keeps showing whichever tile has focus
+                if self.zoomed_tile.is_some() {
+                    self.zoomed_tile = Some(view_id);
+                }
                 return true;
             }
         }
         false
     }
 
+    /// Show the actor in a new tab of the tile (or its existing one)
     fn attach_actor_to_view(&mut self, view_id: Uuid, actor_id: Uuid) -> bool {
         if let Some(tile) = self.tiles.get_mut(&view_id) {
-            tile.actor_id = Some(actor_id);
+            tile.add_tab(actor_id);
             true
         } else {
             false
         }
     }
 
+    /// Take the shown tab out of the tile
     fn detach_actor_from_view(&mut self, view_id: Uuid) -> bool {
-        if let Some(tile) = self.tiles.get_mut(&view_id) {
-            tile.actor_id = None;
-            true
-        } else {
-            false
+        match self.tiles.get_mut(&view_id) {
+            Some(tile) => match tile.actor_id() {
+                Some(actor_id) => tile.remove_tab(actor_id).is_some(),
+                None => true,
+            },
+            None => false,
         }
     }
 
     fn get_view_actor(&self, view_id: Uuid) -> Option<Uuid> {
-        self.tiles.get(&view_id).and_then(|tile| tile.actor_id)
+        self.tiles.get(&view_id).and_then(Tile::actor_id)
+    }
+
+    fn get_view_actors(&self, view_id: Uuid) -> Vec<Uuid> {
+        self.tabs(view_id).to_vec()
+    }
+
+    /// Take `actor_id`'s tab out of `view_id`, leaving the tile in place
+    fn remove_tab(&mut self, view_id: Uuid, actor_id: Uuid) -> bool {
+        self.tiles.get_mut(&view_id).and_then(|tile| tile.remove_tab(actor_id)).is_some()
+    }
+
+    fn take_close_requests(&mut self) -> Vec<(Uuid, Uuid)> {
+        std::mem::take(&mut self.close_requests)
+    }
+
+    /// Show the next (or previous) tab of the focused tile, wrapping around
+    fn cycle_tab(&mut self, forward: bool) -> bool {
+        let Some(tile) = self.active_tile.and_then(|id| self.tiles.get_mut(&id)) else {
+            return false;
+        };
+        let count = tile.tabs.len();
+        if count < 2 {
+            return false;
+        }
+        tile.active_tab = if forward { (tile.active_tab + 1) % count } else { (tile.active_tab + count - 1) % count };
+        true
     }
 
     fn render_views(&mut self, ui: &mut egui::Ui, render_actor: &dyn Fn(&mut egui::Ui, Uuid)) {
@@ -165,83 +1014,96 @@ impl ViewSystem for TilingSystem {
 
         // Recalculate layout
         self.recalculate_layout(available_rect);
+        self.tab_bars.clear();
 
-        // Collect tile data to avoid borrowing issues
-        let tiles_data: Vec<_> = self.tiles.iter()
-            .map(|(&id, tile)| (id, tile.clone()))
-            .collect();
-
-        // Render each tile
-        for (tile_id, tile) in tiles_data {
-            let tile_response = ui.allocate_rect(tile.rect, egui::Sense::click());
+        // A zoomed tile covers everything; the layout stays as it was for
+        // when it's unzoomed
+        if let Some(zoomed) = self.zoomed_tile.filter(|id| self.tiles.contains_key(id)) {
+            if let Some(mut tile) = self.tiles.get(&zoomed).cloned() {
+                tile.rect = available_rect;
+                Self::render_tile(ui, &tile, render_actor);
+                self.render_tab_bar(ui, &tile);
+            }
+            self.drag_tab(ui);
+            return;
+        }
 
-            // Handle tile selection
-            if tile_response.clicked() {
+        // Pressing anywhere in a tile focuses it, even over its content
+        // (but not through floating panes or windows over it)
+        let pressed_at = ui.input(|i| if i.pointer.any_pressed() { i.pointer.interact_pos() } else { None })
+            .filter(|&pos| ui.ctx().layer_id_at(pos).is_none_or(|layer| layer == ui.layer_id()));
+        let on_divider = pressed_at.is_some_and(|pos| self.dividers.iter().any(|divider| divider.rect.expand(2.0).contains(pos)));
+        if let Some(pos) = pressed_at.filter(|_| !on_divider) {
+            let pressed_tile = self.tiles.values().find(|tile| tile.rect.contains(pos)).map(|tile| tile.id);
+            if let Some(tile_id) = pressed_tile {
                 self.set_active_view(tile_id);
             }
+        }
 
-            // Draw tile background
-            let fill_color = if tile.is_focused {
-                egui::Color32::from_gray(60)
-            } else {
-                egui::Color32::from_gray(40)
-            };
-
-            ui.painter().rect_filled(
-                tile.rect,
-                egui::Rounding::same(2.0),
-                fill_color
-            );
-
-            // Draw tile border
-            let stroke_color = if tile.is_focused {
-                egui::Color32::from_rgb(100, 150, 255)
-            } else {
-                egui::Color32::from_gray(80)
-            };
+        // Collect tile data in layout order to avoid borrowing issues
+        let tiles_data: Vec<_> = self.get_view_ids().into_iter()
+            .filter_map(|id| self.tiles.get(&id).cloned())
+            .collect();
 
-            ui.painter().rect_stroke(
-                tile.rect,
-                egui::Rounding::same(2.0),
-                egui::Stroke::new(1.0, stroke_color)
-            );
-
-            // Render tile content
-            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(tile.rect.shrink(4.0)), |ui| {
-                if let Some(actor_id) = tile.actor_id {
-                    render_actor(ui, actor_id);
-                } else {
-                    ui.centered_and_justified(|ui| {
-                        ui.label(&tile.title);
-                    });
-                }
-            });
+        // Render each tile
+        for tile in tiles_data {
+            Self::render_tile(ui, &tile, render_actor);
+            self.render_tab_bar(ui, &tile);
         }
+
+        // Dividers go last so they sit above the tiles' content
+        self.interact_dividers(ui);
+        self.drag_tab(ui);
     }
 
+    /// Ctrl+Alt+Arrow moves focus between tiles, Ctrl+Alt+Shift+Arrow swaps the
+    /// focused tile with a neighbor, Ctrl+Alt+F zooms it and
+    /// Ctrl+PageUp/PageDown cycle its tabs (Ctrl+Tab cycling is handled by
+    /// the IDE for every view system)
     fn handle_input(&mut self, ui: &mut egui::Ui) -> bool {
-        // Handle keyboard shortcuts for tile management
-        let handled = ui.ctx().input(|i| {
-            if i.key_pressed(egui::Key::Tab) && i.modifiers.ctrl {
-                // Cycle through tiles
-                if let Some(current_id) = self.active_tile {
-                    let tile_ids: Vec<_> = self.tiles.keys().cloned().collect();
-                    if let Some(current_index) = tile_ids.iter().position(|&id| id == current_id) {
-                        let next_index = (current_index + 1) % tile_ids.len();
-                        let next_id = tile_ids[next_index];
-                        return Some(next_id);
-                    }
-                }
-            }
-            None
+        let arrows = [
+            (egui::Key::ArrowLeft, PaneDirection::Left),
+            (egui::Key::ArrowRight, PaneDirection::Right),
+            (egui::Key::ArrowUp, PaneDirection::Up),
+            (egui::Key::ArrowDown, PaneDirection::Down),
+        ];
+        // Plain Alt+Arrow is left to the editors
+        let focus_modifiers = egui::Modifiers::COMMAND | egui::Modifiers::ALT;
+        let swap_modifiers = focus_modifiers | egui::Modifiers::SHIFT;
+        // Exact matches, so editor chords with extra modifiers still get through
+        let pressed = |i: &mut egui::InputState, modifiers: egui::Modifiers, key: egui::Key| {
+            i.modifiers.matches_exact(modifiers) && i.consume_key(modifiers, key)
+        };
+
+        let (focus, swap, zoom, tab) = ui.ctx().input_mut(|i| {
+            let focus = arrows.iter().find(|(key, _)| pressed(i, focus_modifiers, *key)).map(|(_, direction)| *direction);
+            let swap = arrows.iter().find(|(key, _)| pressed(i, swap_modifiers, *key)).map(|(_, direction)| *direction);
+            let zoom = pressed(i, focus_modifiers, egui::Key::F);
+            let tab = if pressed(i, egui::Modifiers::COMMAND, egui::Key::PageDown) {
+                Some(true)
+            } else if pressed(i, egui::Modifiers::COMMAND, egui::Key::PageUp) {
+                Some(false)
+            } else {
+                None
+            };
+            (focus, swap, zoom, tab)
         });
 
-        if let Some(next_id) = handled {
-            self.set_active_view(next_id);
-            true
-        } else {
-            false
+        let mut handled = false;
+        if let Some(direction) = focus {
+            handled |= self.focus_direction(direction);
+        }
+        if let Some(direction) = swap {
+            handled |= self.swap_with_neighbor(direction);
+        }
+        if zoom {
+            self.toggle_zoom();
+            handled = true;
         }
+        if let Some(forward) = tab {
+            handled |= self.cycle_tab(forward);
+        }
+        handled
     }
 
     fn view_count(&self) -> usize {
@@ -250,9 +1112,16 @@ impl ViewSystem for TilingSystem {
 
     fn remove_view(&mut self, view_id: Uuid) -> bool {
         if self.tiles.remove(&view_id).is_some() {
+            // The tile's neighbor in the split takes over its space and focus
+            let sibling = self.root.as_ref().and_then(|root| root.sibling_leaf(view_id));
+            self.root = self.root.take().and_then(|root| root.remove_leaf(view_id));
+            if self.zoomed_tile == Some(view_id) {
+                self.zoomed_tile = None;
+            }
+
             // If we removed the active tile, select another one
             if self.active_tile == Some(view_id) {
-                self.active_tile = self.tiles.keys().next().cloned();
+                self.active_tile = sibling.or_else(|| self.get_view_ids().first().copied());
                 if let Some(new_active) = self.active_tile {
                     self.set_active_view(new_active);
                 }
@@ -264,7 +1133,7 @@ impl ViewSystem for TilingSystem {
     }
 
     fn get_view_ids(&self) -> Vec<Uuid> {
-        self.tiles.keys().cloned().collect()
+        self.root.as_ref().map(TileNode::leaves).unwrap_or_default()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -299,4 +1168,4 @@ impl Transformable for TilingSystem {
             can_pan: false,
         }
     }
-}
\ No newline at end of file
+}
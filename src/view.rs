@@ -1,9 +1,11 @@
-use crate::actor::ActorManager;
-use egui;
+use crate::tiling_system::{self, TabLabel, TAB_BAR_HEIGHT};
+use crate::view_system::{ViewSystem, Transformable, TransformInfo};
+use egui::{self, Pos2, Rect, Vec2};
 use uuid::Uuid;
+use std::any::Any;
 use std::collections::HashMap;
 
-/// View primitive - can house actors and be arranged in panels/tabs/windows
+/// View primitive - can house actors and be arranged in splits and tabs
 #[derive(Clone)]
 pub struct View {
     pub id: Uuid,
@@ -12,6 +14,17 @@ pub struct View {
     pub actor_id: Option<Uuid>,
 }
 
+impl View {
+    pub fn leaf(title: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            title,
+            view_type: ViewType::Leaf,
+            actor_id: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ViewType {
     /// A single view containing an actor
@@ -27,32 +40,32 @@ pub enum ViewType {
     Split {
         direction: SplitDirection,
         children: Vec<Uuid>,
+        /// Each child's share of the split, summing to 1
         ratios: Vec<f32>,
     },
-
-    /// Floating window
-    Floating {
-        position: egui::Pos2,
-        size: egui::Vec2,
-    },
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SplitDirection {
     Horizontal, // Split left/right
     Vertical,   // Split top/bottom
 }
 
+/// Width of the resize borders along the inside of each view's edges
+const BORDER_WIDTH: f32 = 8.0;
+
+/// Smallest a view can be resized to along either axis
+const MIN_VIEW_SIZE: f32 = 50.0;
+
 /// Resize state for tracking active resize operations
 #[derive(Debug, Clone)]
 struct ResizeState {
     pub view_id: Uuid,
     pub resize_type: ResizeType,
-    pub start_pos: egui::Pos2,
-    pub original_size: egui::Vec2,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The edge or corner of a view a resize border is on
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum ResizeType {
     Top,
     Bottom,
@@ -64,45 +77,117 @@ enum ResizeType {
     BottomRight,
 }
 
+impl ResizeType {
+    const ALL: [ResizeType; 8] = [
+        ResizeType::Top,
+        ResizeType::Bottom,
+        ResizeType::Left,
+        ResizeType::Right,
+        ResizeType::TopLeft,
+        ResizeType::TopRight,
+        ResizeType::BottomLeft,
+        ResizeType::BottomRight,
+    ];
+
+    /// Which sides the border moves: -1 for the left or top, 1 for the
+    /// right or bottom, 0 for neither
+    fn sides(self) -> (i8, i8) {
+        match self {
+            ResizeType::Top => (0, -1),
+            ResizeType::Bottom => (0, 1),
+            ResizeType::Left => (-1, 0),
+            ResizeType::Right => (1, 0),
+            ResizeType::TopLeft => (-1, -1),
+            ResizeType::TopRight => (1, -1),
+            ResizeType::BottomLeft => (-1, 1),
+            ResizeType::BottomRight => (1, 1),
+        }
+    }
+
+    fn cursor(self) -> egui::CursorIcon {
+        match self {
+            ResizeType::Top | ResizeType::Bottom => egui::CursorIcon::ResizeVertical,
+            ResizeType::Left | ResizeType::Right => egui::CursorIcon::ResizeHorizontal,
+            ResizeType::TopLeft | ResizeType::BottomRight => egui::CursorIcon::ResizeNwSe,
+            ResizeType::TopRight | ResizeType::BottomLeft => egui::CursorIcon::ResizeNeSw,
+        }
+    }
+
+    /// The border's strip of `rect`
+    fn border(self, rect: Rect) -> Rect {
+        let (x_side, y_side) = self.sides();
+        let span = |side: i8, min: f32, max: f32| match side {
+            -1 => egui::Rangef::new(min, min + BORDER_WIDTH),
+            1 => egui::Rangef::new(max - BORDER_WIDTH, max),
+            _ => egui::Rangef::new(min + BORDER_WIDTH, max - BORDER_WIDTH),
+        };
+        Rect::from_x_y_ranges(span(x_side, rect.min.x, rect.max.x), span(y_side, rect.min.y, rect.max.y))
+    }
+}
+
+/// Where a dragged tab goes if dropped
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum BorderEdge {
-    Top,
-    Bottom,
-    Left,
-    Right,
-    TopLeft,
-    TopRight,
-    BottomLeft,
-    BottomRight,
+enum TabDrop {
+    /// Into a tab container's bar, at `index`
+    Tab { container: Uuid, index: usize },
+    /// Into a new split with `target`, before (left of or above) it or after
+    Split { target: Uuid, direction: SplitDirection, before: bool },
 }
 
-/// Manages the view hierarchy
+/// Nested view system: a tree of splits (of any number of views, each with
+/// its share) and tab containers, with leaf views holding the actors
+///
+/// Dragging a view's border moves the boundary between it and its
+/// neighbor in the nearest split along that axis.
 pub struct ViewManager {
     pub views: Vec<View>,
-    root_view: Uuid,
-    active_view: Uuid,
-    view_sizes: HashMap<Uuid, egui::Vec2>,
+    root_view: Option<Uuid>,
+    /// The leaf view with focus
+    active_view: Option<Uuid>,
+    /// Where each shown view was laid out on the last frame
+    view_rects: HashMap<Uuid, Rect>,
     active_resize: Option<ResizeState>,
+    tab_labels: HashMap<Uuid, TabLabel>,
+    /// Where each tab container's tabs were drawn on the last frame
+    tab_rects: HashMap<Uuid, Vec<Rect>>,
+    /// The leaf whose tab is being dragged
+    dragged_tab: Option<Uuid>,
+    /// Tabs whose close button was clicked, as `(leaf, actor)`
+    close_requests: Vec<(Uuid, Uuid)>,
 }
 
 impl ViewManager {
     pub fn new() -> Self {
-        let root_view = View {
-            id: Uuid::new_v4(),
-            title: "Main".to_string(),
-            view_type: ViewType::Leaf,
-            actor_id: None,
-        };
-
+        let root_view = View::leaf("Main".to_string());
         let root_id = root_view.id;
 
         Self {
             views: vec![root_view],
-            root_view: root_id,
-            active_view: root_id,
-            view_sizes: HashMap::new(),
+            root_view: Some(root_id),
+            active_view: Some(root_id),
+            view_rects: HashMap::new(),
             active_resize: None,
+            tab_labels: HashMap::new(),
+            tab_rects: HashMap::new(),
+            dragged_tab: None,
+            close_requests: Vec::new(),
+        }
+    }
+
+    /// Nested views of `views`, which make up one tree under `root`,
+    /// focusing the first leaf shown in it
+    pub fn from_views(views: Vec<View>, root: Uuid) -> Self {
+        let mut manager = Self { views, root_view: Some(root), active_view: None, ..Self::new() };
+        let mut shown = root;
+        while let Some(view) = manager.get_view(shown) {
+            shown = match &view.view_type {
+                ViewType::Leaf => break,
+                ViewType::Tabs { children, active_tab } => children[*active_tab],
+                ViewType::Split { children, .. } => children[0],
+            };
         }
+        manager.active_view = Some(shown);
+        manager
     }
 
     pub fn get_view(&self, id: Uuid) -> Option<&View> {
@@ -113,355 +198,824 @@ impl ViewManager {
         self.views.iter_mut().find(|v| v.id == id)
     }
 
-    pub fn active_view(&self) -> Uuid {
-        self.active_view
+    /// The outermost view, if there are any
+    pub fn root(&self) -> Option<Uuid> {
+        self.root_view
     }
 
-    pub fn set_active_view(&mut self, id: Uuid) {
-        self.active_view = id;
+    /// Names and unsaved-changes markers for the tabs' actors
+    pub fn set_tab_labels(&mut self, labels: HashMap<Uuid, TabLabel>) {
+        self.tab_labels = labels;
     }
 
-    pub fn attach_actor_to_view(&mut self, view_id: Uuid, actor_id: Uuid) {
-        if let Some(view) = self.get_view_mut(view_id) {
-            view.actor_id = Some(actor_id);
+    fn children(&self, id: Uuid) -> &[Uuid] {
+        match self.get_view(id).map(|view| &view.view_type) {
+            Some(ViewType::Tabs { children, .. } | ViewType::Split { children, .. }) => children,
+            _ => &[],
         }
     }
 
-    pub fn split_view(&mut self, view_id: Uuid, direction: SplitDirection) -> Option<Uuid> {
-        // Find the view to split
-        let view_idx = self.views.iter().position(|v| v.id == view_id)?;
-
-        // Create two new child views
-        let child1 = View {
-            id: Uuid::new_v4(),
-            title: "Split 1".to_string(),
-            view_type: ViewType::Leaf,
-            actor_id: self.views[view_idx].actor_id,
-        };
-
-        let child2 = View {
-            id: Uuid::new_v4(),
-            title: "Split 2".to_string(),
-            view_type: ViewType::Leaf,
-            actor_id: None,
-        };
+    fn parent_of(&self, id: Uuid) -> Option<Uuid> {
+        self.views.iter().find(|view| self.children(view.id).contains(&id)).map(|view| view.id)
+    }
 
-        let child1_id = child1.id;
-        let child2_id = child2.id;
+    fn is_leaf(&self, id: Uuid) -> bool {
+        self.get_view(id).is_some_and(|view| matches!(view.view_type, ViewType::Leaf))
+    }
 
-        // Add children to view list
-        self.views.push(child1);
-        self.views.push(child2);
+    /// The leaves of tab container `id` in tab order, or `id` itself if it's
+    /// a leaf
+    pub fn tab_leaves(&self, id: Uuid) -> Vec<Uuid> {
+        if self.is_leaf(id) {
+            return vec![id];
+        }
+        match self.get_view(id).map(|view| &view.view_type) {
+            Some(ViewType::Tabs { children, .. }) => children.iter().copied().filter(|&child| self.is_leaf(child)).collect(),
+            _ => Vec::new(),
+        }
+    }
 
-        // Convert parent to split container
-        if let Some(parent) = self.get_view_mut(view_id) {
-            parent.view_type = ViewType::Split {
-                direction,
-                children: vec![child1_id, child2_id],
-                ratios: vec![0.5, 0.5],
-            };
-            parent.actor_id = None;
+    /// The leaf views under `id`, in order
+    fn leaves(&self, id: Uuid) -> Vec<Uuid> {
+        if self.is_leaf(id) {
+            return vec![id];
         }
+        self.children(id).iter().flat_map(|&child| self.leaves(child)).collect()
+    }
 
-        Some(child2_id)
+    /// Put `new` where `old` is in the tree
+    fn replace_in_parent(&mut self, old: Uuid, new: Uuid) {
+        let parent = self.parent_of(old);
+        match parent.and_then(|parent| self.get_view_mut(parent)) {
+            Some(View { view_type: ViewType::Tabs { children, .. } | ViewType::Split { children, .. }, .. }) => {
+                for child in children.iter_mut().filter(|child| **child == old) {
+                    *child = new;
+                }
+            }
+            _ => self.root_view = Some(new),
+        }
     }
 
-    pub fn render(&mut self, ui: &mut egui::Ui, actors: &mut ActorManager) {
-        let root_view = self.root_view;
-        self.render_view(root_view, ui, actors);
+    /// Put a new leaf beside `view_id` (right of or below it), sharing its
+    /// space, and return the new leaf's ID
+    pub fn split_view(&mut self, view_id: Uuid, direction: SplitDirection) -> Option<Uuid> {
+        self.get_view(view_id)?;
+        let leaf = View::leaf("Split".to_string());
+        let leaf_id = leaf.id;
+        self.views.push(leaf);
+        self.insert_beside(view_id, leaf_id, direction, false)?;
+        Some(leaf_id)
     }
 
-    fn render_view_with_borders<F>(&mut self, ui: &mut egui::Ui, view_id: Uuid, is_active: bool, content_renderer: F)
-    where
-        F: FnOnce(&mut Self, &mut egui::Ui),
-    {
-        let border_width = 8.0; // Invisible border width for resize detection
-        let available_rect = ui.available_rect_before_wrap();
+    /// Put view `new`, which isn't in the tree, beside `view_id` (before or
+    /// after it), the two sharing its space
+    fn insert_beside(&mut self, view_id: Uuid, new: Uuid, direction: SplitDirection, before: bool) -> Option<()> {
+        // A split the same way just gets another child
+        let parent = self.parent_of(view_id);
+        if let Some(View { view_type: ViewType::Split { direction: parent_direction, children, ratios }, .. }) =
+            parent.and_then(|parent| self.get_view_mut(parent))
+        {
+            if *parent_direction == direction {
+                let index = children.iter().position(|&child| child == view_id)?;
+                ratios[index] /= 2.0;
+                let at = if before { index } else { index + 1 };
+                children.insert(at, new);
+                ratios.insert(at, ratios[index]);
+                return Some(());
+            }
+        }
 
-        // Get or initialize view size
-        let current_size = self.view_sizes.get(&view_id).copied().unwrap_or(available_rect.size());
+        let split = View {
+            view_type: ViewType::Split {
+                direction,
+                children: if before { vec![new, view_id] } else { vec![view_id, new] },
+                ratios: vec![0.5, 0.5],
+            },
+            ..View::leaf("Split".to_string())
+        };
+        let split_id = split.id;
+        self.replace_in_parent(view_id, split_id);
+        self.views.push(split);
+        Some(())
+    }
 
-        // Update stored size if it has changed significantly
-        if (current_size - available_rect.size()).length() > 1.0 {
-            self.view_sizes.insert(view_id, available_rect.size());
+    /// Put a new leaf in a tab after `view_id`, turning it into a tab
+    /// container if it isn't in one, and return the new leaf's ID
+    pub fn add_tab(&mut self, view_id: Uuid, title: String) -> Option<Uuid> {
+        self.get_view(view_id)?;
+        let leaf = View::leaf(title);
+        let leaf_id = leaf.id;
+        self.views.push(leaf);
+
+        let parent = self.parent_of(view_id);
+        if let Some(View { view_type: ViewType::Tabs { children, active_tab }, .. }) =
+            parent.and_then(|parent| self.get_view_mut(parent))
+        {
+            let index = children.iter().position(|&child| child == view_id)? + 1;
+            children.insert(index, leaf_id);
+            *active_tab = index;
+            return Some(leaf_id);
         }
 
-        // Create resize areas around the content
-        let content_rect = available_rect.shrink(border_width);
+        let tabs = View {
+            view_type: ViewType::Tabs { children: vec![view_id, leaf_id], active_tab: 1 },
+            ..View::leaf("Tabs".to_string())
+        };
+        let tabs_id = tabs.id;
+        self.replace_in_parent(view_id, tabs_id);
+        self.views.push(tabs);
+        Some(leaf_id)
+    }
 
-        // Handle resize interactions on borders
-        let mut hovered_edges = Vec::new();  // Track which edges are being hovered
+    /// Show the tab of `container` at `index`, focusing its first leaf
+    pub fn select_tab(&mut self, container: Uuid, index: usize) -> bool {
+        let Some(View { view_type: ViewType::Tabs { children, active_tab }, .. }) = self.get_view_mut(container) else {
+            return false;
+        };
+        let Some(&child) = children.get(index) else {
+            return false;
+        };
+        *active_tab = index;
+        self.active_view = self.leaves(child).first().copied().or(self.active_view);
+        true
+    }
+
+    /// Take leaf `view_id` out of the tree; a container left with one view
+    /// is replaced by it
+    fn remove_leaf(&mut self, view_id: Uuid) -> bool {
+        if !self.is_leaf(view_id) {
+            return false;
+        }
+        let next = self.detach(view_id);
+        self.views.retain(|view| view.id != view_id);
+        self.view_rects.remove(&view_id);
+        if self.active_view == Some(view_id) {
+            self.active_view = next.and_then(|next| self.leaves(next).first().copied())
+                .or_else(|| self.get_view_ids().first().copied());
+        }
+        true
+    }
 
-        // Check for active resize or start new resize
-        if let Some(resize_state) = &self.active_resize {
-            if resize_state.view_id == view_id {
-                // Handle ongoing resize
-                let current_pos = ui.ctx().pointer_latest_pos().unwrap_or(resize_state.start_pos);
-                let delta = current_pos - resize_state.start_pos;
+    /// Take `view_id` out of its container, keeping the view, and return
+    /// the view shown in its place (its neighbor or the next tab)
+    fn detach(&mut self, view_id: Uuid) -> Option<Uuid> {
+        let Some(parent) = self.parent_of(view_id) else {
+            self.root_view = None;
+            return None;
+        };
 
-                // Calculate new size based on resize type
-                let mut new_size = resize_state.original_size;
-                match resize_state.resize_type {
-                    ResizeType::Right | ResizeType::TopRight | ResizeType::BottomRight => {
-                        new_size.x = (resize_state.original_size.x + delta.x).max(50.0);
-                    }
-                    ResizeType::Left | ResizeType::TopLeft | ResizeType::BottomLeft => {
-                        new_size.x = (resize_state.original_size.x - delta.x).max(50.0);
+        let mut next = None;
+        let mut remaining = Vec::new();
+        if let Some(view) = self.get_view_mut(parent) {
+            match &mut view.view_type {
+                ViewType::Split { children, ratios, .. } => {
+                    let index = children.iter().position(|&child| child == view_id).unwrap_or(0);
+                    children.remove(index);
+                    let share = ratios.remove(index);
+                    // The neighbor before it (or after, for the first) takes its space
+                    let neighbor = index.saturating_sub(1).min(children.len().saturating_sub(1));
+                    if let Some(ratio) = ratios.get_mut(neighbor) {
+                        *ratio += share;
                     }
-                    _ => {}
+                    next = children.get(neighbor).copied();
+                    remaining = children.clone();
                 }
-                match resize_state.resize_type {
-                    ResizeType::Bottom | ResizeType::BottomLeft | ResizeType::BottomRight => {
-                        new_size.y = (resize_state.original_size.y + delta.y).max(50.0);
-                    }
-                    ResizeType::Top | ResizeType::TopLeft | ResizeType::TopRight => {
-                        new_size.y = (resize_state.original_size.y - delta.y).max(50.0);
+                ViewType::Tabs { children, active_tab } => {
+                    let index = children.iter().position(|&child| child == view_id).unwrap_or(0);
+                    children.remove(index);
+                    if index < *active_tab || *active_tab >= children.len() {
+                        *active_tab = active_tab.saturating_sub(1);
                     }
-                    _ => {}
+                    next = children.get(*active_tab).copied();
+                    remaining = children.clone();
                 }
+                ViewType::Leaf => {}
+            }
+        }
 
-                // Update view size
-                self.view_sizes.insert(view_id, new_size);
+        if let [only] = remaining[..] {
+            self.replace_in_parent(parent, only);
+            self.views.retain(|view| view.id != parent);
+            self.view_rects.remove(&parent);
+        }
+        next
+    }
 
-                // End resize if mouse released
-                if !ui.ctx().input(|i| i.pointer.primary_down()) {
-                    self.active_resize = None;
+    /// Move the tab of leaf `view_id` where it was dropped, focusing it
+    fn move_tab(&mut self, view_id: Uuid, drop: TabDrop) -> bool {
+        if !self.is_leaf(view_id) || self.parent_of(view_id).is_none() {
+            return false;
+        }
+        match drop {
+            TabDrop::Tab { container, index } => {
+                let old_index = self.children(container).iter().position(|&child| child == view_id);
+                if old_index.is_none() {
+                    self.detach(view_id);
                 }
-            }
-        } else {
-            // Check for new resize starts
-            macro_rules! handle_resize_border {
-                ($border:expr, $sense:expr, $cursor:expr, $resize_type:expr, $edge_name:expr) => {
-                    let response = ui.allocate_rect($border, $sense);
-                    if response.hovered() {
-                        ui.ctx().set_cursor_icon($cursor);
-                        hovered_edges.push($edge_name);
-                    }
-                    if response.drag_started() {
-                        self.active_resize = Some(ResizeState {
-                            view_id,
-                            resize_type: $resize_type,
-                            start_pos: ui.ctx().pointer_latest_pos().unwrap_or(egui::Pos2::ZERO),
-                            original_size: current_size,
-                        });
+                let Some(View { view_type: ViewType::Tabs { children, active_tab }, .. }) = self.get_view_mut(container) else {
+                    return false;
+                };
+                // Dropping past its old place in the same bar shifts the tabs after it
+                let index = match old_index {
+                    Some(old_index) => {
+                        children.remove(old_index);
+                        if old_index < index { index - 1 } else { index }
                     }
+                    None => index,
                 };
+                let index = index.min(children.len());
+                children.insert(index, view_id);
+                *active_tab = index;
             }
+            TabDrop::Split { target, direction, before } => {
+                let next = self.detach(view_id);
+                // The tab container it left may have given way to its last tab
+                let target = if self.get_view(target).is_none() { next } else { Some(target) };
+                let Some(target) = target else {
+                    return false;
+                };
+                self.insert_beside(target, view_id, direction, before);
+            }
+        }
+        self.set_active_view(view_id)
+    }
 
-            // Top border
-            let top_border = egui::Rect::from_min_max(
-                available_rect.min,
-                egui::pos2(available_rect.max.x, available_rect.min.y + border_width)
-            );
-            handle_resize_border!(top_border, egui::Sense::drag(), egui::CursorIcon::ResizeVertical, ResizeType::Top, BorderEdge::Top);
-
-            // Bottom border
-            let bottom_border = egui::Rect::from_min_max(
-                egui::pos2(available_rect.min.x, available_rect.max.y - border_width),
-                available_rect.max
-            );
-            handle_resize_border!(bottom_border, egui::Sense::drag(), egui::CursorIcon::ResizeVertical, ResizeType::Bottom, BorderEdge::Bottom);
-
-            // Left border
-            let left_border = egui::Rect::from_min_max(
-                available_rect.min,
-                egui::pos2(available_rect.min.x + border_width, available_rect.max.y)
-            );
-            handle_resize_border!(left_border, egui::Sense::drag(), egui::CursorIcon::ResizeHorizontal, ResizeType::Left, BorderEdge::Left);
-
-            // Right border
-            let right_border = egui::Rect::from_min_max(
-                egui::pos2(available_rect.max.x - border_width, available_rect.min.y),
-                available_rect.max
-            );
-            handle_resize_border!(right_border, egui::Sense::drag(), egui::CursorIcon::ResizeHorizontal, ResizeType::Right, BorderEdge::Right);
-
-            // Corner resize handles
-            let tl_corner = egui::Rect::from_min_size(available_rect.min, egui::vec2(border_width, border_width));
-            handle_resize_border!(tl_corner, egui::Sense::drag(), egui::CursorIcon::ResizeNwSe, ResizeType::TopLeft, BorderEdge::TopLeft);
-
-            let tr_corner = egui::Rect::from_min_size(
-                egui::pos2(available_rect.max.x - border_width, available_rect.min.y),
-                egui::vec2(border_width, border_width)
-            );
-            handle_resize_border!(tr_corner, egui::Sense::drag(), egui::CursorIcon::ResizeNeSw, ResizeType::TopRight, BorderEdge::TopRight);
-
-            let bl_corner = egui::Rect::from_min_size(
-                egui::pos2(available_rect.min.x, available_rect.max.y - border_width),
-                egui::vec2(border_width, border_width)
-            );
-            handle_resize_border!(bl_corner, egui::Sense::drag(), egui::CursorIcon::ResizeNeSw, ResizeType::BottomLeft, BorderEdge::BottomLeft);
-
-            let br_corner = egui::Rect::from_min_size(
-                egui::pos2(available_rect.max.x - border_width, available_rect.max.y - border_width),
-                egui::vec2(border_width, border_width)
-            );
-            handle_resize_border!(br_corner, egui::Sense::drag(), egui::CursorIcon::ResizeNwSe, ResizeType::BottomRight, BorderEdge::BottomRight);
-        }
-
-        // Visual feedback for borders
-        let border_stroke_width = 2.0;
-        let hover_color = egui::Color32::from_rgb(100, 150, 255);
-        let resize_color = egui::Color32::from_rgb(50, 200, 100);
-        let active_color = egui::Color32::from_rgba_unmultiplied(100, 150, 255, 60);
+    /// Lay out `id` and the views it shows within `rect`
+    fn layout(&self, id: Uuid, rect: Rect, rects: &mut HashMap<Uuid, Rect>) {
+        rects.insert(id, rect);
+        match self.get_view(id).map(|view| &view.view_type) {
+            Some(ViewType::Split { direction, children, ratios }) => {
+                let total: f32 = ratios.iter().sum::<f32>().max(f32::EPSILON);
+                let mut start = 0.0;
+                for (&child, ratio) in children.iter().zip(ratios) {
+                    let end = start + ratio / total;
+                    let child_rect = match direction {
+                        SplitDirection::Horizontal => Rect::from_x_y_ranges(
+                            rect.min.x + rect.width() * start..=rect.min.x + rect.width() * end,
+                            rect.y_range(),
+                        ),
+                        SplitDirection::Vertical => Rect::from_x_y_ranges(
+                            rect.x_range(),
+                            rect.min.y + rect.height() * start..=rect.min.y + rect.height() * end,
+                        ),
+                    };
+                    self.layout(child, child_rect, rects);
+                    start = end;
+                }
+            }
+            Some(ViewType::Tabs { children, active_tab }) => {
+                if let Some(&child) = children.get(*active_tab) {
+                    let mut content = rect;
+                    content.min.y = (rect.min.y + TAB_BAR_HEIGHT).min(rect.max.y);
+                    self.layout(child, content, rects);
+                }
+            }
+            _ => {}
+        }
+    }
 
-        // Show full border when actively resizing
-        if let Some(resize_state) = &self.active_resize {
-            if resize_state.view_id == view_id {
-                ui.painter().rect_stroke(
-                    available_rect.shrink(1.0),
-                    egui::Rounding::same(2.0),
-                    egui::Stroke::new(border_stroke_width, resize_color)
-                );
+    /// Where the tab of `dragged` goes if dropped at `pos`: into the tab bar
+    /// under it, or beside the view under it on the side of its nearest edge
+    fn tab_drop_target(&self, dragged: Uuid, pos: Pos2) -> Option<TabDrop> {
+        for (&container, tabs) in &self.tab_rects {
+            let Some(rect) = self.view_rects.get(&container) else {
+                continue;
+            };
+            if Rect::from_min_size(rect.min, Vec2::new(rect.width(), TAB_BAR_HEIGHT)).contains(pos) {
+                let index = tabs.iter().filter(|tab| tab.center().x < pos.x).count();
+                return Some(TabDrop::Tab { container, index });
             }
         }
-        // Show edge borders on hover (only when not resizing)
-        else if !hovered_edges.is_empty() {
-            let inner_rect = available_rect.shrink(1.0);
-
-            for edge in &hovered_edges {
-                match edge {
-                    BorderEdge::Top => {
-                        ui.painter().line_segment(
-                            [inner_rect.left_top(), inner_rect.right_top()],
-                            egui::Stroke::new(border_stroke_width, hover_color)
-                        );
-                    },
-                    BorderEdge::Bottom => {
-                        ui.painter().line_segment(
-                            [inner_rect.left_bottom(), inner_rect.right_bottom()],
-                            egui::Stroke::new(border_stroke_width, hover_color)
-                        );
-                    },
-                    BorderEdge::Left => {
-                        ui.painter().line_segment(
-                            [inner_rect.left_top(), inner_rect.left_bottom()],
-                            egui::Stroke::new(border_stroke_width, hover_color)
-                        );
-                    },
-                    BorderEdge::Right => {
-                        ui.painter().line_segment(
-                            [inner_rect.right_top(), inner_rect.right_bottom()],
-                            egui::Stroke::new(border_stroke_width, hover_color)
-                        );
-                    },
-                    BorderEdge::TopLeft | BorderEdge::TopRight | BorderEdge::BottomLeft | BorderEdge::BottomRight => {
-                        // For corner hovers, show the full border
-                        ui.painter().rect_stroke(
-                            inner_rect,
-                            egui::Rounding::same(2.0),
-                            egui::Stroke::new(border_stroke_width, hover_color)
-                        );
-                    },
+
+        let (&leaf, rect) = self.view_rects.iter().find(|(&id, rect)| self.is_leaf(id) && rect.contains(pos))?;
+        // Tabs split off as a whole, so tab containers only hold leaves
+        let target = self.parent_of(leaf)
+            .filter(|&parent| matches!(self.get_view(parent).map(|view| &view.view_type), Some(ViewType::Tabs { .. })))
+            .unwrap_or(leaf);
+        if target == dragged {
+            return None;
+        }
+        let offset = (pos - rect.center()) / rect.size().max(Vec2::splat(1.0));
+        let (direction, before) = if offset.x.abs() > offset.y.abs() {
+            (SplitDirection::Horizontal, offset.x < 0.0)
+        } else {
+            (SplitDirection::Vertical, offset.y < 0.0)
+        };
+        Some(TabDrop::Split { target, direction, before })
+    }
+
+    /// Follow the pointer with the dragged tab, and move it where it's dropped
+    fn drag_tab(&mut self, ui: &mut egui::Ui) {
+        let Some(dragged) = self.dragged_tab.filter(|&id| self.is_leaf(id)) else {
+            self.dragged_tab = None;
+            return;
+        };
+        let (pos, down) = ui.input(|i| (i.pointer.interact_pos(), i.pointer.primary_down()));
+        let target = pos.and_then(|pos| self.tab_drop_target(dragged, pos));
+        if !down {
+            self.dragged_tab = None;
+            if let Some(drop) = target {
+                self.move_tab(dragged, drop);
+            }
+            return;
+        }
+
+        let Some(pos) = pos else {
+            return;
+        };
+        let mut marker = None;
+        match target {
+            Some(TabDrop::Tab { container, index }) => {
+                marker = self.view_rects.get(&container).zip(self.tab_rects.get(&container))
+                    .map(|(&rect, tabs)| (rect, tabs.as_slice(), index));
+            }
+            Some(TabDrop::Split { target, direction, before }) => {
+                // The half of the view the tab would take
+                if let Some(&rect) = self.view_rects.get(&target) {
+                    let half = match (direction, before) {
+                        (SplitDirection::Horizontal, true) => rect.with_max_x(rect.center().x),
+                        (SplitDirection::Horizontal, false) => rect.with_min_x(rect.center().x),
+                        (SplitDirection::Vertical, true) => rect.with_max_y(rect.center().y),
+                        (SplitDirection::Vertical, false) => rect.with_min_y(rect.center().y),
+                    };
+                    ui.painter().rect_filled(half, 2.0, egui::Color32::from_rgba_unmultiplied(100, 150, 255, 40));
                 }
             }
+            None => {}
         }
-        // Subtle border for active view when not hovering or resizing
-        else if is_active {
-            ui.painter().rect_stroke(
-                available_rect.shrink(1.0),
-                egui::Rounding::same(2.0),
-                egui::Stroke::new(1.0, active_color)
-            );
+        tiling_system::paint_dragged_tab(ui, &self.tab_label(dragged).name, pos, marker);
+    }
+
+    /// The nearest split in `direction` around `view_id` with a boundary
+    /// on its `side` (-1 before, 1 after), and the children either side
+    fn resize_target(&self, view_id: Uuid, direction: SplitDirection, side: i8) -> Option<(Uuid, usize, usize)> {
+        let mut child = view_id;
+        while let Some(parent) = self.parent_of(child) {
+            if let Some(ViewType::Split { direction: split_direction, children, .. }) = self.get_view(parent).map(|view| &view.view_type) {
+                let index = children.iter().position(|&id| id == child)?;
+                if *split_direction == direction {
+                    if side > 0 && index + 1 < children.len() {
+                        return Some((parent, index, index + 1));
+                    }
+                    if side < 0 && index > 0 {
+                        return Some((parent, index - 1, index));
+                    }
+                }
+            }
+            child = parent;
         }
+        None
+    }
 
-        // Render the content in the inner area
-        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(content_rect), |ui| {
-            content_renderer(self, ui);
-        });
+    /// Whether the border can move anything (outer edges can't)
+    fn can_resize(&self, view_id: Uuid, resize_type: ResizeType) -> bool {
+        let (x_side, y_side) = resize_type.sides();
+        let x = x_side == 0 || self.resize_target(view_id, SplitDirection::Horizontal, x_side).is_some();
+        let y = y_side == 0 || self.resize_target(view_id, SplitDirection::Vertical, y_side).is_some();
+        x && y
     }
 
-    fn render_view(&mut self, view_id: Uuid, ui: &mut egui::Ui, actors: &mut ActorManager) {
+    /// Move the boundaries the `resize_type` border of `view_id` sits on by
+    /// `delta`, keeping every view at least the minimum size
+    fn resize(&mut self, view_id: Uuid, resize_type: ResizeType, delta: Vec2) {
+        let (x_side, y_side) = resize_type.sides();
+        for (direction, side, delta) in [(SplitDirection::Horizontal, x_side, delta.x), (SplitDirection::Vertical, y_side, delta.y)] {
+            if side == 0 || delta == 0.0 {
+                continue;
+            }
+            let Some((split, before, after)) = self.resize_target(view_id, direction, side) else {
+                continue;
+            };
+            let Some(extent) = self.view_rects.get(&split).map(|rect| match direction {
+                SplitDirection::Horizontal => rect.width(),
+                SplitDirection::Vertical => rect.height(),
+            }) else {
+                continue;
+            };
+            if let Some(ViewType::Split { ratios, .. }) = self.get_view_mut(split).map(|view| &mut view.view_type) {
+                let total: f32 = ratios.iter().sum();
+                let min = (MIN_VIEW_SIZE / extent.max(1.0) * total).min((ratios[before] + ratios[after]) / 2.0);
+                let shift = (delta / extent.max(1.0) * total).clamp(min - ratios[before], ratios[after] - min);
+                ratios[before] += shift;
+                ratios[after] -= shift;
+            }
+        }
+    }
+
+    fn render_view(&mut self, view_id: Uuid, ui: &mut egui::Ui, render_actor: &dyn Fn(&mut egui::Ui, Uuid)) {
         let Some(view) = self.get_view(view_id).cloned() else { return };
-        let is_active = view_id == self.active_view;
+        let Some(&rect) = self.view_rects.get(&view_id) else { return };
 
-        // Apply universal border rendering to all view types
-        self.render_view_with_borders(ui, view_id, is_active, |view_manager, ui| {
-            match view.view_type {
-                ViewType::Leaf => {
-                    // Render the actor content
+        match view.view_type {
+            ViewType::Leaf => {
+                let is_active = self.active_view == Some(view_id);
+                ui.painter().rect_filled(rect.shrink(1.0), egui::Rounding::same(2.0), egui::Color32::from_gray(if is_active { 45 } else { 38 }));
+
+                let content_rect = rect.shrink(BORDER_WIDTH);
+                ui.allocate_new_ui(egui::UiBuilder::new().max_rect(content_rect), |ui| {
+                    ui.set_clip_rect(content_rect.intersect(ui.clip_rect()));
                     if let Some(actor_id) = view.actor_id {
-                        if let Some(actor) = actors.get_actor_mut(actor_id) {
-                            actor.render(ui);
-                        } else {
-                            ui.centered_and_justified(|ui| {
-                                ui.label("Empty View");
-                            });
-                        }
+                        render_actor(ui, actor_id);
                     } else {
                         ui.centered_and_justified(|ui| {
                             ui.label("Empty View - No Actor");
                         });
                     }
-                },
-
-                ViewType::Split { direction, children, ratios } => {
-                    match direction {
-                        SplitDirection::Horizontal => {
-                            ui.horizontal(|ui| {
-                                for (i, &child_id) in children.iter().enumerate() {
-                                    let width = ui.available_width() * ratios.get(i).unwrap_or(&0.5);
-                                    ui.allocate_ui_with_layout(
-                                        egui::vec2(width, ui.available_height()),
-                                        egui::Layout::default(),
-                                        |ui| view_manager.render_view(child_id, ui, actors)
-                                    );
-
-                                    if i < children.len() - 1 {
-                                        ui.separator();
-                                    }
-                                }
-                            });
-                        },
-                        SplitDirection::Vertical => {
-                            ui.vertical(|ui| {
-                                for (i, &child_id) in children.iter().enumerate() {
-                                    let height = ui.available_height() * ratios.get(i).unwrap_or(&0.5);
-                                    ui.allocate_ui_with_layout(
-                                        egui::vec2(ui.available_width(), height),
-                                        egui::Layout::default(),
-                                        |ui| view_manager.render_view(child_id, ui, actors)
-                                    );
-
-                                    if i < children.len() - 1 {
-                                        ui.separator();
-                                    }
-                                }
-                            });
-                        },
-                    }
-                },
+                });
+                self.render_borders(ui, view_id, rect, is_active);
+            },
 
-                ViewType::Tabs { children, active_tab } => {
-                    ui.vertical(|ui| {
-                        // Tab bar
-                        ui.horizontal(|ui| {
-                            for (i, &child_id) in children.iter().enumerate() {
-                                let child = view_manager.get_view(child_id);
-                                let title = child.map(|v| v.title.as_str()).unwrap_or("Tab");
-
-                                if ui.selectable_label(i == active_tab, title).clicked() {
-                                    // Would need mutable access to change active_tab
-                                }
-                            }
-                        });
+            ViewType::Split { children, .. } => {
+                for child in children {
+                    self.render_view(child, ui, render_actor);
+                }
+            },
 
-                        ui.separator();
+            ViewType::Tabs { children, active_tab } => {
+                let tabs: Vec<(Uuid, TabLabel)> = children.iter().map(|&child| (child, self.tab_label(child))).collect();
+                let focused = self.active_view.is_some_and(|active| self.leaves(view_id).contains(&active));
+                let response = tiling_system::tab_bar(ui, rect, ui.id().with(("view_tab", view_id)), &tabs, active_tab, focused);
+                self.tab_rects.insert(view_id, response.tabs);
 
-                        // Active tab content
-                        if let Some(&active_child) = children.get(active_tab) {
-                            view_manager.render_view(active_child, ui, actors);
+                if let Some(index) = response.selected {
+                    self.select_tab(view_id, index);
+                }
+                if let Some(index) = response.drag_started.filter(|&index| self.is_leaf(children[index])) {
+                    self.dragged_tab = Some(children[index]);
+                }
+                if let Some(&child) = response.closed.and_then(|index| children.get(index)) {
+                    // A tab without an actor has nothing to save, so it just goes
+                    match self.get_view(child).and_then(|view| view.actor_id) {
+                        Some(actor_id) => self.close_requests.push((child, actor_id)),
+                        None => {
+                            self.remove_leaf(child);
                         }
-                    });
-                },
-
-                ViewType::Floating { position, size: _ } => {
-                    // Floating windows - for now just show placeholder
-                    ui.centered_and_justified(|ui| {
-                        ui.label(format!("Floating window at {:?}", position));
-                    });
-                },
+                    }
+                }
+
+                // Active tab content
+                if let Some(&active_child) = children.get(active_tab) {
+                    self.render_view(active_child, ui, render_actor);
+                }
+            },
+        }
+    }
+
+    /// What a tab shows for `view_id`: its actor's name and whether it has
+    /// unsaved changes, or the view's title
+    fn tab_label(&self, view_id: Uuid) -> TabLabel {
+        let label = self.get_view(view_id)
+            .and_then(|view| view.actor_id)
+            .and_then(|actor_id| self.tab_labels.get(&actor_id));
+        match label {
+            Some(label) => label.clone(),
+            None => TabLabel::named(self.get_view(view_id).map_or("Tab", |view| view.title.as_str())),
+        }
+    }
+
+    /// The resize borders of leaf `view_id`, where they can move a boundary,
+    /// highlighted while hovered or dragged
+    fn render_borders(&mut self, ui: &mut egui::Ui, view_id: Uuid, rect: Rect, is_active: bool) {
+        let hover_color = egui::Color32::from_rgb(100, 150, 255);
+        let resize_color = egui::Color32::from_rgb(50, 200, 100);
+        let active_color = egui::Color32::from_rgba_unmultiplied(100, 150, 255, 60);
+        let inner_rect = rect.shrink(1.0);
+
+        let mut hovered = false;
+        for resize_type in ResizeType::ALL {
+            if !self.can_resize(view_id, resize_type) {
+                continue;
+            }
+            let border = resize_type.border(rect);
+            let response = ui.interact(border, ui.id().with(("view_border", view_id, resize_type as u8)), egui::Sense::drag());
+            if response.drag_started() {
+                self.active_resize = Some(ResizeState { view_id, resize_type });
+            }
+            if response.dragged() {
+                self.resize(view_id, resize_type, response.drag_delta());
+            }
+            if response.drag_stopped() {
+                self.active_resize = None;
+            }
+            if response.hovered() || response.dragged() {
+                ui.ctx().set_cursor_icon(resize_type.cursor());
+            }
+            if response.hovered() && self.active_resize.is_none() {
+                hovered = true;
+                let (x_side, y_side) = resize_type.sides();
+                let stroke = egui::Stroke::new(2.0, hover_color);
+                match (x_side, y_side) {
+                    (0, -1) => { ui.painter().line_segment([inner_rect.left_top(), inner_rect.right_top()], stroke); }
+                    (0, 1) => { ui.painter().line_segment([inner_rect.left_bottom(), inner_rect.right_bottom()], stroke); }
+                    (-1, 0) => { ui.painter().line_segment([inner_rect.left_top(), inner_rect.left_bottom()], stroke); }
+                    (1, 0) => { ui.painter().line_segment([inner_rect.right_top(), inner_rect.right_bottom()], stroke); }
+                    // For corner hovers, show the full border
+                    _ => { ui.painter().rect_stroke(inner_rect, egui::Rounding::same(2.0), stroke); }
+                }
+            }
+        }
+
+        // Show full border when actively resizing, keeping the cursor even
+        // once the pointer's off the border
+        if let Some(resize) = self.active_resize.as_ref().filter(|resize| resize.view_id == view_id) {
+            ui.ctx().set_cursor_icon(resize.resize_type.cursor());
+            ui.painter().rect_stroke(inner_rect, egui::Rounding::same(2.0), egui::Stroke::new(2.0, resize_color));
+        }
+        // Subtle border for active view when not hovering or resizing
+        else if is_active && !hovered {
+            ui.painter().rect_stroke(inner_rect, egui::Rounding::same(2.0), egui::Stroke::new(1.0, active_color));
+        }
+    }
+}
+
+impl ViewSystem for ViewManager {
+    /// Add a leaf at the end of the outermost split, sized evenly with the
+    /// views already in it
+    fn create_view(&mut self, title: String) -> Uuid {
+        let leaf = View::leaf(title);
+        let id = leaf.id;
+        self.views.push(leaf);
+
+        let root = self.root_view;
+        match root.and_then(|root| self.get_view_mut(root)) {
+            None => {
+                self.root_view = Some(id);
+                self.active_view = Some(id);
+            }
+            Some(View { view_type: ViewType::Split { direction: SplitDirection::Horizontal, children, ratios }, .. }) => {
+                let count = children.len() as f32;
+                for ratio in ratios.iter_mut() {
+                    *ratio *= count / (count + 1.0);
+                }
+                children.push(id);
+                ratios.push(1.0 / (count + 1.0));
+            }
+            Some(root) => {
+                let root_id = root.id;
+                let split = View {
+                    view_type: ViewType::Split {
+                        direction: SplitDirection::Horizontal,
+                        children: vec![root_id, id],
+                        ratios: vec![0.5, 0.5],
+                    },
+                    ..View::leaf("Split".to_string())
+                };
+                self.root_view = Some(split.id);
+                self.views.push(split);
+            }
+        }
+        id
+    }
+
+    fn active_view(&self) -> Option<Uuid> {
+        self.active_view
+    }
+
+    /// Focus a leaf, showing the tabs it's in
+    fn set_active_view(&mut self, view_id: Uuid) -> bool {
+        if !self.is_leaf(view_id) {
+            return false;
+        }
+        let mut child = view_id;
+        while let Some(parent) = self.parent_of(child) {
+            if let Some(ViewType::Tabs { children, active_tab }) = self.get_view_mut(parent).map(|view| &mut view.view_type) {
+                if let Some(index) = children.iter().position(|&id| id == child) {
+                    *active_tab = index;
+                }
+            }
+            child = parent;
+        }
+        self.active_view = Some(view_id);
+        true
+    }
+
+    fn attach_actor_to_view(&mut self, view_id: Uuid, actor_id: Uuid) -> bool {
+        match self.get_view_mut(view_id) {
+            Some(view) if matches!(view.view_type, ViewType::Leaf) => {
+                view.actor_id = Some(actor_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn detach_actor_from_view(&mut self, view_id: Uuid) -> bool {
+        match self.get_view_mut(view_id) {
+            Some(view) => {
+                view.actor_id = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// A leaf's actor, or that of the leaf shown in a tab container
+    fn get_view_actor(&self, view_id: Uuid) -> Option<Uuid> {
+        match &self.get_view(view_id)?.view_type {
+            ViewType::Leaf => self.get_view(view_id)?.actor_id,
+            ViewType::Tabs { children, active_tab } => self.get_view(*children.get(*active_tab)?)
+                .filter(|child| matches!(child.view_type, ViewType::Leaf))?
+                .actor_id,
+            ViewType::Split { .. } => None,
+        }
+    }
+
+    /// A leaf's actor, or those of the leaves in a tab container's tabs
+    fn get_view_actors(&self, view_id: Uuid) -> Vec<Uuid> {
+        match self.get_view(view_id).map(|view| &view.view_type) {
+            Some(ViewType::Tabs { children, .. }) => children.iter()
+                .filter(|&&child| self.is_leaf(child))
+                .filter_map(|&child| self.get_view_actor(child))
+                .collect(),
+            _ => self.get_view_actor(view_id).into_iter().collect(),
+        }
+    }
+
+    /// Take the actor out of its leaf, and the leaf out of the tree unless
+    /// it's the last one
+    fn remove_tab(&mut self, view_id: Uuid, actor_id: Uuid) -> bool {
+        if self.get_view(view_id).and_then(|view| view.actor_id) != Some(actor_id) {
+            return false;
+        }
+        if self.view_count() > 1 {
+            self.remove_leaf(view_id)
+        } else {
+            self.detach_actor_from_view(view_id)
+        }
+    }
+
+    fn take_close_requests(&mut self) -> Vec<(Uuid, Uuid)> {
+        std::mem::take(&mut self.close_requests)
+    }
+
+    /// Show the next (or previous) tab of the tab container nearest the
+    /// active view, wrapping around
+    fn cycle_tab(&mut self, forward: bool) -> bool {
+        let mut child = match self.active_view {
+            Some(active) => active,
+            None => return false,
+        };
+        while let Some(parent) = self.parent_of(child) {
+            if let Some(View { view_type: ViewType::Tabs { children, active_tab }, .. }) = self.get_view(parent) {
+                let count = children.len();
+                if count < 2 {
+                    return false;
+                }
+                let index = if forward { (active_tab + 1) % count } else { (active_tab + count - 1) % count };
+                return self.select_tab(parent, index);
+            }
+            child = parent;
+        }
+        false
+    }
+
+    fn render_views(&mut self, ui: &mut egui::Ui, render_actor: &dyn Fn(&mut egui::Ui, Uuid)) {
+        let available_rect = ui.available_rect_before_wrap();
+        let Some(root) = self.root_view else {
+            return;
+        };
+        let mut rects = HashMap::new();
+        self.layout(root, available_rect, &mut rects);
+        self.view_rects = rects;
+        self.tab_rects.clear();
+
+        // Pressing anywhere in a leaf focuses it, unless a layer is over it
+        let pressed_at = ui.input(|i| if i.pointer.any_pressed() { i.pointer.interact_pos() } else { None })
+            .filter(|&pos| ui.ctx().layer_id_at(pos).is_none_or(|layer| layer == ui.layer_id()));
+        if let Some(pos) = pressed_at {
+            let pressed = self.view_rects.iter()
+                .find(|(&id, rect)| self.is_leaf(id) && rect.contains(pos))
+                .map(|(&id, _)| id);
+            if let Some(view_id) = pressed {
+                self.active_view = Some(view_id);
+            }
+        }
+
+        self.render_view(root, ui, render_actor);
+        self.drag_tab(ui);
+    }
+
+    /// Ctrl+PageUp/PageDown cycle the tabs around the active view
+    fn handle_input(&mut self, ui: &mut egui::Ui) -> bool {
+        let tab = ui.ctx().input_mut(|i| {
+            if !i.modifiers.matches_exact(egui::Modifiers::COMMAND) {
+                None
+            } else if i.consume_key(egui::Modifiers::COMMAND, egui::Key::PageDown) {
+                Some(true)
+            } else if i.consume_key(egui::Modifiers::COMMAND, egui::Key::PageUp) {
+                Some(false)
+            } else {
+                None
             }
         });
+        tab.is_some_and(|forward| self.cycle_tab(forward))
+    }
+
+    fn view_count(&self) -> usize {
+        self.get_view_ids().len()
+    }
+
+    fn remove_view(&mut self, view_id: Uuid) -> bool {
+        self.remove_leaf(view_id)
+    }
+
+    /// The leaf views, in tree order
+    fn get_view_ids(&self) -> Vec<Uuid> {
+        self.root_view.map(|root| self.leaves(root)).unwrap_or_default()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Transformable for ViewManager {
+    fn zoom(&mut self, _factor: f32, _center: Option<Pos2>) {
+        // Nested views are laid out to fit; there's nothing to zoom
+    }
+
+    fn pan(&mut self, _delta: Vec2) {
+        // Nor to pan
+    }
+
+    fn reset_transform(&mut self) {}
+
+    fn get_transform_info(&self) -> TransformInfo {
+        TransformInfo {
+            zoom: 1.0,
+            pan_offset: Vec2::ZERO,
+            can_zoom: false,
+            can_pan: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A manager whose root leaf holds two more leaves as tabs, each with
+    /// an actor, returning the tab leaves and the container
+    fn tabbed() -> (ViewManager, [Uuid; 3], Uuid) {
+        let mut manager = ViewManager::new();
+        let first = manager.root().unwrap();
+        let second = manager.add_tab(first, "Second".to_string()).unwrap();
+        let third = manager.add_tab(second, "Third".to_string()).unwrap();
+        for leaf in [first, second, third] {
+            manager.attach_actor_to_view(leaf, Uuid::new_v4());
+        }
+        let container = manager.root().unwrap();
+        (manager, [first, second, third], container)
+    }
+
+    #[test]
+    fn closing_the_last_tab_of_a_leaf_removes_it() {
+        let (mut manager, [first, second, third], container) = tabbed();
+        let actor = manager.get_view_actor(second).unwrap();
+        assert!(!manager.remove_tab(second, Uuid::new_v4()));
+        assert!(manager.remove_tab(second, actor));
+        assert_eq!(manager.tab_leaves(container), [first, third]);
+
+        // With one tab left the container gives way to it
+        manager.remove_tab(third, manager.get_view_actor(third).unwrap());
+        assert_eq!(manager.root(), Some(first));
+        assert_eq!(manager.active_view(), Some(first));
+
+        // The last view stays, empty
+        manager.remove_tab(first, manager.get_view_actor(first).unwrap());
+        assert_eq!(manager.get_view_ids(), [first]);
+        assert_eq!(manager.get_view_actor(first), None);
+    }
+
+    #[test]
+    fn tabs_move_within_their_bar() {
+        let (mut manager, [first, second, third], container) = tabbed();
+        assert!(manager.move_tab(first, TabDrop::Tab { container, index: 3 }));
+        assert_eq!(manager.tab_leaves(container), [second, third, first]);
+        assert!(manager.move_tab(third, TabDrop::Tab { container, index: 0 }));
+        assert_eq!(manager.tab_leaves(container), [third, second, first]);
+        assert_eq!(manager.active_view(), Some(third));
+    }
+
+    #[test]
+    fn tabs_dropped_beside_a_view_split_it() {
+        let (mut manager, [first, second, third], container) = tabbed();
+        let drop = TabDrop::Split { target: container, direction: SplitDirection::Horizontal, before: true };
+        assert!(manager.move_tab(second, drop));
+        assert_eq!(manager.get_view_ids(), [second, first, third]);
+        assert_eq!(manager.tab_leaves(container), [first, third]);
+        assert_eq!(manager.active_view(), Some(second));
+
+        // Splitting off one of the last two tabs leaves the other in their place
+        let drop = TabDrop::Split { target: container, direction: SplitDirection::Vertical, before: false };
+        assert!(manager.move_tab(first, drop));
+        assert!(manager.get_view(container).is_none());
+        assert_eq!(manager.get_view_ids(), [second, third, first]);
+        let split = manager.root().unwrap();
+        assert!(matches!(&manager.get_view(split).unwrap().view_type, ViewType::Split { children, .. } if children.len() == 2));
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
 use egui::{self, Vec2, Pos2};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::any::Any;
 
@@ -22,6 +23,28 @@ pub trait ViewSystem: Send + Sync {
     /// Get the actor ID for a view
     fn get_view_actor(&self, view_id: Uuid) -> Option<Uuid>;
 
+    /// Every actor a view holds (e.g. one per tab), in order
+    fn get_view_actors(&self, view_id: Uuid) -> Vec<Uuid> {
+        self.get_view_actor(view_id).into_iter().collect()
+    }
+
+    /// Take `actor_id`'s tab out of `view_id`, leaving it open elsewhere
+    fn remove_tab(&mut self, view_id: Uuid, actor_id: Uuid) -> bool {
+        self.get_view_actor(view_id) == Some(actor_id) && self.detach_actor_from_view(view_id)
+    }
+
+    /// Show the next (or previous) tab of the focused view, wrapping
+    /// around; false if it has no other tab to show
+    fn cycle_tab(&mut self, _forward: bool) -> bool {
+        false
+    }
+
+    /// Tabs closed from their tab bars since the last call, as `(view,
+    /// actor)`; they stay open until removed
+    fn take_close_requests(&mut self) -> Vec<(Uuid, Uuid)> {
+        Vec::new()
+    }
+
     /// Render the view system
     fn render_views(&mut self, ui: &mut egui::Ui, render_actor: &dyn Fn(&mut egui::Ui, Uuid));
 
@@ -37,6 +60,15 @@ pub trait ViewSystem: Send + Sync {
     /// Get all view IDs
     fn get_view_ids(&self) -> Vec<Uuid>;
 
+    /// The system's camera bookmarks, if it keeps any
+    fn as_bookmarks(&self) -> Option<&dyn TransformBookmarks> {
+        None
+    }
+
+    fn as_bookmarks_mut(&mut self) -> Option<&mut dyn TransformBookmarks> {
+        None
+    }
+
     /// Cast to Any for downcasting to specific view system types
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
@@ -57,6 +89,34 @@ pub trait Transformable {
     fn get_transform_info(&self) -> TransformInfo;
 }
 
+/// A named zoom and pan to come back to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub zoom: f32,
+    pub offset: [f32; 2],
+}
+
+/// Transformables that remember named camera positions of their active
+/// view, so widgets and commands can list them and jump between them
+pub trait TransformBookmarks: Transformable {
+    /// The active view's bookmarks, in slot order
+    fn bookmarks(&self) -> &[CameraBookmark];
+
+    /// Bookmark the current zoom and pan as `name`, returning its slot
+    fn save_bookmark(&mut self, name: String) -> Option<usize>;
+
+    /// Move bookmark `index` to the current zoom and pan
+    fn update_bookmark(&mut self, index: usize) -> bool;
+
+    fn rename_bookmark(&mut self, index: usize, name: String) -> bool;
+
+    fn remove_bookmark(&mut self, index: usize) -> bool;
+
+    /// Animate the camera to bookmark `index`
+    fn jump_to_bookmark(&mut self, index: usize) -> bool;
+}
+
 /// Information about current transform state
 #[derive(Debug, Clone)]
 pub struct TransformInfo {
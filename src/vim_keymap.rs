@@ -0,0 +1,1619 @@
+use crate::keymap::{
+    self, char_at, first_non_blank, line_end, line_start, next_char, prev_char, Key, KeyOutcome,
+    KeyPress, Keymap, KeymapContext, KeymapRequest,
+};
+use crate::text_search::{self, Search, SearchOptions};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Lines moved by Ctrl-d / Ctrl-u
+const HALF_PAGE: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+    CommandLine,
+    Search { forward: bool },
+}
+
+#[derive(Debug, Clone, Default)]
+struct Register {
+    text: String,
+    linewise: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+    Lowercase,
+    Uppercase,
+    ToggleCase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w`/`W`, `e`/`E`, `b`/`B` (the flag selects WORDs)
+    WordForward(bool),
+    WordEnd(bool),
+    WordBackward(bool),
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    /// `gg` (first line, or line `count`)
+    FileStart,
+    /// `G` (last line, or line `count`)
+    FileEnd,
+    FindChar { c: char, forward: bool, till: bool },
+    RepeatFind { reverse: bool },
+    MatchPair,
+    ParagraphForward,
+    ParagraphBackward,
+    SearchNext { reverse: bool },
+}
+
+/// How a motion's range is interpreted by an operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MotionKind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextObject {
+    Word { big: bool },
+    Quote(char),
+    Pair(char, char),
+    Paragraph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Motion(Motion),
+    Object { object: TextObject, inner: bool },
+    /// Doubled operator (`dd`, `>>`, ...)
+    Lines,
+    /// The visual selection
+    Selection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InsertAt {
+    Cursor,
+    After,
+    LineStart,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Move(Motion),
+    Operate(Operator, Target),
+    SelectObject { object: TextObject, inner: bool },
+    Insert(InsertAt),
+    Visual { linewise: bool },
+    SwapVisualEnds,
+    Paste { before: bool },
+    ReplaceChar(char),
+    JoinLines,
+    ToggleCaseChar,
+    Undo,
+    Redo,
+    RepeatLast,
+    EnterCommandLine,
+    EnterSearch { forward: bool },
+}
+
+impl Command {
+    /// Whether `.` should repeat this command
+    fn is_change(&self) -> bool {
+        match self {
+            Command::Operate(op, _) => *op != Operator::Yank,
+            Command::Insert(_)
+            | Command::Paste { .. }
+            | Command::ReplaceChar(_)
+            | Command::JoinLines
+            | Command::ToggleCaseChar => true,
+            _ => false,
+        }
+    }
+}
+
+struct Parsed {
+    register: Option<char>,
+    count: Option<usize>,
+    command: Command,
+}
+
+enum Parse<T> {
+    Incomplete,
+    Invalid,
+    Done(T),
+}
+
+/// Vim emulation: normal/insert/visual modes, operators, motions, text
+/// objects, registers, counts, dot-repeat and `:` commands
+///
+/// `:` commands that aren't built in (`:w`, `:q`, `:wq`, `:s`, `:<line>`)
+/// are handed to the command palette.
+pub struct VimKeymap {
+    mode: Mode,
+    /// Keys of the normal/visual command being typed
+    pending: Vec<KeyPress>,
+    registers: HashMap<char, Register>,
+    /// Keys of the last change, replayed by `.`
+    last_change: Option<Vec<KeyPress>>,
+    /// Keys of the change in progress (including its insert session)
+    recording: Option<Vec<KeyPress>>,
+    replaying: bool,
+    /// Text typed after `:`, `/` or `?`
+    prompt: String,
+    last_search: Option<(String, bool)>,
+    last_find: Option<(char, bool, bool)>,
+    /// Column `j`/`k` try to keep
+    goal_column: Option<usize>,
+    /// Where the current insert session started, and how often to repeat it
+    insert_start: usize,
+    insert_count: usize,
+    /// Selection the command line was opened from, for `'<,'>`
+    visual_range: Option<(usize, usize)>,
+    message: Option<String>,
+}
+
+impl VimKeymap {
+    pub fn new() -> Self {
+        Self {
+            mode: Mode::Normal,
+            pending: Vec::new(),
+            registers: HashMap::new(),
+            last_change: None,
+            recording: None,
+            replaying: false,
+            prompt: String::new(),
+            last_search: None,
+            last_find: None,
+            goal_column: None,
+            insert_start: 0,
+            insert_count: 1,
+            visual_range: None,
+            message: None,
+        }
+    }
+
+    fn is_visual(&self) -> bool {
+        matches!(self.mode, Mode::Visual | Mode::VisualLine)
+    }
+
+    fn handle_command_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        if self.mode == Mode::Normal {
+            let cursor = clamp_normal(ctx.text(), ctx.cursor);
+            ctx.set_cursor(cursor);
+        }
+
+        if key.key == Key::Escape {
+            self.pending.clear();
+            if self.is_visual() {
+                self.mode = Mode::Normal;
+                let cursor = clamp_normal(ctx.text(), ctx.cursor);
+                ctx.set_cursor(cursor);
+            }
+            return KeyOutcome::Handled;
+        }
+
+        self.pending.push(key);
+        let parsed = match parse_command(&self.pending, self.is_visual()) {
+            Parse::Incomplete => return KeyOutcome::Handled,
+            Parse::Invalid => {
+                let keys = std::mem::take(&mut self.pending);
+                // Unbound chords belong to the host (save shortcuts and the like)
+                if keys.len() == 1 && (key.ctrl || key.alt) {
+                    return KeyOutcome::Ignored;
+                }
+                return KeyOutcome::Handled;
+            }
+            Parse::Done(parsed) => parsed,
+        };
+
+        let keys = std::mem::take(&mut self.pending);
+        if self.mode == Mode::Normal && !self.replaying && parsed.command.is_change() {
+            self.recording = Some(keys);
+        }
+
+        self.execute(parsed, ctx);
+
+        if self.mode != Mode::Insert {
+            if let Some(keys) = self.recording.take() {
+                self.last_change = Some(keys);
+            }
+        }
+        if self.mode == Mode::Normal {
+            let cursor = clamp_normal(ctx.text(), ctx.cursor);
+            ctx.set_cursor(cursor);
+        }
+        KeyOutcome::Handled
+    }
+
+    fn execute(&mut self, parsed: Parsed, ctx: &mut KeymapContext) {
+        let count = parsed.count.unwrap_or(1);
+        if !matches!(parsed.command, Command::Move(Motion::Up | Motion::Down)) {
+            self.goal_column = None;
+        }
+
+        match parsed.command {
+            Command::Move(motion) => {
+                let target = self.motion_target(ctx.buffer.text(), ctx.cursor, motion, parsed.count, false);
+                if let Some((target, _)) = target {
+                    ctx.cursor = target;
+                    if !self.is_visual() {
+                        ctx.anchor = target;
+                    }
+                }
+            }
+            Command::Operate(op, target) => self.operate(op, target, parsed.count, parsed.register, ctx),
+            Command::SelectObject { object, inner } => {
+                if let Some((range, _)) = text_object(ctx.text(), ctx.cursor, object, inner) {
+                    ctx.anchor = range.start;
+                    ctx.cursor = prev_char(ctx.text(), range.end).max(range.start);
+                }
+            }
+            Command::Insert(at) => self.start_insert(at, count, ctx),
+            Command::Visual { linewise } => {
+                let mode = if linewise { Mode::VisualLine } else { Mode::Visual };
+                if self.mode == mode {
+                    self.mode = Mode::Normal;
+                    ctx.anchor = ctx.cursor;
+                } else {
+                    if self.mode == Mode::Normal {
+                        ctx.anchor = ctx.cursor;
+                    }
+                    self.mode = mode;
+                }
+            }
+            Command::SwapVisualEnds => std::mem::swap(&mut ctx.anchor, &mut ctx.cursor),
+            Command::Paste { before } => self.paste(before, count, parsed.register, ctx),
+            Command::ReplaceChar(c) => {
+                let text = ctx.text();
+                let mut end = ctx.cursor;
+                for _ in 0..count {
+                    match char_at(text, end) {
+                        Some(ch) if ch != '\n' => end += ch.len_utf8(),
+                        _ => return,
+                    }
+                }
+                let start = ctx.cursor;
+                ctx.replace(start..end, &c.to_string().repeat(count));
+                let cursor = prev_char(ctx.text(), ctx.cursor);
+                ctx.set_cursor(cursor);
+            }
+            Command::JoinLines => {
+                for _ in 0..count.max(2) - 1 {
+                    let text = ctx.text();
+                    let end = line_end(text, ctx.cursor);
+                    if end >= text.len() {
+                        break;
+                    }
+                    let next_content = text[end + 1..]
+                        .find(|c: char| c != ' ' && c != '\t')
+                        .map(|i| end + 1 + i)
+                        .unwrap_or(text.len());
+                    let bare = matches!(char_at(text, next_content), None | Some('\n') | Some(')'))
+                        || end == line_start(text, end);
+                    ctx.replace(end..next_content, if bare { "" } else { " " });
+                    ctx.set_cursor(end);
+                }
+            }
+            Command::ToggleCaseChar => {
+                let text = ctx.text();
+                let start = ctx.cursor;
+                let end = text[start..line_end(text, start)]
+                    .char_indices()
+                    .nth(count)
+                    .map(|(i, _)| start + i)
+                    .unwrap_or_else(|| line_end(text, start));
+                let toggled = toggle_case(&text[start..end]);
+                ctx.replace(start..end, &toggled);
+            }
+            Command::Undo => {
+                for _ in 0..count {
+                    match ctx.buffer.undo() {
+                        Some(cursor) => ctx.set_cursor(cursor),
+                        None => {
+                            self.message = Some("Already at oldest change".to_string());
+                            break;
+                        }
+                    }
+                }
+            }
+            Command::Redo => {
+                for _ in 0..count {
+                    match ctx.buffer.redo() {
+                        Some(cursor) => ctx.set_cursor(cursor),
+                        None => {
+                            self.message = Some("Already at newest change".to_string());
+                            break;
+                        }
+                    }
+                }
+            }
+            Command::RepeatLast => {
+                if let Some(mut keys) = self.last_change.clone() {
+                    if let Some(count) = parsed.count {
+                        keys = with_count(keys, count);
+                    }
+                    self.replaying = true;
+                    for key in keys {
+                        self.handle_key(key, ctx);
+                    }
+                    self.replaying = false;
+                }
+            }
+            Command::EnterCommandLine => {
+                self.prompt.clear();
+                if self.is_visual() {
+                    self.visual_range = Some((ctx.anchor.min(ctx.cursor), ctx.anchor.max(ctx.cursor)));
+                    self.prompt.push_str("'<,'>");
+                    ctx.anchor = ctx.cursor;
+                }
+                self.mode = Mode::CommandLine;
+            }
+            Command::EnterSearch { forward } => {
+                self.prompt.clear();
+                self.mode = Mode::Search { forward };
+            }
+        }
+    }
+
+    /// Where a motion lands, and how operators should treat the range
+    fn motion_target(&mut self, text: &str, cursor: usize, motion: Motion, count: Option<usize>, operator: bool) -> Option<(usize, MotionKind)> {
+        let n = count.unwrap_or(1);
+        let repeat = |f: &dyn Fn(usize) -> usize| (0..n).fold(cursor, |pos, _| f(pos));
+
+        let result = match motion {
+            Motion::Left => {
+                let start = line_start(text, cursor);
+                (repeat(&|pos| if pos > start { prev_char(text, pos) } else { pos }), MotionKind::Exclusive)
+            }
+            Motion::Right => {
+                let end = line_end(text, cursor);
+                // Operators may reach the end of the line (`x` on the last character)
+                let limit = if operator { end } else { prev_char(text, end).max(line_start(text, cursor)) };
+                (repeat(&|pos| if pos < limit { next_char(text, pos) } else { pos }), MotionKind::Exclusive)
+            }
+            Motion::Up | Motion::Down => {
+                let column = *self.goal_column.get_or_insert_with(|| keymap::column(text, cursor));
+                let delta = if motion == Motion::Up { -(n as isize) } else { n as isize };
+                let line = keymap::line_offset(text, cursor, delta);
+                let target = keymap::offset_at_column(text, line, column);
+                (if operator { target } else { clamp_normal(text, target) }, MotionKind::Linewise)
+            }
+            Motion::WordForward(big) => {
+                let mut target = repeat(&|pos| keymap::word_forward(text, pos, big));
+                // `dw` on the last word of a line stops at the line end
+                if operator && target > line_end(text, cursor) && cursor < line_end(text, cursor) {
+                    target = line_end(text, cursor);
+                }
+                (target, MotionKind::Exclusive)
+            }
+            Motion::WordEnd(big) => (repeat(&|pos| keymap::word_end(text, pos, big)), MotionKind::Inclusive),
+            Motion::WordBackward(big) => (repeat(&|pos| keymap::word_backward(text, pos, big)), MotionKind::Exclusive),
+            Motion::LineStart => (line_start(text, cursor), MotionKind::Exclusive),
+            Motion::FirstNonBlank => (first_non_blank(text, cursor), MotionKind::Exclusive),
+            Motion::LineEnd => {
+                let line = keymap::line_offset(text, cursor, n as isize - 1);
+                let end = line_end(text, line);
+                if operator {
+                    (end, MotionKind::Exclusive)
+                } else {
+                    (clamp_normal(text, end), MotionKind::Inclusive)
+                }
+            }
+            Motion::FileStart | Motion::FileEnd => {
+                let line = match (count, motion) {
+                    (Some(line), _) => keymap::line_at(text, line.saturating_sub(1)),
+                    (None, Motion::FileStart) => 0,
+                    (None, _) => line_start(text, text.len()),
+                };
+                (first_non_blank(text, line), MotionKind::Linewise)
+            }
+            Motion::FindChar { c, forward, till } => {
+                self.last_find = Some((c, forward, till));
+                return find_char(text, cursor, c, forward, till, n);
+            }
+            Motion::RepeatFind { reverse } => {
+                let (c, forward, till) = self.last_find?;
+                // Step past the character a `t` stopped in front of
+                let from = match (till, forward != reverse) {
+                    (true, true) => next_char(text, cursor),
+                    (true, false) => prev_char(text, cursor),
+                    _ => cursor,
+                };
+                return find_char(text, from, c, forward != reverse, till, n);
+            }
+            Motion::MatchPair => (match_pair(text, cursor)?, MotionKind::Inclusive),
+            Motion::ParagraphForward => (repeat(&|pos| paragraph_forward(text, pos)), MotionKind::Exclusive),
+            Motion::ParagraphBackward => (repeat(&|pos| paragraph_backward(text, pos)), MotionKind::Exclusive),
+            Motion::SearchNext { reverse } => {
+                let mut pos = cursor;
+                for _ in 0..n {
+                    pos = self.search_from(text, pos, reverse)?;
+                }
+                (pos, MotionKind::Exclusive)
+            }
+        };
+        Some(result)
+    }
+
+    /// Next match of the last search, wrapping around the buffer
+    fn search_from(&mut self, text: &str, cursor: usize, reverse: bool) -> Option<usize> {
+        let Some((pattern, forward)) = self.last_search.clone() else {
+            self.message = Some("No previous search".to_string());
+            return None;
+        };
+        let options = SearchOptions {
+            regex: true,
+            // Smart case: a capital letter makes the search case sensitive
+            case_sensitive: pattern.chars().any(char::is_uppercase),
+            whole_word: false,
+        };
+        let search = match Search::new(&pattern, options) {
+            Ok(search) => search,
+            Err(e) => {
+                self.message = Some(e.to_string());
+                return None;
+            }
+        };
+
+        let matches = search.find_all(text);
+        let found = if forward != reverse {
+            matches.iter().find(|m| m.start > cursor).or_else(|| matches.first())
+        } else {
+            matches.iter().rev().find(|m| m.start < cursor).or_else(|| matches.last())
+        };
+        if found.is_none() {
+            self.message = Some(format!("Pattern not found: {}", pattern));
+        }
+        found.map(|m| m.start)
+    }
+
+    fn operate(&mut self, op: Operator, target: Target, count: Option<usize>, register: Option<char>, ctx: &mut KeymapContext) {
+        let text = ctx.buffer.text();
+        let cursor = ctx.cursor;
+        let n = count.unwrap_or(1);
+
+        let (range, linewise) = match target {
+            Target::Selection => {
+                let (start, end) = (ctx.anchor.min(cursor), ctx.anchor.max(cursor));
+                let linewise = self.mode == Mode::VisualLine;
+                self.mode = Mode::Normal;
+                if linewise {
+                    (line_start(text, start)..line_end(text, end), true)
+                } else {
+                    (start..next_char(text, end), false)
+                }
+            }
+            Target::Lines => {
+                let last = keymap::line_offset(text, cursor, n as isize - 1);
+                (line_start(text, cursor)..line_end(text, last), true)
+            }
+            Target::Object { object, inner } => match text_object(text, cursor, object, inner) {
+                Some(range) => range,
+                None => return,
+            },
+            Target::Motion(motion) => {
+                // `cw` on a word changes to its end, like `ce`
+                let motion = match motion {
+                    Motion::WordForward(big) if op == Operator::Change
+                        && char_at(text, cursor).is_some_and(|c| !c.is_whitespace()) =>
+                    {
+                        let target = change_word_end(text, cursor, big, n);
+                        return self.apply_operator(op, cursor..next_char(text, target), false, register, ctx);
+                    }
+                    motion => motion,
+                };
+                let Some((target, kind)) = self.motion_target(text, cursor, motion, count, true) else {
+                    return;
+                };
+                let (start, end) = (cursor.min(target), cursor.max(target));
+                match kind {
+                    MotionKind::Exclusive => (start..end, false),
+                    MotionKind::Inclusive => (start..next_char(text, end), false),
+                    MotionKind::Linewise => (line_start(text, start)..line_end(text, end), true),
+                }
+            }
+        };
+
+        self.apply_operator(op, range, linewise, register, ctx);
+    }
+
+    /// Apply an operator; linewise ranges span whole lines without the final newline
+    fn apply_operator(&mut self, op: Operator, range: Range<usize>, linewise: bool, register: Option<char>, ctx: &mut KeymapContext) {
+        let text = ctx.buffer.text();
+        let text_len = text.len();
+        let mut content = text[range.clone()].to_string();
+        if linewise {
+            content.push('\n');
+        }
+
+        match op {
+            Operator::Yank => {
+                let cursor = if linewise && line_start(text, ctx.cursor) == range.start {
+                    ctx.cursor
+                } else {
+                    range.start
+                };
+                self.store_register(register, Register { text: content, linewise }, true, ctx);
+                ctx.set_cursor(cursor);
+            }
+            Operator::Delete => {
+                let range = if !linewise {
+                    range
+                } else if range.end < text_len {
+                    range.start..range.end + 1
+                } else {
+                    range.start.saturating_sub(1)..range.end
+                };
+                self.store_register(register, Register { text: content, linewise }, false, ctx);
+                ctx.replace(range.clone(), "");
+                if linewise {
+                    let line = range.start.min(ctx.text().len());
+                    let line = if range.start > 0 && range.end >= text_len {
+                        line_start(ctx.text(), line)
+                    } else {
+                        line
+                    };
+                    let cursor = first_non_blank(ctx.text(), line);
+                    ctx.set_cursor(cursor);
+                }
+            }
+            Operator::Change => {
+                let indent = if linewise { indentation(text, range.start) } else { String::new() };
+                self.store_register(register, Register { text: content, linewise }, false, ctx);
+                ctx.replace(range, &indent);
+                self.enter_insert(ctx, 1);
+            }
+            Operator::Indent | Operator::Outdent => {
+                let indent = " ".repeat(ctx.tab_width.max(1));
+                let mut edits = Vec::new();
+                let mut line = line_start(text, range.start);
+                loop {
+                    let end = line_end(text, line);
+                    let line_text = &text[line..end];
+                    if op == Operator::Indent {
+                        if !line_text.trim().is_empty() {
+                            edits.push((line..line, indent.clone()));
+                        }
+                    } else {
+                        let width = if line_text.starts_with('\t') {
+                            1
+                        } else {
+                            line_text.len() - line_text.trim_start_matches(' ').len()
+                        };
+                        let width = width.min(indent.len());
+                        if width > 0 {
+                            edits.push((line..line + width, String::new()));
+                        }
+                    }
+                    if end >= range.end || end >= text.len() {
+                        break;
+                    }
+                    line = end + 1;
+                }
+                let first_line = line_start(text, range.start);
+                ctx.buffer.apply_edits(edits);
+                let cursor = first_non_blank(ctx.text(), first_line);
+                ctx.set_cursor(cursor);
+            }
+            Operator::Lowercase | Operator::Uppercase | Operator::ToggleCase => {
+                let changed = match op {
+                    Operator::Lowercase => content.trim_end_matches('\n').to_lowercase(),
+                    Operator::Uppercase => content.trim_end_matches('\n').to_uppercase(),
+                    _ => toggle_case(&text[range.clone()]),
+                };
+                let start = range.start;
+                ctx.replace(range, &changed);
+                ctx.set_cursor(start);
+            }
+        }
+    }
+
+    fn store_register(&mut self, name: Option<char>, register: Register, yank: bool, ctx: &mut KeymapContext) {
+        match name {
+            Some('_') => return,
+            Some(c) if c.is_ascii_uppercase() => {
+                let entry = self.registers.entry(c.to_ascii_lowercase()).or_default();
+                entry.text.push_str(&register.text);
+                entry.linewise |= register.linewise;
+            }
+            Some(c) => {
+                if c == '+' || c == '*' {
+                    ctx.requests.push(KeymapRequest::CopyToClipboard(register.text.clone()));
+                }
+                self.registers.insert(c, register.clone());
+            }
+            None if yank => {
+                self.registers.insert('0', register.clone());
+            }
+            None => {}
+        }
+        self.registers.insert('"', register);
+    }
+
+    fn paste(&mut self, before: bool, count: usize, register: Option<char>, ctx: &mut KeymapContext) {
+        let name = register.unwrap_or('"').to_ascii_lowercase();
+        let Some(register) = self.registers.get(&name).cloned() else {
+            self.message = Some(format!("Nothing in register {}", name));
+            return;
+        };
+        let text = ctx.text();
+        let content = register.text.repeat(count);
+
+        if register.linewise {
+            let (at, insert, first_line) = if before {
+                let at = line_start(text, ctx.cursor);
+                (at, content, at)
+            } else {
+                let end = line_end(text, ctx.cursor);
+                if end < text.len() {
+                    (end + 1, content, end + 1)
+                } else {
+                    // Last line without a trailing newline
+                    (end, format!("\n{}", content.trim_end_matches('\n')), end + 1)
+                }
+            };
+            ctx.buffer.apply_edits(vec![(at..at, insert)]);
+            let cursor = first_non_blank(ctx.text(), first_line);
+            ctx.set_cursor(cursor);
+        } else {
+            let at = match char_at(text, ctx.cursor) {
+                Some(c) if !before && c != '\n' => ctx.cursor + c.len_utf8(),
+                _ => ctx.cursor,
+            };
+            ctx.replace(at..at, &content);
+            let cursor = prev_char(ctx.text(), ctx.cursor).max(at);
+            ctx.set_cursor(cursor);
+        }
+    }
+
+    fn start_insert(&mut self, at: InsertAt, count: usize, ctx: &mut KeymapContext) {
+        let text = ctx.text();
+        let cursor = ctx.cursor;
+        let pos = match at {
+            InsertAt::Cursor => cursor,
+            InsertAt::After => match char_at(text, cursor) {
+                Some(c) if c != '\n' => cursor + c.len_utf8(),
+                _ => cursor,
+            },
+            InsertAt::LineStart => first_non_blank(text, cursor),
+            InsertAt::LineEnd => line_end(text, cursor),
+            InsertAt::LineBelow => {
+                let end = line_end(text, cursor);
+                let indent = indentation(text, cursor);
+                ctx.replace(end..end, &format!("\n{}", indent));
+                self.enter_insert(ctx, 1);
+                return;
+            }
+            InsertAt::LineAbove => {
+                let start = line_start(text, cursor);
+                let indent = indentation(text, cursor);
+                ctx.replace(start..start, &format!("{}\n", indent));
+                ctx.set_cursor(start + indent.len());
+                self.enter_insert(ctx, 1);
+                return;
+            }
+        };
+        ctx.set_cursor(pos);
+        self.enter_insert(ctx, count);
+    }
+
+    fn enter_insert(&mut self, ctx: &mut KeymapContext, count: usize) {
+        self.mode = Mode::Insert;
+        self.insert_start = ctx.cursor;
+        self.insert_count = count;
+        ctx.buffer.break_undo_group();
+    }
+
+    fn handle_insert_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        let text = ctx.buffer.text();
+        let cursor = ctx.cursor;
+
+        let handled = match key.key {
+            Key::Escape => {
+                // A count repeats the inserted text (`3ihi<Esc>`)
+                if self.insert_count > 1 && cursor >= self.insert_start {
+                    let typed = text[self.insert_start..cursor].to_string();
+                    ctx.type_text(&typed.repeat(self.insert_count - 1));
+                }
+                self.mode = Mode::Normal;
+                let cursor = ctx.cursor;
+                if cursor > line_start(ctx.text(), cursor) {
+                    ctx.set_cursor(prev_char(ctx.text(), cursor));
+                }
+                true
+            }
+            Key::Char(c) if !key.ctrl && !key.alt => {
+                ctx.type_text(c.encode_utf8(&mut [0; 4]));
+                true
+            }
+            Key::Char('w') if key.ctrl => {
+                let start = keymap::word_backward(text, cursor, false).max(line_start(text, cursor.saturating_sub(1)));
+                ctx.replace(start..cursor, "");
+                true
+            }
+            Key::Char('u') if key.ctrl => {
+                let start = line_start(text, cursor);
+                ctx.replace(start..cursor, "");
+                true
+            }
+            Key::Backspace | Key::Char('h') if key.key == Key::Backspace || key.ctrl => {
+                let start = prev_char(text, cursor);
+                ctx.buffer.replace_range(start..cursor, "");
+                ctx.set_cursor(start);
+                true
+            }
+            Key::Delete => {
+                let end = next_char(text, cursor);
+                ctx.buffer.replace_range(cursor..end, "");
+                true
+            }
+            Key::Enter => {
+                let indent = indentation(text, cursor);
+                let indent = &indent[..indent.len().min(cursor - line_start(text, cursor))];
+                ctx.type_text(&format!("\n{}", indent));
+                true
+            }
+            Key::Tab => {
+                ctx.type_text("\t");
+                true
+            }
+            Key::Left | Key::Right | Key::Up | Key::Down | Key::Home | Key::End => {
+                let target = match key.key {
+                    Key::Left => prev_char(text, cursor).max(line_start(text, cursor)),
+                    Key::Right => if cursor < line_end(text, cursor) { next_char(text, cursor) } else { cursor },
+                    Key::Up | Key::Down => {
+                        let column = keymap::column(text, cursor);
+                        let line = keymap::line_offset(text, cursor, if key.key == Key::Up { -1 } else { 1 });
+                        keymap::offset_at_column(text, line, column)
+                    }
+                    Key::Home => line_start(text, cursor),
+                    _ => line_end(text, cursor),
+                };
+                ctx.set_cursor(target);
+                ctx.buffer.break_undo_group();
+                self.insert_start = target;
+                true
+            }
+            _ => false,
+        };
+
+        if !handled {
+            return KeyOutcome::Ignored;
+        }
+        if let Some(keys) = self.recording.as_mut() {
+            keys.push(key);
+        }
+        if self.mode != Mode::Insert {
+            if let Some(keys) = self.recording.take() {
+                self.last_change = Some(keys);
+            }
+        }
+        KeyOutcome::Handled
+    }
+
+    /// Keys typed into the `:` / `/` / `?` prompt
+    fn handle_prompt_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        match key.key {
+            Key::Escape => {
+                self.mode = Mode::Normal;
+                self.prompt.clear();
+                self.visual_range = None;
+            }
+            Key::Backspace if self.prompt.is_empty() => self.mode = Mode::Normal,
+            Key::Backspace => {
+                self.prompt.pop();
+            }
+            Key::Enter => {
+                let prompt = std::mem::take(&mut self.prompt);
+                let mode = self.mode;
+                self.mode = Mode::Normal;
+                match mode {
+                    Mode::Search { forward } => {
+                        if !prompt.is_empty() {
+                            self.last_search = Some((prompt, forward));
+                        }
+                        if let Some(target) = self.search_from(ctx.buffer.text(), ctx.cursor, false) {
+                            ctx.set_cursor(target);
+                        }
+                    }
+                    _ => self.run_command_line(&prompt, ctx),
+                }
+            }
+            Key::Char(c) if !key.ctrl && !key.alt => self.prompt.push(c),
+            _ => {}
+        }
+        KeyOutcome::Handled
+    }
+
+    fn run_command_line(&mut self, line: &str, ctx: &mut KeymapContext) {
+        let (lines, command) = self.parse_line_range(line.trim(), ctx.text(), ctx.cursor);
+        self.visual_range = None;
+
+        match command {
+            "" => {
+                if let Some((_, last)) = lines {
+                    let target = first_non_blank(ctx.text(), keymap::line_at(ctx.text(), last));
+                    ctx.set_cursor(target);
+                }
+            }
+            "w" | "write" => ctx.requests.push(KeymapRequest::Save),
+            "q" | "q!" | "quit" | "close" => {
+                ctx.requests.push(KeymapRequest::PaletteCommand("tab.close".to_string()));
+            }
+            "wq" | "x" | "xit" => {
+                ctx.requests.push(KeymapRequest::Save);
+                ctx.requests.push(KeymapRequest::PaletteCommand("tab.close".to_string()));
+            }
+            "noh" | "nohlsearch" => {}
+            _ if command.len() > 1
+                && command.starts_with('s')
+                && !command[1..].starts_with(|c: char| c.is_alphanumeric()) =>
+            {
+                self.substitute(lines, &command[1..], ctx);
+            }
+            _ => ctx.requests.push(KeymapRequest::PaletteCommand(line.trim().to_string())),
+        }
+    }
+
+    /// Split a leading line range (`%`, `'<,'>`, `N`, `N,M`) off a command
+    ///
+    /// Returns zero-based first/last lines.
+    fn parse_line_range<'l>(&self, line: &'l str, text: &str, cursor: usize) -> (Option<(usize, usize)>, &'l str) {
+        let line_of = |pos: usize| text[..pos].matches('\n').count();
+
+        if let Some(rest) = line.strip_prefix('%') {
+            return (Some((0, line_of(text.len()))), rest);
+        }
+        if let Some(rest) = line.strip_prefix("'<,'>") {
+            let (start, end) = self.visual_range.unwrap_or((cursor, cursor));
+            return (Some((line_of(start), line_of(end))), rest);
+        }
+
+        let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(line.len());
+        let Ok(first) = line[..digits].parse::<usize>() else {
+            return (None, line);
+        };
+        let rest = &line[digits..];
+        if let Some(after_comma) = rest.strip_prefix(',') {
+            let digits = after_comma.find(|c: char| !c.is_ascii_digit()).unwrap_or(after_comma.len());
+            if let Ok(last) = after_comma[..digits].parse::<usize>() {
+                return (Some((first.saturating_sub(1), last.saturating_sub(1))), &after_comma[digits..]);
+            }
+        }
+        (Some((first.saturating_sub(1), first.saturating_sub(1))), rest)
+    }
+
+    /// `:s/pattern/replacement/flags` over a line range (the current line by default)
+    ///
+    /// Patterns use regex syntax; `\1` and `&` in the replacement refer to groups.
+    fn substitute(&mut self, lines: Option<(usize, usize)>, spec: &str, ctx: &mut KeymapContext) {
+        let Some(delimiter) = spec.chars().next() else {
+            return;
+        };
+        let parts = split_unescaped(&spec[delimiter.len_utf8()..], delimiter);
+        let pattern = match parts.first().map(String::as_str) {
+            Some("") | None => match &self.last_search {
+                Some((pattern, _)) => pattern.clone(),
+                None => {
+                    self.message = Some("No previous pattern".to_string());
+                    return;
+                }
+            },
+            Some(pattern) => pattern.to_string(),
+        };
+        let replacement = vim_replacement(parts.get(1).map(String::as_str).unwrap_or(""));
+        let flags = parts.get(2).map(String::as_str).unwrap_or("");
+
+        let options = SearchOptions {
+            regex: true,
+            case_sensitive: !flags.contains('i'),
+            whole_word: false,
+        };
+        let search = match Search::new(&pattern, options) {
+            Ok(search) => search,
+            Err(e) => {
+                self.message = Some(e.to_string());
+                return;
+            }
+        };
+
+        let text = ctx.buffer.text();
+        let current = text[..ctx.cursor].matches('\n').count();
+        let (first, last) = lines.unwrap_or((current, current));
+        let start = keymap::line_at(text, first);
+        let end = line_end(text, keymap::line_at(text, last));
+
+        let mut edits = Vec::new();
+        let mut last_line = None;
+        for range in search.find_all(text) {
+            if range.start < start || range.start > end {
+                continue;
+            }
+            let line = line_start(text, range.start);
+            if !flags.contains('g') && last_line == Some(line) {
+                continue;
+            }
+            last_line = Some(line);
+            let new_text = search.replacement_for(text, &range, &replacement, false);
+            edits.push((range, new_text));
+        }
+
+        if edits.is_empty() {
+            self.message = Some(format!("Pattern not found: {}", pattern));
+            return;
+        }
+        let new_ranges = text_search::ranges_after_edits(&edits);
+        let substitutions = edits.len();
+        ctx.buffer.apply_edits(edits);
+        if let Some(last) = new_ranges.last() {
+            let cursor = first_non_blank(ctx.text(), last.start);
+            ctx.set_cursor(cursor);
+        }
+        self.message = Some(format!("{} substitutions", substitutions));
+    }
+}
+
+impl Default for VimKeymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keymap for VimKeymap {
+    fn handle_key(&mut self, key: KeyPress, ctx: &mut KeymapContext) -> KeyOutcome {
+        if !self.replaying {
+            self.message = None;
+        }
+        match self.mode {
+            Mode::Insert => self.handle_insert_key(key, ctx),
+            Mode::CommandLine | Mode::Search { .. } => self.handle_prompt_key(key, ctx),
+            Mode::Normal | Mode::Visual | Mode::VisualLine => self.handle_command_key(key, ctx),
+        }
+    }
+
+    fn status(&self) -> String {
+        let mode = match self.mode {
+            Mode::Normal => "NORMAL".to_string(),
+            Mode::Insert => "-- INSERT --".to_string(),
+            Mode::Visual => "-- VISUAL --".to_string(),
+            Mode::VisualLine => "-- VISUAL LINE --".to_string(),
+            Mode::CommandLine => return format!(":{}", self.prompt),
+            Mode::Search { forward } => return format!("{}{}", if forward { '/' } else { '?' }, self.prompt),
+        };
+        let pending: String = self.pending.iter().filter_map(|k| k.plain_char()).collect();
+        match &self.message {
+            Some(message) => format!("{}  {}", mode, message),
+            None if !pending.is_empty() => format!("{}  {}", mode, pending),
+            None => mode,
+        }
+    }
+
+    fn accepts_text(&self) -> bool {
+        self.mode == Mode::Insert
+    }
+
+    fn block_cursor(&self) -> bool {
+        self.mode == Mode::Normal
+    }
+
+    fn display_selection(&self, text: &str, anchor: usize, cursor: usize) -> (usize, usize) {
+        match self.mode {
+            Mode::VisualLine => {
+                if cursor >= anchor {
+                    (line_start(text, anchor), line_end(text, cursor))
+                } else {
+                    (line_end(text, anchor), line_start(text, cursor))
+                }
+            }
+            // The character under the cursor is part of a visual selection
+            Mode::Visual if cursor >= anchor => (anchor, next_char(text, cursor)),
+            Mode::Visual => (next_char(text, anchor), cursor),
+            _ => (cursor, cursor),
+        }
+    }
+}
+
+fn parse_command(keys: &[KeyPress], visual: bool) -> Parse<Parsed> {
+    let plain = |i: usize| keys.get(i).and_then(|k| k.plain_char());
+
+    let mut i = 0;
+    let mut register = None;
+    if plain(0) == Some('"') {
+        match keys.get(1) {
+            None => return Parse::Incomplete,
+            Some(k) => match k.plain_char() {
+                Some(c) => register = Some(c),
+                None => return Parse::Invalid,
+            },
+        }
+        i = 2;
+    }
+
+    let (count, i) = parse_count(keys, i);
+    let Some(&key) = keys.get(i) else {
+        return Parse::Incomplete;
+    };
+    let done = |command| Parse::Done(Parsed { register, count, command });
+
+    if key.ctrl {
+        let pages = |lines: usize| Some(count.unwrap_or(1) * lines);
+        let (command, count) = match key.key {
+            Key::Char('r') if !visual => (Command::Redo, count),
+            Key::Char('d') => (Command::Move(Motion::Down), pages(HALF_PAGE)),
+            Key::Char('u') => (Command::Move(Motion::Up), pages(HALF_PAGE)),
+            Key::Char('f') => (Command::Move(Motion::Down), pages(2 * HALF_PAGE)),
+            Key::Char('b') => (Command::Move(Motion::Up), pages(2 * HALF_PAGE)),
+            _ => return Parse::Invalid,
+        };
+        return Parse::Done(Parsed { register, count, command });
+    }
+    if key.alt {
+        return Parse::Invalid;
+    }
+
+    // Operators, applied to the selection right away in visual mode
+    let operator = match (key.key, plain(i + 1)) {
+        (Key::Char('d'), _) | (Key::Char('x'), _) if visual => Some((Operator::Delete, 1)),
+        (Key::Char('c'), _) | (Key::Char('s'), _) if visual => Some((Operator::Change, 1)),
+        (Key::Char('u'), _) if visual => Some((Operator::Lowercase, 1)),
+        (Key::Char('U'), _) if visual => Some((Operator::Uppercase, 1)),
+        (Key::Char('~'), _) if visual => Some((Operator::ToggleCase, 1)),
+        (Key::Char('d'), _) => Some((Operator::Delete, 1)),
+        (Key::Char('c'), _) => Some((Operator::Change, 1)),
+        (Key::Char('y'), _) => Some((Operator::Yank, 1)),
+        (Key::Char('>'), _) => Some((Operator::Indent, 1)),
+        (Key::Char('<'), _) => Some((Operator::Outdent, 1)),
+        (Key::Char('g'), Some('~')) => Some((Operator::ToggleCase, 2)),
+        (Key::Char('g'), Some('u')) => Some((Operator::Lowercase, 2)),
+        (Key::Char('g'), Some('U')) => Some((Operator::Uppercase, 2)),
+        _ => None,
+    };
+    if let Some((op, len)) = operator {
+        if visual {
+            return done(Command::Operate(op, Target::Selection));
+        }
+        let op_char = plain(i + len - 1);
+        let (inner_count, j) = parse_count(keys, i + len);
+        let count = match (count, inner_count) {
+            (Some(a), Some(b)) => Some(a * b),
+            (a, b) => a.or(b),
+        };
+        let Some(&next) = keys.get(j) else {
+            return Parse::Incomplete;
+        };
+        let command = |target| Parse::Done(Parsed { register, count, command: Command::Operate(op, target) });
+
+        if next.plain_char().is_some() && next.plain_char() == op_char {
+            return command(Target::Lines);
+        }
+        return match next.plain_char() {
+            Some(c @ ('i' | 'a')) => match keys.get(j + 1) {
+                None => Parse::Incomplete,
+                Some(k) => match k.plain_char().and_then(text_object_for) {
+                    Some(object) => command(Target::Object { object, inner: c == 'i' }),
+                    None => Parse::Invalid,
+                },
+            },
+            _ => match parse_motion(keys, j) {
+                Parse::Done(motion) => command(Target::Motion(motion)),
+                Parse::Incomplete => Parse::Incomplete,
+                Parse::Invalid => Parse::Invalid,
+            },
+        };
+    }
+
+    let command = match key.plain_char() {
+        Some('i') | Some('a') if visual => {
+            return match keys.get(i + 1) {
+                None => Parse::Incomplete,
+                Some(k) => match k.plain_char().and_then(text_object_for) {
+                    Some(object) => done(Command::SelectObject { object, inner: key.plain_char() == Some('i') }),
+                    None => Parse::Invalid,
+                },
+            };
+        }
+        Some('o') if visual => Command::SwapVisualEnds,
+        Some('y') if visual => Command::Operate(Operator::Yank, Target::Selection),
+        Some('i') => Command::Insert(InsertAt::Cursor),
+        Some('a') => Command::Insert(InsertAt::After),
+        Some('I') => Command::Insert(InsertAt::LineStart),
+        Some('A') => Command::Insert(InsertAt::LineEnd),
+        Some('o') => Command::Insert(InsertAt::LineBelow),
+        Some('O') => Command::Insert(InsertAt::LineAbove),
+        Some('v') => Command::Visual { linewise: false },
+        Some('V') => Command::Visual { linewise: true },
+        Some('p') if !visual => Command::Paste { before: false },
+        Some('P') if !visual => Command::Paste { before: true },
+        Some('x') => Command::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+        Some('X') => Command::Operate(Operator::Delete, Target::Motion(Motion::Left)),
+        Some('D') => Command::Operate(Operator::Delete, Target::Motion(Motion::LineEnd)),
+        Some('C') => Command::Operate(Operator::Change, Target::Motion(Motion::LineEnd)),
+        Some('Y') => Command::Operate(Operator::Yank, Target::Lines),
+        Some('s') => Command::Operate(Operator::Change, Target::Motion(Motion::Right)),
+        Some('S') => Command::Operate(Operator::Change, Target::Lines),
+        Some('r') if !visual => {
+            return match keys.get(i + 1) {
+                None => Parse::Incomplete,
+                Some(k) => match k.plain_char() {
+                    Some(c) => done(Command::ReplaceChar(c)),
+                    None => Parse::Invalid,
+                },
+            };
+        }
+        Some('J') if !visual => Command::JoinLines,
+        Some('~') => Command::ToggleCaseChar,
+        Some('u') => Command::Undo,
+        Some('.') if !visual => Command::RepeatLast,
+        Some(':') => Command::EnterCommandLine,
+        Some('/') => Command::EnterSearch { forward: true },
+        Some('?') => Command::EnterSearch { forward: false },
+        _ => match key.key {
+            Key::Delete if !visual => Command::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+            _ => {
+                return match parse_motion(keys, i) {
+                    Parse::Done(motion) => done(Command::Move(motion)),
+                    Parse::Incomplete => Parse::Incomplete,
+                    Parse::Invalid => Parse::Invalid,
+                };
+            }
+        },
+    };
+    done(command)
+}
+
+/// Parse a count prefix starting at `i` (a leading `0` is a motion, not a count)
+fn parse_count(keys: &[KeyPress], mut i: usize) -> (Option<usize>, usize) {
+    let mut count: Option<usize> = None;
+    while let Some(d) = keys.get(i).and_then(|k| k.plain_char()).and_then(|c| c.to_digit(10)) {
+        if d == 0 && count.is_none() {
+            break;
+        }
+        count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(d as usize));
+        i += 1;
+    }
+    (count, i)
+}
+
+fn parse_motion(keys: &[KeyPress], i: usize) -> Parse<Motion> {
+    let Some(key) = keys.get(i) else {
+        return Parse::Incomplete;
+    };
+    let next = keys.get(i + 1);
+
+    let motion = match key.key {
+        Key::Left | Key::Backspace => Motion::Left,
+        Key::Right => Motion::Right,
+        Key::Up => Motion::Up,
+        Key::Down | Key::Enter => Motion::Down,
+        Key::Home => Motion::LineStart,
+        Key::End => Motion::LineEnd,
+        Key::Char(c) if !key.ctrl && !key.alt => match c {
+            'h' => Motion::Left,
+            'l' | ' ' => Motion::Right,
+            'j' | '+' => Motion::Down,
+            'k' | '-' => Motion::Up,
+            'w' => Motion::WordForward(false),
+            'W' => Motion::WordForward(true),
+            'e' => Motion::WordEnd(false),
+            'E' => Motion::WordEnd(true),
+            'b' => Motion::WordBackward(false),
+            'B' => Motion::WordBackward(true),
+            '0' => Motion::LineStart,
+            '^' | '_' => Motion::FirstNonBlank,
+            '$' => Motion::LineEnd,
+            'G' => Motion::FileEnd,
+            'g' => match next.map(|k| k.plain_char()) {
+                None => return Parse::Incomplete,
+                Some(Some('g')) => Motion::FileStart,
+                Some(_) => return Parse::Invalid,
+            },
+            'f' | 'F' | 't' | 'T' => match next {
+                None => return Parse::Incomplete,
+                Some(k) => match k.plain_char() {
+                    Some(target) => Motion::FindChar {
+                        c: target,
+                        forward: c == 'f' || c == 't',
+                        till: c == 't' || c == 'T',
+                    },
+                    None => return Parse::Invalid,
+                },
+            },
+            ';' => Motion::RepeatFind { reverse: false },
+            ',' => Motion::RepeatFind { reverse: true },
+            '%' => Motion::MatchPair,
+            '}' => Motion::ParagraphForward,
+            '{' => Motion::ParagraphBackward,
+            'n' => Motion::SearchNext { reverse: false },
+            'N' => Motion::SearchNext { reverse: true },
+            _ => return Parse::Invalid,
+        },
+        _ => return Parse::Invalid,
+    };
+    Parse::Done(motion)
+}
+
+fn text_object_for(c: char) -> Option<TextObject> {
+    Some(match c {
+        'w' => TextObject::Word { big: false },
+        'W' => TextObject::Word { big: true },
+        '"' | '\'' | '`' => TextObject::Quote(c),
+        '(' | ')' | 'b' => TextObject::Pair('(', ')'),
+        '{' | '}' | 'B' => TextObject::Pair('{', '}'),
+        '[' | ']' => TextObject::Pair('[', ']'),
+        '<' | '>' => TextObject::Pair('<', '>'),
+        'p' => TextObject::Paragraph,
+        _ => return None,
+    })
+}
+
+/// Range of a text object around `cursor`, and whether it is linewise
+fn text_object(text: &str, cursor: usize, object: TextObject, inner: bool) -> Option<(Range<usize>, bool)> {
+    match object {
+        TextObject::Word { big } => {
+            let c = char_at(text, cursor).filter(|&c| c != '\n')?;
+            let class = keymap::char_class(c, big);
+            let same = |c: char| c != '\n' && keymap::char_class(c, big) == class;
+            let start = text[..cursor].char_indices().rev()
+                .take_while(|&(_, c)| same(c))
+                .last()
+                .map(|(i, _)| i)
+                .unwrap_or(cursor);
+            let end = text[cursor..].char_indices()
+                .find(|&(_, c)| !same(c))
+                .map(|(i, _)| cursor + i)
+                .unwrap_or(text.len());
+            if inner || class == 0 {
+                return Some((start..end, false));
+            }
+            // `aw` takes trailing blanks, or leading ones at the end of a line
+            let is_blank = |c: char| c == ' ' || c == '\t';
+            let trailing = text[end..].find(|c: char| !is_blank(c)).map(|i| end + i).unwrap_or(text.len());
+            if trailing > end {
+                Some((start..trailing, false))
+            } else {
+                let leading = text[..start].char_indices().rev()
+                    .take_while(|&(_, c)| is_blank(c))
+                    .last()
+                    .map(|(i, _)| i)
+                    .unwrap_or(start);
+                Some((leading..end, false))
+            }
+        }
+        TextObject::Quote(quote) => {
+            let start = line_start(text, cursor);
+            let end = line_end(text, cursor);
+            let quotes: Vec<usize> = text[start..end].char_indices()
+                .filter(|&(i, c)| c == quote && !text[..start + i].ends_with('\\'))
+                .map(|(i, _)| start + i)
+                .collect();
+            let (open, close) = quotes.chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .find(|&(_, close)| cursor <= close)?;
+            if inner {
+                Some((open + 1..close, false))
+            } else {
+                Some((open..close + 1, false))
+            }
+        }
+        TextObject::Pair(open, close) => {
+            let open_pos = if char_at(text, cursor) == Some(open) {
+                cursor
+            } else {
+                let mut depth = 0;
+                text[..cursor].char_indices().rev().find(|&(_, c)| {
+                    if c == close {
+                        depth += 1;
+                    } else if c == open {
+                        if depth == 0 {
+                            return true;
+                        }
+                        depth -= 1;
+                    }
+                    false
+                })?.0
+            };
+            let close_pos = matching_close(text, open_pos, open, close)?;
+            if inner {
+                Some((open_pos + 1..close_pos, false))
+            } else {
+                Some((open_pos..close_pos + 1, false))
+            }
+        }
+        TextObject::Paragraph => {
+            let blank = is_blank_line(text, cursor);
+            let mut first = line_start(text, cursor);
+            while first > 0 && is_blank_line(text, first - 1) == blank {
+                first = line_start(text, first - 1);
+            }
+            let mut last = line_start(text, cursor);
+            while let Some(next) = next_line(text, last) {
+                if is_blank_line(text, next) != blank {
+                    break;
+                }
+                last = next;
+            }
+            if !inner && !blank {
+                while let Some(next) = next_line(text, last) {
+                    if !is_blank_line(text, next) {
+                        break;
+                    }
+                    last = next;
+                }
+            }
+            Some((first..line_end(text, last), true))
+        }
+    }
+}
+
+fn find_char(text: &str, cursor: usize, c: char, forward: bool, till: bool, count: usize) -> Option<(usize, MotionKind)> {
+    let start = line_start(text, cursor);
+    let end = line_end(text, cursor);
+    let found = if forward {
+        text[next_char(text, cursor).min(end)..end].char_indices()
+            .filter(|&(_, ch)| ch == c)
+            .nth(count - 1)
+            .map(|(i, _)| next_char(text, cursor) + i)?
+    } else {
+        text[start..cursor].char_indices().rev()
+            .filter(|&(_, ch)| ch == c)
+            .nth(count - 1)
+            .map(|(i, _)| start + i)?
+    };
+    match (forward, till) {
+        (true, false) => Some((found, MotionKind::Inclusive)),
+        (true, true) => Some((prev_char(text, found), MotionKind::Inclusive)),
+        (false, false) => Some((found, MotionKind::Exclusive)),
+        (false, true) => Some((next_char(text, found), MotionKind::Exclusive)),
+    }
+}
+
+/// Jump from the bracket at or after the cursor (on its line) to its partner
+fn match_pair(text: &str, cursor: usize) -> Option<usize> {
+    const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+    let end = line_end(text, cursor);
+    let (pos, c) = text[cursor..end].char_indices()
+        .map(|(i, c)| (cursor + i, c))
+        .find(|&(_, c)| PAIRS.iter().any(|&(o, cl)| c == o || c == cl))?;
+
+    if let Some(&(open, close)) = PAIRS.iter().find(|&&(o, _)| o == c) {
+        return matching_close(text, pos, open, close);
+    }
+    let &(open, close) = PAIRS.iter().find(|&&(_, cl)| cl == c)?;
+    let mut depth = 0;
+    text[..pos].char_indices().rev().find(|&(_, ch)| {
+        if ch == close {
+            depth += 1;
+        } else if ch == open {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+        false
+    }).map(|(i, _)| i)
+}
+
+fn matching_close(text: &str, open_pos: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text[open_pos..].char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(open_pos + i);
+            }
+        }
+    }
+    None
+}
+
+fn is_blank_line(text: &str, pos: usize) -> bool {
+    let start = line_start(text, pos);
+    text[start..line_end(text, start)].trim().is_empty()
+}
+
+fn next_line(text: &str, pos: usize) -> Option<usize> {
+    let end = line_end(text, pos);
+    (end < text.len()).then_some(end + 1)
+}
+
+fn paragraph_forward(text: &str, pos: usize) -> usize {
+    let mut line = line_start(text, pos);
+    while is_blank_line(text, line) {
+        match next_line(text, line) {
+            Some(next) => line = next,
+            None => return text.len(),
+        }
+    }
+    while !is_blank_line(text, line) {
+        match next_line(text, line) {
+            Some(next) => line = next,
+            None => return text.len(),
+        }
+    }
+    line
+}
+
+fn paragraph_backward(text: &str, pos: usize) -> usize {
+    let mut line = line_start(text, pos);
+    let previous = |line: usize| (line > 0).then(|| line_start(text, line - 1));
+    while is_blank_line(text, line) {
+        match previous(line) {
+            Some(prev) => line = prev,
+            None => return 0,
+        }
+    }
+    while !is_blank_line(text, line) {
+        match previous(line) {
+            Some(prev) => line = prev,
+            None => return 0,
+        }
+    }
+    line
+}
+
+/// End of the word `cw` changes: the current word, then `count - 1` more
+fn change_word_end(text: &str, cursor: usize, big: bool, count: usize) -> usize {
+    let class = char_at(text, cursor).map(|c| keymap::char_class(c, big));
+    let next_same = char_at(text, next_char(text, cursor))
+        .is_some_and(|c| c != '\n' && Some(keymap::char_class(c, big)) == class);
+    let first = if next_same { keymap::word_end(text, cursor, big) } else { cursor };
+    (1..count).fold(first, |pos, _| keymap::word_end(text, pos, big))
+}
+
+/// Normal mode keeps the cursor on a character, never on a line's newline
+fn clamp_normal(text: &str, pos: usize) -> usize {
+    let pos = pos.min(text.len());
+    if pos == line_end(text, pos) && pos > line_start(text, pos) {
+        prev_char(text, pos)
+    } else {
+        pos
+    }
+}
+
+fn indentation(text: &str, pos: usize) -> String {
+    let start = line_start(text, pos);
+    text[start..first_non_blank(text, start)].to_string()
+}
+
+fn toggle_case(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| {
+            let toggled: Vec<char> = if c.is_uppercase() {
+                c.to_lowercase().collect()
+            } else {
+                c.to_uppercase().collect()
+            };
+            toggled
+        })
+        .collect()
+}
+
+/// Replace the count in a recorded command (`3.` after `2dw` runs `3dw`)
+fn with_count(keys: Vec<KeyPress>, count: usize) -> Vec<KeyPress> {
+    let prefix = if keys.first().and_then(|k| k.plain_char()) == Some('"') { 2 } else { 0 };
+    let (_, after_count) = parse_count(&keys, prefix);
+    let mut result = keys[..prefix.min(keys.len())].to_vec();
+    result.extend(count.to_string().chars().map(|c| KeyPress::new(Key::Char(c))));
+    result.extend_from_slice(&keys[after_count..]);
+    result
+}
+
+fn split_unescaped(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                Some(next) => {
+                    let part = parts.last_mut().unwrap();
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => parts.last_mut().unwrap().push('\\'),
+            }
+        } else if c == delimiter {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+    parts
+}
+
+/// Translate Vim replacement syntax (`\1`, `&`) into regex crate syntax
+fn vim_replacement(replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(d) if d.is_ascii_digit() => result.push_str(&format!("${{{}}}", d)),
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('$') => result.push_str("$$"),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            },
+            '&' => result.push_str("${0}"),
+            '$' => result.push_str("$$"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_buffer::EditorBuffer;
+    use crate::keymap::parse_keys;
+
+    /// Feed `keys` to a fresh Vim keymap over `text` with the cursor at
+    /// `cursor`, returning the text and cursor after
+    fn run(text: &str, cursor: usize, keys: &str) -> (String, usize) {
+        let mut keymap = VimKeymap::new();
+        let mut buffer = EditorBuffer::new(text.to_string());
+        let mut ctx = KeymapContext::new(&mut buffer, cursor, cursor, 4);
+        for key in parse_keys(keys).unwrap() {
+            keymap.handle_key(key, &mut ctx);
+        }
+        let cursor = ctx.cursor;
+        (buffer.text().to_string(), cursor)
+    }
+
+    #[test]
+    fn delete_word() {
+        assert_eq!(run("foo bar baz", 4, "dw"), ("foo baz".to_string(), 4));
+    }
+
+    #[test]
+    fn dot_repeats_the_last_change() {
+        assert_eq!(run("one two three four", 0, "dw.."), ("four".to_string(), 0));
+        assert_eq!(run("a\nb\nc\n", 0, "A;<Esc>j."), ("a;\nb;\nc\n".to_string(), 4));
+    }
+
+    #[test]
+    fn count_repeats_a_command() {
+        assert_eq!(run("abcdef", 1, "3x"), ("aef".to_string(), 1));
+    }
+
+    #[test]
+    fn change_inner_word() {
+        assert_eq!(run("let foo = 1;", 5, "ciwbar<Esc>"), ("let bar = 1;".to_string(), 6));
+    }
+
+    #[test]
+    fn named_register_yank_and_put() {
+        assert_eq!(run("first\nsecond\n", 0, "\"ayyj\"ap"), ("first\nsecond\nfirst\n".to_string(), 13));
+    }
+
+    #[test]
+    fn substitute_on_the_current_line() {
+        assert_eq!(run("banana\naaa\n", 0, ":s/a/b/g<CR>"), ("bbnbnb\naaa\n".to_string(), 0));
+    }
+}
@@ -229,6 +229,13 @@ impl Widget for TransformWidget {
                         }
                     }
 
+                    // Camera bookmarks, numbered by quick slot
+                    if let Some(bookmarks) = ctx.view_system.as_bookmarks() {
+                        for (index, bookmark) in bookmarks.bookmarks().iter().enumerate().take(9) {
+                            ui.small(format!("{} {}", index + 1, bookmark.name));
+                        }
+                    }
+
                     // Reset button only if transform is supported
                     if transform.can_zoom || transform.can_pan {
                         if ui.small_button("Reset").clicked() {